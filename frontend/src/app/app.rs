@@ -17,7 +17,10 @@ use yew_router::hooks::{use_navigator, use_route};
 use yew_router::prelude::{use_location, NavigationError, Navigator};
 use yew_router::{BrowserRouter, Switch};
 
-use crate::app::pages::{create_game::CreateGame, game::Game, home::Home, info::Info};
+use crate::app::pages::{
+    create_game::CreateGame, game::Game, history::History, home::Home, info::Info, join::Join,
+    login::Login, replay::Replay,
+};
 use crate::app::state::{ServerAction, ServerState};
 
 use super::pages::download::Download;
@@ -71,12 +74,14 @@ fn dispatch_server_message(
             turn,
             counter,
             incheck,
+            pockets,
         } => {
             dispatch.dispatch(ServerAction::SetBoard {
                 board,
                 turn,
                 counter,
                 incheck,
+                pockets,
             });
         }
         ServerMessage::LegalMoves { moves } => {
@@ -85,8 +90,44 @@ fn dispatch_server_message(
         ServerMessage::GameOver {
             room_status,
             result,
+            rating_changes,
+            variant_win,
+            ..
         } => {
-            dispatch.dispatch(ServerAction::SetGameOver(result, room_status));
+            dispatch.dispatch(ServerAction::SetGameOver(
+                result,
+                room_status,
+                rating_changes,
+                variant_win,
+            ));
+        }
+        ServerMessage::BughouseSeats { seats, seat } => {
+            dispatch.dispatch(ServerAction::SetSeats(seats, seat));
+        }
+        ServerMessage::BughouseBoard {
+            board_id,
+            board,
+            turn,
+            counter,
+            incheck,
+            pockets,
+            clock,
+        } => {
+            dispatch.dispatch(ServerAction::SetBughouseBoard {
+                board_id,
+                board,
+                turn,
+                counter,
+                incheck,
+                pockets,
+                clock,
+            });
+        }
+        ServerMessage::BughousePockets { board_id, pockets } => {
+            dispatch.dispatch(ServerAction::SetBughousePockets(board_id, pockets));
+        }
+        ServerMessage::VariantStatus { variant, state } => {
+            dispatch.dispatch(ServerAction::SetVariant(variant, state));
         }
         ServerMessage::Info { msg } => {
             dispatch.dispatch(ServerAction::SetInfo(msg));
@@ -124,12 +165,14 @@ fn dispatch_server_message(
             room_status,
             board,
             turn,
+            ..
         } => {
             dispatch.dispatch(ServerAction::SetBoard {
                 board,
                 turn,
                 counter: 0,
                 incheck: None,
+                pockets: None,
             });
             dispatch.dispatch(ServerAction::SetRoomStatus(room_status));
         }
@@ -144,6 +187,113 @@ fn dispatch_server_message(
         ServerMessage::CloseRoom { id } => {
             dispatch.dispatch(ServerAction::SetQuit);
         }
+        ServerMessage::LoggedIn { account, token } => {
+            LocalStorage::set("session_token", token).expect("failed to store session_token");
+            dispatch.dispatch(ServerAction::SetAccount(Some(account)));
+        }
+        ServerMessage::LoggedOut => {
+            LocalStorage::delete("session_token");
+            dispatch.dispatch(ServerAction::SetAccount(None));
+        }
+        ServerMessage::Snapshot {
+            board,
+            turn,
+            moves,
+            spectators,
+            spectator_delay,
+            ..
+        } => {
+            dispatch.dispatch(ServerAction::SetBoard {
+                board,
+                turn,
+                counter: moves.len(),
+                incheck: None,
+                pockets: None,
+            });
+            dispatch.dispatch(ServerAction::SetSpectators(spectators));
+            dispatch.dispatch(ServerAction::SetSpectatorDelay(spectator_delay));
+        }
+        ServerMessage::RematchOffered { by } => {
+            dispatch.dispatch(ServerAction::SetRematchOffer(Some(by)));
+        }
+        ServerMessage::RematchDeclined { by } => {
+            dispatch.dispatch(ServerAction::SetRematchOffer(None));
+            dispatch.dispatch(ServerAction::SetInfo(format!(
+                "{} declined the rematch",
+                by
+            )));
+        }
+        ServerMessage::GameModeChanged {
+            gamemod,
+            role,
+            room_status,
+        } => {
+            dispatch.dispatch(ServerAction::ChangeMode(gamemod, role, room_status));
+        }
+        ServerMessage::RematchStarted { role, room_status } => {
+            dispatch.dispatch(ServerAction::StartRematch(role, room_status));
+        }
+        ServerMessage::Chat { entry } => {
+            dispatch.dispatch(ServerAction::AddChat(entry));
+        }
+        ServerMessage::ChatHistory { entries } => {
+            dispatch.dispatch(ServerAction::SetChat(entries));
+        }
+        ServerMessage::SpectatorCount { count } => {
+            dispatch.dispatch(ServerAction::SetSpectators(count));
+        }
+        ServerMessage::MateSolution { n, outcome } => {
+            dispatch.dispatch(ServerAction::SetMateSolution(Some((n, outcome))));
+        }
+        ServerMessage::Evaluation { breakdown } => {
+            dispatch.dispatch(ServerAction::SetEvaluation(Some(breakdown)));
+        }
+        ServerMessage::PuzzleStarted { puzzle, stats } => {
+            dispatch.dispatch(ServerAction::StartPuzzle(puzzle, stats));
+        }
+        ServerMessage::PuzzleMove { correct, .. } => {
+            dispatch.dispatch(ServerAction::SetPuzzleFeedback(correct));
+        }
+        ServerMessage::PuzzleFinished {
+            solved,
+            solution,
+            stats,
+            rating_before,
+        } => {
+            dispatch.dispatch(ServerAction::FinishPuzzle {
+                solved,
+                solution,
+                stats,
+                rating_before,
+            });
+        }
+        ServerMessage::InviteCode { code, .. } => {
+            dispatch.dispatch(ServerAction::SetInviteCode(code));
+        }
+        ServerMessage::LobbySnapshot { rooms } => {
+            dispatch.dispatch(ServerAction::SetLobby(rooms));
+        }
+        ServerMessage::LobbyRoomAdded { room } | ServerMessage::LobbyRoomUpdated { room } => {
+            dispatch.dispatch(ServerAction::LobbyUpsert(room));
+        }
+        ServerMessage::LobbyRoomRemoved { room_id } => {
+            dispatch.dispatch(ServerAction::LobbyRemove(room_id));
+        }
+        ServerMessage::Engines { names } => {
+            dispatch.dispatch(ServerAction::SetEngines(names));
+        }
+        ServerMessage::Queued { time_control, .. } => {
+            dispatch.dispatch(ServerAction::SetQueued(true, time_control));
+        }
+        ServerMessage::LeftQueue => {
+            dispatch.dispatch(ServerAction::SetQueued(false, None));
+        }
+        ServerMessage::GameList { games } => {
+            dispatch.dispatch(ServerAction::SetGameList(games));
+        }
+        ServerMessage::ArchivedGame { game } => {
+            dispatch.dispatch(ServerAction::SetReplayGame(*game));
+        }
         _ => {
             web_sys::console::log_1(&format!("❓ Message inattendu: {:?}", msg).into());
         }
@@ -237,7 +387,7 @@ fn app_inner() -> Html {
     html! {
         <main>
             {
-                if matches!(current_route, Route::Home | Route::Info | Route::Download|Route::NotFound) {
+                if matches!(current_route, Route::Home | Route::Info | Route::Download | Route::History | Route::Login | Route::NotFound) {
                     html! { <Navbar /> }
                 } else {
                     html! {}
@@ -269,6 +419,10 @@ fn switch_with_props(
         },
         Route::Info => html! { <Info /> },
         Route::Download => html! { <Download /> },
+        Route::Login => html! { <Login /> },
+        Route::History => html! { <History /> },
+        Route::Replay { id } => html! { <Replay {id} /> },
+        Route::Join { code } => html! { <Join {code} /> },
         Route::CreateGame => html! {
             <CreateGame on_create_game={Callback::from(|_| {})} />
         },
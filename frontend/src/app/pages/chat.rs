@@ -0,0 +1,103 @@
+use crate::app::state::ServerState;
+use crate::ws::WsContext;
+use game_lib::messages::ClientMessage;
+use game_lib::sharedenums::{ChatChannel, PlayerRole};
+use yew::prelude::*;
+
+#[function_component(Chat)]
+pub fn chat() -> Html {
+    let server_state =
+        use_context::<UseReducerHandle<ServerState>>().expect("ServerState context is missing");
+    let ctx = use_context::<WsContext>().expect("WsContext missing");
+    let text = use_state(|| "".to_string());
+    let channel = use_state(|| ChatChannel::Players);
+    let spectator = server_state.role == Some(PlayerRole::Spectator);
+    // Au Bughouse, les joueurs assis peuvent parler à leur partenaire seul
+    let seated = server_state.seat.is_some();
+
+    let oninput = {
+        let text = text.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            text.set(input.value());
+        })
+    };
+
+    let on_send = {
+        let ctx = ctx.clone();
+        let text = text.clone();
+        let channel = channel.clone();
+        Callback::from(move |_| {
+            if !text.trim().is_empty() {
+                ctx.send(ClientMessage::Chat {
+                    channel: *channel,
+                    text: (*text).clone(),
+                });
+                text.set("".to_string());
+            }
+        })
+    };
+
+    let on_channel = {
+        let channel = channel.clone();
+        Callback::from(move |c: ChatChannel| channel.set(c))
+    };
+
+    html! {
+        <div class="chat-container">
+            <h4>{ "Chat" }</h4>
+            <div class="chat-messages">
+                { for server_state.chat.iter().map(|entry| {
+                    let target = entry.author_id;
+                    let moderation = if server_state.host {
+                        let ctx = ctx.clone();
+                        let ctx_kick = ctx.clone();
+                        html! {
+                            <>
+                                <button class="chat-action" onclick={Callback::from(move |_| ctx.send(ClientMessage::MutePlayer { client_id: target, muted: true }))}>{ "mute" }</button>
+                                <button class="chat-action" onclick={Callback::from(move |_| ctx_kick.send(ClientMessage::KickPlayer { client_id: target }))}>{ "kick" }</button>
+                            </>
+                        }
+                    } else {
+                        html! {}
+                    };
+                    let class = match entry.channel {
+                        ChatChannel::Players => "chat-message",
+                        ChatChannel::Spectators => "chat-message spectators",
+                        ChatChannel::Team => "chat-message team",
+                    };
+                    html! {
+                        <p class={class}>
+                            <strong>{ format!("{}: ", entry.author) }</strong>
+                            { &entry.text }
+                            { moderation }
+                        </p>
+                    }
+                }) }
+            </div>
+            {
+                if spectator {
+                    html! {
+                        <div class="chat-channels">
+                            <button class="chat-action" disabled={*channel == ChatChannel::Players} onclick={on_channel.reform(|_| ChatChannel::Players)}>{ "All" }</button>
+                            <button class="chat-action" disabled={*channel == ChatChannel::Spectators} onclick={on_channel.reform(|_| ChatChannel::Spectators)}>{ "Spectators" }</button>
+                        </div>
+                    }
+                } else if seated {
+                    html! {
+                        <div class="chat-channels">
+                            <button class="chat-action" disabled={*channel == ChatChannel::Players} onclick={on_channel.reform(|_| ChatChannel::Players)}>{ "All" }</button>
+                            <button class="chat-action" disabled={*channel == ChatChannel::Team} onclick={on_channel.reform(|_| ChatChannel::Team)}>{ "Partner" }</button>
+                        </div>
+                    }
+                } else {
+                    html! {}
+                }
+            }
+            <div class="chat-input">
+                <input type="text" maxlength="500" value={(*text).clone()} {oninput} />
+                <button class="game-button" onclick={on_send}>{ "Send" }</button>
+            </div>
+        </div>
+    }
+}
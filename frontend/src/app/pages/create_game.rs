@@ -1,6 +1,13 @@
+use crate::app::state::ServerState;
 use crate::{routes::Route, ws::WsContext};
-use game_lib::automation::ai::Difficulty;
+use game_lib::automation::ai::{AiProfile, Difficulty, EvalVariant};
+use game_lib::automation::book::BookUsage;
+use game_lib::bughouse::{Seat, SEATS};
+use game_lib::handicap::{Handicap, MaterialOdds};
+use game_lib::piece::Color;
+use game_lib::variant::Variant;
 use game_lib::{messages::ClientMessage, sharedenums::GameMode};
+use web_sys::HtmlSelectElement;
 use yew::prelude::*;
 use yew_router::hooks::use_navigator;
 
@@ -9,21 +16,149 @@ pub struct CreateGameProps {
     pub on_create_game: Callback<(GameMode, Option<Difficulty>)>, // ajout difficulté
 }
 
+// Valeur d'un <select> de moteur: "easy", "medium", "hard" ou "engine:<nom>"
+fn parse_profile(value: &str, use_book: bool) -> AiProfile {
+    let difficulty = match value {
+        "medium" => Difficulty::Medium,
+        "hard" => Difficulty::Hard,
+        v => match v.strip_prefix("engine:") {
+            Some(name) => {
+                return AiProfile::External {
+                    name: name.to_string(),
+                    movetime_ms: 1000,
+                }
+            }
+            None => Difficulty::Easy,
+        },
+    };
+    AiProfile::Builtin {
+        difficulty,
+        eval: EvalVariant::default(),
+        book: BookUsage {
+            enabled: use_book,
+            max_plies: None,
+        },
+    }
+}
+
+// Valeur du <select> de handicap: "none", "pawn", "knight", "rook" ou "queen"
+fn parse_handicap(value: &str, giver: Color) -> Option<Handicap> {
+    let odds = match value {
+        "pawn" => MaterialOdds::PawnAndMove,
+        "knight" => MaterialOdds::Knight,
+        "rook" => MaterialOdds::Rook,
+        "queen" => MaterialOdds::Queen,
+        _ => return None,
+    };
+    Some(Handicap::Material { giver, odds })
+}
+
+// Valeur du <select> de variante
+fn parse_variant(value: &str) -> Variant {
+    match value {
+        "koth" => Variant::KingOfTheHill,
+        "threecheck" => Variant::ThreeCheck,
+        "atomic" => Variant::Atomic,
+        "crazyhouse" => Variant::Crazyhouse,
+        _ => Variant::Standard,
+    }
+}
+
+// Valeur du <select> de difficulté des IA du Bughouse
+fn parse_difficulty(value: &str) -> Difficulty {
+    match value {
+        "medium" => Difficulty::Medium,
+        "hard" => Difficulty::Hard,
+        _ => Difficulty::Easy,
+    }
+}
+
+// Position de départ saisie, None si le champ est vide
+fn start_fen(value: &str) -> Option<String> {
+    let fen = value.trim();
+    (!fen.is_empty()).then(|| fen.to_string())
+}
+
 #[function_component(CreateGame)]
 pub fn create_game(props: &CreateGameProps) -> Html {
     let selected_mode = use_state(|| None as Option<GameMode>);
+    let rated = use_state(|| false);
+    let public = use_state(|| true);
+    let white_ai = use_state(|| "easy".to_string());
+    let black_ai = use_state(|| "easy".to_string());
+    let use_book = use_state(|| true);
+    let odds = use_state(|| "none".to_string());
+    let odds_giver = use_state(|| Color::White);
+    let fen = use_state(String::new);
+    let variant = use_state(|| "standard".to_string());
+    let ai_seats = use_state(Vec::<Seat>::new);
+    let seat_difficulty = use_state(|| "easy".to_string());
+    let server_state =
+        use_context::<UseReducerHandle<ServerState>>().expect("ServerState context is missing");
+    let logged_in = server_state.account.is_some();
     let ctx = use_context::<WsContext>().expect("WsContext missing");
     let navigator = use_navigator().unwrap();
 
+    {
+        let ctx = ctx.clone();
+        use_effect_with_deps(
+            move |_| {
+                ctx.send(ClientMessage::ListEngines);
+                || ()
+            },
+            (),
+        );
+    }
+
     let on_mode_click = {
         let selected_mode = selected_mode.clone();
         let ctx = ctx.clone();
+        let rated = rated.clone();
+        let public = public.clone();
+        let odds = odds.clone();
+        let odds_giver = odds_giver.clone();
+        let fen = fen.clone();
+        let variant = variant.clone();
         Callback::from(move |mode: GameMode| {
             selected_mode.set(Some(mode.clone()));
-            if matches!(mode, GameMode::PlayerVsPlayer | GameMode::Sandbox) {
+            if matches!(
+                mode,
+                GameMode::PlayerVsPlayer | GameMode::Sandbox | GameMode::Puzzle
+            ) {
+                let handicap = if mode == GameMode::PlayerVsPlayer {
+                    parse_handicap(&odds, *odds_giver)
+                } else {
+                    None
+                };
+                // Les puzzles restent en échecs classiques
+                let variant = if mode == GameMode::Puzzle {
+                    Variant::Standard
+                } else {
+                    parse_variant(&variant)
+                };
+                // Seules les parties entre joueurs connectés sont classées, sans handicap ni variante
+                let rated = *rated
+                    && logged_in
+                    && mode == GameMode::PlayerVsPlayer
+                    && handicap.is_none()
+                    && variant == Variant::Standard;
                 ctx.send(ClientMessage::CreateRoom {
                     mode,
                     difficulty: None,
+                    rated,
+                    time_control: None,
+                    public: *public,
+                    spectator_delay: 0,
+                    white_ai: None,
+                    black_ai: None,
+                    handicap,
+                    fen: if mode == GameMode::Puzzle {
+                        None
+                    } else {
+                        start_fen(&fen)
+                    },
+                    variant,
+                    ai_seats: Vec::new(),
                 });
             }
         })
@@ -32,29 +167,301 @@ pub fn create_game(props: &CreateGameProps) -> Html {
     let on_difficulty_click = {
         let selected_mode = selected_mode.clone();
         let ctx = ctx.clone();
+        let public = public.clone();
+        let odds = odds.clone();
+        let odds_giver = odds_giver.clone();
+        let fen = fen.clone();
+        let variant = variant.clone();
         Callback::from(move |difficulty: Difficulty| {
             if let Some(mode) = (*selected_mode).clone() {
                 ctx.send(ClientMessage::CreateRoom {
                     mode,
                     difficulty: Some(difficulty),
+                    rated: false,
+                    time_control: None,
+                    public: *public,
+                    spectator_delay: 0,
+                    white_ai: None,
+                    black_ai: None,
+                    handicap: parse_handicap(&odds, *odds_giver),
+                    fen: start_fen(&fen),
+                    variant: parse_variant(&variant),
+                    ai_seats: Vec::new(),
                 });
             }
         })
     };
 
+    let on_start_ai_game = {
+        let ctx = ctx.clone();
+        let public = public.clone();
+        let white_ai = white_ai.clone();
+        let black_ai = black_ai.clone();
+        let use_book = use_book.clone();
+        let fen = fen.clone();
+        let variant = variant.clone();
+        Callback::from(move |_| {
+            ctx.send(ClientMessage::CreateRoom {
+                mode: GameMode::AIvsAI,
+                difficulty: None,
+                rated: false,
+                time_control: None,
+                public: *public,
+                spectator_delay: 0,
+                white_ai: Some(parse_profile(&white_ai, *use_book)),
+                black_ai: Some(parse_profile(&black_ai, *use_book)),
+                handicap: None,
+                fen: start_fen(&fen),
+                variant: parse_variant(&variant),
+                ai_seats: Vec::new(),
+            });
+        })
+    };
+
+    let on_start_bughouse = {
+        let ctx = ctx.clone();
+        let public = public.clone();
+        let ai_seats = ai_seats.clone();
+        let seat_difficulty = seat_difficulty.clone();
+        Callback::from(move |_| {
+            let difficulty = (!ai_seats.is_empty()).then(|| parse_difficulty(&seat_difficulty));
+            ctx.send(ClientMessage::CreateRoom {
+                mode: GameMode::Bughouse,
+                difficulty,
+                rated: false,
+                time_control: None,
+                public: *public,
+                spectator_delay: 0,
+                white_ai: None,
+                black_ai: None,
+                handicap: None,
+                fen: None,
+                variant: Variant::Standard,
+                ai_seats: (*ai_seats).clone(),
+            });
+        })
+    };
+
+    // Places du Bughouse confiées à l'IA
+    let seat_toggles = {
+        let toggles = SEATS.iter().map(|&seat| {
+            let checked = ai_seats.contains(&seat);
+            let onchange = {
+                let ai_seats = ai_seats.clone();
+                Callback::from(move |_| {
+                    let mut seats = (*ai_seats).clone();
+                    if checked {
+                        seats.retain(|s| *s != seat);
+                    } else {
+                        seats.push(seat);
+                    }
+                    ai_seats.set(seats);
+                })
+            };
+            html! {
+                <label class="create-game-rated">
+                    <input type="checkbox" {checked} {onchange} />
+                    { format!(" Board {:?}, {:?}", seat.board, seat.color) }
+                </label>
+            }
+        });
+        let onchange = {
+            let seat_difficulty = seat_difficulty.clone();
+            Callback::from(move |e: Event| {
+                let select: HtmlSelectElement = e.target_unchecked_into();
+                seat_difficulty.set(select.value());
+            })
+        };
+        html! {
+            <>
+                { for toggles }
+                <label class="create-game-rated">
+                    { "AI difficulty: " }
+                    <select {onchange}>
+                        <option value="easy" selected={*seat_difficulty == "easy"}>{ "Easy" }</option>
+                        <option value="medium" selected={*seat_difficulty == "medium"}>{ "Medium" }</option>
+                        <option value="hard" selected={*seat_difficulty == "hard"}>{ "Hard" }</option>
+                    </select>
+                </label>
+            </>
+        }
+    };
+
+    let engine_select = |label: &str, choice: UseStateHandle<String>| {
+        let onchange = {
+            let choice = choice.clone();
+            Callback::from(move |e: Event| {
+                let select: HtmlSelectElement = e.target_unchecked_into();
+                choice.set(select.value());
+            })
+        };
+        let engines = server_state.engines.iter().map(|name| {
+            let value = format!("engine:{}", name);
+            html! { <option value={value.clone()} selected={*choice == value}>{ name }</option> }
+        });
+        html! {
+            <label class="create-game-rated">
+                { label }
+                <select {onchange}>
+                    <option value="easy" selected={*choice == "easy"}>{ "Easy" }</option>
+                    <option value="medium" selected={*choice == "medium"}>{ "Medium" }</option>
+                    <option value="hard" selected={*choice == "hard"}>{ "Hard" }</option>
+                    { for engines }
+                </select>
+            </label>
+        }
+    };
+    let white_select = engine_select("White: ", white_ai.clone());
+    let black_select = engine_select("Black: ", black_ai.clone());
+    let book_toggle = {
+        let use_book = use_book.clone();
+        html! {
+            <label class="create-game-rated">
+                <input type="checkbox" checked={*use_book}
+                    onchange={Callback::from(move |_| use_book.set(!*use_book))} />
+                { " Play opening book moves" }
+            </label>
+        }
+    };
+
+    // Handicap matériel pour les leçons: le joueur fort retire une pièce
+    let odds_select = {
+        let on_odds = {
+            let odds = odds.clone();
+            Callback::from(move |e: Event| {
+                let select: HtmlSelectElement = e.target_unchecked_into();
+                odds.set(select.value());
+            })
+        };
+        let on_giver = {
+            let odds_giver = odds_giver.clone();
+            Callback::from(move |e: Event| {
+                let select: HtmlSelectElement = e.target_unchecked_into();
+                odds_giver.set(if select.value() == "black" {
+                    Color::Black
+                } else {
+                    Color::White
+                });
+            })
+        };
+        html! {
+            <label class="create-game-rated">
+                { "Odds: " }
+                <select onchange={on_odds}>
+                    <option value="none" selected={*odds == "none"}>{ "None" }</option>
+                    <option value="pawn" selected={*odds == "pawn"}>{ "Pawn and move" }</option>
+                    <option value="knight" selected={*odds == "knight"}>{ "Knight" }</option>
+                    <option value="rook" selected={*odds == "rook"}>{ "Rook" }</option>
+                    <option value="queen" selected={*odds == "queen"}>{ "Queen" }</option>
+                </select>
+                { " given by " }
+                <select onchange={on_giver}>
+                    <option value="white" selected={*odds_giver == Color::White}>{ "White" }</option>
+                    <option value="black" selected={*odds_giver == Color::Black}>{ "Black" }</option>
+                </select>
+            </label>
+        }
+    };
+
+    let fen_input = {
+        let fen = fen.clone();
+        html! {
+            <label class="create-game-rated">
+                { "Start position (FEN): " }
+                <input type="text" class="create-game-fen" placeholder="standard" value={(*fen).clone()}
+                    oninput={Callback::from(move |e: InputEvent| {
+                        let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+                        fen.set(input.value());
+                    })} />
+            </label>
+        }
+    };
+
+    let variant_select = {
+        let variant = variant.clone();
+        let onchange = {
+            let variant = variant.clone();
+            Callback::from(move |e: Event| {
+                let select: HtmlSelectElement = e.target_unchecked_into();
+                variant.set(select.value());
+            })
+        };
+        html! {
+            <label class="create-game-rated">
+                { "Variant: " }
+                <select {onchange}>
+                    <option value="standard" selected={*variant == "standard"}>{ "Standard" }</option>
+                    <option value="koth" selected={*variant == "koth"}>{ "King of the Hill" }</option>
+                    <option value="threecheck" selected={*variant == "threecheck"}>{ "Three-check" }</option>
+                    <option value="atomic" selected={*variant == "atomic"}>{ "Atomic" }</option>
+                    <option value="crazyhouse" selected={*variant == "crazyhouse"}>{ "Crazyhouse" }</option>
+                </select>
+            </label>
+        }
+    };
+
+    let public_toggle = {
+        let public = public.clone();
+        html! {
+            <label class="create-game-rated">
+                <input type="checkbox" checked={*public}
+                    onchange={Callback::from(move |_| public.set(!*public))} />
+                { " Public room (listed in the lobby)" }
+            </label>
+        }
+    };
+
     html! {
         <div class="create-game-container">
             <button class="create-game-button" onclick={Callback::from(move |_| navigator.push(&Route::Home))}>{ "Retour" }</button>
             <h2 class="create-game-title">{ "Create a new game" }</h2>
+            { public_toggle }
+            { variant_select }
+            { odds_select }
+            { fen_input }
+            {
+                if logged_in {
+                    let rated = rated.clone();
+                    html! {
+                        <label class="create-game-rated">
+                            <input type="checkbox" checked={*rated}
+                                onchange={Callback::from(move |_| rated.set(!*rated))} />
+                            { " Rated game (Player vs Player)" }
+                        </label>
+                    }
+                } else {
+                    html! {}
+                }
+            }
             <div class="create-game-buttons">
                 <button class="create-game-button" onclick={on_mode_click.reform(|_| GameMode::PlayerVsPlayer)}>{ "Player vs Player" }</button>
                 <button class="create-game-button" onclick={on_mode_click.reform(|_| GameMode::PlayerVsAI)}>{ "Player vs AI" }</button>
                 <button class="create-game-button" onclick={on_mode_click.reform(|_| GameMode::AIvsAI)}>{ "AI vs AI" }</button>
                 <button class="create-game-button" onclick={on_mode_click.reform(|_| GameMode::Sandbox)}>{ "Sandbox mode" }</button>
+                <button class="create-game-button" onclick={on_mode_click.reform(|_| GameMode::Puzzle)}>{ "Puzzles" }</button>
+                <button class="create-game-button" onclick={on_mode_click.reform(|_| GameMode::Bughouse)}>{ "Bughouse (4 players)" }</button>
             </div>
             {
                 if let Some(mode) = (*selected_mode).clone() {
-                    if matches!(mode, GameMode::PlayerVsAI | GameMode::AIvsAI) {
+                    if mode == GameMode::AIvsAI {
+                        html! {
+                            <div class="create-game-difficulty">
+                                <p>{ "Select the engine of each side:" }</p>
+                                { white_select }
+                                { black_select }
+                                { book_toggle }
+                                <button class="create-game-button" onclick={on_start_ai_game}>{ "Start" }</button>
+                            </div>
+                        }
+                    } else if mode == GameMode::Bughouse {
+                        html! {
+                            <div class="create-game-difficulty">
+                                <p>{ "Seats played by the AI:" }</p>
+                                { seat_toggles }
+                                <button class="create-game-button" onclick={on_start_bughouse}>{ "Create" }</button>
+                            </div>
+                        }
+                    } else if mode == GameMode::PlayerVsAI {
                         html! {
                             <div class="create-game-difficulty">
                                 <p>{ "Select difficulty:" }</p>
@@ -1,9 +1,15 @@
+use crate::app::pages::chat::Chat;
 use crate::app::{state::ServerState, ServerAction};
 use game_lib::{
-    messages::ClientMessage,
+    automation::ai::{AiProfile, Difficulty},
+    automation::mate::MateOutcome,
+    bughouse::{ClockState, SEATS},
+    crazyhouse::{piece_letter, DROPPABLE},
+    messages::{ClientMessage, SandboxOpponent},
     piece::Color,
     position::Position,
     sharedenums::{GameMode, PlayerRole, RoomStatus},
+    variant::{Variant, CHECKS_TO_WIN},
 };
 use std::rc::Rc;
 use uuid::Uuid;
@@ -21,8 +27,169 @@ pub fn game(props: &GameProps) -> Html {
     let ctx = use_context::<crate::ws::WsContext>().expect("missing WsContext");
     let selected_piece = use_state(|| None as Option<String>);
     let selected_square = use_state(|| None as Option<Position>);
+    // Lettre de la pièce de la poche à parachuter ('N', 'P'...)
+    let selected_drop = use_state(|| None as Option<char>);
     let board_theme = use_state(|| "blue-theme".to_string());
 
+    let on_rematch = {
+        let ctx = ctx.clone();
+        Callback::from(move |_| ctx.send(ClientMessage::OfferRematch))
+    };
+    let on_decline_rematch = {
+        let ctx = ctx.clone();
+        Callback::from(move |_| ctx.send(ClientMessage::DeclineRematch))
+    };
+    let mate_depth = use_state(|| 2u8);
+    let on_solve_mate = {
+        let ctx = ctx.clone();
+        let mate_depth = mate_depth.clone();
+        let server_state = server_state.clone();
+        Callback::from(move |_| {
+            server_state.dispatch(ServerAction::SetMateSolution(None));
+            ctx.send(ClientMessage::SolveMate { n: *mate_depth });
+        })
+    };
+    let on_mate_depth = {
+        let mate_depth = mate_depth.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            if let Ok(n) = input.value().parse() {
+                mate_depth.set(n);
+            }
+        })
+    };
+    let mate_result = match &server_state.mate_solution {
+        Some((_, MateOutcome::Found { lines })) => html! {
+            <ul class="mate-lines">
+                { for lines.iter().map(|l| html! {
+                    <li>{ format!("#{}: {}", l.moves_to_mate, l.line.iter().map(|m| m.replace("->", "")).collect::<Vec<_>>().join(" ")) }</li>
+                }) }
+            </ul>
+        },
+        Some((n, MateOutcome::NoMate)) => html! { <p>{ format!("No mate in {}.", n) }</p> },
+        Some((_, MateOutcome::Inconclusive { nodes })) => {
+            html! { <p>{ format!("Search stopped after {} nodes.", nodes) }</p> }
+        }
+        None => html! {},
+    };
+    let analysis = use_state(|| false);
+    let on_toggle_analysis = {
+        let ctx = ctx.clone();
+        let analysis = analysis.clone();
+        let server_state = server_state.clone();
+        Callback::from(move |_| {
+            let enabled = !*analysis;
+            if !enabled {
+                server_state.dispatch(ServerAction::SetEvaluation(None));
+            }
+            analysis.set(enabled);
+            ctx.send(ClientMessage::SetAnalysis { enabled });
+        })
+    };
+    // Détail de l'évaluation, un terme par ligne
+    let evaluation = match &server_state.evaluation {
+        Some(breakdown) => html! {
+            <table class="eval-breakdown">
+                <tr><th>{ "Term" }</th><th>{ "White" }</th><th>{ "Black" }</th><th>{ "Diff" }</th></tr>
+                { for breakdown.terms.iter().map(|t| html! {
+                    <tr>
+                        <td>{ format!("{:?}", t.term) }</td>
+                        <td>{ t.white }</td>
+                        <td>{ t.black }</td>
+                        <td>{ format!("{:+}", t.white - t.black) }</td>
+                    </tr>
+                }) }
+                <tr class="eval-total">
+                    <td>{ "Total" }</td><td></td><td></td>
+                    <td>{ format!("{:+.2}", breakdown.total() as f64 / 100.0) }</td>
+                </tr>
+            </table>
+        },
+        None => html! {},
+    };
+    // Jouer la position du bac à sable contre l'IA ou un ami
+    let play_side = use_state(|| Color::White);
+    let play_opponent = use_state(|| "easy".to_string());
+    let on_play_side = {
+        let play_side = play_side.clone();
+        Callback::from(move |e: Event| {
+            let select: web_sys::HtmlSelectElement = e.target_unchecked_into();
+            play_side.set(if select.value() == "black" {
+                Color::Black
+            } else {
+                Color::White
+            });
+        })
+    };
+    let on_play_opponent = {
+        let play_opponent = play_opponent.clone();
+        Callback::from(move |e: Event| {
+            let select: web_sys::HtmlSelectElement = e.target_unchecked_into();
+            play_opponent.set(select.value());
+        })
+    };
+    let on_play_position = {
+        let ctx = ctx.clone();
+        let play_side = play_side.clone();
+        let play_opponent = play_opponent.clone();
+        Callback::from(move |_| {
+            let opponent = match play_opponent.as_str() {
+                "human" => SandboxOpponent::Human,
+                level => SandboxOpponent::Ai {
+                    profile: AiProfile::builtin(match level {
+                        "medium" => Difficulty::Medium,
+                        "hard" => Difficulty::Hard,
+                        _ => Difficulty::Easy,
+                    }),
+                },
+            };
+            ctx.send(ClientMessage::PlaySandboxPosition {
+                side: *play_side,
+                opponent,
+            });
+        })
+    };
+    let on_next_puzzle = {
+        let ctx = ctx.clone();
+        Callback::from(move |_| ctx.send(ClientMessage::NextPuzzle))
+    };
+    let puzzle_status = match (&server_state.puzzle_result, server_state.puzzle_feedback) {
+        (Some((solved, solution, rating_before)), _) => {
+            let after = server_state
+                .puzzle_stats
+                .as_ref()
+                .map_or(*rating_before, |s| s.rating);
+            html! {
+                <>
+                    <p>{ if *solved { "Puzzle solved!" } else { "Puzzle failed." } }</p>
+                    <p>{ format!("Rating: {:.0} → {:.0} ({:+.0})", rating_before, after, after - rating_before) }</p>
+                    <p>{ format!("Solution: {}", solution.iter().map(|m| m.replace("->", "")).collect::<Vec<_>>().join(" ")) }</p>
+                </>
+            }
+        }
+        (None, Some(true)) => html! { <p>{ "Best move! Keep going." }</p> },
+        _ => {
+            let side = server_state
+                .puzzle
+                .as_ref()
+                .map_or("...".to_string(), |p| format!("{:?}", p.color));
+            html! { <p>{ format!("Find the best move for {}.", side) }</p> }
+        }
+    };
+    let seated = matches!(server_state.role, Some(PlayerRole::White | PlayerRole::Black));
+
+    let on_click_invite = {
+        let ctx = ctx.clone();
+        Callback::from(move |_| ctx.send(ClientMessage::CreateInvite))
+    };
+    // Lien à partager: https://<hôte>/join/<code>
+    let invite_link = server_state.invite_code.as_ref().map(|code| {
+        let origin = web_sys::window()
+            .and_then(|w| w.location().origin().ok())
+            .unwrap_or_default();
+        format!("{}/join/{}", origin, code)
+    });
+
     let on_click_pause = {
         let ctx = ctx.clone();
         Callback::from(move |_| {
@@ -91,11 +258,178 @@ pub fn game(props: &GameProps) -> Html {
         .as_ref()
         .map(|r| html! { <p>{ format!("Game Over: {}", r) }</p> });
 
+    // Variant de la salle, avec les échecs donnés en Three-check
+    let variant_display = match server_state.variant {
+        Some((variant, state)) if variant != Variant::Standard => html! {
+            <>
+                <p><strong>{ "Variant: " }</strong>{ variant.name() }</p>
+                {
+                    if variant == Variant::ThreeCheck {
+                        html! {
+                            <p><strong>{ "Checks: " }</strong>{ format!(
+                                "White {}/{}, Black {}/{}",
+                                state.white_checks, CHECKS_TO_WIN, state.black_checks, CHECKS_TO_WIN
+                            ) }</p>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </>
+        },
+        _ => html! {},
+    };
+
+    // Poches de la Crazyhouse: le joueur choisit dans la sienne la pièce à poser
+    let pocket_display = match server_state.pockets {
+        Some(pockets) => {
+            let my_color = match server_state.role {
+                Some(PlayerRole::White) => Some(Color::White),
+                Some(PlayerRole::Black) => Some(Color::Black),
+                _ => None,
+            };
+            let row = |color: Color| {
+                let pocket = *pockets.of(color);
+                let prefix = if color == Color::White { "w" } else { "b" };
+                html! {
+                    <div class="pocket">
+                        <span class="pocket-label">{ format!("{:?}: ", color) }</span>
+                        { for DROPPABLE.iter().filter(|&&kind| pocket.count(kind) > 0).map(|&kind| {
+                            let letter = piece_letter(kind);
+                            let code = format!("{}{}", prefix, letter.to_ascii_lowercase());
+                            let mine = my_color == Some(color);
+                            let class = if *selected_drop == Some(letter) && mine {
+                                "sandbox-piece selected"
+                            } else {
+                                "sandbox-piece"
+                            };
+                            let onclick = {
+                                let selected_drop = selected_drop.clone();
+                                Callback::from(move |_| {
+                                    if mine {
+                                        selected_drop.set(Some(letter));
+                                    }
+                                })
+                            };
+                            html! {
+                                <div {class} {onclick}>
+                                    { get_piece_emoji(&code) }
+                                    <span class="pocket-count">{ pocket.count(kind) }</span>
+                                </div>
+                            }
+                        }) }
+                    </div>
+                }
+            };
+            html! {
+                <div class="pockets">
+                    { row(Color::Black) }
+                    { row(Color::White) }
+                </div>
+            }
+        }
+        None => html! {},
+    };
+
+    // Bughouse: places des quatre joueurs, pendule et plateau du partenaire
+    let bughouse_display = if server_state.gamemod == Some(GameMode::Bughouse) {
+        let waiting = server_state.room_status != Some(RoomStatus::Running)
+            && server_state.game_over.is_none();
+        let seats = SEATS.iter().map(|&seat| {
+            let taken = server_state.seats.iter().find(|info| info.seat == seat);
+            let label = format!("Board {:?}, {:?} (team {:?}): ", seat.board, seat.color, seat.team());
+            let mine = server_state.seat == Some(seat);
+            match taken {
+                Some(info) => html! {
+                    <p>
+                        <strong>{ label }</strong>
+                        { &info.name }
+                        { if info.is_ai { " (AI)" } else if mine { " (you)" } else { "" } }
+                    </p>
+                },
+                None if waiting => {
+                    let ctx = ctx.clone();
+                    html! {
+                        <p>
+                            <strong>{ label }</strong>
+                            <button class="chat-action" onclick={Callback::from(move |_| ctx.send(ClientMessage::TakeSeat { seat }))}>{ "Take seat" }</button>
+                        </p>
+                    }
+                }
+                None => html! { <p><strong>{ label }</strong>{ "empty" }</p> },
+            }
+        });
+        let clock = |clock: &Option<ClockState>| match clock {
+            Some(clock) => {
+                let fmt = |ms: u64| format!("{}:{:02}", ms / 60_000, ms / 1000 % 60);
+                html! {
+                    <p>
+                        <strong>{ "Clock: " }</strong>
+                        { format!("White {} / Black {}", fmt(clock.white_ms), fmt(clock.black_ms)) }
+                    </p>
+                }
+            }
+            None => html! {},
+        };
+        let partner = match &server_state.partner_board {
+            Some(view) => {
+                let pocket_row = |color: Color| {
+                    let pocket = *view.pockets.of(color);
+                    let prefix = if color == Color::White { "w" } else { "b" };
+                    html! {
+                        <div class="pocket">
+                            <span class="pocket-label">{ format!("{:?}: ", color) }</span>
+                            { for DROPPABLE.iter().filter(|&&kind| pocket.count(kind) > 0).map(|&kind| {
+                                let code = format!("{}{}", prefix, piece_letter(kind).to_ascii_lowercase());
+                                html! {
+                                    <div class="sandbox-piece">
+                                        { get_piece_emoji(&code) }
+                                        <span class="pocket-count">{ pocket.count(kind) }</span>
+                                    </div>
+                                }
+                            }) }
+                        </div>
+                    }
+                };
+                html! {
+                    <div class="partner-board">
+                        <p><strong>{ "Other board, turn: " }</strong>{ format!("{:?}", view.turn) }</p>
+                        { clock(&view.clock) }
+                        <div class={classes!("chess-board", "small", (*board_theme).clone())}>
+                            { for view.board.iter().enumerate().map(|(r, row)| html! {
+                                { for row.iter().enumerate().map(|(c, cell)| {
+                                    let class = if (r + c) % 2 == 0 { "chess-cell light" } else { "chess-cell dark" };
+                                    let piece = cell.as_ref().map_or("", |s| s.trim_end_matches('~'));
+                                    html! { <div {class}>{ get_piece_emoji(piece) }</div> }
+                                }) }
+                            }) }
+                        </div>
+                        <div class="pockets">
+                            { pocket_row(Color::Black) }
+                            { pocket_row(Color::White) }
+                        </div>
+                    </div>
+                }
+            }
+            None => html! {},
+        };
+        html! {
+            <div class="bughouse-info">
+                { for seats }
+                { clock(&server_state.clock) }
+                { partner }
+            </div>
+        }
+    } else {
+        html! {}
+    };
+
     // Legal moves and piece selection
     let legal_moves = server_state.legals_moves.clone();
     let clicked = {
         let selected_square = selected_square.clone();
         let selected_piece = selected_piece.clone();
+        let selected_drop = selected_drop.clone();
         let legal_moves = legal_moves.clone();
         let server_state = server_state.clone();
         Callback::from(move |pos: (u8, u8)| {
@@ -109,6 +443,18 @@ pub fn game(props: &GameProps) -> Html {
                 .as_ref()
                 .map_or(true, |s| s.is_empty());
 
+            // Crazyhouse: une pièce de la poche est posée sur une case vide
+            if let Some(piece) = *selected_drop {
+                if is_empty {
+                    ctx.send(ClientMessage::DropPiece {
+                        piece,
+                        square: pos.to_algebraic(),
+                    });
+                }
+                selected_drop.set(None);
+                return;
+            }
+
             if is_empty && selected_square.is_none() && selected_piece.is_none() {
                 return; // Ignore clicks on empty squares if nothing is selected
             }
@@ -156,6 +502,32 @@ pub fn game(props: &GameProps) -> Html {
                         <div class="game-over-message">
                             <h3>{ "Game Over" }</h3>
                             <p>{ game_over_message }</p>
+                            {
+                                match server_state.variant_win {
+                                    Some(win) => html! { <p class="variant-win">{ format!("Won by variant rule: {}", win.description()) }</p> },
+                                    None => html! {},
+                                }
+                            }
+                            { for server_state.rating_changes.iter()
+                                .filter(|c| server_state.account.as_ref().is_some_and(|a| a.id == c.account_id))
+                                .map(|c| html! {
+                                    <p>{ format!("Rating ({:?}): {:.0} → {:.0} ({:+.0})", c.category, c.before, c.after, c.after - c.before) }</p>
+                                }) }
+                            {
+                                if !seated {
+                                    html! {}
+                                } else if let Some(by) = &server_state.rematch_offer {
+                                    html! {
+                                        <>
+                                            <p>{ format!("{} offers a rematch", by) }</p>
+                                            <button class="game-button" onclick={on_rematch.clone()}>{ "Accept" }</button>
+                                            <button class="game-button" onclick={on_decline_rematch.clone()}>{ "Decline" }</button>
+                                        </>
+                                    }
+                                } else {
+                                    html! { <button class="game-button" onclick={on_rematch.clone()}>{ "Rematch" }</button> }
+                                }
+                            }
                             <button class="game-button" onclick={on_click_quit.clone()}>{ "Quit Game" }</button>
                         </div>
                     }
@@ -242,6 +614,22 @@ pub fn game(props: &GameProps) -> Html {
                         }
                     }
                     <button class="game-button" onclick={on_click_quit}>{ "Quit Game" }</button>
+                    {
+                        if server_state.gamemod == Some(GameMode::PlayerVsPlayer)
+                            && server_state.room_status == Some(RoomStatus::WaitingPlayers)
+                        {
+                            match &invite_link {
+                                Some(link) => html! {
+                                    <p><strong>{ "Invite link: " }</strong><a href={link.clone()}>{ link.clone() }</a></p>
+                                },
+                                None => html! {
+                                    <button class="game-button" onclick={on_click_invite}>{ "Invite a friend" }</button>
+                                },
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
 
 
                     // Informations de la salle
@@ -249,12 +637,23 @@ pub fn game(props: &GameProps) -> Html {
                         <p><strong>{ "Room ID: " }</strong>{ room_id_display.clone() }</p>
                         <p><strong>{ "Room Status: " }</strong>{ room_status_display.clone() }</p>
                         <p><strong>{ "Role: " }</strong>{ role_display.clone() }</p>
+                        <p><strong>{ "Spectators: " }</strong>{ server_state.spectators }</p>
+                        {
+                            if server_state.role == Some(PlayerRole::Spectator) && server_state.spectator_delay > 0 {
+                                html! { <p>{ format!("Delayed by {} moves", server_state.spectator_delay) }</p> }
+                            } else {
+                                html! {}
+                            }
+                        }
                         {
                             if server_state.role != Some(PlayerRole::Spectator) && server_state.gamemod != Some(GameMode::AIvsAI){
                             html!(<p><strong>{ "Ready: " }</strong>{ ready_display }</p>)
                         }else{html!()}}
                         <p><strong>{ "Turn: " }</strong>{ turn_display }</p>
                         <p><strong>{ "Turn: " }</strong>{ turn_color }</p>
+                        { variant_display }
+                        { pocket_display }
+                        { bughouse_display }
 
                     </div>
                 </div>
@@ -266,7 +665,10 @@ pub fn game(props: &GameProps) -> Html {
                             let pos = Position { row: r, col: c };
                             let is_legal = legal_moves.contains(&pos.to_algebraic());
                             let is_selected = *selected_square == Some(pos);
-                            let piece = cell.as_ref().map_or("", |s| s.as_str());
+                            let cell = cell.as_ref().map_or("", |s| s.as_str());
+                            // "wq~": dame issue d'une promotion, qui redevient un pion une fois prise
+                            let promoted = cell.ends_with('~');
+                            let piece = cell.trim_end_matches('~');
 
                             let mut class = if (r + c) % 2 == 0 { "chess-cell light" } else { "chess-cell dark" }.to_string();
                             if (piece == "wk" && server_state.incheck == Some(Color::White) )|| (piece == "bk" && server_state.incheck == Some(Color::Black) ) { //&& incheck
@@ -279,6 +681,9 @@ pub fn game(props: &GameProps) -> Html {
                             if is_selected {
                                 class += " selected";
                             }
+                            if promoted {
+                                class += " promoted";
+                            }
 
 
                             html! {
@@ -320,18 +725,82 @@ pub fn game(props: &GameProps) -> Html {
                                     let sp = selected_piece.clone();
                                     move |_| sp.set(Some("".to_string()))
                                 })}>{ "Place Empty Piece" }</button>
+                                <h4>{ "Mate search" }</h4>
+                                <div class="mate-search">
+                                    <input type="number" min="1" max="8" value={mate_depth.to_string()} oninput={on_mate_depth} />
+                                    <button class="game-button" onclick={on_solve_mate}>{ "Find mate" }</button>
+                                </div>
+                                { mate_result }
+                                <h4>{ "Analysis" }</h4>
+                                <button class="game-button" onclick={on_toggle_analysis}>
+                                    { if *analysis { "Hide evaluation" } else { "Explain evaluation" } }
+                                </button>
+                                { evaluation }
+                                <h4>{ "Play this position" }</h4>
+                                <div class="mate-search">
+                                    <select onchange={on_play_side}>
+                                        <option value="white" selected={*play_side == Color::White}>{ "As White" }</option>
+                                        <option value="black" selected={*play_side == Color::Black}>{ "As Black" }</option>
+                                    </select>
+                                    <select onchange={on_play_opponent}>
+                                        <option value="easy" selected={*play_opponent == "easy"}>{ "AI (Easy)" }</option>
+                                        <option value="medium" selected={*play_opponent == "medium"}>{ "AI (Medium)" }</option>
+                                        <option value="hard" selected={*play_opponent == "hard"}>{ "AI (Hard)" }</option>
+                                        <option value="human" selected={*play_opponent == "human"}>{ "A friend" }</option>
+                                    </select>
+                                    <button class="game-button" onclick={on_play_position}>{ "Play" }</button>
+                                </div>
                             </div>
                         }
                     }
                      else {html! {}}
                 }
+                {
+                    if server_state.gamemod == Some(GameMode::Puzzle) {
+                        html! {
+                            <div class="puzzle-container">
+                                <h4>{ "Puzzle" }</h4>
+                                {
+                                    match &server_state.puzzle {
+                                        Some(puzzle) => html! {
+                                            <>
+                                                <p><strong>{ "Puzzle rating: " }</strong>{ puzzle.rating }</p>
+                                                <p class="puzzle-themes">{ puzzle.themes.join(", ") }</p>
+                                            </>
+                                        },
+                                        None => html! { <p>{ "Loading puzzle..." }</p> },
+                                    }
+                                }
+                                { puzzle_status }
+                                {
+                                    match &server_state.puzzle_stats {
+                                        Some(stats) => html! {
+                                            <div class="puzzle-stats">
+                                                <p><strong>{ "Your rating: " }</strong>{ format!("{:.0}", stats.rating) }</p>
+                                                <p><strong>{ "Streak: " }</strong>{ format!("{} (best {})", stats.streak, stats.best_streak) }</p>
+                                                <p><strong>{ "Solved: " }</strong>{ format!("{}/{}", stats.solved, stats.attempts) }</p>
+                                            </div>
+                                        },
+                                        None => html! {},
+                                    }
+                                }
+                                <button class="game-button" onclick={on_next_puzzle}>
+                                    { if server_state.puzzle_result.is_some() { "Next puzzle" } else { "Skip puzzle" } }
+                                </button>
+                            </div>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+                <Chat />
             </div>
         </div>
     }
 }
 
 // Helper function to get chess piece emojis
-fn get_piece_emoji(piece: &str) -> &str {
+pub fn get_piece_emoji(piece: &str) -> &str {
     match piece {
         "wp" => "♙", // White Pawn
         "bp" => "♟", // Black Pawn
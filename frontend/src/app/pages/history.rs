@@ -0,0 +1,87 @@
+use crate::app::state::ServerState;
+use crate::routes::Route;
+use crate::ws::WsContext;
+use game_lib::history::{pgn_date, GameQuery};
+use game_lib::messages::ClientMessage;
+use game_lib::sharedenums::GameMode;
+use yew::prelude::*;
+use yew_router::hooks::use_navigator;
+
+#[function_component(History)]
+pub fn history() -> Html {
+    let server_state =
+        use_context::<UseReducerHandle<ServerState>>().expect("ServerState context is missing");
+    let ctx = use_context::<WsContext>().expect("WsContext missing");
+    let navigator = use_navigator().expect("navigator not available");
+    let mode_filter = use_state(|| None as Option<GameMode>);
+
+    // Recharge la liste à chaque changement de filtre
+    {
+        let ctx = ctx.clone();
+        use_effect_with_deps(
+            move |mode: &Option<GameMode>| {
+                ctx.send(ClientMessage::ListGames {
+                    query: GameQuery {
+                        mode: mode.clone(),
+                        ..GameQuery::default()
+                    },
+                });
+                || ()
+            },
+            (*mode_filter).clone(),
+        );
+    }
+
+    let on_filter = {
+        let mode_filter = mode_filter.clone();
+        Callback::from(move |mode: Option<GameMode>| mode_filter.set(mode))
+    };
+
+    html! {
+        <div class="history-container">
+            <h2 class="history-title">{ "Game history" }</h2>
+            <div class="history-filters">
+                <button class="history-button" onclick={on_filter.reform(|_| None)}>{ "All" }</button>
+                <button class="history-button" onclick={on_filter.reform(|_| Some(GameMode::PlayerVsPlayer))}>{ "Player vs Player" }</button>
+                <button class="history-button" onclick={on_filter.reform(|_| Some(GameMode::PlayerVsAI))}>{ "Player vs AI" }</button>
+                <button class="history-button" onclick={on_filter.reform(|_| Some(GameMode::AIvsAI))}>{ "AI vs AI" }</button>
+            </div>
+            <table class="history-table">
+                <tr>
+                    <th>{ "Date" }</th>
+                    <th>{ "Mode" }</th>
+                    <th>{ "White" }</th>
+                    <th>{ "Black" }</th>
+                    <th>{ "Result" }</th>
+                    <th>{ "Moves" }</th>
+                    <th></th>
+                </tr>
+                { for server_state.games.iter().map(|game| {
+                    let id = game.id;
+                    let navigator = navigator.clone();
+                    html! {
+                        <tr>
+                            <td>{ pgn_date(game.ended_at) }</td>
+                            <td>{ format!("{:?}", game.mode) }</td>
+                            <td>{ &game.white.name }</td>
+                            <td>{ &game.black.name }</td>
+                            <td>{ game.result.to_pgn() }</td>
+                            <td>{ game.moves.len() }</td>
+                            <td>
+                                <button class="history-button" onclick={Callback::from(move |_| navigator.push(&Route::Replay { id }))}>{ "Replay" }</button>
+                                <a class="history-button" href={format!("/api/games/{}/pgn", id)} download="">{ "PGN" }</a>
+                            </td>
+                        </tr>
+                    }
+                })}
+            </table>
+            {
+                if server_state.games.is_empty() {
+                    html! { <p>{ "No archived game yet." }</p> }
+                } else {
+                    html! {}
+                }
+            }
+        </div>
+    }
+}
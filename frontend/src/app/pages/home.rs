@@ -1,7 +1,9 @@
+use crate::app::state::ServerState;
 use crate::routes::Route;
 use crate::ws::WsContext;
 use game_lib::messages::{ClientMessage, ServerMessage};
-use game_lib::sharedenums::GameMode;
+use game_lib::sharedenums::{GameMode, RoomStatus, TimeControl};
+use game_lib::variant::Variant;
 use uuid::Uuid;
 use yew::prelude::*;
 
@@ -12,9 +14,75 @@ pub struct HomeProps {
     pub join_error: Option<String>,
 }
 
+// Cadences proposées pour la recherche de partie: (minutes, incrément)
+const QUICK_PAIRINGS: [(u64, u64); 4] = [(3, 2), (5, 0), (10, 0), (15, 10)];
+const DEFAULT_RATING_RANGE: f64 = 200.0;
+
 #[function_component(Home)]
 pub fn home(props: &HomeProps) -> Html {
     let room_id = use_state(|| "".to_string());
+    let server_state =
+        use_context::<UseReducerHandle<ServerState>>().expect("ServerState context is missing");
+    let ctx = use_context::<WsContext>().expect("WsContext missing");
+    let rated = use_state(|| false);
+    let logged_in = server_state.account.is_some();
+
+    let on_seek = {
+        let ctx = ctx.clone();
+        let rated = rated.clone();
+        Callback::from(move |(minutes, increment): (u64, u64)| {
+            ctx.send(ClientMessage::JoinQueue {
+                time_control: Some(TimeControl {
+                    initial: minutes * 60,
+                    increment,
+                }),
+                rated: *rated && logged_in,
+                rating_range: DEFAULT_RATING_RANGE,
+            });
+        })
+    };
+    let on_cancel_seek = {
+        let ctx = ctx.clone();
+        Callback::from(move |_| ctx.send(ClientMessage::LeaveQueue))
+    };
+
+    // Abonnement au lobby tant que la page est affichée
+    {
+        let ctx = ctx.clone();
+        use_effect_with_deps(
+            move |_| {
+                ctx.send(ClientMessage::SubscribeLobby);
+                move || ctx.send(ClientMessage::UnsubscribeLobby)
+            },
+            (),
+        );
+    }
+
+    let lobby = server_state.lobby.iter().map(|room| {
+        let cb = props.on_join_room.clone();
+        let id = room.room_id;
+        let label = if room.status == RoomStatus::Running { "Regarder" } else { "Rejoindre" };
+        html! {
+            <tr>
+                <td>{ if room.variant == Variant::Standard {
+                    format!("{:?}", room.mode)
+                } else {
+                    format!("{:?} ({})", room.mode, room.variant.name())
+                } }</td>
+                <td>{ room.time_control.map_or("-".to_string(), |tc| format!("{}+{}", tc.initial / 60, tc.increment)) }</td>
+                <td>{ match (room.rated, room.handicap) {
+                    (true, _) => "Classée",
+                    (false, Some(_)) => "Handicap",
+                    (false, None) => "Amicale",
+                } }</td>
+                <td>{ &room.host }</td>
+                <td>{ room.host_rating.map_or("-".to_string(), |r| format!("{:.0}", r)) }</td>
+                <td>{ room.players }</td>
+                <td>{ room.spectators }</td>
+                <td><button class="home-button" onclick={Callback::from(move |_| cb.emit(id))}>{ label }</button></td>
+            </tr>
+        }
+    });
 
     let oninput = {
         let room_id = room_id.clone();
@@ -46,6 +114,42 @@ pub fn home(props: &HomeProps) -> Html {
     html! {
         <div class="home-container">
             <button class="home-button" onclick={on_click_create}>{ "Créer une room" }</button>
+            {
+                if server_state.in_queue {
+                    let label = server_state.queued.map_or("Recherche d'un adversaire...".to_string(), |tc| {
+                        format!("Recherche d'un adversaire ({}+{})...", tc.initial / 60, tc.increment)
+                    });
+                    html! {
+                        <div class="home-join-container">
+                            <p>{ label }</p>
+                            <button class="home-button" onclick={on_cancel_seek}>{ "Annuler" }</button>
+                        </div>
+                    }
+                } else {
+                    let rated_toggle = if logged_in {
+                        let rated = rated.clone();
+                        html! {
+                            <label>
+                                <input type="checkbox" checked={*rated}
+                                    onchange={Callback::from(move |_| rated.set(!*rated))} />
+                                { " Classée" }
+                            </label>
+                        }
+                    } else {
+                        html! {}
+                    };
+                    html! {
+                        <div class="home-join-container">
+                            { for QUICK_PAIRINGS.iter().map(|&(minutes, increment)| html! {
+                                <button class="home-button" onclick={on_seek.reform(move |_| (minutes, increment))}>
+                                    { format!("{}+{}", minutes, increment) }
+                                </button>
+                            }) }
+                            { rated_toggle }
+                        </div>
+                    }
+                }
+            }
             <div class="home-join-container">
                 <input
                     class="home-input"
@@ -56,6 +160,26 @@ pub fn home(props: &HomeProps) -> Html {
                 />
                 <button class="home-button" onclick={on_click_join}>{ "Rejoindre la partie" }</button>
             </div>
+            <table class="history-table">
+                <tr>
+                    <th>{ "Mode" }</th>
+                    <th>{ "Cadence" }</th>
+                    <th>{ "Type" }</th>
+                    <th>{ "Hôte" }</th>
+                    <th>{ "Elo" }</th>
+                    <th>{ "Joueurs" }</th>
+                    <th>{ "Spectateurs" }</th>
+                    <th></th>
+                </tr>
+                { for lobby }
+            </table>
+            {
+                if server_state.lobby.is_empty() {
+                    html! { <p>{ "Aucune partie publique pour le moment." }</p> }
+                } else {
+                    html! {}
+                }
+            }
             {
                 if let Some(err) = &props.join_error {
                     html! { <p class="home-error">{ err }</p> }
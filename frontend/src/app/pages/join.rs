@@ -0,0 +1,35 @@
+use crate::routes::Route;
+use crate::ws::WsContext;
+use game_lib::messages::ClientMessage;
+use yew::prelude::*;
+use yew_router::hooks::use_navigator;
+
+#[derive(Properties, PartialEq)]
+pub struct JoinProps {
+    pub code: String,
+}
+
+// Page ouverte par un lien d'invitation: rejoint la room dès l'affichage
+#[function_component(Join)]
+pub fn join(props: &JoinProps) -> Html {
+    let navigator = use_navigator().expect("navigator not available");
+    let ctx = use_context::<WsContext>().expect("WsContext missing");
+
+    {
+        let ctx = ctx.clone();
+        use_effect_with_deps(
+            move |code: &String| {
+                ctx.send(ClientMessage::JoinByCode { code: code.clone() });
+                || ()
+            },
+            props.code.clone(),
+        );
+    }
+
+    html! {
+        <div class="home-container">
+            <p>{ format!("Connexion à la partie {}...", props.code) }</p>
+            <button class="home-button" onclick={Callback::from(move |_| navigator.push(&Route::Home))}>{ "Retour" }</button>
+        </div>
+    }
+}
@@ -0,0 +1,77 @@
+use crate::app::state::ServerState;
+use crate::ws::WsContext;
+use game_lib::messages::ClientMessage;
+use yew::prelude::*;
+
+#[function_component(Login)]
+pub fn login() -> Html {
+    let server_state =
+        use_context::<UseReducerHandle<ServerState>>().expect("ServerState context is missing");
+    let ctx = use_context::<WsContext>().expect("WsContext missing");
+    let username = use_state(|| "".to_string());
+    let password = use_state(|| "".to_string());
+    let display_name = use_state(|| "".to_string());
+
+    let bind = |field: &UseStateHandle<String>| {
+        let field = field.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            field.set(input.value());
+        })
+    };
+
+    let on_login = {
+        let ctx = ctx.clone();
+        let username = username.clone();
+        let password = password.clone();
+        Callback::from(move |_| {
+            ctx.send(ClientMessage::Login {
+                username: (*username).clone(),
+                password: (*password).clone(),
+            });
+        })
+    };
+
+    let on_register = {
+        let ctx = ctx.clone();
+        let username = username.clone();
+        let password = password.clone();
+        let display_name = display_name.clone();
+        Callback::from(move |_| {
+            ctx.send(ClientMessage::Register {
+                username: (*username).clone(),
+                password: (*password).clone(),
+                display_name: Some((*display_name).clone()).filter(|n| !n.is_empty()),
+            });
+        })
+    };
+
+    let on_logout = {
+        let ctx = ctx.clone();
+        Callback::from(move |_| ctx.send(ClientMessage::Logout))
+    };
+
+    if let Some(account) = &server_state.account {
+        return html! {
+            <div class="home-container">
+                <p>{ format!("Connecté en tant que {}", account.display_name) }</p>
+                <button class="home-button" onclick={on_logout}>{ "Se déconnecter" }</button>
+            </div>
+        };
+    }
+
+    html! {
+        <div class="home-container">
+            <input class="home-input" type="text" placeholder="Nom d'utilisateur"
+                value={(*username).clone()} oninput={bind(&username)} />
+            <input class="home-input" type="password" placeholder="Mot de passe"
+                value={(*password).clone()} oninput={bind(&password)} />
+            <input class="home-input" type="text" placeholder="Nom affiché (inscription)"
+                value={(*display_name).clone()} oninput={bind(&display_name)} />
+            <div class="home-join-container">
+                <button class="home-button" onclick={on_login}>{ "Se connecter" }</button>
+                <button class="home-button" onclick={on_register}>{ "Créer un compte" }</button>
+            </div>
+        </div>
+    }
+}
@@ -1,7 +1,12 @@
+pub mod chat;
 pub mod create_game;
 pub mod download;
 pub mod game;
+pub mod history;
 pub mod home;
 pub mod info;
+pub mod join;
+pub mod login;
 pub mod navbar;
 pub mod not_found;
+pub mod replay;
@@ -13,6 +13,12 @@ pub fn navbar() -> Html {
                 <Link<Route> to={Route::Info}>
                     <button class="navbar-button">{ "Info" }</button>
                 </Link<Route>>
+                <Link<Route> to={Route::History}>
+                    <button class="navbar-button">{ "History" }</button>
+                </Link<Route>>
+                <Link<Route> to={Route::Login}>
+                    <button class="navbar-button">{ "Account" }</button>
+                </Link<Route>>
                 <Link<Route> to={Route::Download}>
                     <button class="navbar-button">{ "Download" }</button>
                 </Link<Route>>
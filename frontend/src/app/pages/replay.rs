@@ -0,0 +1,127 @@
+use crate::app::pages::game::get_piece_emoji;
+use crate::app::state::ServerState;
+use crate::routes::Route;
+use crate::ws::WsContext;
+use game_lib::game::Game as ChessGame;
+use game_lib::messages::ClientMessage;
+use uuid::Uuid;
+use yew::prelude::*;
+use yew_router::hooks::use_navigator;
+
+#[derive(Properties, PartialEq)]
+pub struct ReplayProps {
+    pub id: Uuid,
+}
+
+#[function_component(Replay)]
+pub fn replay(props: &ReplayProps) -> Html {
+    let server_state =
+        use_context::<UseReducerHandle<ServerState>>().expect("ServerState context is missing");
+    let ctx = use_context::<WsContext>().expect("WsContext missing");
+    let navigator = use_navigator().expect("navigator not available");
+    let ply = use_state(|| 0usize);
+
+    {
+        let ctx = ctx.clone();
+        let ply = ply.clone();
+        use_effect_with_deps(
+            move |&game_id| {
+                ply.set(0);
+                ctx.send(ClientMessage::GetGame { game_id });
+                || ()
+            },
+            props.id,
+        );
+    }
+
+    let game = match server_state.replay_game.as_ref() {
+        Some(game) if game.id == props.id => game,
+        _ => return html! { <div class="history-container">{ "Loading..." }</div> },
+    };
+    let total = game.moves.len();
+
+    // Rejoue les coups depuis la position de départ jusqu'au demi-coup courant
+    let board = {
+        // Les compteurs du Three-check ne font pas partie de la FEN du moteur
+        let start = game
+            .start_position()
+            .and_then(|fen| game.variant.split_fen(&fen).ok());
+        let mut replayed = match start {
+            Some((fen, _)) => ChessGame::from_fen(&fen).unwrap_or_else(|_| ChessGame::init(false)),
+            None => ChessGame::init(false),
+        };
+        for mv in game.moves.iter().take(*ply) {
+            if let Err(e) = replayed.make_move_algebraic(mv) {
+                log::error!("Cannot replay move {}: {}", mv, e);
+                break;
+            }
+        }
+        replayed.board.export_display_board()
+    };
+
+    let go_to = {
+        let ply = ply.clone();
+        Callback::from(move |target: usize| ply.set(target.min(total)))
+    };
+    let on_jump = {
+        let go_to = go_to.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            if let Ok(target) = input.value().parse::<usize>() {
+                go_to.emit(target);
+            }
+        })
+    };
+    let current = *ply;
+
+    html! {
+        <div class="game-container">
+            <h2 class="game-title">{ "Replay" }</h2>
+            <div class="game-layout">
+                <div class="game-controls">
+                    <button class="game-button" onclick={Callback::from(move |_| navigator.push(&Route::History))}>{ "Back to history" }</button>
+                    <div class="game-info">
+                        <p><strong>{ "Mode: " }</strong>{ format!("{:?}", game.mode) }</p>
+                        <p><strong>{ "White: " }</strong>{ &game.white.name }</p>
+                        <p><strong>{ "Black: " }</strong>{ &game.black.name }</p>
+                        <p><strong>{ "Result: " }</strong>{ format!("{} ({})", game.result.to_pgn(), game.reason) }</p>
+                        <p><strong>{ "Move: " }</strong>{ format!("{} / {}", current, total) }</p>
+                    </div>
+                    <div class="replay-controls">
+                        <button class="game-button" onclick={go_to.reform(|_| 0)}>{ "|<" }</button>
+                        <button class="game-button" onclick={go_to.reform(move |_| current.saturating_sub(1))}>{ "<" }</button>
+                        <button class="game-button" onclick={go_to.reform(move |_| current + 1)}>{ ">" }</button>
+                        <button class="game-button" onclick={go_to.reform(move |_| total)}>{ ">|" }</button>
+                    </div>
+                    <input type="range" min="0" max={total.to_string()} value={current.to_string()} oninput={on_jump} />
+                    <a class="game-button" href={format!("/api/games/{}/pgn", game.id)} download="">{ "Download PGN" }</a>
+                </div>
+
+                <div class="chess-board blue-theme">
+                    { for board.iter().enumerate().map(|(r, row)| html! {
+                        { for row.iter().enumerate().map(|(c, cell)| {
+                            let piece = cell.as_ref().map_or("", |s| s.as_str());
+                            let class = if (r + c) % 2 == 0 { "chess-cell light" } else { "chess-cell dark" };
+                            html! { <div class={class}>{ get_piece_emoji(piece).to_string() }</div> }
+                        }) }
+                    }) }
+                </div>
+
+                <div class="replay-moves">
+                    { for game.moves.chunks(2).enumerate().map(|(i, pair)| {
+                        html! {
+                            <p>
+                                { format!("{}. ", i + 1) }
+                                { for pair.iter().enumerate().map(|(j, mv)| {
+                                    let target = i * 2 + j + 1;
+                                    let class = if target == current { "replay-move current" } else { "replay-move" };
+                                    html! { <span class={class} onclick={go_to.reform(move |_| target)}>{ format!("{} ", mv) }</span> }
+                                }) }
+                            </p>
+                        }
+                    }) }
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -1,5 +1,14 @@
+use game_lib::automation::eval::EvalBreakdown;
+use game_lib::automation::mate::MateOutcome;
+use game_lib::bughouse::{BoardId, ClockState, Seat};
+use game_lib::crazyhouse::Pockets;
+use game_lib::history::GameRecord;
+use game_lib::messages::{
+    AccountInfo, ChatEntry, PuzzleInfo, PuzzleStats, RatingChange, RoomSummary, SeatInfo,
+};
 use game_lib::piece::Color;
-use game_lib::sharedenums::{GameMode, PlayerRole, RoomStatus};
+use game_lib::sharedenums::{GameMode, PlayerRole, RoomStatus, TimeControl};
+use game_lib::variant::{Variant, VariantState, VariantWin};
 use serde::{Deserialize, Serialize};
 use std::rc::Rc;
 use uuid::Uuid;
@@ -7,6 +16,15 @@ use yew::prelude::*;
 
 use crate::routes::Route;
 
+/// Board of the partner in a Bughouse match.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct BughouseView {
+    pub board: Vec<Vec<Option<String>>>,
+    pub turn: Color,
+    pub pockets: Pockets,
+    pub clock: Option<ClockState>,
+}
+
 #[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct ServerState {
     pub host: bool,
@@ -23,9 +41,52 @@ pub struct ServerState {
     pub turn: Option<Color>,
     pub counter: usize,
     pub incheck: Option<Color>,
+    /// Pieces in hand of a Crazyhouse game.
+    pub pockets: Option<Pockets>,
     pub game_over: Option<String>,
+    /// Variant rule that ended the game, if any.
+    pub variant_win: Option<VariantWin>,
+    pub rating_changes: Vec<RatingChange>,
+    pub rematch_offer: Option<String>,
     pub paused: bool,
     pub ingame: bool,
+    pub invite_code: Option<String>,
+    //Sandbox mate search
+    pub mate_solution: Option<(u8, MateOutcome)>,
+    //Sandbox analysis
+    pub evaluation: Option<EvalBreakdown>,
+    //Variant of the room and its counters
+    pub variant: Option<(Variant, VariantState)>,
+    //Bughouse
+    pub seat: Option<Seat>,
+    pub seats: Vec<SeatInfo>,
+    pub partner_board: Option<BughouseView>,
+    /// Clock of the board shown in the main view.
+    pub clock: Option<ClockState>,
+    //Puzzles
+    pub puzzle: Option<PuzzleInfo>,
+    pub puzzle_stats: Option<PuzzleStats>,
+    /// Whether the last move of the solver was right.
+    pub puzzle_feedback: Option<bool>,
+    /// Solved or not, the solution and the rating before the attempt.
+    pub puzzle_result: Option<(bool, Vec<String>, f64)>,
+    //Chat
+    pub chat: Vec<ChatEntry>,
+    //Spectators
+    pub spectators: usize,
+    pub spectator_delay: usize,
+    //Account
+    pub account: Option<AccountInfo>,
+    //Lobby
+    pub lobby: Vec<RoomSummary>,
+    //External engines offered by the server
+    pub engines: Vec<String>,
+    //Matchmaking
+    pub queued: Option<TimeControl>,
+    pub in_queue: bool,
+    //History
+    pub games: Vec<GameRecord>,
+    pub replay_game: Option<GameRecord>,
     //Other
     pub info: Option<String>,
     pub error: Option<String>,
@@ -48,12 +109,38 @@ impl Default for ServerState {
             turn: None,
             counter: 0,
             incheck: None,
+            pockets: None,
             game_over: None,
+            variant_win: None,
+            rating_changes: Vec::new(),
+            rematch_offer: None,
             info: None,
             error: None,
             last_page: None,
             paused: false,
             ingame: false,
+            account: None,
+            invite_code: None,
+            mate_solution: None,
+            evaluation: None,
+            variant: None,
+            seat: None,
+            seats: Vec::new(),
+            partner_board: None,
+            clock: None,
+            puzzle: None,
+            puzzle_stats: None,
+            puzzle_feedback: None,
+            puzzle_result: None,
+            chat: Vec::new(),
+            spectators: 0,
+            spectator_delay: 0,
+            lobby: Vec::new(),
+            engines: Vec::new(),
+            queued: None,
+            in_queue: false,
+            games: Vec::new(),
+            replay_game: None,
         }
     }
 }
@@ -70,15 +157,19 @@ impl Reducible for ServerState {
                 turn,
                 counter,
                 incheck,
+                pockets,
             } => {
                 new_state.board = board;
+                new_state.pockets = pockets;
                 new_state.turn = Some(turn);
                 new_state.legals_moves = Vec::new();
                 new_state.counter = counter;
                 new_state.incheck = incheck;
             }
-            ServerAction::SetGameOver(result, room_status) => {
+            ServerAction::SetGameOver(result, room_status, rating_changes, variant_win) => {
                 new_state.game_over = Some(result);
+                new_state.variant_win = variant_win;
+                new_state.rating_changes = rating_changes;
                 new_state.room_status = Some(room_status);
             }
             ServerAction::SetInfo(msg) => {
@@ -104,6 +195,7 @@ impl Reducible for ServerState {
                 new_state.room_status = Some(room_status);
                 new_state.host = host;
                 new_state.ingame = true;
+                new_state.in_queue = false;
                 web_sys::console::log_1(
                     &format!(
                         "role: {:?}, gammemod: {:?}",
@@ -116,7 +208,143 @@ impl Reducible for ServerState {
                 new_state.legals_moves = mv;
             }
             ServerAction::SetQuit => {
+                // Quitter une partie ne déconnecte pas le compte
+                let account = new_state.account.take();
                 new_state = ServerState::default();
+                new_state.account = account;
+            }
+            ServerAction::SetAccount(account) => {
+                new_state.account = account;
+            }
+            ServerAction::SetRematchOffer(by) => {
+                new_state.rematch_offer = by;
+            }
+            ServerAction::ChangeMode(gamemod, role, room_status) => {
+                // Le bac à sable devient une partie depuis la même position
+                new_state.gamemod = Some(gamemod);
+                new_state.role = Some(role);
+                new_state.room_status = Some(room_status);
+                new_state.mate_solution = None;
+                new_state.evaluation = None;
+                new_state.legals_moves = Vec::new();
+            }
+            ServerAction::StartRematch(role, room_status) => {
+                // Nouvelle partie dans la même room: on garde le chat et les spectateurs
+                new_state.role = Some(role);
+                new_state.room_status = Some(room_status);
+                new_state.game_over = None;
+                new_state.variant_win = None;
+                new_state.rating_changes = Vec::new();
+                new_state.rematch_offer = None;
+                new_state.legals_moves = Vec::new();
+                new_state.board = vec![vec![None; 8]; 8];
+                new_state.incheck = None;
+            }
+            ServerAction::AddChat(entry) => {
+                new_state.chat.push(entry);
+            }
+            ServerAction::SetChat(entries) => {
+                new_state.chat = entries;
+            }
+            ServerAction::SetSpectators(count) => {
+                new_state.spectators = count;
+            }
+            ServerAction::SetSpectatorDelay(delay) => {
+                new_state.spectator_delay = delay;
+            }
+            ServerAction::SetMateSolution(solution) => {
+                new_state.mate_solution = solution;
+            }
+            ServerAction::SetEvaluation(breakdown) => {
+                new_state.evaluation = breakdown;
+            }
+            ServerAction::SetVariant(variant, state) => {
+                if variant == Variant::Crazyhouse {
+                    new_state.pockets = Some(state.pockets);
+                }
+                new_state.variant = Some((variant, state));
+            }
+            ServerAction::SetSeats(seats, seat) => {
+                new_state.seats = seats;
+                new_state.seat = seat;
+            }
+            ServerAction::SetBughouseBoard {
+                board_id,
+                board,
+                turn,
+                counter,
+                incheck,
+                pockets,
+                clock,
+            } => {
+                // Le plateau du joueur va dans la vue principale, l'autre à côté
+                if board_id == new_state.seat.map_or(BoardId::A, |seat| seat.board) {
+                    new_state.board = board;
+                    new_state.turn = Some(turn);
+                    new_state.counter = counter;
+                    new_state.incheck = incheck;
+                    new_state.pockets = Some(pockets);
+                    new_state.clock = clock;
+                } else {
+                    new_state.partner_board = Some(BughouseView {
+                        board,
+                        turn,
+                        pockets,
+                        clock,
+                    });
+                }
+            }
+            ServerAction::SetBughousePockets(board_id, pockets) => {
+                if board_id == new_state.seat.map_or(BoardId::A, |seat| seat.board) {
+                    new_state.pockets = Some(pockets);
+                } else if let Some(view) = new_state.partner_board.as_mut() {
+                    view.pockets = pockets;
+                }
+            }
+            ServerAction::StartPuzzle(puzzle, stats) => {
+                new_state.puzzle = Some(puzzle);
+                new_state.puzzle_stats = Some(stats);
+                new_state.puzzle_feedback = None;
+                new_state.puzzle_result = None;
+                new_state.legals_moves = Vec::new();
+            }
+            ServerAction::SetPuzzleFeedback(correct) => {
+                new_state.puzzle_feedback = Some(correct);
+            }
+            ServerAction::FinishPuzzle {
+                solved,
+                solution,
+                stats,
+                rating_before,
+            } => {
+                new_state.puzzle_stats = Some(stats);
+                new_state.puzzle_result = Some((solved, solution, rating_before));
+            }
+            ServerAction::SetInviteCode(code) => {
+                new_state.invite_code = Some(code);
+            }
+            ServerAction::SetLobby(rooms) => {
+                new_state.lobby = rooms;
+            }
+            ServerAction::LobbyUpsert(room) => {
+                match new_state
+                    .lobby
+                    .iter_mut()
+                    .find(|r| r.room_id == room.room_id)
+                {
+                    Some(current) => *current = room,
+                    None => new_state.lobby.push(room),
+                }
+            }
+            ServerAction::LobbyRemove(room_id) => {
+                new_state.lobby.retain(|r| r.room_id != room_id);
+            }
+            ServerAction::SetEngines(names) => {
+                new_state.engines = names;
+            }
+            ServerAction::SetQueued(queued, time_control) => {
+                new_state.in_queue = queued;
+                new_state.queued = time_control;
             }
             ServerAction::Ping => {
                 new_state.ping = true;
@@ -128,6 +356,12 @@ impl Reducible for ServerState {
             ServerAction::Pausing => {
                 new_state.paused = !new_state.paused;
             }
+            ServerAction::SetGameList(games) => {
+                new_state.games = games;
+            }
+            ServerAction::SetReplayGame(game) => {
+                new_state.replay_game = Some(game);
+            }
         }
 
         Rc::new(new_state)
@@ -140,9 +374,10 @@ pub enum ServerAction {
         turn: Color,
         counter: usize,
         incheck: Option<Color>,
+        pockets: Option<Pockets>,
     },
     SetLegalMoves(Vec<String>),
-    SetGameOver(String, RoomStatus),
+    SetGameOver(String, RoomStatus, Vec<RatingChange>, Option<VariantWin>),
     SetInfo(String),
     SetRole(PlayerRole, uuid::Uuid, RoomStatus, GameMode),
     SetReady(bool),
@@ -154,4 +389,42 @@ pub enum ServerAction {
     ResetPing,
     SetLastPage(Route),
     Pausing,
+    SetAccount(Option<AccountInfo>),
+    SetQueued(bool, Option<TimeControl>),
+    SetLobby(Vec<RoomSummary>),
+    SetInviteCode(String),
+    SetMateSolution(Option<(u8, MateOutcome)>),
+    SetEvaluation(Option<EvalBreakdown>),
+    SetVariant(Variant, VariantState),
+    ChangeMode(GameMode, PlayerRole, RoomStatus),
+    SetSeats(Vec<SeatInfo>, Option<Seat>),
+    SetBughouseBoard {
+        board_id: BoardId,
+        board: Vec<Vec<Option<String>>>,
+        turn: Color,
+        counter: usize,
+        incheck: Option<Color>,
+        pockets: Pockets,
+        clock: Option<ClockState>,
+    },
+    SetBughousePockets(BoardId, Pockets),
+    StartPuzzle(PuzzleInfo, PuzzleStats),
+    SetPuzzleFeedback(bool),
+    FinishPuzzle {
+        solved: bool,
+        solution: Vec<String>,
+        stats: PuzzleStats,
+        rating_before: f64,
+    },
+    SetSpectators(usize),
+    AddChat(ChatEntry),
+    SetRematchOffer(Option<String>),
+    StartRematch(PlayerRole, RoomStatus),
+    SetChat(Vec<ChatEntry>),
+    SetSpectatorDelay(usize),
+    LobbyUpsert(RoomSummary),
+    LobbyRemove(Uuid),
+    SetEngines(Vec<String>),
+    SetGameList(Vec<GameRecord>),
+    SetReplayGame(GameRecord),
 }
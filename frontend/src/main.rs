@@ -65,6 +65,18 @@ fn main() {
         .append_child(&download_css)
         .unwrap();
 
+    // Import history.css
+    let history_css = gloo_utils::document().create_element("link").unwrap();
+    history_css.set_attribute("rel", "stylesheet").unwrap();
+    history_css
+        .set_attribute("href", "/static/styles/history.css")
+        .unwrap();
+    gloo_utils::document()
+        .head()
+        .unwrap()
+        .append_child(&history_css)
+        .unwrap();
+
     let presentation_css = gloo_utils::document().create_element("link").unwrap();
     presentation_css.set_attribute("rel", "stylesheet").unwrap();
     presentation_css
@@ -1,6 +1,7 @@
 use std::fmt::{Display, Formatter};
 
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
@@ -16,6 +17,14 @@ pub enum Route {
     CreateGame,
     #[at("/game")]
     Game,
+    #[at("/login")]
+    Login,
+    #[at("/history")]
+    History,
+    #[at("/replay/:id")]
+    Replay { id: Uuid },
+    #[at("/join/:code")]
+    Join { code: String },
     #[not_found]
     #[at("/404")]
     NotFound,
@@ -31,6 +40,8 @@ impl std::str::FromStr for Route {
             "Game" => Ok(Route::Game),
             "Info" => Ok(Route::Info),
             "Download" => Ok(Route::Download),
+            "History" => Ok(Route::History),
+            "Login" => Ok(Route::Login),
             _ => Ok(Route::Home),
         }
     }
@@ -43,6 +54,8 @@ impl ToString for Route {
             Route::Game => "Game".to_string(),
             Route::Info => "Info".to_string(),
             Route::Download => "Download".to_string(),
+            Route::History => "History".to_string(),
+            Route::Login => "Login".to_string(),
             _ => "Home".to_string(),
         }
     }
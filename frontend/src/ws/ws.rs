@@ -61,7 +61,10 @@ pub fn ws_provider(props: &WsProviderProps) -> Html {
                 spawn_local(async move {
                     let location = web_sys::window().unwrap().location();
                     let host = location.host().unwrap(); // ex: "mchess.fr" ou "mchess.fr:8080"
-                    let ws_url = format!("ws://{}/ws", host);
+                    let ws_url = match LocalStorage::get::<String>("session_token") {
+                        Ok(token) => format!("ws://{}/ws?token={}", host, token),
+                        Err(_) => format!("ws://{}/ws", host),
+                    };
 
                     match WebSocket::open(&ws_url) {
                         Ok(ws) => {
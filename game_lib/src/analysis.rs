@@ -0,0 +1,408 @@
+//! Tactical motifs of a position: hanging pieces, pins, skewers, forks,
+//! discovered attacks, overloaded defenders and mate threats. Shared by
+//! hints, puzzle tagging and the teaching overlays of the frontend.
+
+use serde::{Deserialize, Serialize};
+
+use crate::automation::mate::MatePosition;
+use crate::piece::Color;
+use crate::zobrist::PieceKind;
+
+/// Read access to a position. Squares go from 0 (a1) to 63 (h8).
+pub trait BoardView {
+    fn turn(&self) -> Color;
+    fn piece_at(&self, square: u8) -> Option<(Color, PieceKind)>;
+}
+
+/// Positions that can give the move to the other side without playing,
+/// to see what the opponent threatens.
+pub trait NullMove: MatePosition + BoardView {
+    fn null_move(&self) -> Self;
+}
+
+/// One motif, squares written as "e4". `side` is the side that can make
+/// use of it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "motif")]
+pub enum Motif {
+    /// A piece that can be taken for free or by a cheaper piece.
+    Hanging {
+        side: Color,
+        square: String,
+        attackers: Vec<String>,
+    },
+    /// `pinned` cannot move without exposing `target`. Absolute pins are
+    /// pins against the king: the pinned piece may not move at all.
+    Pin {
+        side: Color,
+        pinner: String,
+        pinned: String,
+        target: String,
+        absolute: bool,
+    },
+    /// The valuable `front` piece must move and leaves `back` en prise.
+    Skewer {
+        side: Color,
+        attacker: String,
+        front: String,
+        back: String,
+    },
+    Fork {
+        side: Color,
+        forker: String,
+        targets: Vec<String>,
+    },
+    /// Moving `front` out of the way lets `attacker` hit `target`.
+    DiscoveredAttack {
+        side: Color,
+        front: String,
+        attacker: String,
+        target: String,
+    },
+    /// Only defender of several attacked pieces: it cannot keep them all.
+    Overloaded {
+        side: Color,
+        defender: String,
+        defended: Vec<String>,
+    },
+    /// Moves that would mate if `side` had the move.
+    MateThreat { side: Color, moves: Vec<String> },
+}
+
+impl Motif {
+    pub fn side(&self) -> Color {
+        match self {
+            Motif::Hanging { side, .. }
+            | Motif::Pin { side, .. }
+            | Motif::Skewer { side, .. }
+            | Motif::Fork { side, .. }
+            | Motif::DiscoveredAttack { side, .. }
+            | Motif::Overloaded { side, .. }
+            | Motif::MateThreat { side, .. } => *side,
+        }
+    }
+}
+
+pub fn piece_value(kind: PieceKind) -> i32 {
+    match kind {
+        PieceKind::Pawn => 100,
+        PieceKind::Knight => 300,
+        PieceKind::Bishop => 320,
+        PieceKind::Rook => 500,
+        PieceKind::Queen => 900,
+        PieceKind::King => 0,
+    }
+}
+
+// Pour comparer les cibles, le roi vaut plus que tout le reste
+fn target_value(kind: PieceKind) -> i32 {
+    if kind == PieceKind::King {
+        10_000
+    } else {
+        piece_value(kind)
+    }
+}
+
+pub fn square_name(square: u8) -> String {
+    format!(
+        "{}{}",
+        (b'a' + square % 8) as char,
+        (b'1' + square / 8) as char
+    )
+}
+
+pub fn parse_square(s: &str) -> Option<u8> {
+    let b = s.as_bytes();
+    if b.len() != 2 || !(b'a'..=b'h').contains(&b[0]) || !(b'1'..=b'8').contains(&b[1]) {
+        return None;
+    }
+    Some((b[1] - b'1') * 8 + (b[0] - b'a'))
+}
+
+/// "e2->e4" or "e7->e8q" to the starting and target squares.
+pub fn move_squares(mv: &str) -> Option<(u8, u8)> {
+    let (from, to) = mv.split_once("->")?;
+    Some((parse_square(from)?, parse_square(to.get(..2)?)?))
+}
+
+// Case voisine dans la direction (colonne, rangée), None au bord du plateau
+pub(crate) fn step(square: u8, df: i8, dr: i8) -> Option<u8> {
+    let file = (square % 8) as i8 + df;
+    let row = (square / 8) as i8 + dr;
+    ((0..8).contains(&file) && (0..8).contains(&row)).then(|| (row * 8 + file) as u8)
+}
+
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+pub(crate) const KNIGHT_JUMPS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+pub(crate) fn slider_directions(kind: PieceKind) -> Vec<(i8, i8)> {
+    match kind {
+        PieceKind::Rook => ROOK_DIRECTIONS.to_vec(),
+        PieceKind::Bishop => BISHOP_DIRECTIONS.to_vec(),
+        PieceKind::Queen => [ROOK_DIRECTIONS, BISHOP_DIRECTIONS].concat(),
+        _ => Vec::new(),
+    }
+}
+
+/// Squares attacked by the piece on `square`, blockers included.
+pub fn attacked_squares<P: BoardView>(position: &P, square: u8) -> Vec<u8> {
+    let Some((color, kind)) = position.piece_at(square) else {
+        return Vec::new();
+    };
+    let jumps: &[(i8, i8)] = match kind {
+        PieceKind::Knight => &KNIGHT_JUMPS,
+        PieceKind::King => &[
+            (1, 0),
+            (-1, 0),
+            (0, 1),
+            (0, -1),
+            (1, 1),
+            (1, -1),
+            (-1, 1),
+            (-1, -1),
+        ],
+        PieceKind::Pawn if color == Color::White => &[(1, 1), (-1, 1)],
+        PieceKind::Pawn => &[(1, -1), (-1, -1)],
+        _ => &[],
+    };
+    let mut squares: Vec<u8> = jumps
+        .iter()
+        .filter_map(|&(df, dr)| step(square, df, dr))
+        .collect();
+    for (df, dr) in slider_directions(kind) {
+        let mut current = square;
+        while let Some(next) = step(current, df, dr) {
+            squares.push(next);
+            if position.piece_at(next).is_some() {
+                break;
+            }
+            current = next;
+        }
+    }
+    squares
+}
+
+/// Pieces of `color` attacking `square`.
+pub fn attackers_of<P: BoardView>(position: &P, square: u8, color: Color) -> Vec<u8> {
+    (0..64)
+        .filter(|&sq| matches!(position.piece_at(sq), Some((c, _)) if c == color))
+        .filter(|&sq| attacked_squares(position, sq).contains(&square))
+        .collect()
+}
+
+/// Pieces of `color` that can really take on `square`: the king cannot
+/// take a defended piece.
+fn capturers<P: BoardView>(position: &P, square: u8, color: Color) -> Vec<u8> {
+    let attackers = attackers_of(position, square, color);
+    if attackers_of(position, square, color.opposite()).is_empty() {
+        return attackers;
+    }
+    attackers
+        .into_iter()
+        .filter(|&a| !matches!(position.piece_at(a), Some((_, PieceKind::King))))
+        .collect()
+}
+
+// Les deux premières pièces rencontrées dans une direction
+fn first_two_pieces<P: BoardView>(position: &P, from: u8, df: i8, dr: i8) -> Vec<u8> {
+    let mut found = Vec::new();
+    let mut current = from;
+    while let Some(next) = step(current, df, dr) {
+        if position.piece_at(next).is_some() {
+            found.push(next);
+            if found.len() == 2 {
+                break;
+            }
+        }
+        current = next;
+    }
+    found
+}
+
+fn names(squares: &[u8]) -> Vec<String> {
+    squares.iter().map(|&sq| square_name(sq)).collect()
+}
+
+/// Motifs that do not need move generation, for both sides.
+pub fn motifs<P: BoardView>(position: &P) -> Vec<Motif> {
+    let mut motifs = Vec::new();
+    let pieces: Vec<(u8, Color, PieceKind)> = (0..64)
+        .filter_map(|sq| position.piece_at(sq).map(|(c, k)| (sq, c, k)))
+        .collect();
+
+    for &(square, color, kind) in &pieces {
+        let enemy = color.opposite();
+        if kind != PieceKind::King {
+            let attackers = capturers(position, square, enemy);
+            let defended = !attackers_of(position, square, color).is_empty();
+            let cheaper = attackers.iter().any(|&a| {
+                position
+                    .piece_at(a)
+                    .is_some_and(|(_, k)| piece_value(k) < piece_value(kind))
+            });
+            if !attackers.is_empty() && (!defended || cheaper) {
+                motifs.push(Motif::Hanging {
+                    side: enemy,
+                    square: square_name(square),
+                    attackers: names(&attackers),
+                });
+            }
+        }
+
+        // Fourchette: au moins deux cibles que la pièce gagne à prendre
+        let targets: Vec<u8> = attacked_squares(position, square)
+            .into_iter()
+            .filter(|&t| match position.piece_at(t) {
+                Some((c, k)) if c == enemy => {
+                    k == PieceKind::King
+                        || (kind != PieceKind::King && piece_value(k) > piece_value(kind))
+                        || attackers_of(position, t, enemy).is_empty()
+                }
+                _ => false,
+            })
+            .collect();
+        if targets.len() >= 2 {
+            motifs.push(Motif::Fork {
+                side: color,
+                forker: square_name(square),
+                targets: names(&targets),
+            });
+        }
+
+        for (df, dr) in slider_directions(kind) {
+            let [front, back] = first_two_pieces(position, square, df, dr)[..] else {
+                continue;
+            };
+            let (Some((front_color, front_kind)), Some((back_color, back_kind))) =
+                (position.piece_at(front), position.piece_at(back))
+            else {
+                continue;
+            };
+            if front_color == enemy && back_color == enemy {
+                if target_value(back_kind) > target_value(front_kind) {
+                    motifs.push(Motif::Pin {
+                        side: color,
+                        pinner: square_name(square),
+                        pinned: square_name(front),
+                        target: square_name(back),
+                        absolute: back_kind == PieceKind::King,
+                    });
+                } else if target_value(front_kind) > target_value(back_kind)
+                    && piece_value(back_kind) >= piece_value(PieceKind::Knight)
+                {
+                    motifs.push(Motif::Skewer {
+                        side: color,
+                        attacker: square_name(square),
+                        front: square_name(front),
+                        back: square_name(back),
+                    });
+                }
+            } else if front_color == color && back_color == enemy {
+                // Batterie: la pièce de devant démasque l'attaque en bougeant
+                let valuable = target_value(back_kind) >= piece_value(PieceKind::Rook);
+                if valuable || attackers_of(position, back, enemy).is_empty() {
+                    motifs.push(Motif::DiscoveredAttack {
+                        side: color,
+                        front: square_name(front),
+                        attacker: square_name(square),
+                        target: square_name(back),
+                    });
+                }
+            }
+        }
+    }
+
+    // Défenseur surchargé: seul à protéger plusieurs pièces attaquées
+    for &(square, color, _) in &pieces {
+        let defended: Vec<u8> = pieces
+            .iter()
+            .filter(|&&(sq, c, k)| c == color && sq != square && k != PieceKind::King)
+            .filter(|&&(sq, _, _)| !capturers(position, sq, color.opposite()).is_empty())
+            .filter(|&&(sq, _, _)| attackers_of(position, sq, color) == [square])
+            .map(|&(sq, _, _)| sq)
+            .collect();
+        if defended.len() >= 2 {
+            motifs.push(Motif::Overloaded {
+                side: color.opposite(),
+                defender: square_name(square),
+                defended: names(&defended),
+            });
+        }
+    }
+    motifs
+}
+
+/// Mates in one the opponent of the side to move would have if it were
+/// its turn.
+pub fn threatened_mates<P: NullMove>(position: &P) -> Vec<String> {
+    let passed = position.null_move();
+    passed
+        .legal_moves()
+        .into_iter()
+        .filter(|mv| {
+            let mut next = passed.clone();
+            next.play(mv);
+            next.in_check() && next.legal_moves().is_empty()
+        })
+        .collect()
+}
+
+/// Every motif of the position, mate threats included.
+pub fn analyze<P: NullMove>(position: &P) -> Vec<Motif> {
+    let mut found = motifs(position);
+    // Un camp en échec ne peut pas passer son tour
+    if !position.in_check() {
+        let moves = threatened_mates(position);
+        if !moves.is_empty() {
+            found.push(Motif::MateThreat {
+                side: position.turn().opposite(),
+                moves,
+            });
+        }
+    }
+    found
+}
+
+/*
+impl BoardView for crate::board::Board {
+    fn turn(&self) -> Color {
+        self.turn
+    }
+
+    fn piece_at(&self, square: u8) -> Option<(Color, PieceKind)> {
+        let pos = crate::position::Position {
+            row: (square / 8) as usize,
+            col: (square % 8) as usize,
+        };
+        self.piece_at(&pos).map(|p| (p.color, p.kind()))
+    }
+}
+
+impl BoardView for crate::game::Game {
+    fn turn(&self) -> Color {
+        self.board.turn
+    }
+
+    fn piece_at(&self, square: u8) -> Option<(Color, PieceKind)> {
+        BoardView::piece_at(&self.board, square)
+    }
+}
+
+impl NullMove for crate::game::Game {
+    fn null_move(&self) -> Self {
+        let mut game = self.clone();
+        game.board.turn = game.board.turn.opposite();
+        game
+    }
+}
+*/
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 
+use crate::automation::book::BookUsage;
 use crate::board::Board;
 use crate::game::*;
 use crate::piece::{Color, Piece, PieceType};
@@ -15,3 +16,72 @@ pub enum Difficulty {}
 pub struct AI {}
 
 impl AI {}
+
+/// Evaluation function used by the built-in engine. `Standard` keeps the
+/// engine's own evaluation; the others make it search on the matching
+/// terms of `eval::evaluate` instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum EvalVariant {
+    #[default]
+    Standard,
+    /// Material count only.
+    Material,
+    /// Material plus piece-square tables.
+    Positional,
+}
+
+/// Engine playing one side of a room.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "engine")]
+pub enum AiProfile {
+    Builtin {
+        difficulty: Difficulty,
+        #[serde(default)]
+        eval: EvalVariant,
+        #[serde(default)]
+        book: BookUsage,
+    },
+    /// UCI engine declared in the server's engine list, referenced by name.
+    External {
+        name: String,
+        #[serde(default = "default_movetime")]
+        movetime_ms: u64,
+    },
+}
+
+fn default_movetime() -> u64 {
+    1000
+}
+
+impl AiProfile {
+    pub fn builtin(difficulty: Difficulty) -> Self {
+        AiProfile::Builtin {
+            difficulty,
+            eval: EvalVariant::default(),
+            book: BookUsage::default(),
+        }
+    }
+
+    /// Evaluation chosen for the built-in engine, the default for others.
+    pub fn eval(&self) -> EvalVariant {
+        match self {
+            AiProfile::Builtin { eval, .. } => *eval,
+            AiProfile::External { .. } => EvalVariant::default(),
+        }
+    }
+
+    /// Name shown for the player and written in the PGN.
+    pub fn label(&self) -> String {
+        match self {
+            AiProfile::Builtin {
+                difficulty,
+                eval: EvalVariant::Standard,
+                ..
+            } => format!("AI ({:?})", difficulty),
+            AiProfile::Builtin {
+                difficulty, eval, ..
+            } => format!("AI ({:?}, {:?})", difficulty, eval),
+            AiProfile::External { name, movetime_ms } => format!("{} ({} ms)", name, movetime_ms),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{parse_square, BoardView};
+use crate::fen::FenPosition;
+use crate::zobrist::{KeyInput, PieceKind, ZobristKeys};
+
+// Une entrée Polyglot: clé u64, coup u16, poids u16, learn u32, en big-endian
+const ENTRY_LEN: usize = 16;
+
+/// One move of a Polyglot `.bin` book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookEntry {
+    pub key: u64,
+    pub raw_move: u16,
+    pub weight: u16,
+}
+
+impl BookEntry {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        BookEntry {
+            key: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            raw_move: u16::from_be_bytes(bytes[8..10].try_into().unwrap()),
+            weight: u16::from_be_bytes(bytes[10..12].try_into().unwrap()),
+        }
+    }
+
+    /// Starting square of the move, e.g. "e2".
+    pub fn from_square(&self) -> String {
+        let m = self.raw_move;
+        square((m >> 6) & 7, (m >> 9) & 7)
+    }
+
+    /// The move in server notation ("e2->e4", "e7->e8q"). Polyglot writes
+    /// castling as the king taking its own rook, so `king_on_from` tells
+    /// whether e1h1 means castling or a rook move.
+    pub fn to_move(&self, king_on_from: bool) -> String {
+        let m = self.raw_move;
+        let (to_file, to_row) = (m & 7, (m >> 3) & 7);
+        let promotion = match (m >> 12) & 7 {
+            1 => "n",
+            2 => "b",
+            3 => "r",
+            4 => "q",
+            _ => "",
+        };
+        let from = self.from_square();
+        let to = match (king_on_from, from.as_str(), to_file, to_row) {
+            (true, "e1", 7, 0) => "g1".to_string(),
+            (true, "e1", 0, 0) => "c1".to_string(),
+            (true, "e8", 7, 7) => "g8".to_string(),
+            (true, "e8", 0, 7) => "c8".to_string(),
+            _ => square(to_file, to_row),
+        };
+        format!("{}->{}{}", from, to, promotion)
+    }
+}
+
+fn square(file: u16, row: u16) -> String {
+    format!(
+        "{}{}",
+        (b'a' + file as u8) as char,
+        (b'1' + row as u8) as char
+    )
+}
+
+/// How the built-in engine uses the opening book.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BookUsage {
+    pub enabled: bool,
+    /// Plies after which the book is no longer used, `None` to follow it
+    /// as long as it has moves.
+    pub max_plies: Option<usize>,
+}
+
+impl Default for BookUsage {
+    fn default() -> Self {
+        BookUsage {
+            enabled: true,
+            max_plies: None,
+        }
+    }
+}
+
+impl BookUsage {
+    pub fn allows(&self, ply: usize) -> bool {
+        self.enabled && self.max_plies.is_none_or(|max| ply < max)
+    }
+}
+
+/// Polyglot opening book, with the Zobrist keys needed to look it up.
+#[derive(Debug, Clone)]
+pub struct OpeningBook {
+    keys: ZobristKeys,
+    entries: Vec<BookEntry>,
+}
+
+impl OpeningBook {
+    pub fn from_bytes(keys: ZobristKeys, bytes: &[u8]) -> Result<Self, String> {
+        if !bytes.len().is_multiple_of(ENTRY_LEN) {
+            return Err(format!(
+                "Book size {} is not a multiple of {}",
+                bytes.len(),
+                ENTRY_LEN
+            ));
+        }
+        let mut entries: Vec<BookEntry> = bytes
+            .chunks_exact(ENTRY_LEN)
+            .map(BookEntry::from_bytes)
+            .collect();
+        // Les livres sont normalement triés, mais la recherche en dépend
+        entries.sort_by_key(|e| e.key);
+        Ok(OpeningBook { keys, entries })
+    }
+
+    pub fn keys(&self) -> &ZobristKeys {
+        &self.keys
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Book moves of the position, in file order.
+    pub fn moves(&self, key: u64) -> &[BookEntry] {
+        let start = self.entries.partition_point(|e| e.key < key);
+        let end = self.entries.partition_point(|e| e.key <= key);
+        &self.entries[start..end]
+    }
+
+    /// Book move for `position` in server notation, drawn with `choose`.
+    pub fn pick(&self, position: &FenPosition, roll: impl FnOnce(u32) -> u32) -> Option<String> {
+        let key = self.keys.key(&KeyInput::of(position));
+        let entry = self.choose(key, roll)?;
+        let king_on_from = parse_square(&entry.from_square())
+            .and_then(|sq| position.piece_at(sq))
+            .is_some_and(|(_, kind)| kind == PieceKind::King);
+        Some(entry.to_move(king_on_from))
+    }
+
+    /// Picks a move with a probability proportional to its weight. `roll`
+    /// gets the total weight and returns a number in `0..total`; moves of
+    /// weight 0 are never played.
+    pub fn choose(&self, key: u64, roll: impl FnOnce(u32) -> u32) -> Option<BookEntry> {
+        let moves = self.moves(key);
+        let total: u32 = moves.iter().map(|e| e.weight as u32).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut pick = roll(total);
+        for entry in moves {
+            if pick < entry.weight as u32 {
+                return Some(*entry);
+            }
+            pick -= entry.weight as u32;
+        }
+        None
+    }
+}
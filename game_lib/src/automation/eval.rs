@@ -0,0 +1,305 @@
+//! Static evaluation used by the variant search (`Variant::best_move`) and
+//! by the sandbox analysis, split into terms so the score can be
+//! explained: every term is given for both sides, in centipawns.
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{attacked_squares, piece_value, step, BoardView};
+use crate::automation::ai::EvalVariant;
+use crate::piece::Color;
+use crate::zobrist::PieceKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EvalTerm {
+    Material,
+    PieceSquare,
+    Mobility,
+    KingSafety,
+    PawnStructure,
+    PassedPawns,
+    BishopPair,
+}
+
+impl EvalTerm {
+    /// Terms computed by each evaluation function.
+    pub fn of(variant: EvalVariant) -> &'static [EvalTerm] {
+        match variant {
+            EvalVariant::Material => &[EvalTerm::Material],
+            EvalVariant::Positional => &[EvalTerm::Material, EvalTerm::PieceSquare],
+            EvalVariant::Standard => &[
+                EvalTerm::Material,
+                EvalTerm::PieceSquare,
+                EvalTerm::Mobility,
+                EvalTerm::KingSafety,
+                EvalTerm::PawnStructure,
+                EvalTerm::PassedPawns,
+                EvalTerm::BishopPair,
+            ],
+        }
+    }
+}
+
+/// Contribution of one term, positive when it helps the side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TermScore {
+    pub term: EvalTerm,
+    pub white: i32,
+    pub black: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvalBreakdown {
+    pub variant: EvalVariant,
+    pub terms: Vec<TermScore>,
+}
+
+impl EvalBreakdown {
+    /// Score from White's point of view, the number `Variant::best_move`
+    /// searches with.
+    pub fn total(&self) -> i32 {
+        self.terms.iter().map(|t| t.white - t.black).sum()
+    }
+
+    /// Score from the point of view of `color`.
+    pub fn for_side(&self, color: Color) -> i32 {
+        match color {
+            Color::White => self.total(),
+            Color::Black => -self.total(),
+        }
+    }
+}
+
+// Tables de placement, vues par les blancs: première ligne = 8e rangée
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+#[rustfmt::skip]
+const KING_TABLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+// Bonus par case atteignable, selon la pièce
+fn mobility_weight(kind: PieceKind) -> i32 {
+    match kind {
+        PieceKind::Knight => 4,
+        PieceKind::Bishop => 5,
+        PieceKind::Rook => 2,
+        PieceKind::Queen => 1,
+        _ => 0,
+    }
+}
+
+const SHIELD_PAWN: i32 = 10;
+const KING_ZONE_ATTACK: i32 = 8;
+const DOUBLED_PAWN: i32 = 15;
+const ISOLATED_PAWN: i32 = 12;
+// Indexé par la rangée du pion vue de son camp
+const PASSED_PAWN: [i32; 8] = [0, 5, 10, 20, 35, 60, 100, 0];
+const BISHOP_PAIR: i32 = 30;
+
+fn piece_square(kind: PieceKind, color: Color, square: u8) -> i32 {
+    let table = match kind {
+        PieceKind::Pawn => &PAWN_TABLE,
+        PieceKind::Knight => &KNIGHT_TABLE,
+        PieceKind::Bishop => &BISHOP_TABLE,
+        PieceKind::Rook => &ROOK_TABLE,
+        PieceKind::Queen => &QUEEN_TABLE,
+        PieceKind::King => &KING_TABLE,
+    };
+    let (row, file) = ((square / 8) as usize, (square % 8) as usize);
+    match color {
+        Color::White => table[(7 - row) * 8 + file],
+        Color::Black => table[row * 8 + file],
+    }
+}
+
+// Rangée vue du camp: 0 pour sa première rangée, 7 pour la dernière
+fn relative_row(color: Color, square: u8) -> i32 {
+    match color {
+        Color::White => (square / 8) as i32,
+        Color::Black => 7 - (square / 8) as i32,
+    }
+}
+
+/// Evaluates the position with the terms of `variant`.
+pub fn evaluate<P: BoardView>(position: &P, variant: EvalVariant) -> EvalBreakdown {
+    let pieces: Vec<(u8, Color, PieceKind)> = (0..64)
+        .filter_map(|sq| position.piece_at(sq).map(|(c, k)| (sq, c, k)))
+        .collect();
+    let terms = EvalTerm::of(variant)
+        .iter()
+        .map(|&term| TermScore {
+            term,
+            white: side_term(position, &pieces, term, Color::White),
+            black: side_term(position, &pieces, term, Color::Black),
+        })
+        .collect();
+    EvalBreakdown { variant, terms }
+}
+
+fn side_term<P: BoardView>(
+    position: &P,
+    pieces: &[(u8, Color, PieceKind)],
+    term: EvalTerm,
+    color: Color,
+) -> i32 {
+    let own: Vec<(u8, PieceKind)> = pieces
+        .iter()
+        .filter(|p| p.1 == color)
+        .map(|&(sq, _, kind)| (sq, kind))
+        .collect();
+    let pawns = |c: Color| -> Vec<u8> {
+        pieces
+            .iter()
+            .filter(|&&(_, pc, kind)| pc == c && kind == PieceKind::Pawn)
+            .map(|&(sq, _, _)| sq)
+            .collect()
+    };
+    match term {
+        EvalTerm::Material => own.iter().map(|&(_, kind)| piece_value(kind)).sum(),
+        EvalTerm::PieceSquare => own
+            .iter()
+            .map(|&(sq, kind)| piece_square(kind, color, sq))
+            .sum(),
+        EvalTerm::Mobility => own
+            .iter()
+            .map(|&(sq, kind)| {
+                let reachable = attacked_squares(position, sq)
+                    .into_iter()
+                    .filter(|&t| !matches!(position.piece_at(t), Some((c, _)) if c == color))
+                    .count() as i32;
+                reachable * mobility_weight(kind)
+            })
+            .sum(),
+        EvalTerm::KingSafety => {
+            let Some(&(king, _)) = own.iter().find(|p| p.1 == PieceKind::King) else {
+                return 0;
+            };
+            let forward = if color == Color::White { 1 } else { -1 };
+            // Bouclier de pions devant un roi resté sur ses deux premières rangées
+            let shield = if relative_row(color, king) <= 1 {
+                (-1..=1)
+                    .flat_map(|df| {
+                        let one = step(king, df, forward);
+                        [one, one.and_then(|sq| step(sq, 0, forward))]
+                    })
+                    .flatten()
+                    .filter(|&sq| position.piece_at(sq) == Some((color, PieceKind::Pawn)))
+                    .count() as i32
+            } else {
+                0
+            };
+            let zone: Vec<u8> = (-1..=1)
+                .flat_map(|df| (-1..=1).filter_map(move |dr| step(king, df, dr)))
+                .collect();
+            let attacked = zone
+                .iter()
+                .filter(|&&sq| {
+                    pieces
+                        .iter()
+                        .filter(|p| p.1 != color)
+                        .any(|&(from, _, _)| attacked_squares(position, from).contains(&sq))
+                })
+                .count() as i32;
+            shield * SHIELD_PAWN - attacked * KING_ZONE_ATTACK
+        }
+        EvalTerm::PawnStructure => {
+            let own_pawns = pawns(color);
+            let on_file = |file: i32| {
+                own_pawns
+                    .iter()
+                    .filter(|&&sq| (sq % 8) as i32 == file)
+                    .count()
+            };
+            (0..8)
+                .map(|file| {
+                    let count = on_file(file) as i32;
+                    let isolated = count > 0 && on_file(file - 1) == 0 && on_file(file + 1) == 0;
+                    -(count - 1).max(0) * DOUBLED_PAWN
+                        - if isolated { count * ISOLATED_PAWN } else { 0 }
+                })
+                .sum()
+        }
+        EvalTerm::PassedPawns => {
+            let enemy_pawns = pawns(color.opposite());
+            pawns(color)
+                .into_iter()
+                .filter(|&sq| {
+                    // Aucun pion adverse devant lui sur sa colonne ou les voisines
+                    !enemy_pawns.iter().any(|&e| {
+                        ((e % 8) as i32 - (sq % 8) as i32).abs() <= 1
+                            && relative_row(color, e) > relative_row(color, sq)
+                    })
+                })
+                .map(|sq| PASSED_PAWN[relative_row(color, sq) as usize])
+                .sum()
+        }
+        EvalTerm::BishopPair => {
+            let bishops = own.iter().filter(|p| p.1 == PieceKind::Bishop).count();
+            if bishops >= 2 {
+                BISHOP_PAIR
+            } else {
+                0
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+
+/// What the mate search needs from a position. Moves use the server
+/// notation ("e2->e4").
+pub trait MatePosition: Clone {
+    fn legal_moves(&self) -> Vec<String>;
+    /// Plays a legal move for the side to move.
+    fn play(&mut self, mv: &str);
+    fn in_check(&self) -> bool;
+}
+
+// Garde-fou: un problème en 5 avec beaucoup de pièces peut exploser
+pub const DEFAULT_MAX_NODES: u64 = 20_000_000;
+pub const MAX_MATE_DEPTH: u8 = 8;
+
+/// One key move and the line that follows against the longest defence.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MateLine {
+    /// Moves of the attacker, including the key move.
+    pub moves_to_mate: u8,
+    pub line: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome")]
+pub enum MateOutcome {
+    /// Every key move that mates in at most N, shortest mates first.
+    Found { lines: Vec<MateLine> },
+    /// The whole tree was searched: there is no mate in N.
+    NoMate,
+    /// The node limit was hit before the search could conclude.
+    Inconclusive { nodes: u64 },
+}
+
+/// Exhaustive mate search: the attacker tries every move, the defender
+/// every reply. Checking moves are tried first, which finds most
+/// composed mates quickly, but the result does not depend on the order.
+#[derive(Debug, Clone)]
+pub struct MateSearch {
+    pub max_nodes: u64,
+    nodes: u64,
+}
+
+impl Default for MateSearch {
+    fn default() -> Self {
+        MateSearch::new(DEFAULT_MAX_NODES)
+    }
+}
+
+// Le budget de noeuds est épuisé, la recherche remonte sans conclure
+struct OutOfNodes;
+
+impl MateSearch {
+    pub fn new(max_nodes: u64) -> Self {
+        MateSearch {
+            max_nodes,
+            nodes: 0,
+        }
+    }
+
+    /// Nodes visited by the last call to `solve`.
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// Finds all key moves of the side to move that force mate in at most
+    /// `n` moves, or proves there are none.
+    pub fn solve<P: MatePosition>(&mut self, position: &P, n: u8) -> MateOutcome {
+        self.nodes = 0;
+        let n = n.min(MAX_MATE_DEPTH);
+        let mut lines = Vec::new();
+        for mv in ordered_moves(position) {
+            let mut next = position.clone();
+            next.play(&mv);
+            // Approfondissement progressif: on garde le mat le plus court
+            let mut found = None;
+            for depth in 1..=n {
+                match self.defender_loses(&next, depth) {
+                    Ok(Some(rest)) => {
+                        found = Some((depth, rest));
+                        break;
+                    }
+                    Ok(None) => {}
+                    Err(OutOfNodes) => return MateOutcome::Inconclusive { nodes: self.nodes },
+                }
+            }
+            if let Some((depth, rest)) = found {
+                let mut line = vec![mv];
+                line.extend(rest);
+                lines.push(MateLine {
+                    moves_to_mate: depth,
+                    line,
+                });
+            }
+        }
+        if lines.is_empty() {
+            return MateOutcome::NoMate;
+        }
+        lines.sort_by_key(|l| l.moves_to_mate);
+        MateOutcome::Found { lines }
+    }
+
+    // L'attaquant a joué; il lui reste `n - 1` coups après celui-ci pour
+    // mater. Renvoie la suite contre la meilleure défense si le mat est forcé.
+    fn defender_loses<P: MatePosition>(
+        &mut self,
+        position: &P,
+        n: u8,
+    ) -> Result<Option<Vec<String>>, OutOfNodes> {
+        self.visit()?;
+        let replies = position.legal_moves();
+        if replies.is_empty() {
+            // Mat si le défenseur est en échec, sinon pat
+            return Ok(position.in_check().then(Vec::new));
+        }
+        if n <= 1 {
+            return Ok(None);
+        }
+        let mut longest: Option<Vec<String>> = None;
+        for reply in replies {
+            let mut next = position.clone();
+            next.play(&reply);
+            match self.attacker_mates(&next, n - 1)? {
+                Some(rest) => {
+                    if longest.as_ref().is_none_or(|l| rest.len() + 1 > l.len()) {
+                        let mut line = vec![reply];
+                        line.extend(rest);
+                        longest = Some(line);
+                    }
+                }
+                // Une défense suffit à réfuter le coup de l'attaquant
+                None => return Ok(None),
+            }
+        }
+        Ok(longest)
+    }
+
+    // Trait à l'attaquant, mat en au plus `n`: renvoie la suite la plus courte
+    fn attacker_mates<P: MatePosition>(
+        &mut self,
+        position: &P,
+        n: u8,
+    ) -> Result<Option<Vec<String>>, OutOfNodes> {
+        self.visit()?;
+        for depth in 1..=n {
+            for mv in ordered_moves(position) {
+                let mut next = position.clone();
+                next.play(&mv);
+                if let Some(rest) = self.defender_loses(&next, depth)? {
+                    let mut line = vec![mv];
+                    line.extend(rest);
+                    return Ok(Some(line));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn visit(&mut self) -> Result<(), OutOfNodes> {
+        self.nodes += 1;
+        if self.nodes > self.max_nodes {
+            return Err(OutOfNodes);
+        }
+        Ok(())
+    }
+}
+
+// Les échecs d'abord: ils réduisent les défenses possibles
+fn ordered_moves<P: MatePosition>(position: &P) -> Vec<String> {
+    let (mut checks, quiet): (Vec<String>, Vec<String>) =
+        position.legal_moves().into_iter().partition(|mv| {
+            let mut next = position.clone();
+            next.play(mv);
+            next.in_check()
+        });
+    checks.extend(quiet);
+    checks
+}
+
+/*
+impl MatePosition for crate::game::Game {
+    fn legal_moves(&self) -> Vec<String> {
+        let turn = self.board.turn;
+        self.board
+            .all_legal_moves(turn)
+            .iter()
+            .map(|(from, to)| format!("{}->{}", from.to_algebraic(), to.to_algebraic()))
+            .collect()
+    }
+
+    fn play(&mut self, mv: &str) {
+        let _ = self.make_move_algebraic(mv);
+    }
+
+    fn in_check(&self) -> bool {
+        let turn = self.board.turn;
+        let king_pos = self.board.pieces[turn as usize][15].position;
+        self.board.is_attacked(&king_pos, turn)
+    }
+}
+*/
@@ -0,0 +1,438 @@
+//! Puzzle mining: walks through a game, looks for positions where a single
+//! move clearly wins (forced mate or decisive material gain) and follows
+//! the solution as long as it stays the only good move.
+
+use crate::analysis::{
+    motifs, move_squares, piece_value, slider_directions, square_name, step, BoardView, Motif,
+};
+use crate::automation::mate::MatePosition;
+use crate::piece::Color;
+use crate::puzzle::Puzzle;
+use crate::zobrist::PieceKind;
+
+/// What the miner needs on top of the mate search and the board view.
+pub trait MinerPosition: MatePosition + BoardView {
+    fn fen(&self) -> String;
+}
+
+const MATE: i32 = 100_000;
+const INFINITY: i32 = 1_000_000;
+// Profondeur maximale des captures examinées au-delà de l'horizon
+const QUIESCENCE_DEPTH: u8 = 6;
+
+#[derive(Debug, Clone, Copy)]
+pub struct MinerConfig {
+    /// Search depth in plies.
+    pub depth: u8,
+    /// Gain in centipawns for a move to count as winning, and margin over
+    /// the second best move for it to be the only solution.
+    pub min_gain: i32,
+    /// Longest solution, in moves of the solver.
+    pub max_solver_moves: usize,
+    /// Node budget of each search, the position is skipped beyond.
+    pub max_nodes: u64,
+}
+
+impl Default for MinerConfig {
+    fn default() -> Self {
+        MinerConfig {
+            depth: 4,
+            min_gain: 200,
+            max_solver_moves: 4,
+            max_nodes: 2_000_000,
+        }
+    }
+}
+
+struct OutOfNodes;
+
+fn is_mate_score(score: i32) -> bool {
+    score.abs() > MATE - 1000
+}
+
+/// Material balance from the side to move, in centipawns.
+fn material<P: MinerPosition>(position: &P) -> i32 {
+    let turn = position.turn();
+    (0..64)
+        .filter_map(|sq| position.piece_at(sq))
+        .map(|(color, kind)| {
+            let value = piece_value(kind);
+            if color == turn {
+                value
+            } else {
+                -value
+            }
+        })
+        .sum()
+}
+
+fn is_capture<P: MinerPosition>(position: &P, mv: &str) -> bool {
+    let promotion = mv.split_once("->").is_some_and(|(_, to)| to.len() > 2);
+    promotion
+        || move_squares(mv)
+            .and_then(|(_, to)| position.piece_at(to))
+            .is_some_and(|(color, _)| color != position.turn())
+}
+
+struct Search {
+    nodes: u64,
+    max_nodes: u64,
+}
+
+impl Search {
+    fn visit(&mut self) -> Result<(), OutOfNodes> {
+        self.nodes += 1;
+        if self.nodes > self.max_nodes {
+            return Err(OutOfNodes);
+        }
+        Ok(())
+    }
+
+    // Score exact de chaque coup, du point de vue du camp au trait
+    fn score_moves<P: MinerPosition>(
+        &mut self,
+        position: &P,
+        depth: u8,
+    ) -> Result<Vec<(String, i32)>, OutOfNodes> {
+        self.nodes = 0;
+        let mut scored = Vec::new();
+        for mv in position.legal_moves() {
+            let mut next = position.clone();
+            next.play(&mv);
+            let score = -self.negamax(&next, depth.saturating_sub(1), -INFINITY, INFINITY, 1)?;
+            scored.push((mv, score));
+        }
+        scored.sort_by_key(|(_, score)| -score);
+        Ok(scored)
+    }
+
+    fn negamax<P: MinerPosition>(
+        &mut self,
+        position: &P,
+        depth: u8,
+        mut alpha: i32,
+        beta: i32,
+        ply: i32,
+    ) -> Result<i32, OutOfNodes> {
+        self.visit()?;
+        let moves = position.legal_moves();
+        if moves.is_empty() {
+            return Ok(if position.in_check() { ply - MATE } else { 0 });
+        }
+        if depth == 0 {
+            return self.quiesce(position, alpha, beta, QUIESCENCE_DEPTH);
+        }
+        // Les captures d'abord, pour couper plus tôt
+        let (mut ordered, quiet): (Vec<String>, Vec<String>) =
+            moves.into_iter().partition(|mv| is_capture(position, mv));
+        ordered.extend(quiet);
+        let mut best = -INFINITY;
+        for mv in ordered {
+            let mut next = position.clone();
+            next.play(&mv);
+            let score = -self.negamax(&next, depth - 1, -beta, -alpha, ply + 1)?;
+            best = best.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        Ok(best)
+    }
+
+    fn quiesce<P: MinerPosition>(
+        &mut self,
+        position: &P,
+        mut alpha: i32,
+        beta: i32,
+        depth: u8,
+    ) -> Result<i32, OutOfNodes> {
+        self.visit()?;
+        let stand_pat = material(position);
+        if stand_pat >= beta || depth == 0 {
+            return Ok(stand_pat);
+        }
+        alpha = alpha.max(stand_pat);
+        for mv in position.legal_moves() {
+            if !is_capture(position, &mv) {
+                continue;
+            }
+            let mut next = position.clone();
+            next.play(&mv);
+            let score = -self.quiesce(&next, -beta, -alpha, depth - 1)?;
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        Ok(alpha)
+    }
+}
+
+// Le meilleur coup s'il est le seul à gagner: seul mat, ou nettement
+// meilleur que le deuxième
+fn only_winning_move(scored: &[(String, i32)], min_gain: i32) -> Option<&(String, i32)> {
+    let best = scored.first()?;
+    let second = scored.get(1).map_or(-INFINITY, |(_, s)| *s);
+    let unique = if is_mate_score(best.1) && best.1 > 0 {
+        !(is_mate_score(second) && second > 0)
+    } else {
+        best.1 - second >= min_gain
+    };
+    unique.then_some(best)
+}
+
+/// Puzzles found in a game. `start` is the initial position and `moves` the
+/// moves played from it; puzzle ids are `id_prefix` followed by the ply of
+/// the opponent's move that starts the puzzle.
+pub fn mine_game<P: MinerPosition>(
+    start: &P,
+    moves: &[String],
+    id_prefix: &str,
+    config: &MinerConfig,
+) -> Vec<Puzzle> {
+    let mut search = Search {
+        nodes: 0,
+        max_nodes: config.max_nodes,
+    };
+    let mut positions = vec![start.clone()];
+    for mv in moves {
+        let mut next = positions[positions.len() - 1].clone();
+        next.play(mv);
+        positions.push(next);
+    }
+
+    let mut puzzles = Vec::new();
+    // Valeur de la position précédente, pour savoir si le coup adverse était une faute
+    let mut previous_best: Option<i32> = None;
+    let mut ply = 1;
+    while ply < positions.len() {
+        let position = &positions[ply];
+        let scored = match search.score_moves(position, config.depth) {
+            Ok(scored) => scored,
+            Err(OutOfNodes) => {
+                previous_best = None;
+                ply += 1;
+                continue;
+            }
+        };
+        let best = scored.first().map(|(_, s)| *s);
+        let baseline = match previous_best {
+            Some(score) => -score,
+            None => material(position),
+        };
+        previous_best = best;
+
+        // Gain mesuré par rapport à la position avant la faute de l'adversaire
+        let winning = only_winning_move(&scored, config.min_gain).filter(|(_, score)| {
+            (is_mate_score(*score) && *score > 0) || *score - baseline >= config.min_gain
+        });
+        let found = winning.and_then(|(key, score)| {
+            follow_solution(&mut search, position, key, *score, config)
+                .ok()
+                .flatten()
+        });
+        match found {
+            Some(Solution { line, themes }) => {
+                let line_len = line.len();
+                let mut puzzle_moves = vec![moves[ply - 1].clone()];
+                puzzle_moves.extend(line);
+                // Un coup clé sans prise ni échec est plus difficile à trouver
+                let mut after_key = position.clone();
+                after_key.play(&puzzle_moves[1]);
+                let quiet_key = !is_capture(position, &puzzle_moves[1]) && !after_key.in_check();
+                puzzles.push(Puzzle {
+                    id: format!("{}{}", id_prefix, ply),
+                    fen: positions[ply - 1].fen(),
+                    rating: estimate_rating(line_len.div_ceil(2), quiet_key),
+                    deviation: 350,
+                    moves: puzzle_moves,
+                    themes,
+                });
+                // Pas de puzzle qui recoupe le précédent
+                ply += line_len + 1;
+                previous_best = None;
+            }
+            None => ply += 1,
+        }
+    }
+    puzzles
+}
+
+// Coups du joueur et réponses de l'adversaire, à partir du coup clé
+struct Solution {
+    line: Vec<String>,
+    themes: Vec<String>,
+}
+
+// Suit la solution tant qu'elle reste unique, None si elle n'aboutit pas
+fn follow_solution<P: MinerPosition>(
+    search: &mut Search,
+    root: &P,
+    key: &str,
+    key_score: i32,
+    config: &MinerConfig,
+) -> Result<Option<Solution>, OutOfNodes> {
+    let mating = is_mate_score(key_score);
+    let solver = root.turn();
+    let root_material = material(root);
+    let mut line = vec![key.to_string()];
+    let mut current = root.clone();
+    current.play(key);
+    let mut solver_moves = 1;
+
+    loop {
+        let checkmate = current.legal_moves().is_empty() && current.in_check();
+        if checkmate || solver_moves >= config.max_solver_moves {
+            break;
+        }
+        // Gain de matériel acquis: l'adversaire ne peut pas le reprendre
+        if !mating {
+            let realized = -search.quiesce(&current, -INFINITY, INFINITY, QUIESCENCE_DEPTH)?;
+            if realized - root_material >= config.min_gain {
+                break;
+            }
+        }
+        let replies = search.score_moves(&current, config.depth)?;
+        let Some((reply, _)) = replies.first() else {
+            break;
+        };
+        let mut next = current.clone();
+        next.play(reply);
+        let scored = search.score_moves(&next, config.depth)?;
+        let still_winning = |score: i32| {
+            if mating {
+                is_mate_score(score) && score > 0
+            } else {
+                score - root_material >= config.min_gain
+            }
+        };
+        match only_winning_move(&scored, config.min_gain) {
+            Some((mv, score)) if still_winning(*score) => {
+                line.push(reply.clone());
+                line.push(mv.clone());
+                current = next;
+                current.play(mv);
+                solver_moves += 1;
+            }
+            _ => break,
+        }
+    }
+
+    let checkmate = current.legal_moves().is_empty() && current.in_check();
+    // Une ligne de mat doit aller jusqu'au mat
+    if mating && !checkmate {
+        return Ok(None);
+    }
+    let mut themes = Vec::new();
+    if checkmate {
+        themes.push("mate".to_string());
+        themes.push(format!("mateIn{}", solver_moves));
+        if is_back_rank_mate(&current, &line[line.len() - 1], solver) {
+            themes.push("backRankMate".to_string());
+        }
+    } else if key_score - root_material >= 600 {
+        themes.push("crushing".to_string());
+    } else {
+        themes.push("advantage".to_string());
+    }
+    themes.extend(key_move_motifs(root, key));
+    themes.push(
+        match solver_moves {
+            1 => "oneMove",
+            2 => "short",
+            3 => "long",
+            _ => "veryLong",
+        }
+        .to_string(),
+    );
+    Ok(Some(Solution { line, themes }))
+}
+
+/// Rough rating of a generated puzzle: longer lines and quiet key moves
+/// (neither a capture nor a check) are harder. Players' results correct it afterwards.
+pub fn estimate_rating(solver_moves: usize, quiet_key: bool) -> u32 {
+    let mut rating = 1000 + 200 * solver_moves.saturating_sub(1) as u32;
+    if quiet_key {
+        rating += 250;
+    }
+    rating
+}
+
+// Roi maté sur sa première rangée par une tour ou une dame sur cette rangée
+fn is_back_rank_mate<P: MinerPosition>(position: &P, last_move: &str, solver: Color) -> bool {
+    let back_rank = if solver == Color::White { 7 } else { 0 };
+    let king =
+        (0..64).find(|&sq| position.piece_at(sq) == Some((solver.opposite(), PieceKind::King)));
+    let mating_piece =
+        move_squares(last_move).and_then(|(_, to)| position.piece_at(to).map(|p| (to, p)));
+    match (king, mating_piece) {
+        (Some(king), Some((to, (_, PieceKind::Rook | PieceKind::Queen)))) => {
+            king / 8 == back_rank && to / 8 == back_rank
+        }
+        _ => false,
+    }
+}
+
+// Motifs créés par le coup clé: fourchette, clouage, enfilade, attaque à la découverte
+fn key_move_motifs<P: MinerPosition>(root: &P, key: &str) -> Vec<String> {
+    let Some((from, to)) = move_squares(key) else {
+        return Vec::new();
+    };
+    let solver = root.turn();
+    let mut after = root.clone();
+    after.play(key);
+    let moved_to = square_name(to);
+    // Seuls les motifs de la pièce qui vient de jouer sont dus au coup
+    let mut found: Vec<String> = motifs(&after)
+        .into_iter()
+        .filter(|motif| motif.side() == solver)
+        .filter_map(|motif| match motif {
+            Motif::Fork { forker, .. } if forker == moved_to => Some("fork"),
+            Motif::Pin { pinner, .. } if pinner == moved_to => Some("pin"),
+            Motif::Skewer { attacker, .. } if attacker == moved_to => Some("skewer"),
+            _ => None,
+        })
+        .map(String::from)
+        .collect();
+
+    // Une pièce à longue portée que le coup démasque sur une cible de valeur
+    let discovered = (0..64)
+        .filter(|&sq| sq != to)
+        .any(|sq| match after.piece_at(sq) {
+            Some((color, kind)) if color == solver => {
+                slider_directions(kind).iter().any(|&(df, dr)| {
+                    let mut current = sq;
+                    let mut through_from = false;
+                    while let Some(next) = step(current, df, dr) {
+                        if next == from {
+                            through_from = true;
+                        }
+                        if let Some((c, k)) = after.piece_at(next) {
+                            return through_from
+                                && c != solver
+                                && (k == PieceKind::King
+                                    || piece_value(k) >= piece_value(PieceKind::Rook));
+                        }
+                        current = next;
+                    }
+                    false
+                })
+            }
+            _ => false,
+        });
+    if discovered {
+        found.push("discoveredAttack".to_string());
+    }
+    found.sort();
+    found.dedup();
+    found
+}
+
+/*
+impl MinerPosition for crate::game::Game {
+    fn fen(&self) -> String {
+        self.board.to_fen()
+    }
+}
+*/
@@ -1 +1,7 @@
 pub mod ai;
+pub mod book;
+pub mod eval;
+pub mod mate;
+pub mod miner;
+pub mod tablebase;
+pub(crate) mod syzygy;
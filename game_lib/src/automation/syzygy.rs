@@ -0,0 +1,787 @@
+//! Reader for the Syzygy file format: table header, canonical Huffman
+//! blocks expanded by recursive pairing, and the index of a position in a
+//! table. The layout is the one read by the reference prober (tbprobe).
+
+use std::sync::OnceLock;
+
+use super::tablebase::Wdl;
+use crate::analysis::BoardView;
+use crate::piece::Color;
+use crate::zobrist::PieceKind;
+
+// Drapeaux d'une table (octet de tête de chaque PairsData)
+const STM: u8 = 1;
+const MAPPED: u8 = 2;
+const WIN_PLIES: u8 = 4;
+const LOSS_PLIES: u8 = 8;
+const WIDE: u8 = 16;
+const SINGLE_VALUE: u8 = 128;
+
+const MAX_PIECES: usize = 7;
+
+/// Index tables shared by every Syzygy table.
+pub(crate) struct Indices {
+    /// Squares below the a1-h8 diagonal to 0..28.
+    pub map_b1h1h7: [u64; 64],
+    /// The a1-d1-d4 triangle to 0..10, diagonal squares last.
+    pub map_a1d1d4: [u64; 64],
+    /// The 462 placements of two kings, the first in the triangle.
+    pub map_kk: [[u64; 64]; 10],
+    pub binomial: [[u64; 64]; MAX_PIECES],
+    /// Pawn squares a2-h7 to 0..48, edge files and low ranks highest.
+    pub map_pawns: [u64; 64],
+    pub lead_pawn_idx: [[u64; 64]; MAX_PIECES],
+    pub lead_pawns_size: [[u64; 4]; MAX_PIECES],
+}
+
+fn off_a1h8(square: u8) -> i32 {
+    (square / 8) as i32 - (square % 8) as i32
+}
+
+fn king_touches(a: u8, b: u8) -> bool {
+    ((a % 8) as i32 - (b % 8) as i32).abs() <= 1 && ((a / 8) as i32 - (b / 8) as i32).abs() <= 1
+}
+
+pub(crate) fn indices() -> &'static Indices {
+    static INDICES: OnceLock<Indices> = OnceLock::new();
+    INDICES.get_or_init(|| {
+        let mut ix = Indices {
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+            binomial: [[0; 64]; MAX_PIECES],
+            map_pawns: [0; 64],
+            lead_pawn_idx: [[0; 64]; MAX_PIECES],
+            lead_pawns_size: [[0; 4]; MAX_PIECES],
+        };
+        let mut code = 0;
+        for sq in 0..64u8 {
+            if off_a1h8(sq) < 0 {
+                ix.map_b1h1h7[sq as usize] = code;
+                code += 1;
+            }
+        }
+        // Le triangle a1-d1-d4, les cases de la diagonale en dernier
+        let mut diagonal = Vec::new();
+        code = 0;
+        for sq in 0..28u8 {
+            if off_a1h8(sq) < 0 && sq % 8 <= 3 {
+                ix.map_a1d1d4[sq as usize] = code;
+                code += 1;
+            } else if off_a1h8(sq) == 0 && sq % 8 <= 3 {
+                diagonal.push(sq);
+            }
+        }
+        for sq in diagonal {
+            ix.map_a1d1d4[sq as usize] = code;
+            code += 1;
+        }
+        // Deux rois: si le premier est sur la diagonale, le second n'est
+        // jamais au-dessus, et les deux sur la diagonale viennent en dernier
+        let mut both_on_diagonal = Vec::new();
+        code = 0;
+        for idx in 0..10 {
+            for s1 in 0..28u8 {
+                // b1 vaut 0, comme les cases hors du triangle
+                if ix.map_a1d1d4[s1 as usize] != idx || (idx == 0 && s1 != 1) {
+                    continue;
+                }
+                for s2 in 0..64u8 {
+                    if king_touches(s1, s2) || (off_a1h8(s1) == 0 && off_a1h8(s2) > 0) {
+                        continue;
+                    }
+                    if off_a1h8(s1) == 0 && off_a1h8(s2) == 0 {
+                        both_on_diagonal.push((idx, s2));
+                    } else {
+                        ix.map_kk[idx as usize][s2 as usize] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (idx, s2) in both_on_diagonal {
+            ix.map_kk[idx as usize][s2 as usize] = code;
+            code += 1;
+        }
+        ix.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..MAX_PIECES.min(n + 1) {
+                ix.binomial[k][n] = if k > 0 { ix.binomial[k - 1][n - 1] } else { 0 }
+                    + if k < n { ix.binomial[k][n - 1] } else { 0 };
+            }
+        }
+        // Les pions de tête: le plus près du bord et le plus bas d'abord
+        let mut available = 47;
+        for lead in 1..MAX_PIECES {
+            for file in 0..4u8 {
+                let mut idx = 0;
+                for rank in 1..7u8 {
+                    let sq = (rank * 8 + file) as usize;
+                    if lead == 1 {
+                        ix.map_pawns[sq] = available;
+                        ix.map_pawns[sq ^ 7] = available - 1;
+                        available = available.saturating_sub(2);
+                    }
+                    ix.lead_pawn_idx[lead][sq] = idx;
+                    idx += ix.binomial[lead - 1][ix.map_pawns[sq] as usize];
+                }
+                ix.lead_pawns_size[lead][file as usize] = idx;
+            }
+        }
+        ix
+    })
+}
+
+fn byte(bytes: &[u8], at: usize) -> Result<u8, String> {
+    bytes.get(at).copied().ok_or_else(truncated)
+}
+
+fn le16(bytes: &[u8], at: usize) -> Result<u16, String> {
+    Ok(u16::from_le_bytes([byte(bytes, at)?, byte(bytes, at + 1)?]))
+}
+
+fn le32(bytes: &[u8], at: usize) -> Result<u32, String> {
+    let b = bytes.get(at..at + 4).ok_or_else(truncated)?;
+    Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn be32(bytes: &[u8], at: usize) -> Result<u32, String> {
+    let b = bytes.get(at..at + 4).ok_or_else(truncated)?;
+    Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn truncated() -> String {
+    "Truncated Syzygy table".into()
+}
+
+// Quartet d'un fichier: bit 3 pour les noirs, 1 à 6 du pion au roi
+fn piece_of_nibble(nibble: u8) -> Result<(Color, PieceKind), String> {
+    let color = if nibble & 8 == 0 {
+        Color::White
+    } else {
+        Color::Black
+    };
+    let kind = match nibble & 7 {
+        1 => PieceKind::Pawn,
+        2 => PieceKind::Knight,
+        3 => PieceKind::Bishop,
+        4 => PieceKind::Rook,
+        5 => PieceKind::Queen,
+        6 => PieceKind::King,
+        _ => return Err(format!("Invalid piece {} in Syzygy table", nibble)),
+    };
+    Ok((color, kind))
+}
+
+/// Material of a table, read from its name ("KRPvKR"), White being the
+/// side written first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Material {
+    pub piece_count: usize,
+    pub has_pawns: bool,
+    /// Some side has exactly one piece of a kind other than the king.
+    pub has_unique_pieces: bool,
+    /// Pawns of the leading side, the one with fewer pawns, then the other.
+    pub pawn_count: [usize; 2],
+    /// Both sides have the same pieces ("KRvKR").
+    pub symmetric: bool,
+}
+
+impl Material {
+    pub fn from_name(name: &str) -> Option<Material> {
+        let (white, black) = name.split_once('v')?;
+        let count = |side: &str, c: char| side.chars().filter(|&x| x == c).count();
+        let has_unique_pieces = [white, black]
+            .iter()
+            .any(|side| "QRBNP".chars().any(|c| count(side, c) == 1));
+        let (white_pawns, black_pawns) = (count(white, 'P'), count(black, 'P'));
+        // Le camp de tête est celui qui a le moins de pions
+        let white_leads = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+        let pawn_count = if white_leads {
+            [white_pawns, black_pawns]
+        } else {
+            [black_pawns, white_pawns]
+        };
+        Some(Material {
+            piece_count: white.len() + black.len(),
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces,
+            pawn_count,
+            symmetric: white == black,
+        })
+    }
+
+    // Pions dans les deux camps: les pions restants forment un groupe à part
+    fn both_sides_have_pawns(&self) -> bool {
+        self.has_pawns && self.pawn_count[1] > 0
+    }
+}
+
+/// Compressed values of one side to move and one lead pawn file.
+#[derive(Debug, Clone, Default)]
+struct PairsData {
+    flags: u8,
+    pieces: Vec<(Color, PieceKind)>,
+    group_len: Vec<usize>,
+    group_idx: Vec<u64>,
+    size_of_block: u64,
+    span: u64,
+    blocks_num: u64,
+    block_length_size: u64,
+    sparse_index_size: u64,
+    max_sym_len: u8,
+    min_sym_len: u8,
+    lowest_sym: usize,
+    base64: Vec<u64>,
+    symlen: Vec<u8>,
+    btree: usize,
+    sparse_index: usize,
+    block_length: usize,
+    data: usize,
+    map_idx: [usize; 4],
+}
+
+impl PairsData {
+    fn new(
+        material: &Material,
+        pieces: Vec<(Color, PieceKind)>,
+        order: [usize; 2],
+        file: usize,
+    ) -> PairsData {
+        let ix = indices();
+        let mut group_len = vec![1];
+        // Les premières pièces vont ensemble: les deux rois, ou trois pièces
+        // uniques; sans pions, une pièce de même sorte rejoint le groupe
+        let mut first_len: i32 = if material.has_pawns {
+            0
+        } else if material.has_unique_pieces {
+            3
+        } else {
+            2
+        };
+        for i in 1..pieces.len() {
+            first_len -= 1;
+            if first_len > 0 || pieces[i] == pieces[i - 1] {
+                *group_len.last_mut().unwrap() += 1;
+            } else {
+                group_len.push(1);
+            }
+        }
+        let n = group_len.len();
+        group_len.push(0);
+
+        let pp = material.both_sides_have_pawns();
+        let mut next = if pp { 2 } else { 1 };
+        let mut free_squares = 64 - group_len[0] - if pp { group_len[1] } else { 0 };
+        let mut group_idx = vec![0; n + 1];
+        let mut idx = 1u64;
+        let mut k = 0;
+        while next < n || k == order[0] || k == order[1] {
+            if k == order[0] {
+                group_idx[0] = idx;
+                idx *= if material.has_pawns {
+                    ix.lead_pawns_size[group_len[0]][file]
+                } else if material.has_unique_pieces {
+                    31332
+                } else {
+                    462
+                };
+            } else if k == order[1] {
+                group_idx[1] = idx;
+                idx *= ix.binomial[group_len[1]][48 - group_len[0]];
+            } else {
+                group_idx[next] = idx;
+                idx *= ix.binomial[group_len[next]][free_squares];
+                free_squares -= group_len[next];
+                next += 1;
+            }
+            k += 1;
+        }
+        group_idx[n] = idx;
+        PairsData {
+            pieces,
+            group_len,
+            group_idx,
+            ..PairsData::default()
+        }
+    }
+
+    // Nombre de positions de la table, bornant l'index
+    fn table_size(&self) -> u64 {
+        let n = self.group_len.iter().position(|&l| l == 0).unwrap_or(0);
+        self.group_idx[n]
+    }
+
+    // Lit les tailles et le code de Huffman canonique, rend la position suivante
+    fn read_sizes(&mut self, bytes: &[u8], mut at: usize) -> Result<usize, String> {
+        self.flags = byte(bytes, at)?;
+        at += 1;
+        if self.flags & SINGLE_VALUE != 0 {
+            // La seule valeur de la table est rangée à la place de min_sym_len
+            self.min_sym_len = byte(bytes, at)?;
+            return Ok(at + 1);
+        }
+        self.size_of_block = 1 << byte(bytes, at)?;
+        self.span = 1 << byte(bytes, at + 1)?;
+        self.sparse_index_size = self.table_size().div_ceil(self.span);
+        let padding = byte(bytes, at + 2)? as u64;
+        self.blocks_num = le32(bytes, at + 3)? as u64;
+        self.block_length_size = self.blocks_num + padding;
+        self.max_sym_len = byte(bytes, at + 7)?;
+        self.min_sym_len = byte(bytes, at + 8)?;
+        at += 9;
+        if self.max_sym_len < self.min_sym_len {
+            return Err("Invalid symbol lengths in Syzygy table".into());
+        }
+        self.lowest_sym = at;
+        // Code canonique: base64[l] est le plus petit code de longueur
+        // min_sym_len + l, aligné à gauche sur 64 bits
+        let lengths = (self.max_sym_len - self.min_sym_len) as usize + 1;
+        let mut base64 = vec![0u64; lengths];
+        for i in (0..lengths - 1).rev() {
+            let lowest = le16(bytes, self.lowest_sym + 2 * i)? as u64;
+            let next = le16(bytes, self.lowest_sym + 2 * (i + 1))? as u64;
+            base64[i] = base64[i + 1].wrapping_add(lowest).wrapping_sub(next) / 2;
+        }
+        for (i, base) in base64.iter_mut().enumerate() {
+            let shift = 64 - i as u32 - self.min_sym_len as u32;
+            *base = base.checked_shl(shift).unwrap_or(0);
+        }
+        self.base64 = base64;
+        at += 2 * lengths;
+
+        let symbols = le16(bytes, at)? as usize;
+        at += 2;
+        self.btree = at;
+        if bytes.len() < at + 3 * symbols {
+            return Err(truncated());
+        }
+        self.symlen = vec![0; symbols];
+        let mut visited = vec![false; symbols];
+        for sym in 0..symbols {
+            if !visited[sym] {
+                self.symlen[sym] = self.set_symlen(bytes, sym, &mut visited)?;
+            }
+        }
+        Ok(at + 3 * symbols + (symbols & 1))
+    }
+
+    // Nombre de valeurs (moins une) qu'un symbole développe
+    fn set_symlen(&mut self, bytes: &[u8], sym: usize, visited: &mut [bool]) -> Result<u8, String> {
+        visited[sym] = true;
+        let (left, right) = self.children(bytes, sym);
+        if right == 0xFFF {
+            return Ok(0);
+        }
+        for child in [left, right] {
+            if child >= self.symlen.len() {
+                return Err("Invalid symbol in Syzygy table".into());
+            }
+            if !visited[child] {
+                self.symlen[child] = self.set_symlen(bytes, child, visited)?;
+            }
+        }
+        Ok(self.symlen[left]
+            .wrapping_add(self.symlen[right])
+            .wrapping_add(1))
+    }
+
+    // Paire d'un symbole: 12 bits à gauche, 12 bits à droite
+    fn children(&self, bytes: &[u8], sym: usize) -> (usize, usize) {
+        let lr = &bytes[self.btree + 3 * sym..self.btree + 3 * sym + 3];
+        let left = ((lr[1] as usize & 0xF) << 8) | lr[0] as usize;
+        let right = ((lr[2] as usize) << 4) | (lr[1] as usize >> 4);
+        (left, right)
+    }
+
+    // Valeur rangée à l'index `idx`
+    fn decompress(&self, bytes: &[u8], idx: u64) -> Result<u16, String> {
+        if self.flags & SINGLE_VALUE != 0 {
+            return Ok(self.min_sym_len as u16);
+        }
+        // L'index clairsemé donne le bloc d'un index proche, puis on avance
+        // de bloc en bloc jusqu'au bon
+        let k = idx / self.span;
+        if k >= self.sparse_index_size {
+            return Err("Index out of the Syzygy table".into());
+        }
+        let entry = self.sparse_index + 6 * k as usize;
+        let mut block = le32(bytes, entry)? as u64;
+        let mut offset = le16(bytes, entry + 4)? as i64;
+        offset += (idx % self.span) as i64 - (self.span / 2) as i64;
+        let block_length = |block: u64| -> Result<i64, String> {
+            if block >= self.block_length_size {
+                return Err("Block out of the Syzygy table".into());
+            }
+            Ok(le16(bytes, self.block_length + 2 * block as usize)? as i64)
+        };
+        while offset < 0 {
+            block = block
+                .checked_sub(1)
+                .ok_or("Block out of the Syzygy table")?;
+            offset += block_length(block)? + 1;
+        }
+        while offset > block_length(block)? {
+            offset -= block_length(block)? + 1;
+            block += 1;
+        }
+
+        // Bloc de symboles de Huffman, lus en big-endian
+        let mut ptr = self.data + (block * self.size_of_block) as usize;
+        let mut buf64 = ((be32(bytes, ptr)? as u64) << 32) | be32(bytes, ptr + 4)? as u64;
+        ptr += 8;
+        let mut buf_size = 64;
+        let min = self.min_sym_len as usize;
+        let mut sym;
+        loop {
+            let mut len = 0;
+            while buf64 < self.base64[len] {
+                len += 1;
+            }
+            sym = (buf64 - self.base64[len])
+                .checked_shr((64 - len - min) as u32)
+                .unwrap_or(0) as usize;
+            sym += le16(bytes, self.lowest_sym + 2 * len)? as usize;
+            let expands = *self
+                .symlen
+                .get(sym)
+                .ok_or("Invalid symbol in Syzygy table")? as i64
+                + 1;
+            if offset < expands {
+                break;
+            }
+            offset -= expands;
+            len += min;
+            buf64 <<= len;
+            buf_size -= len;
+            if buf_size <= 32 {
+                buf_size += 32;
+                buf64 |= (be32(bytes, ptr)? as u64) << (64 - buf_size);
+                ptr += 4;
+            }
+        }
+        // Le symbole trouvé se développe en paires: on descend jusqu'à la
+        // feuille qui porte la valeur
+        while self.symlen[sym] != 0 {
+            let (left, right) = self.children(bytes, sym);
+            if offset < self.symlen[left] as i64 + 1 {
+                sym = left;
+            } else {
+                offset -= self.symlen[left] as i64 + 1;
+                sym = right;
+            }
+        }
+        Ok(self.children(bytes, sym).0 as u16)
+    }
+}
+
+/// A WDL or DTZ table read into memory.
+pub(crate) struct Table {
+    bytes: Vec<u8>,
+    dtz: bool,
+    material: Material,
+    /// Indexed by side to move, then by lead pawn file.
+    pairs: Vec<Vec<PairsData>>,
+    map: usize,
+}
+
+impl Table {
+    /// Reads the header of a table whose magic has been checked. `name` is
+    /// the material of the file name.
+    pub fn read(bytes: Vec<u8>, name: &str, dtz: bool) -> Result<Table, String> {
+        let material =
+            Material::from_name(name).ok_or_else(|| format!("Invalid table name {}", name))?;
+        if material.piece_count > MAX_PIECES {
+            return Err(format!("{} has too many pieces", name));
+        }
+        let mut at = 4;
+        let flags = byte(&bytes, at)?;
+        at += 1;
+        if (flags & 2 != 0) != material.has_pawns {
+            return Err(format!("{} does not match its header", name));
+        }
+        let sides = if !dtz && !material.symmetric { 2 } else { 1 };
+        let files = if material.has_pawns { 4 } else { 1 };
+        let pp = material.both_sides_have_pawns();
+
+        let mut pairs: Vec<Vec<PairsData>> = vec![Vec::new(); sides];
+        for file in 0..files {
+            let first = byte(&bytes, at)?;
+            let second = if pp { byte(&bytes, at + 1)? } else { 0xFF };
+            let order = [
+                [(first & 0xF) as usize, (second & 0xF) as usize],
+                [(first >> 4) as usize, (second >> 4) as usize],
+            ];
+            at += 1 + pp as usize;
+            let mut pieces = vec![Vec::new(); sides];
+            for _ in 0..material.piece_count {
+                let b = byte(&bytes, at)?;
+                for (side, pieces) in pieces.iter_mut().enumerate() {
+                    pieces.push(piece_of_nibble(if side == 1 { b >> 4 } else { b & 0xF })?);
+                }
+                at += 1;
+            }
+            for (side, pieces) in pieces.into_iter().enumerate() {
+                pairs[side].push(PairsData::new(&material, pieces, order[side], file));
+            }
+        }
+        at += at & 1;
+        for file in 0..files {
+            for side in pairs.iter_mut() {
+                at = side[file].read_sizes(&bytes, at)?;
+            }
+        }
+
+        let map = at;
+        if dtz {
+            // Valeurs de DTZ par résultat: perte, défaite sauvée, gain maudit
+            for d in pairs[0].iter_mut() {
+                if d.flags & MAPPED == 0 {
+                    continue;
+                }
+                if d.flags & WIDE != 0 {
+                    at += at & 1;
+                    for i in 0..4 {
+                        d.map_idx[i] = (at - map) / 2 + 1;
+                        at += 2 * le16(&bytes, at)? as usize + 2;
+                    }
+                } else {
+                    for i in 0..4 {
+                        d.map_idx[i] = at - map + 1;
+                        at += byte(&bytes, at)? as usize + 1;
+                    }
+                }
+            }
+            at += at & 1;
+        }
+
+        for file in 0..files {
+            for side in pairs.iter_mut() {
+                side[file].sparse_index = at;
+                at += 6 * side[file].sparse_index_size as usize;
+            }
+        }
+        for file in 0..files {
+            for side in pairs.iter_mut() {
+                side[file].block_length = at;
+                at += 2 * side[file].block_length_size as usize;
+            }
+        }
+        for file in 0..files {
+            for side in pairs.iter_mut() {
+                // Les blocs sont alignés sur 64 octets
+                at = (at + 0x3F) & !0x3F;
+                side[file].data = at;
+                at += (side[file].blocks_num * side[file].size_of_block) as usize;
+            }
+        }
+        if at > bytes.len() {
+            return Err(format!("{} is truncated", name));
+        }
+        Ok(Table {
+            bytes,
+            dtz,
+            material,
+            pairs,
+            map,
+        })
+    }
+
+    /// Raw value of the position, or None when a DTZ table only stores the
+    /// other side to move. `black_stronger` is true when the table stores
+    /// the position with the colours swapped. Also gives the lead pawn file.
+    pub fn probe<P: BoardView>(
+        &self,
+        position: &P,
+        black_stronger: bool,
+    ) -> Result<Option<(u16, usize)>, String> {
+        let ix = indices();
+        let material = &self.material;
+        // Les tables symétriques ne stockent que les blancs au trait
+        let flip = black_stronger || (material.symmetric && position.turn() == Color::Black);
+        let flip_squares = if flip { 56 } else { 0 };
+        let flip_color = |color: Color| if flip { color.opposite() } else { color };
+        let stm = flip as usize ^ (position.turn() == Color::Black) as usize;
+
+        let occupied: Vec<(u8, (Color, PieceKind))> = (0..64)
+            .filter_map(|sq| position.piece_at(sq).map(|p| (sq, p)))
+            .collect();
+        if occupied.len() != material.piece_count {
+            return Err("Position does not match the Syzygy table".into());
+        }
+        let mut squares = Vec::with_capacity(MAX_PIECES);
+        let mut pieces = Vec::with_capacity(MAX_PIECES);
+        let mut lead_pawns = 0;
+        let mut file = 0;
+        let mut lead_color = None;
+        if material.has_pawns {
+            // Les pions de tête sont en tête de chaque table
+            let lead = self.pairs[0][0].pieces[0];
+            let color = flip_color(lead.0);
+            for &(sq, piece) in occupied
+                .iter()
+                .filter(|(_, p)| *p == (color, PieceKind::Pawn))
+            {
+                squares.push(sq ^ flip_squares);
+                pieces.push((flip_color(piece.0), piece.1));
+            }
+            lead_pawns = squares.len();
+            let mut best = 0;
+            for i in 1..lead_pawns {
+                if ix.map_pawns[squares[i] as usize] > ix.map_pawns[squares[best] as usize] {
+                    best = i;
+                }
+            }
+            squares.swap(0, best);
+            let f = (squares[0] % 8) as usize;
+            file = f.min(7 - f);
+            lead_color = Some(color);
+        }
+
+        if self.dtz {
+            let flags = self.pairs[0][file].flags;
+            if (flags & STM) as usize != stm && (material.has_pawns || !material.symmetric) {
+                return Ok(None);
+            }
+        }
+
+        for &(sq, piece) in &occupied {
+            if lead_color.is_some_and(|c| piece == (c, PieceKind::Pawn)) {
+                continue;
+            }
+            squares.push(sq ^ flip_squares);
+            pieces.push((flip_color(piece.0), piece.1));
+        }
+        let d = &self.pairs[stm % self.pairs.len()][file];
+        // Même ordre de pièces que la table
+        for i in lead_pawns..squares.len().saturating_sub(1) {
+            for j in i + 1..squares.len() {
+                if d.pieces[i] == pieces[j] {
+                    pieces.swap(i, j);
+                    squares.swap(i, j);
+                    break;
+                }
+            }
+        }
+        // La première pièce va dans les colonnes a-d
+        if squares[0] % 8 > 3 {
+            for sq in squares.iter_mut() {
+                *sq ^= 7;
+            }
+        }
+
+        let mut idx;
+        if material.has_pawns {
+            idx = ix.lead_pawn_idx[lead_pawns][squares[0] as usize];
+            squares[1..lead_pawns].sort_by_key(|&sq| ix.map_pawns[sq as usize]);
+            for (i, &sq) in squares.iter().enumerate().take(lead_pawns).skip(1) {
+                idx += ix.binomial[i][ix.map_pawns[sq as usize] as usize];
+            }
+        } else {
+            if squares[0] / 8 > 3 {
+                for sq in squares.iter_mut() {
+                    *sq ^= 56;
+                }
+            }
+            // La première pièce hors de la diagonale a1-h8 passe dessous
+            for i in 0..d.group_len[0] {
+                let off = off_a1h8(squares[i]);
+                if off == 0 {
+                    continue;
+                }
+                if off > 0 {
+                    for sq in squares[i..].iter_mut() {
+                        *sq = ((*sq >> 3) | (*sq << 3)) & 63;
+                    }
+                }
+                break;
+            }
+            idx = if material.has_unique_pieces {
+                unique_pieces_index(&squares)
+            } else {
+                ix.map_kk[ix.map_a1d1d4[squares[0] as usize] as usize][squares[1] as usize]
+            };
+        }
+
+        // Les autres groupes, cases croissantes, chacun dans les cases libres
+        idx *= d.group_idx[0];
+        let mut start = d.group_len[0];
+        let mut remaining_pawns = material.both_sides_have_pawns();
+        let mut next = 1;
+        while d.group_len[next] != 0 {
+            let len = d.group_len[next];
+            squares[start..start + len].sort_unstable();
+            let mut n = 0;
+            for i in 0..len {
+                let sq = squares[start + i];
+                let adjust = squares[..start].iter().filter(|&&s| sq > s).count();
+                let free = (sq as usize)
+                    .checked_sub(adjust + if remaining_pawns { 8 } else { 0 })
+                    .ok_or("Position does not match the Syzygy table")?;
+                n += ix.binomial[i + 1][free];
+            }
+            remaining_pawns = false;
+            idx += n * d.group_idx[next];
+            start += len;
+            next += 1;
+        }
+        Ok(Some((d.decompress(&self.bytes, idx)?, file)))
+    }
+
+    /// DTZ in plies from a raw DTZ value, for a position of the given WDL.
+    pub fn dtz_plies(&self, file: usize, value: u16, wdl: Wdl) -> Result<i32, String> {
+        let d = &self.pairs[0][file];
+        let mut value = value as usize;
+        if d.flags & MAPPED != 0 {
+            let map = match wdl {
+                Wdl::Win | Wdl::Draw => 0,
+                Wdl::Loss => 1,
+                Wdl::CursedWin => 2,
+                Wdl::BlessedLoss => 3,
+            };
+            let idx = d.map_idx[map];
+            value = if d.flags & WIDE != 0 {
+                le16(&self.bytes, self.map + 2 * (idx + value))? as usize
+            } else {
+                byte(&self.bytes, self.map + idx + value)? as usize
+            };
+        }
+        let mut value = value as i32;
+        // Les tables comptent en coups ou en demi-coups selon le résultat
+        if (wdl == Wdl::Win && d.flags & WIN_PLIES == 0)
+            || (wdl == Wdl::Loss && d.flags & LOSS_PLIES == 0)
+            || wdl == Wdl::CursedWin
+            || wdl == Wdl::BlessedLoss
+        {
+            value *= 2;
+        }
+        Ok(value + 1)
+    }
+}
+
+// Trois pièces uniques (rois compris) placées ensemble
+fn unique_pieces_index(squares: &[u8]) -> u64 {
+    let ix = indices();
+    let (s0, s1, s2) = (squares[0] as u64, squares[1] as u64, squares[2] as u64);
+    let adjust1 = (s1 > s0) as u64;
+    let adjust2 = (s2 > s0) as u64 + (s2 > s1) as u64;
+    let rank = |sq: u64| sq >> 3;
+    if off_a1h8(squares[0]) != 0 {
+        (ix.map_a1d1d4[s0 as usize] * 63 + (s1 - adjust1)) * 62 + s2 - adjust2
+    } else if off_a1h8(squares[1]) != 0 {
+        (6 * 63 + rank(s0) * 28 + ix.map_b1h1h7[s1 as usize]) * 62 + s2 - adjust2
+    } else if off_a1h8(squares[2]) != 0 {
+        6 * 63 * 62
+            + 4 * 28 * 62
+            + rank(s0) * 7 * 28
+            + (rank(s1) - adjust1) * 28
+            + ix.map_b1h1h7[s2 as usize]
+    } else {
+        6 * 63 * 62
+            + 4 * 28 * 62
+            + 4 * 7 * 28
+            + rank(s0) * 7 * 6
+            + (rank(s1) - adjust1) * 6
+            + (rank(s2) - adjust2)
+    }
+}
@@ -0,0 +1,464 @@
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use super::syzygy::Table;
+use crate::analysis::{move_squares, BoardView};
+use crate::automation::mate::MatePosition;
+use crate::piece::Color;
+use crate::zobrist::PieceKind;
+
+// Premiers octets des fichiers Syzygy (little-endian 0x5d23e871 et 0xa50c66d7)
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+
+/// Win/draw/loss from the side to move, with the 50-move rule taken into
+/// account: a cursed win is a win that the 50-move rule turns into a draw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+
+impl Wdl {
+    /// The same result seen from the other side.
+    pub fn opposite(self) -> Wdl {
+        match self {
+            Wdl::Loss => Wdl::Win,
+            Wdl::BlessedLoss => Wdl::CursedWin,
+            Wdl::Draw => Wdl::Draw,
+            Wdl::CursedWin => Wdl::BlessedLoss,
+            Wdl::Win => Wdl::Loss,
+        }
+    }
+
+    fn from_value(value: u16) -> Result<Wdl, String> {
+        Ok(match value {
+            0 => Wdl::Loss,
+            1 => Wdl::BlessedLoss,
+            2 => Wdl::Draw,
+            3 => Wdl::CursedWin,
+            4 => Wdl::Win,
+            _ => return Err(format!("Invalid WDL value {}", value)),
+        })
+    }
+
+    fn sign(self) -> i32 {
+        match self {
+            Wdl::Loss | Wdl::BlessedLoss => -1,
+            Wdl::Draw => 0,
+            Wdl::CursedWin | Wdl::Win => 1,
+        }
+    }
+
+    // DTZ d'un coup qui remet le compteur des 50 coups à zéro
+    fn dtz_before_zeroing(self) -> Dtz {
+        match self {
+            Wdl::Loss => -1,
+            Wdl::BlessedLoss => -101,
+            Wdl::Draw => 0,
+            Wdl::CursedWin => 101,
+            Wdl::Win => 1,
+        }
+    }
+}
+
+/// Distance to the next zeroing move (capture or pawn move), in plies.
+/// Positive when the side to move wins.
+pub type Dtz = i32;
+
+/// What probing needs beyond the moves. The tables have no castling, and
+/// en passant captures are found by searching the captures.
+pub trait ProbePosition: MatePosition + BoardView {
+    fn en_passant(&self) -> Option<u8>;
+    fn has_castling_rights(&self) -> bool;
+    /// Plies since the last capture or pawn move.
+    fn halfmove_clock(&self) -> u32;
+}
+
+/// A legal move with the verdict of the tables after it, for the side
+/// playing it. `dtz` counts from the position before the move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TablebaseMove {
+    pub mv: String,
+    pub wdl: Wdl,
+    pub dtz: Dtz,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableFiles {
+    pub wdl: Option<PathBuf>,
+    pub dtz: Option<PathBuf>,
+}
+
+/// Syzygy tables found in a directory, indexed by material ("KQvK").
+/// A table is read into memory the first time it is probed.
+#[derive(Default)]
+pub struct Tablebases {
+    tables: HashMap<String, TableFiles>,
+    max_pieces: usize,
+    loaded: RwLock<HashMap<PathBuf, Arc<Table>>>,
+}
+
+impl fmt::Debug for Tablebases {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tablebases")
+            .field("tables", &self.tables.len())
+            .field("max_pieces", &self.max_pieces)
+            .finish()
+    }
+}
+
+// Un coup qui remet le compteur des 50 coups à zéro: prise ou coup de pion
+fn is_zeroing<P: ProbePosition>(position: &P, mv: &str) -> bool {
+    move_squares(mv).is_some_and(|(from, to)| {
+        position.piece_at(to).is_some()
+            || matches!(position.piece_at(from), Some((_, PieceKind::Pawn)))
+    })
+}
+
+fn is_capture<P: ProbePosition>(position: &P, mv: &str) -> bool {
+    move_squares(mv).is_some_and(|(from, to)| {
+        position.piece_at(to).is_some()
+            || (matches!(position.piece_at(from), Some((_, PieceKind::Pawn)))
+                && position.en_passant() == Some(to))
+    })
+}
+
+fn pieces_of<P: BoardView>(position: &P) -> Vec<(Color, PieceKind)> {
+    (0..64).filter_map(|sq| position.piece_at(sq)).collect()
+}
+
+// Classement d'un coup à la racine: gains sûrs, puis gains rattrapés par
+// la règle des 50 coups, nulles, et pertes dans l'ordre inverse
+const MAX_DTZ: i32 = 1 << 18;
+
+impl Tablebases {
+    /// Scans `dir` for `.rtbw` (WDL) and `.rtbz` (DTZ) files. Files whose
+    /// header is not a Syzygy one are rejected.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, String> {
+        let dir = dir.as_ref();
+        let entries =
+            fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        let mut tb = Tablebases::default();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let (material, ext) = match (
+                path.file_stem().and_then(|s| s.to_str()),
+                path.extension().and_then(|s| s.to_str()),
+            ) {
+                (Some(stem), Some(ext)) if ext == "rtbw" || ext == "rtbz" => {
+                    (stem.to_string(), ext)
+                }
+                _ => continue,
+            };
+            if !is_material_name(&material) {
+                continue;
+            }
+            let magic = if ext == "rtbw" { WDL_MAGIC } else { DTZ_MAGIC };
+            check_magic(&path, magic)?;
+
+            tb.max_pieces = tb.max_pieces.max(material.len() - 1);
+            let files = tb.tables.entry(material).or_insert(TableFiles {
+                wdl: None,
+                dtz: None,
+            });
+            if ext == "rtbw" {
+                files.wdl = Some(path);
+            } else {
+                files.dtz = Some(path);
+            }
+        }
+        Ok(tb)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// Largest number of pieces, kings included, covered by the tables.
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    /// Files of the table covering this material. The flag is true when the
+    /// table is stored with the colours swapped, i.e. Black is the side
+    /// written first in the file name.
+    pub fn find(&self, pieces: &[(Color, PieceKind)]) -> Option<(&TableFiles, bool)> {
+        if pieces.len() > self.max_pieces {
+            return None;
+        }
+        let white = material_key(pieces, Color::White);
+        if let Some(files) = self.tables.get(&white) {
+            return Some((files, false));
+        }
+        let black = material_key(pieces, Color::Black);
+        self.tables.get(&black).map(|files| (files, true))
+    }
+
+    /// True when the tables can answer for the position: few enough
+    /// pieces and no castling rights.
+    pub fn covers<P: ProbePosition>(&self, position: &P) -> bool {
+        !position.has_castling_rights() && pieces_of(position).len() <= self.max_pieces
+    }
+
+    /// WDL value of the position for the side to move.
+    pub fn probe_wdl<P: ProbePosition>(&self, position: &P) -> Result<Wdl, String> {
+        self.check_covered(position)?;
+        Ok(self.search(position, false)?.0)
+    }
+
+    /// DTZ value of the position for the side to move, 0 for a draw.
+    pub fn probe_dtz<P: ProbePosition>(&self, position: &P) -> Result<Dtz, String> {
+        self.check_covered(position)?;
+        self.dtz(position)
+    }
+
+    /// Every legal move ranked by the tables, best first: wins that the
+    /// 50-move rule cannot spoil, shortest DTZ first, down to the losses
+    /// that hold out longest.
+    pub fn root_moves<P: ProbePosition>(&self, position: &P) -> Result<Vec<TablebaseMove>, String> {
+        self.check_covered(position)?;
+        let halfmove = position.halfmove_clock() as i32;
+        let mut ranked = Vec::new();
+        for mv in position.legal_moves() {
+            let zeroing = is_zeroing(position, &mv);
+            let mut next = position.clone();
+            next.play(&mv);
+            let mut dtz = if zeroing {
+                self.search(&next, false)?.0.opposite().dtz_before_zeroing()
+            } else {
+                let dtz = -self.dtz(&next)?;
+                dtz + dtz.signum()
+            };
+            // Un coup qui mate compte pour un demi-coup
+            if dtz == 2 && next.in_check() && next.legal_moves().is_empty() {
+                dtz = 1;
+            }
+            let rank = match dtz {
+                d if d > 0 && d + halfmove <= 99 => MAX_DTZ,
+                d if d > 0 => MAX_DTZ - (d + halfmove),
+                d if d < 0 && -d * 2 + halfmove < 100 => -MAX_DTZ,
+                d if d < 0 => -MAX_DTZ + (-d + halfmove),
+                _ => 0,
+            };
+            let wdl = match rank {
+                MAX_DTZ => Wdl::Win,
+                r if r > 0 => Wdl::CursedWin,
+                0 => Wdl::Draw,
+                r if r == -MAX_DTZ => Wdl::Loss,
+                _ => Wdl::BlessedLoss,
+            };
+            ranked.push((rank, TablebaseMove { mv, wdl, dtz }));
+        }
+        // À rang égal, le gain le plus court et la perte la plus longue
+        ranked.sort_by_key(|(rank, m)| std::cmp::Reverse((*rank, -m.dtz)));
+        Ok(ranked.into_iter().map(|(_, m)| m).collect())
+    }
+
+    /// Move that keeps the best result the tables allow, or None when the
+    /// position is not in the tables.
+    pub fn best_move<P: ProbePosition>(&self, position: &P) -> Option<String> {
+        if !self.covers(position) {
+            return None;
+        }
+        self.root_moves(position)
+            .ok()?
+            .into_iter()
+            .next()
+            .map(|m| m.mv)
+    }
+
+    fn check_covered<P: ProbePosition>(&self, position: &P) -> Result<(), String> {
+        if position.has_castling_rights() {
+            return Err("Positions with castling rights are not in the tables".into());
+        }
+        if pieces_of(position).len() > self.max_pieces {
+            return Err("Too many pieces for the tables".into());
+        }
+        Ok(())
+    }
+
+    // Table d'une matière, lue au premier accès
+    fn table(&self, path: &Path, name: &str, dtz: bool) -> Result<Arc<Table>, String> {
+        if let Some(table) = self.loaded.read().unwrap().get(path) {
+            return Ok(table.clone());
+        }
+        let bytes =
+            fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let table = Arc::new(Table::read(bytes, name, dtz)?);
+        self.loaded
+            .write()
+            .unwrap()
+            .insert(path.to_path_buf(), table.clone());
+        Ok(table)
+    }
+
+    // Valeur brute de la table; None si la table DTZ ne garde que l'autre
+    // camp au trait
+    fn probe_table<P: ProbePosition>(
+        &self,
+        position: &P,
+        dtz: bool,
+    ) -> Result<Option<(Arc<Table>, u16, usize)>, String> {
+        let pieces = pieces_of(position);
+        let (files, swapped) = self.find(&pieces).ok_or("No table for this material")?;
+        let path = if dtz { &files.dtz } else { &files.wdl };
+        let path = path.as_ref().ok_or(if dtz {
+            "No DTZ table for this material"
+        } else {
+            "No WDL table for this material"
+        })?;
+        let first = if swapped { Color::Black } else { Color::White };
+        let table = self.table(path, &material_key(&pieces, first), dtz)?;
+        let probed = table.probe(position, swapped)?;
+        Ok(probed.map(|(value, file)| (table, value, file)))
+    }
+
+    fn probe_wdl_table<P: ProbePosition>(&self, position: &P) -> Result<Wdl, String> {
+        // Deux rois seuls: nulle, sans table
+        if pieces_of(position).len() == 2 {
+            return Ok(Wdl::Draw);
+        }
+        match self.probe_table(position, false)? {
+            Some((_, value, _)) => Wdl::from_value(value),
+            None => Err("WDL table without this side to move".into()),
+        }
+    }
+
+    // Les tables ne disent rien des positions où une prise gagne (ni des
+    // prises en passant): on cherche les prises, et avec `zeroing` les
+    // coups de pion. Le booléen dit si le meilleur coup est l'un d'eux.
+    fn search<P: ProbePosition>(&self, position: &P, zeroing: bool) -> Result<(Wdl, bool), String> {
+        let moves = position.legal_moves();
+        let mut best = Wdl::Loss;
+        let mut searched = 0;
+        for mv in &moves {
+            let pawn = matches!(
+                move_squares(mv).and_then(|(from, _)| position.piece_at(from)),
+                Some((_, PieceKind::Pawn))
+            );
+            if !is_capture(position, mv) && (!zeroing || !pawn) {
+                continue;
+            }
+            searched += 1;
+            let mut next = position.clone();
+            next.play(mv);
+            let value = self.search(&next, false)?.0.opposite();
+            if value > best {
+                best = value;
+                if value == Wdl::Win {
+                    return Ok((value, true));
+                }
+            }
+        }
+        let no_more_moves = searched > 0 && searched == moves.len();
+        let value = if no_more_moves {
+            best
+        } else {
+            self.probe_wdl_table(position)?
+        };
+        if best >= value {
+            return Ok((best, best > Wdl::Draw || no_more_moves));
+        }
+        Ok((value, false))
+    }
+
+    fn dtz<P: ProbePosition>(&self, position: &P) -> Result<Dtz, String> {
+        let (wdl, zeroing_best) = self.search(position, true)?;
+        if wdl == Wdl::Draw {
+            return Ok(0);
+        }
+        if zeroing_best {
+            return Ok(wdl.dtz_before_zeroing());
+        }
+        if let Some((table, value, file)) = self.probe_table(position, true)? {
+            let dtz = table.dtz_plies(file, value, wdl)?;
+            let cursed = matches!(wdl, Wdl::CursedWin | Wdl::BlessedLoss);
+            return Ok((dtz + if cursed { 100 } else { 0 }) * wdl.sign());
+        }
+        // La table ne garde que l'autre camp au trait: un coup de plus
+        let mut min_dtz = 0xFFFF;
+        for mv in position.legal_moves() {
+            let zeroing = is_zeroing(position, &mv);
+            let mut next = position.clone();
+            next.play(&mv);
+            let mut dtz = if zeroing {
+                -self.search(&next, false)?.0.dtz_before_zeroing()
+            } else {
+                -self.dtz(&next)?
+            };
+            if dtz == 1 && next.in_check() && next.legal_moves().is_empty() {
+                min_dtz = 1;
+            }
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+            if dtz < min_dtz && dtz.signum() == wdl.sign() {
+                min_dtz = dtz;
+            }
+        }
+        // Sans coup légal, le camp au trait est mat
+        Ok(if min_dtz == 0xFFFF { -1 } else { min_dtz })
+    }
+}
+
+fn check_magic(path: &Path, magic: [u8; 4]) -> Result<(), String> {
+    let mut header = [0u8; 4];
+    fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut header))
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if header != magic {
+        return Err(format!("{} is not a Syzygy table", path.display()));
+    }
+    Ok(())
+}
+
+fn letter(kind: PieceKind) -> char {
+    match kind {
+        PieceKind::King => 'K',
+        PieceKind::Queen => 'Q',
+        PieceKind::Rook => 'R',
+        PieceKind::Bishop => 'B',
+        PieceKind::Knight => 'N',
+        PieceKind::Pawn => 'P',
+    }
+}
+
+/// Table name of a material, `first` being the side written before the
+/// 'v': "KQvK", "KRPvKR".
+pub fn material_key(pieces: &[(Color, PieceKind)], first: Color) -> String {
+    let side = |color: Color| {
+        let mut kinds: Vec<PieceKind> = pieces
+            .iter()
+            .filter(|(c, _)| *c == color)
+            .map(|(_, k)| *k)
+            .collect();
+        // Ordre des noms Syzygy: roi, dame, tour, fou, cavalier, pion
+        kinds.sort_by_key(|k| std::cmp::Reverse(*k as u8));
+        kinds.into_iter().map(letter).collect::<String>()
+    };
+    format!("{}v{}", side(first), side(first.opposite()))
+}
+
+// "KQvK" -> valide si chaque camp a exactement un roi en tête
+fn is_material_name(name: &str) -> bool {
+    let Some((white, black)) = name.split_once('v') else {
+        return false;
+    };
+    let valid = |side: &str| {
+        side.starts_with('K') && side[1..].chars().all(|c| "QRBNP".contains(c)) && side.len() <= 6
+    };
+    valid(white) && valid(black)
+}
@@ -0,0 +1,431 @@
+//! Bughouse: two Crazyhouse boards played by two teams of two. A piece
+//! taken on one board goes to the pocket of the taker's partner, who plays
+//! the other colour on the other board. Each board has its own clock and
+//! the match ends as soon as one board is decided.
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::BoardView;
+use crate::automation::ai::EvalVariant;
+use crate::crazyhouse::{piece_letter, Pockets, DROPPABLE};
+use crate::piece::Color;
+use crate::sharedenums::{GameResult, TimeControl};
+use crate::variant::{Variant, VariantPosition, VariantState};
+use crate::zobrist::PieceKind;
+
+// Chaque plateau suit les règles de la Crazyhouse, seules les prises changent de poche
+const RULES: Variant = Variant::Crazyhouse;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BoardId {
+    A,
+    B,
+}
+
+impl BoardId {
+    pub fn other(&self) -> BoardId {
+        match self {
+            BoardId::A => BoardId::B,
+            BoardId::B => BoardId::A,
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            BoardId::A => 0,
+            BoardId::B => 1,
+        }
+    }
+}
+
+/// Team One plays White on board A and Black on board B, team Two the
+/// other way round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Team {
+    One,
+    Two,
+}
+
+impl Team {
+    pub fn opposite(&self) -> Team {
+        match self {
+            Team::One => Team::Two,
+            Team::Two => Team::One,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Seat {
+    pub board: BoardId,
+    pub color: Color,
+}
+
+/// Seats in the order they are given to players joining the room.
+pub const SEATS: [Seat; 4] = [
+    Seat {
+        board: BoardId::A,
+        color: Color::White,
+    },
+    Seat {
+        board: BoardId::A,
+        color: Color::Black,
+    },
+    Seat {
+        board: BoardId::B,
+        color: Color::White,
+    },
+    Seat {
+        board: BoardId::B,
+        color: Color::Black,
+    },
+];
+
+impl Seat {
+    /// Teammate, playing the other colour on the other board.
+    pub fn partner(&self) -> Seat {
+        Seat {
+            board: self.board.other(),
+            color: self.color.opposite(),
+        }
+    }
+
+    pub fn team(&self) -> Team {
+        match (self.board, self.color) {
+            (BoardId::A, Color::White) | (BoardId::B, Color::Black) => Team::One,
+            _ => Team::Two,
+        }
+    }
+}
+
+/// Clock of one board. Times are in milliseconds, `now` being counted by
+/// the caller from any fixed instant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clock {
+    white_ms: u64,
+    black_ms: u64,
+    increment_ms: u64,
+    /// Side whose time is running, and since when.
+    running: Option<(Color, u64)>,
+}
+
+/// Clock as sent to the clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockState {
+    pub white_ms: u64,
+    pub black_ms: u64,
+    pub running: Option<Color>,
+}
+
+impl Clock {
+    pub fn new(time_control: TimeControl) -> Self {
+        Self {
+            white_ms: time_control.initial * 1000,
+            black_ms: time_control.initial * 1000,
+            increment_ms: time_control.increment * 1000,
+            running: None,
+        }
+    }
+
+    fn left_mut(&mut self, color: Color) -> &mut u64 {
+        match color {
+            Color::White => &mut self.white_ms,
+            Color::Black => &mut self.black_ms,
+        }
+    }
+
+    /// Time left to `color`, running time included.
+    pub fn remaining(&self, color: Color, now: u64) -> u64 {
+        let left = match color {
+            Color::White => self.white_ms,
+            Color::Black => self.black_ms,
+        };
+        match self.running {
+            Some((side, since)) if side == color => left.saturating_sub(now.saturating_sub(since)),
+            _ => left,
+        }
+    }
+
+    pub fn start(&mut self, color: Color, now: u64) {
+        self.running = Some((color, now));
+    }
+
+    pub fn stop(&mut self, now: u64) {
+        if let Some((side, _)) = self.running {
+            *self.left_mut(side) = self.remaining(side, now);
+            self.running = None;
+        }
+    }
+
+    /// Ends the turn of the running side: its time stops, gets the
+    /// increment, and the opponent's time starts.
+    pub fn press(&mut self, now: u64) {
+        if let Some((side, _)) = self.running {
+            self.stop(now);
+            *self.left_mut(side) += self.increment_ms;
+            self.start(side.opposite(), now);
+        }
+    }
+
+    /// Side whose time ran out.
+    pub fn flagged(&self, now: u64) -> Option<Color> {
+        let (side, _) = self.running?;
+        (self.remaining(side, now) == 0).then_some(side)
+    }
+
+    /// Time before the running side flags.
+    pub fn until_flag(&self, now: u64) -> Option<u64> {
+        let (side, _) = self.running?;
+        Some(self.remaining(side, now))
+    }
+
+    pub fn state(&self, now: u64) -> ClockState {
+        ClockState {
+            white_ms: self.remaining(Color::White, now),
+            black_ms: self.remaining(Color::Black, now),
+            running: self.running.map(|(side, _)| side),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BughouseBoard<P> {
+    pub position: P,
+    /// Pockets of both sides and promoted pieces.
+    pub state: VariantState,
+    pub moves: Vec<String>,
+    pub clock: Option<Clock>,
+}
+
+impl<P: VariantPosition> BughouseBoard<P> {
+    // Le camp au trait pourrait-il parer l'échec avec une pièce de chaque sorte en poche ?
+    fn drop_could_block(&self) -> bool {
+        let turn = self.position.turn();
+        let mut state = self.state;
+        for kind in DROPPABLE {
+            state.pockets.of_mut(turn).add(kind);
+        }
+        !RULES.legal_moves(&self.position, &state).is_empty()
+    }
+
+    pub fn in_check(&self) -> bool {
+        RULES.in_check(&self.position)
+    }
+
+    /// Pieces as "wq", promoted ones as "wq~", indexed by rank then file.
+    pub fn display(&self) -> Vec<Vec<Option<String>>> {
+        let mut board = crate::fen::display_board(&self.position);
+        crate::crazyhouse::mark_promoted(&mut board, self.state.promoted);
+        board
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BughouseEnd {
+    Checkmate,
+    Stalemate,
+    Timeout,
+    /// A player resigned or left the room.
+    Forfeit,
+}
+
+/// How the match ended: the result of the board that decided it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BughouseResult {
+    pub board: BoardId,
+    pub result: GameResult,
+    pub end: BughouseEnd,
+}
+
+impl BughouseResult {
+    pub fn winner(&self) -> Option<Team> {
+        let color = match self.result {
+            GameResult::WhiteWins => Color::White,
+            GameResult::BlackWins => Color::Black,
+            GameResult::Draw | GameResult::Aborted => return None,
+        };
+        Some(
+            Seat {
+                board: self.board,
+                color,
+            }
+            .team(),
+        )
+    }
+
+    pub fn description(&self) -> String {
+        let reason = match self.end {
+            BughouseEnd::Checkmate => "checkmate",
+            BughouseEnd::Stalemate => "stalemate",
+            BughouseEnd::Timeout => "time out",
+            BughouseEnd::Forfeit => "forfeit",
+        };
+        match self.winner() {
+            Some(team) => format!(
+                "Team {:?} wins by {} on board {:?}.",
+                team, reason, self.board
+            ),
+            None => format!("Draw by {} on board {:?}.", reason, self.board),
+        }
+    }
+}
+
+/// Both boards of a match. As in over-the-board bughouse, a check that a
+/// drop could block is not mate, even with an empty pocket: the side waits
+/// for its partner to send a piece, its clock running.
+#[derive(Debug, Clone)]
+pub struct Bughouse<P> {
+    boards: [BughouseBoard<P>; 2],
+    result: Option<BughouseResult>,
+}
+
+impl<P: VariantPosition> Bughouse<P> {
+    /// Both boards start from `start`, with empty pockets.
+    pub fn new(start: P, time_control: Option<TimeControl>) -> Self {
+        let board = BughouseBoard {
+            position: start,
+            state: VariantState::default(),
+            moves: Vec::new(),
+            clock: time_control.map(Clock::new),
+        };
+        Self {
+            boards: [board.clone(), board],
+            result: None,
+        }
+    }
+
+    pub fn board(&self, id: BoardId) -> &BughouseBoard<P> {
+        &self.boards[id.index()]
+    }
+
+    fn board_mut(&mut self, id: BoardId) -> &mut BughouseBoard<P> {
+        &mut self.boards[id.index()]
+    }
+
+    pub fn result(&self) -> Option<BughouseResult> {
+        self.result
+    }
+
+    /// Starts the clocks of the sides to move.
+    pub fn start(&mut self, now: u64) {
+        for board in &mut self.boards {
+            let turn = board.position.turn();
+            if let Some(clock) = &mut board.clock {
+                clock.start(turn, now);
+            }
+        }
+    }
+
+    pub fn legal_moves(&self, board: BoardId) -> Vec<String> {
+        let board = self.board(board);
+        RULES.legal_moves(&board.position, &board.state)
+    }
+
+    /// Plays `mv` for the player of `seat`. A piece taken is returned once
+    /// it has been put in the partner's pocket.
+    pub fn play(&mut self, seat: Seat, mv: &str, now: u64) -> Result<Option<PieceKind>, String> {
+        if self.result.is_some() || self.check_flags(now).is_some() {
+            return Err("The match is over.".into());
+        }
+        if self.board(seat.board).position.turn() != seat.color {
+            return Err("It's not your turn.".into());
+        }
+        let board = self.board_mut(seat.board);
+        let mv = RULES
+            .legal_move(&board.position, &board.state, mv)
+            .ok_or_else(|| format!("Illegal move {}", mv))?;
+        let taken = RULES.play_capturing(&mut board.position, &mut board.state, &mv);
+        board.moves.push(mv);
+        if let Some(clock) = &mut board.clock {
+            clock.press(now);
+        }
+        if let Some(kind) = taken {
+            let partner = seat.partner();
+            self.board_mut(partner.board)
+                .state
+                .pockets
+                .of_mut(partner.color)
+                .add(kind);
+        }
+
+        let board = self.board(seat.board);
+        let result = RULES
+            .result_without_moves(&board.position, &board.state)
+            .filter(|&result| result == GameResult::Draw || !board.drop_could_block());
+        if let Some(result) = result {
+            let end = if result == GameResult::Draw {
+                BughouseEnd::Stalemate
+            } else {
+                BughouseEnd::Checkmate
+            };
+            self.finish(
+                BughouseResult {
+                    board: seat.board,
+                    result,
+                    end,
+                },
+                now,
+            );
+        }
+        Ok(taken)
+    }
+
+    /// Ends the match if a clock ran out.
+    pub fn check_flags(&mut self, now: u64) -> Option<BughouseResult> {
+        if self.result.is_some() {
+            return self.result;
+        }
+        let flag = [BoardId::A, BoardId::B].into_iter().find_map(|id| {
+            let flagged = self.board(id).clock.and_then(|clock| clock.flagged(now))?;
+            Some(BughouseResult {
+                board: id,
+                result: GameResult::winner(flagged.opposite()),
+                end: BughouseEnd::Timeout,
+            })
+        })?;
+        self.finish(flag, now);
+        self.result
+    }
+
+    /// Time before the first clock runs out, None without clocks.
+    pub fn until_flag(&self, now: u64) -> Option<u64> {
+        self.boards
+            .iter()
+            .filter_map(|board| board.clock.and_then(|clock| clock.until_flag(now)))
+            .min()
+    }
+
+    /// The team of `seat` loses the match.
+    pub fn forfeit(&mut self, seat: Seat, now: u64) -> BughouseResult {
+        if let Some(result) = self.result {
+            return result;
+        }
+        let result = BughouseResult {
+            board: seat.board,
+            result: GameResult::winner(seat.color.opposite()),
+            end: BughouseEnd::Forfeit,
+        };
+        self.finish(result, now);
+        result
+    }
+
+    fn finish(&mut self, result: BughouseResult, now: u64) {
+        for board in &mut self.boards {
+            if let Some(clock) = &mut board.clock {
+                clock.stop(now);
+            }
+        }
+        self.result = Some(result);
+    }
+
+    /// Move of the built-in engine for the side to move on `board`. It
+    /// only sees its own pocket, not what the partner may send.
+    pub fn best_move(&self, board: BoardId, eval: EvalVariant, depth: u8) -> Option<String> {
+        let board = self.board(board);
+        RULES.best_move(&board.position, &board.state, eval, depth)
+    }
+
+    pub fn pockets(&self, board: BoardId) -> Pockets {
+        self.board(board).state.pockets
+    }
+}
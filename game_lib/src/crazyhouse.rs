@@ -0,0 +1,273 @@
+//! Crazyhouse pockets: captured pieces change sides and can be dropped
+//! back on an empty square instead of moving. Drops are written "N@e4",
+//! as in UCI.
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{parse_square, piece_value, square_name, BoardView};
+use crate::piece::Color;
+use crate::zobrist::PieceKind;
+
+/// Kinds a pocket can hold, in the order they are shown.
+pub const DROPPABLE: [PieceKind; 5] = [
+    PieceKind::Queen,
+    PieceKind::Rook,
+    PieceKind::Bishop,
+    PieceKind::Knight,
+    PieceKind::Pawn,
+];
+
+/// Pieces in hand of one side.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pocket {
+    pub pawns: u8,
+    pub knights: u8,
+    pub bishops: u8,
+    pub rooks: u8,
+    pub queens: u8,
+}
+
+impl Pocket {
+    fn slot(&mut self, kind: PieceKind) -> Option<&mut u8> {
+        match kind {
+            PieceKind::Pawn => Some(&mut self.pawns),
+            PieceKind::Knight => Some(&mut self.knights),
+            PieceKind::Bishop => Some(&mut self.bishops),
+            PieceKind::Rook => Some(&mut self.rooks),
+            PieceKind::Queen => Some(&mut self.queens),
+            PieceKind::King => None,
+        }
+    }
+
+    pub fn count(&self, kind: PieceKind) -> u8 {
+        match kind {
+            PieceKind::Pawn => self.pawns,
+            PieceKind::Knight => self.knights,
+            PieceKind::Bishop => self.bishops,
+            PieceKind::Rook => self.rooks,
+            PieceKind::Queen => self.queens,
+            PieceKind::King => 0,
+        }
+    }
+
+    pub fn add(&mut self, kind: PieceKind) {
+        if let Some(n) = self.slot(kind) {
+            *n += 1;
+        }
+    }
+
+    /// Takes a piece out to drop it, false when there is none.
+    pub fn take(&mut self, kind: PieceKind) -> bool {
+        match self.slot(kind) {
+            Some(n) if *n > 0 => {
+                *n -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        DROPPABLE.iter().all(|&kind| self.count(kind) == 0)
+    }
+
+    /// Material in hand, in centipawns.
+    pub fn value(&self) -> i32 {
+        DROPPABLE
+            .iter()
+            .map(|&kind| self.count(kind) as i32 * piece_value(kind))
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pockets {
+    pub white: Pocket,
+    pub black: Pocket,
+}
+
+impl Pockets {
+    pub fn of(&self, color: Color) -> &Pocket {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+
+    pub fn of_mut(&mut self, color: Color) -> &mut Pocket {
+        match color {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        }
+    }
+}
+
+pub fn piece_letter(kind: PieceKind) -> char {
+    match kind {
+        PieceKind::Pawn => 'P',
+        PieceKind::Knight => 'N',
+        PieceKind::Bishop => 'B',
+        PieceKind::Rook => 'R',
+        PieceKind::Queen => 'Q',
+        PieceKind::King => 'K',
+    }
+}
+
+fn kind_from_letter(c: char) -> Option<PieceKind> {
+    DROPPABLE
+        .into_iter()
+        .find(|&kind| piece_letter(kind) == c.to_ascii_uppercase())
+}
+
+/// "N@e4" for a knight dropped on e4.
+pub fn drop_move(kind: PieceKind, square: u8) -> String {
+    format!("{}@{}", piece_letter(kind), square_name(square))
+}
+
+/// Kind and square of a drop, None for a board move.
+pub fn parse_drop(mv: &str) -> Option<(PieceKind, u8)> {
+    let (piece, square) = mv.split_once('@')?;
+    let mut letters = piece.chars();
+    let kind = kind_from_letter(letters.next()?)?;
+    if letters.next().is_some() {
+        return None;
+    }
+    Some((kind, parse_square(square)?))
+}
+
+/// Destinations among `moves` for a legal moves request: `from` is either
+/// a square ("e2") or a pocket piece ("N" or "N@") to get its drop squares.
+pub fn move_targets(moves: &[String], from: &str) -> Vec<String> {
+    let from = from.trim().replace('"', "");
+    let prefix = match from.trim_end_matches('@') {
+        piece if piece.len() == 1 => format!("{}@", piece.to_ascii_uppercase()),
+        square => format!("{}->", square),
+    };
+    moves
+        .iter()
+        .filter_map(|m| m.strip_prefix(&prefix).map(str::to_string))
+        .collect()
+}
+
+/// Drops allowed by the piece rules, own king safety left unchecked: any
+/// empty square, except the first and last ranks for pawns.
+pub fn drops<P: BoardView>(position: &P, pocket: &Pocket) -> Vec<String> {
+    DROPPABLE
+        .into_iter()
+        .filter(|&kind| pocket.count(kind) > 0)
+        .flat_map(|kind| {
+            (0..64)
+                .filter(move |&sq| kind != PieceKind::Pawn || (8..56).contains(&sq))
+                .filter(|&sq| position.piece_at(sq).is_none())
+                .map(move |sq| drop_move(kind, sq))
+        })
+        .collect()
+}
+
+/// Pocket part of the FEN, as in "[QNqp]".
+pub fn pockets_to_fen(pockets: &Pockets) -> String {
+    let mut out = String::from("[");
+    for color in [Color::White, Color::Black] {
+        for kind in DROPPABLE {
+            let letter = match color {
+                Color::White => piece_letter(kind),
+                Color::Black => piece_letter(kind).to_ascii_lowercase(),
+            };
+            for _ in 0..pockets.of(color).count(kind) {
+                out.push(letter);
+            }
+        }
+    }
+    out.push(']');
+    out
+}
+
+fn bit(square: u8) -> u64 {
+    1 << square
+}
+
+/// Splits a Crazyhouse placement field ("...RNBQKB~R[Nq]") into the
+/// standard placement, the pockets and the squares of promoted pieces.
+pub(crate) fn split_placement(placement: &str) -> Result<(String, Pockets, u64), String> {
+    let (board, pockets) = match placement.split_once('[') {
+        Some((board, rest)) => {
+            let letters = rest
+                .strip_suffix(']')
+                .ok_or_else(|| format!("Unclosed pocket in {}", placement))?;
+            let mut pockets = Pockets::default();
+            for c in letters.chars() {
+                let kind =
+                    kind_from_letter(c).ok_or_else(|| format!("Invalid pocket piece {}", c))?;
+                let color = if c.is_ascii_uppercase() {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+                pockets.of_mut(color).add(kind);
+            }
+            (board, pockets)
+        }
+        None => (placement, Pockets::default()),
+    };
+    // Les "~" suivent les pièces issues d'une promotion
+    let mut out = String::new();
+    let mut promoted = 0;
+    let (mut rank, mut file) = (7i32, 0i32);
+    for c in board.chars() {
+        match c {
+            '~' => {
+                if (0..8).contains(&rank) && (1..=8).contains(&file) {
+                    promoted |= bit((rank * 8 + file - 1) as u8);
+                }
+                continue;
+            }
+            '/' => {
+                rank -= 1;
+                file = 0;
+            }
+            _ => file += c.to_digit(10).map_or(1, |n| n as i32),
+        }
+        out.push(c);
+    }
+    Ok((out, pockets, promoted))
+}
+
+/// Adds the pockets and the promoted marks to a standard placement field.
+pub(crate) fn encode_placement(placement: &str, pockets: &Pockets, promoted: u64) -> String {
+    let mut out = String::new();
+    let (mut rank, mut file) = (7i32, 0i32);
+    for c in placement.chars() {
+        out.push(c);
+        match c {
+            '/' => {
+                rank -= 1;
+                file = 0;
+            }
+            _ if c.is_ascii_digit() => file += c.to_digit(10).unwrap_or(0) as i32,
+            _ => {
+                if (0..8).contains(&rank)
+                    && file < 8
+                    && promoted & bit((rank * 8 + file) as u8) != 0
+                {
+                    out.push('~');
+                }
+                file += 1;
+            }
+        }
+    }
+    out.push_str(&pockets_to_fen(pockets));
+    out
+}
+
+/// Marks promoted pieces of a display board with a trailing "~" ("wq~"),
+/// so that they can be told apart: they go back to a pawn when taken.
+pub fn mark_promoted(board: &mut [Vec<Option<String>>], promoted: u64) {
+    for sq in (0..64).filter(|&sq| promoted & bit(sq) != 0) {
+        let cell = board
+            .get_mut((sq / 8) as usize)
+            .and_then(|row| row.get_mut((sq % 8) as usize));
+        if let Some(Some(piece)) = cell {
+            piece.push('~');
+        }
+    }
+}
@@ -0,0 +1,558 @@
+//! FEN reading and checks done before a room starts from a custom position,
+//! and a light position with its own move generation, used where the full
+//! `Game` is not needed: notation, replays and the offline tools.
+
+use crate::analysis::{
+    attackers_of, move_squares, parse_square, slider_directions, square_name, step, BoardView,
+    NullMove, KNIGHT_JUMPS,
+};
+use crate::automation::mate::MatePosition;
+use crate::automation::miner::MinerPosition;
+use crate::automation::tablebase::ProbePosition;
+use crate::crazyhouse::piece_letter;
+use crate::handicap::START_FEN;
+use crate::piece::Color;
+use crate::variant::VariantPosition;
+use crate::zobrist::PieceKind;
+
+/// Position read from a FEN. Moves use the server notation: "e2->e4",
+/// castling as a king move ("e1->g1") and promotions "e7->e8q".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FenPosition {
+    pieces: [Option<(Color, PieceKind)>; 64],
+    pub turn: Color,
+    pub castling: String,
+    pub en_passant: Option<u8>,
+    pub halfmove: u32,
+    pub fullmove: u32,
+}
+
+fn piece_from_char(c: char) -> Option<(Color, PieceKind)> {
+    let color = if c.is_ascii_uppercase() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    let kind = match c.to_ascii_lowercase() {
+        'p' => PieceKind::Pawn,
+        'n' => PieceKind::Knight,
+        'b' => PieceKind::Bishop,
+        'r' => PieceKind::Rook,
+        'q' => PieceKind::Queen,
+        'k' => PieceKind::King,
+        _ => return None,
+    };
+    Some((color, kind))
+}
+
+impl FenPosition {
+    /// Reads a FEN. The move counters may be left out.
+    pub fn parse(fen: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if !(4..=6).contains(&fields.len()) {
+            return Err(format!("Expected 4 to 6 FEN fields, got {}", fields.len()));
+        }
+        let rows: Vec<&str> = fields[0].split('/').collect();
+        if rows.len() != 8 {
+            return Err(format!("Expected 8 ranks, got {}", rows.len()));
+        }
+        let mut pieces = [None; 64];
+        // La FEN commence par la 8e rangée
+        for (i, row) in rows.iter().enumerate() {
+            let rank = 7 - i;
+            let mut file = 0;
+            for c in row.chars() {
+                if let Some(n) = c.to_digit(10).filter(|n| (1..=8).contains(n)) {
+                    file += n as usize;
+                } else {
+                    let piece = piece_from_char(c).ok_or_else(|| format!("Invalid piece {}", c))?;
+                    if file < 8 {
+                        pieces[rank * 8 + file] = Some(piece);
+                    }
+                    file += 1;
+                }
+                if file > 8 {
+                    break;
+                }
+            }
+            if file != 8 {
+                return Err(format!("Rank {} does not have 8 squares", rank + 1));
+            }
+        }
+        let turn = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            t => return Err(format!("Invalid side to move {}", t)),
+        };
+        let castling = fields[2].to_string();
+        if castling != "-" && !castling.chars().all(|c| "KQkq".contains(c)) {
+            return Err(format!("Invalid castling rights {}", castling));
+        }
+        let en_passant = match fields[3] {
+            "-" => None,
+            sq => {
+                Some(parse_square(sq).ok_or_else(|| format!("Invalid en passant square {}", sq))?)
+            }
+        };
+        let counter = |i: usize, default: u32| -> Result<u32, String> {
+            fields.get(i).map_or(Ok(default), |v| {
+                v.parse().map_err(|_| format!("Invalid move counter {}", v))
+            })
+        };
+        Ok(FenPosition {
+            pieces,
+            turn,
+            castling,
+            en_passant,
+            halfmove: counter(4, 0)?,
+            fullmove: counter(5, 1)?,
+        })
+    }
+
+    /// Checks that a game can be played from the position: one king per
+    /// side, no pawn on the back ranks, castling rights matching the king
+    /// and rook squares, and the side that just moved not left in check.
+    pub fn validate(&self) -> Result<(), String> {
+        for color in [Color::White, Color::Black] {
+            let kings = self
+                .pieces
+                .iter()
+                .filter(|p| **p == Some((color, PieceKind::King)))
+                .count();
+            if kings != 1 {
+                return Err(format!("{:?} must have exactly one king", color));
+            }
+        }
+        let back_rank_pawn = (0..8)
+            .chain(56..64)
+            .any(|sq| matches!(self.pieces[sq], Some((_, PieceKind::Pawn))));
+        if back_rank_pawn {
+            return Err("Pawns cannot stand on the first or last rank".into());
+        }
+        for right in self.castling.chars().filter(|&c| c != '-') {
+            let (color, king, rook) = match right {
+                'K' => (Color::White, 4, 7),
+                'Q' => (Color::White, 4, 0),
+                'k' => (Color::Black, 60, 63),
+                _ => (Color::Black, 60, 56),
+            };
+            if self.pieces[king] != Some((color, PieceKind::King))
+                || self.pieces[rook] != Some((color, PieceKind::Rook))
+            {
+                return Err(format!(
+                    "Castling right {} without king and rook at home",
+                    right
+                ));
+            }
+        }
+        if let Some(ep) = self.en_passant {
+            // Case sautée par le pion qui vient d'avancer de deux cases
+            let expected = if self.turn == Color::White { 5 } else { 2 };
+            if ep / 8 != expected {
+                return Err("En passant square on the wrong rank".into());
+            }
+        }
+        let waiting = self.turn.opposite();
+        let king = (0..64)
+            .find(|&sq| self.piece_at(sq) == Some((waiting, PieceKind::King)))
+            .unwrap_or_default();
+        if !attackers_of(self, king, self.turn).is_empty() {
+            return Err(format!("{:?} is in check but it is not its turn", waiting));
+        }
+        Ok(())
+    }
+}
+
+impl BoardView for FenPosition {
+    fn turn(&self) -> Color {
+        self.turn
+    }
+
+    fn piece_at(&self, square: u8) -> Option<(Color, PieceKind)> {
+        self.pieces.get(square as usize).copied().flatten()
+    }
+}
+
+const PROMOTIONS: [PieceKind; 4] = [
+    PieceKind::Queen,
+    PieceKind::Rook,
+    PieceKind::Bishop,
+    PieceKind::Knight,
+];
+
+// Coup décodé: départ, arrivée et pièce de promotion
+type Move = (u8, u8, Option<PieceKind>);
+
+fn promotion_from_char(c: char) -> Option<PieceKind> {
+    PROMOTIONS
+        .into_iter()
+        .find(|&kind| piece_letter(kind) == c.to_ascii_uppercase())
+}
+
+fn parse_move(mv: &str) -> Option<Move> {
+    let (from, to) = move_squares(mv)?;
+    let (_, target) = mv.split_once("->")?;
+    let promotion = match target.chars().nth(2) {
+        Some(c) => Some(promotion_from_char(c)?),
+        None => None,
+    };
+    Some((from, to, promotion))
+}
+
+fn format_move((from, to, promotion): Move) -> String {
+    let suffix = promotion.map_or(String::new(), |kind| {
+        piece_letter(kind).to_ascii_lowercase().to_string()
+    });
+    format!("{}->{}{}", square_name(from), square_name(to), suffix)
+}
+
+// Les poussées vers la dernière rangée donnent les quatre promotions
+fn push_pawn_move(moves: &mut Vec<Move>, from: u8, to: u8) {
+    if (8..56).contains(&to) {
+        moves.push((from, to, None));
+    } else {
+        moves.extend(PROMOTIONS.map(|kind| (from, to, Some(kind))));
+    }
+}
+
+impl FenPosition {
+    /// The usual starting position.
+    pub fn start() -> Self {
+        Self::parse(START_FEN).expect("the start FEN is valid")
+    }
+
+    /// Writes the position back as a FEN.
+    pub fn to_fen(&self) -> String {
+        let mut rows = Vec::new();
+        for rank in (0..8).rev() {
+            let mut row = String::new();
+            let mut empty = 0;
+            for file in 0..8 {
+                match self.pieces[rank * 8 + file] {
+                    None => empty += 1,
+                    Some((color, kind)) => {
+                        if empty > 0 {
+                            row.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        let letter = piece_letter(kind);
+                        row.push(match color {
+                            Color::White => letter,
+                            Color::Black => letter.to_ascii_lowercase(),
+                        });
+                    }
+                }
+            }
+            if empty > 0 {
+                row.push_str(&empty.to_string());
+            }
+            rows.push(row);
+        }
+        format!(
+            "{} {} {} {} {} {}",
+            rows.join("/"),
+            if self.turn == Color::White { "w" } else { "b" },
+            self.castling,
+            self.en_passant.map_or("-".to_string(), square_name),
+            self.halfmove,
+            self.fullmove
+        )
+    }
+
+    pub fn king_square(&self, color: Color) -> Option<u8> {
+        (0..64).find(|&sq| self.piece_at(sq) == Some((color, PieceKind::King)))
+    }
+
+    /// Whether a piece of `by` attacks `square`.
+    pub fn is_attacked(&self, square: u8, by: Color) -> bool {
+        let holds = |sq: Option<u8>, kinds: &[PieceKind]| {
+            sq.and_then(|sq| self.piece_at(sq))
+                .is_some_and(|(color, kind)| color == by && kinds.contains(&kind))
+        };
+        // Un pion blanc attaque depuis la rangée du dessous
+        let dr = if by == Color::White { -1 } else { 1 };
+        if [-1, 1]
+            .into_iter()
+            .any(|df| holds(step(square, df, dr), &[PieceKind::Pawn]))
+        {
+            return true;
+        }
+        if KNIGHT_JUMPS
+            .iter()
+            .any(|&(df, dr)| holds(step(square, df, dr), &[PieceKind::Knight]))
+        {
+            return true;
+        }
+        for (df, dr) in slider_directions(PieceKind::Queen) {
+            if holds(step(square, df, dr), &[PieceKind::King]) {
+                return true;
+            }
+            let sliders: &[PieceKind] = if df == 0 || dr == 0 {
+                &[PieceKind::Rook, PieceKind::Queen]
+            } else {
+                &[PieceKind::Bishop, PieceKind::Queen]
+            };
+            let mut current = square;
+            while let Some(next) = step(current, df, dr) {
+                if self.piece_at(next).is_some() {
+                    if holds(Some(next), sliders) {
+                        return true;
+                    }
+                    break;
+                }
+                current = next;
+            }
+        }
+        false
+    }
+
+    // Coups selon la marche des pièces, roques compris, sans vérifier le roi
+    fn pseudo_moves(&self) -> Vec<Move> {
+        let turn = self.turn;
+        let mut moves = Vec::new();
+        for from in 0..64 {
+            match self.piece_at(from) {
+                Some((color, PieceKind::Pawn)) if color == turn => {
+                    self.pawn_moves(from, &mut moves)
+                }
+                Some((color, _)) if color == turn => {
+                    for to in crate::analysis::attacked_squares(self, from) {
+                        if !matches!(self.piece_at(to), Some((c, _)) if c == turn) {
+                            moves.push((from, to, None));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.castling_moves(&mut moves);
+        moves
+    }
+
+    fn pawn_moves(&self, from: u8, moves: &mut Vec<Move>) {
+        let (dr, start_rank) = match self.turn {
+            Color::White => (1, 1),
+            Color::Black => (-1, 6),
+        };
+        if let Some(one) = step(from, 0, dr).filter(|&sq| self.piece_at(sq).is_none()) {
+            push_pawn_move(moves, from, one);
+            let two = step(one, 0, dr).filter(|&sq| self.piece_at(sq).is_none());
+            if let Some(two) = two.filter(|_| from / 8 == start_rank) {
+                moves.push((from, two, None));
+            }
+        }
+        for df in [-1, 1] {
+            let Some(to) = step(from, df, dr) else {
+                continue;
+            };
+            let takes = match self.piece_at(to) {
+                Some((color, _)) => color != self.turn,
+                None => self.en_passant == Some(to),
+            };
+            if takes {
+                push_pawn_move(moves, from, to);
+            }
+        }
+    }
+
+    // Le roi ne roque ni en échec, ni à travers une case attaquée
+    fn castling_moves(&self, moves: &mut Vec<Move>) {
+        let (base, rights) = match self.turn {
+            Color::White => (0, ['K', 'Q']),
+            Color::Black => (56, ['k', 'q']),
+        };
+        let king = base + 4;
+        let enemy = self.turn.opposite();
+        if self.piece_at(king) != Some((self.turn, PieceKind::King))
+            || self.is_attacked(king, enemy)
+        {
+            return;
+        }
+        for right in rights.into_iter().filter(|&r| self.castling.contains(r)) {
+            let kingside = right.eq_ignore_ascii_case(&'K');
+            let (rook, empty, passed): (u8, &[u8], [u8; 2]) = if kingside {
+                (base + 7, &[base + 5, base + 6], [base + 5, base + 6])
+            } else {
+                (base, &[base + 1, base + 2, base + 3], [base + 3, base + 2])
+            };
+            if self.piece_at(rook) == Some((self.turn, PieceKind::Rook))
+                && empty.iter().all(|&sq| self.piece_at(sq).is_none())
+                && passed.iter().all(|&sq| !self.is_attacked(sq, enemy))
+            {
+                moves.push((king, passed[1], None));
+            }
+        }
+    }
+
+    // Joue un coup pseudo-légal: prise en passant, roque, promotion et droits
+    fn apply(&mut self, (from, to, promotion): Move) {
+        let Some((color, kind)) = self.piece_at(from) else {
+            return;
+        };
+        let mut capture = self.piece_at(to).is_some();
+        if kind == PieceKind::Pawn && from % 8 != to % 8 && !capture {
+            let taken = if color == Color::White {
+                to - 8
+            } else {
+                to + 8
+            };
+            self.pieces[taken as usize] = None;
+            capture = true;
+        }
+        if kind == PieceKind::King && from.abs_diff(to) == 2 {
+            let (rook_from, rook_to) = if to > from {
+                (from + 3, from + 1)
+            } else {
+                (from - 4, from - 1)
+            };
+            self.pieces[rook_to as usize] = self.pieces[rook_from as usize].take();
+        }
+        // Sans pièce précisée, le pion devient une dame
+        let landed = match kind {
+            PieceKind::Pawn if !(8..56).contains(&to) => promotion.unwrap_or(PieceKind::Queen),
+            _ => kind,
+        };
+        self.pieces[from as usize] = None;
+        self.pieces[to as usize] = Some((color, landed));
+        for (square, rights) in [
+            (4, "KQ"),
+            (0, "Q"),
+            (7, "K"),
+            (60, "kq"),
+            (56, "q"),
+            (63, "k"),
+        ] {
+            if from == square || to == square {
+                self.castling.retain(|c| !rights.contains(c));
+            }
+        }
+        if self.castling.is_empty() {
+            self.castling = "-".into();
+        }
+        self.en_passant =
+            (kind == PieceKind::Pawn && from.abs_diff(to) == 16).then(|| (from + to) / 2);
+        self.halfmove = if kind == PieceKind::Pawn || capture {
+            0
+        } else {
+            self.halfmove + 1
+        };
+        self.pass_turn();
+    }
+
+    fn pass_turn(&mut self) {
+        if self.turn == Color::Black {
+            self.fullmove += 1;
+        }
+        self.turn = self.turn.opposite();
+    }
+
+    // Le camp qui vient de jouer ne doit pas laisser son roi en prise
+    fn leaves_king_safe(&self, mv: Move) -> bool {
+        let mut after = self.clone();
+        after.apply(mv);
+        after
+            .king_square(self.turn)
+            .is_none_or(|king| !after.is_attacked(king, after.turn))
+    }
+}
+
+impl MatePosition for FenPosition {
+    fn legal_moves(&self) -> Vec<String> {
+        self.pseudo_moves()
+            .into_iter()
+            .filter(|&mv| self.leaves_king_safe(mv))
+            .map(format_move)
+            .collect()
+    }
+
+    fn play(&mut self, mv: &str) {
+        if let Some(mv) = parse_move(mv) {
+            self.apply(mv);
+        }
+    }
+
+    fn in_check(&self) -> bool {
+        self.king_square(self.turn)
+            .is_some_and(|king| self.is_attacked(king, self.turn.opposite()))
+    }
+}
+
+impl VariantPosition for FenPosition {
+    fn pseudo_legal_moves(&self) -> Vec<String> {
+        self.pseudo_moves().into_iter().map(format_move).collect()
+    }
+
+    // apply ne vérifie pas la sécurité du roi
+    fn play_pseudo_legal(&mut self, mv: &str) {
+        self.play(mv);
+    }
+
+    fn remove_piece(&mut self, square: u8) {
+        if let Some(piece) = self.pieces.get_mut(square as usize) {
+            *piece = None;
+        }
+    }
+
+    fn drop_piece(&mut self, square: u8, color: Color, kind: PieceKind) {
+        if let Some(piece) = self.pieces.get_mut(square as usize) {
+            *piece = Some((color, kind));
+        }
+        self.en_passant = None;
+        self.halfmove += 1;
+        self.pass_turn();
+    }
+}
+
+impl NullMove for FenPosition {
+    fn null_move(&self) -> Self {
+        let mut next = self.clone();
+        next.en_passant = None;
+        next.pass_turn();
+        next
+    }
+}
+
+impl ProbePosition for FenPosition {
+    fn en_passant(&self) -> Option<u8> {
+        self.en_passant
+    }
+
+    fn has_castling_rights(&self) -> bool {
+        self.castling != "-" && !self.castling.is_empty()
+    }
+
+    fn halfmove_clock(&self) -> u32 {
+        self.halfmove
+    }
+}
+
+impl MinerPosition for FenPosition {
+    fn fen(&self) -> String {
+        self.to_fen()
+    }
+}
+
+/// Board as sent to the clients: ranks from the first, pieces written "wq".
+pub fn display_board<P: BoardView>(position: &P) -> Vec<Vec<Option<String>>> {
+    (0..8u8)
+        .map(|rank| {
+            (0..8u8)
+                .map(|file| {
+                    position.piece_at(rank * 8 + file).map(|(color, kind)| {
+                        let prefix = if color == Color::White { 'w' } else { 'b' };
+                        format!("{}{}", prefix, piece_letter(kind).to_ascii_lowercase())
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Parses and validates a starting position for a room.
+pub fn check_start_fen(fen: &str) -> Result<FenPosition, String> {
+    let position = FenPosition::parse(fen).map_err(|e| format!("Invalid FEN: {}", e))?;
+    position
+        .validate()
+        .map_err(|e| format!("Invalid FEN: {}", e))?;
+    Ok(position)
+}
@@ -0,0 +1,175 @@
+//! Odds games: the stronger player gives material or time to the other.
+
+use serde::{Deserialize, Serialize};
+
+use crate::piece::Color;
+use crate::sharedenums::TimeControl;
+
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Material given by the stronger side, removed from the start position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MaterialOdds {
+    /// The f-pawn, and the first move to the opponent.
+    PawnAndMove,
+    /// The queen's knight.
+    Knight,
+    /// The queen's rook, and with it queenside castling.
+    Rook,
+    Queen,
+}
+
+impl MaterialOdds {
+    // Colonne de la pièce retirée
+    fn file(&self) -> usize {
+        match self {
+            MaterialOdds::PawnAndMove => 5,
+            MaterialOdds::Knight => 1,
+            MaterialOdds::Rook => 0,
+            MaterialOdds::Queen => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum Handicap {
+    Material {
+        giver: Color,
+        odds: MaterialOdds,
+    },
+    /// The giver plays with `time_control` instead of the room's.
+    Time {
+        giver: Color,
+        time_control: TimeControl,
+    },
+}
+
+impl Handicap {
+    pub fn giver(&self) -> Color {
+        match self {
+            Handicap::Material { giver, .. } | Handicap::Time { giver, .. } => *giver,
+        }
+    }
+
+    /// Same odds given by the other colour, for a rematch.
+    pub fn swapped(&self) -> Self {
+        match *self {
+            Handicap::Material { giver, odds } => Handicap::Material {
+                giver: giver.opposite(),
+                odds,
+            },
+            Handicap::Time {
+                giver,
+                time_control,
+            } => Handicap::Time {
+                giver: giver.opposite(),
+                time_control,
+            },
+        }
+    }
+
+    /// Side making the first move.
+    pub fn first_turn(&self) -> Color {
+        match self {
+            Handicap::Material {
+                giver,
+                odds: MaterialOdds::PawnAndMove,
+            } => giver.opposite(),
+            _ => Color::White,
+        }
+    }
+
+    /// Clock of `color`, `base` being the room's time control.
+    pub fn time_control(&self, color: Color, base: Option<TimeControl>) -> Option<TimeControl> {
+        match self {
+            Handicap::Time {
+                giver,
+                time_control,
+            } if *giver == color => Some(*time_control),
+            _ => base,
+        }
+    }
+
+    /// Start position of material odds, None for time odds.
+    pub fn start_fen(&self) -> Option<String> {
+        let Handicap::Material { giver, odds } = self else {
+            return None;
+        };
+        // Rangées de la FEN de départ, de la 8e à la 1re
+        let mut rows: Vec<Vec<char>> = START_FEN
+            .split(' ')
+            .next()?
+            .split('/')
+            .map(|row| {
+                row.chars()
+                    .flat_map(|c| match c.to_digit(10) {
+                        Some(n) => vec!['.'; n as usize],
+                        None => vec![c],
+                    })
+                    .collect()
+            })
+            .collect();
+        let row = match (giver, odds) {
+            (Color::White, MaterialOdds::PawnAndMove) => 6,
+            (Color::White, _) => 7,
+            (Color::Black, MaterialOdds::PawnAndMove) => 1,
+            (Color::Black, _) => 0,
+        };
+        rows[row][odds.file()] = '.';
+
+        let placement: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                let mut out = String::new();
+                let mut empty = 0;
+                for &c in row {
+                    if c == '.' {
+                        empty += 1;
+                        continue;
+                    }
+                    if empty > 0 {
+                        out.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    out.push(c);
+                }
+                if empty > 0 {
+                    out.push_str(&empty.to_string());
+                }
+                out
+            })
+            .collect();
+        let castling = match (giver, odds) {
+            (Color::White, MaterialOdds::Rook) => "Kkq",
+            (Color::Black, MaterialOdds::Rook) => "KQk",
+            _ => "KQkq",
+        };
+        let turn = match self.first_turn() {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+        Some(format!(
+            "{} {} {} - 0 1",
+            placement.join("/"),
+            turn,
+            castling
+        ))
+    }
+
+    /// Odds games are unrated, and time odds must shorten the giver's clock.
+    pub fn check(&self, rated: bool, base: Option<TimeControl>) -> Result<(), String> {
+        if rated {
+            return Err("Odds games cannot be rated.".into());
+        }
+        if let Handicap::Time { time_control, .. } = self {
+            let Some(base) = base else {
+                return Err("Time odds need a time control for the room.".into());
+            };
+            if time_control.initial == 0 || time_control.initial >= base.initial {
+                return Err("Time odds must give less time than the room's time control.".into());
+            }
+        }
+        Ok(())
+    }
+}
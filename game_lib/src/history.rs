@@ -0,0 +1,185 @@
+use crate::automation::ai::{AiProfile, Difficulty};
+use crate::fen::FenPosition;
+use crate::handicap::{Handicap, START_FEN};
+use crate::piece::Color;
+use crate::sharedenums::{GameMode, GameResult, TimeControl};
+use crate::uci::to_uci;
+use crate::variant::Variant;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerRecord {
+    pub id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub name: String,
+    pub is_ai: bool,
+}
+
+/// Finished game as it is archived once the Room reaches `Finished`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub id: Uuid,
+    pub room_id: Uuid,
+    pub mode: GameMode,
+    pub white: PlayerRecord,
+    pub black: PlayerRecord,
+    pub ai_profile: Option<Difficulty>,
+    /// Engines of each side when they differ from `ai_profile`.
+    #[serde(default)]
+    pub white_ai: Option<AiProfile>,
+    #[serde(default)]
+    pub black_ai: Option<AiProfile>,
+    #[serde(default)]
+    pub rated: bool,
+    #[serde(default)]
+    pub time_control: Option<TimeControl>,
+    #[serde(default)]
+    pub handicap: Option<Handicap>,
+    /// Custom starting position of the room.
+    #[serde(default)]
+    pub start_fen: Option<String>,
+    #[serde(default)]
+    pub variant: Variant,
+    pub moves: Vec<String>,
+    pub result: GameResult,
+    pub reason: String,
+    pub started_at: u64,
+    pub ended_at: u64,
+    pub pgn: String,
+}
+
+impl GameRecord {
+    pub fn involves(&self, player_id: Uuid) -> bool {
+        [&self.white, &self.black]
+            .iter()
+            .any(|p| p.id == player_id || p.account_id == Some(player_id))
+    }
+
+    /// Position the moves are played from, None for the usual one.
+    pub fn start_position(&self) -> Option<String> {
+        self.start_fen
+            .clone()
+            .or_else(|| self.handicap.as_ref().and_then(Handicap::start_fen))
+    }
+
+    /// Builds the PGN export, moves written in SAN.
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+        let mut tags = vec![
+            ("Event", format!("M&Chess {:?}", self.mode)),
+            ("Site", "M&Chess".to_string()),
+            ("Date", pgn_date(self.started_at)),
+            ("White", self.white.name.clone()),
+            ("Black", self.black.name.clone()),
+            ("Result", self.result.to_pgn().to_string()),
+            ("Termination", self.reason.clone()),
+        ];
+        if self.variant != Variant::Standard {
+            tags.push(("Variant", self.variant.name().to_string()));
+        }
+        // Position de départ personnalisée ou à handicap
+        let start = self.start_position();
+        if let Some(fen) = &start {
+            tags.push(("SetUp", "1".to_string()));
+            tags.push(("FEN", fen.clone()));
+        }
+        for (tag, value) in tags {
+            pgn.push_str(&format!("[{} \"{}\"]\n", tag, value.replace('"', "'")));
+        }
+        pgn.push('\n');
+
+        pgn.push_str(&pgn_movetext_from(
+            &self.moves,
+            self.result,
+            start.as_deref(),
+            self.variant,
+        ));
+        pgn.push('\n');
+        pgn
+    }
+}
+
+/// Numbered SAN movetext followed by the result, for a standard game
+/// from the usual position.
+pub fn pgn_movetext(moves: &[String], result: GameResult) -> String {
+    pgn_movetext_from(moves, result, None, Variant::Standard)
+}
+
+/// Same as `pgn_movetext` for a game of `variant` starting from
+/// `start_fen`. From the first move that cannot be replayed, the moves are
+/// written in coordinate notation ("e2e4").
+pub fn pgn_movetext_from(
+    moves: &[String],
+    result: GameResult,
+    start_fen: Option<&str>,
+    variant: Variant,
+) -> String {
+    let replay = variant
+        .split_fen(start_fen.unwrap_or(START_FEN))
+        .and_then(|(fen, state)| Ok((FenPosition::parse(&fen)?, state)));
+    let first_turn = replay.as_ref().map_or(Color::White, |(p, _)| p.turn);
+    // Numérotation reprise du compteur de coups de la FEN
+    let first_move = replay
+        .as_ref()
+        .map_or(1, |(p, _)| p.fullmove.max(1) as usize);
+    let mut replay = replay.ok();
+    let mut movetext = Vec::new();
+    // Les noirs qui commencent jouent "1... e5"
+    let offset = if first_turn == Color::Black { 1 } else { 0 };
+    if offset == 1 && !moves.is_empty() {
+        movetext.push(format!("{}...", first_move));
+    }
+    for (i, mv) in moves.iter().enumerate() {
+        let ply = i + offset;
+        if ply.is_multiple_of(2) {
+            movetext.push(format!("{}.", first_move + ply / 2));
+        }
+        let san = replay.as_mut().and_then(|(position, state)| {
+            let san = variant.san(position, state, mv)?;
+            variant.play(position, state, mv);
+            Some(san)
+        });
+        if san.is_none() {
+            replay = None;
+        }
+        movetext.push(san.unwrap_or_else(|| to_uci(mv)));
+    }
+    movetext.push(result.to_pgn().to_string());
+    movetext.join(" ")
+}
+
+/// Filters for `GameStore::query`. Every `None` field matches everything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GameQuery {
+    pub player: Option<Uuid>,
+    pub mode: Option<GameMode>,
+    pub result: Option<GameResult>,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+impl GameQuery {
+    pub fn matches(&self, record: &GameRecord) -> bool {
+        self.player.is_none_or(|id| record.involves(id))
+            && self.mode.as_ref().is_none_or(|m| *m == record.mode)
+            && self.result.is_none_or(|r| r == record.result)
+            && self.from.is_none_or(|t| record.ended_at >= t)
+            && self.to.is_none_or(|t| record.ended_at <= t)
+    }
+}
+
+// Date PGN "YYYY.MM.DD" depuis un timestamp unix (algorithme civil de H. Hinnant)
+pub fn pgn_date(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
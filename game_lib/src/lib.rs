@@ -1,10 +1,20 @@
+pub mod analysis;
 pub mod automation;
 pub mod board;
+pub mod bughouse;
+pub mod crazyhouse;
+pub mod fen;
 pub mod game;
+pub mod handicap;
+pub mod history;
 pub mod messages;
 pub mod piece;
 pub mod position;
+pub mod puzzle;
 
 pub mod sharedenums;
+pub mod uci;
+pub mod variant;
+pub mod zobrist;
 
 pub mod tests;
@@ -1,11 +1,127 @@
+use crate::bughouse::{BoardId, BughouseResult, ClockState, Seat};
+use crate::crazyhouse::Pockets;
+use crate::handicap::Handicap;
+use crate::history::{GameQuery, GameRecord, PlayerRecord};
 use crate::position::Position;
 use crate::sharedenums::GameMode;
 use crate::sharedenums::PlayerRole;
 use crate::sharedenums::RoomStatus;
-use crate::{automation::ai::Difficulty, piece::Color};
+use crate::sharedenums::{ChatChannel, TimeCategory, TimeControl};
+use crate::variant::{Variant, VariantState, VariantWin};
+use crate::{
+    automation::ai::{AiProfile, Difficulty},
+    automation::eval::EvalBreakdown,
+    automation::mate::MateOutcome,
+    piece::Color,
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountInfo {
+    pub id: Uuid,
+    pub display_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RatingChange {
+    pub account_id: Uuid,
+    pub category: TimeCategory,
+    pub before: f64,
+    pub after: f64,
+    pub deviation: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub account_id: Uuid,
+    pub display_name: String,
+    pub rating: f64,
+    pub deviation: f64,
+    pub games: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RatingHistoryEntry {
+    pub account_id: Uuid,
+    pub category: TimeCategory,
+    pub game_id: Uuid,
+    pub rating: f64,
+    pub deviation: f64,
+    pub at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatEntry {
+    pub channel: ChatChannel,
+    pub author_id: Uuid,
+    pub author: String,
+    pub text: String,
+    pub at: u64,
+}
+
+/// Public room as shown in the lobby.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoomSummary {
+    pub room_id: Uuid,
+    pub mode: GameMode,
+    pub status: RoomStatus,
+    pub time_control: Option<TimeControl>,
+    pub rated: bool,
+    pub handicap: Option<Handicap>,
+    #[serde(default)]
+    pub variant: Variant,
+    pub host: String,
+    pub host_rating: Option<f64>,
+    pub players: usize,
+    pub spectators: usize,
+    pub created_at: u64,
+}
+
+/// Opponent of a game played from a sandbox position.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum SandboxOpponent {
+    Ai {
+        profile: AiProfile,
+    },
+    /// A friend joining through the room id or an invite code.
+    Human,
+}
+
+/// Player of a Bughouse seat.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeatInfo {
+    pub seat: Seat,
+    pub name: String,
+    pub is_ai: bool,
+}
+
+/// Puzzle as shown to the solver, without its solution.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PuzzleInfo {
+    pub id: String,
+    /// Position before the setup move.
+    pub fen: String,
+    pub rating: u32,
+    pub themes: Vec<String>,
+    /// Side of the solver.
+    pub color: Color,
+    /// Opponent move played before the solver's turn.
+    pub setup_move: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct PuzzleStats {
+    pub rating: f64,
+    pub deviation: f64,
+    /// Puzzles solved in a row.
+    pub streak: u32,
+    pub best_streak: u32,
+    pub solved: u32,
+    pub attempts: u32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum ServerMessage {
@@ -20,16 +136,39 @@ pub enum ServerMessage {
         room_status: RoomStatus,
         board: Vec<Vec<Option<String>>>,
         turn: Color,
+        /// Clock of each side, different when time odds are given.
+        #[serde(default)]
+        white_time_control: Option<TimeControl>,
+        #[serde(default)]
+        black_time_control: Option<TimeControl>,
     },
     State {
+        /// Pieces as "wq", promoted ones as "wq~" in Crazyhouse.
         board: Vec<Vec<Option<String>>>,
         turn: Color,
         counter: usize,
         incheck: Option<Color>,
+        /// Pieces in hand, Crazyhouse only.
+        #[serde(default)]
+        pockets: Option<Pockets>,
     },
     GameOver {
         room_status: RoomStatus,
         result: String,
+        #[serde(default)]
+        rating_changes: Vec<RatingChange>,
+        /// Set when the game ended on a rule of the room's variant.
+        #[serde(default)]
+        variant_win: Option<VariantWin>,
+        /// Board and team that decided a Bughouse match.
+        #[serde(default)]
+        bughouse: Option<BughouseResult>,
+    },
+    /// Variant of the room and its counters, sent at the start and after
+    /// every move of a variant game.
+    VariantStatus {
+        variant: Variant,
+        state: VariantState,
     },
     Error {
         msg: String,
@@ -46,6 +185,18 @@ pub enum ServerMessage {
     PauseGame {
         room_status: RoomStatus,
     },
+    // Rematch
+    RematchOffered {
+        by: String,
+    },
+    RematchDeclined {
+        by: String,
+    },
+    /// New game in the same room, colours swapped.
+    RematchStarted {
+        role: PlayerRole,
+        room_status: RoomStatus,
+    },
     Info {
         msg: String,
     },
@@ -53,6 +204,63 @@ pub enum ServerMessage {
         piece: String,
         pos: String,
     },
+    /// Result of a `SolveMate` request on the sandbox position.
+    MateSolution {
+        n: u8,
+        outcome: MateOutcome,
+    },
+    /// The sandbox room became a game, `role` being the side of the receiver.
+    GameModeChanged {
+        gamemod: GameMode,
+        role: PlayerRole,
+        room_status: RoomStatus,
+    },
+    /// Static evaluation of the sandbox position, sent after every change
+    /// while analysis is on.
+    Evaluation {
+        breakdown: EvalBreakdown,
+    },
+    // Puzzles
+    PuzzleStarted {
+        puzzle: PuzzleInfo,
+        stats: PuzzleStats,
+    },
+    /// Answer to a move of the solver. A right move is followed by the
+    /// opponent's `reply`, unless it ends the puzzle.
+    PuzzleMove {
+        mv: String,
+        correct: bool,
+        reply: Option<String>,
+    },
+    PuzzleFinished {
+        solved: bool,
+        /// The moves the solver had to find.
+        solution: Vec<String>,
+        stats: PuzzleStats,
+        rating_before: f64,
+    },
+    // Bughouse
+    /// Players of the four seats, sent whenever one changes. `seat` is the
+    /// receiver's, None for spectators.
+    BughouseSeats {
+        seats: Vec<SeatInfo>,
+        seat: Option<Seat>,
+    },
+    /// Position of one board, sent to everyone after each move on it.
+    BughouseBoard {
+        board_id: BoardId,
+        board: Vec<Vec<Option<String>>>,
+        turn: Color,
+        counter: usize,
+        incheck: Option<Color>,
+        pockets: Pockets,
+        clock: Option<ClockState>,
+    },
+    /// Pieces in hand of a board, after a capture on the other one.
+    BughousePockets {
+        board_id: BoardId,
+        pockets: Pockets,
+    },
     CloseRoom {
         id: Uuid,
     },
@@ -63,6 +271,93 @@ pub enum ServerMessage {
     RoomClean {
         room_id: Uuid,
     },
+    // Accounts
+    LoggedIn {
+        account: AccountInfo,
+        token: String,
+    },
+    LoggedOut,
+    // Ratings
+    Leaderboard {
+        category: TimeCategory,
+        entries: Vec<LeaderboardEntry>,
+    },
+    RatingHistory {
+        account_id: Uuid,
+        entries: Vec<RatingHistoryEntry>,
+    },
+    // Spectators
+    Snapshot {
+        room_status: RoomStatus,
+        gamemod: GameMode,
+        board: Vec<Vec<Option<String>>>,
+        turn: Color,
+        moves: Vec<String>,
+        white: PlayerRecord,
+        black: PlayerRecord,
+        time_control: Option<TimeControl>,
+        /// Clock of each side, see `GameStarted`.
+        #[serde(default)]
+        white_time_control: Option<TimeControl>,
+        #[serde(default)]
+        black_time_control: Option<TimeControl>,
+        handicap: Option<Handicap>,
+        variant: Variant,
+        spectators: usize,
+        spectator_delay: usize,
+    },
+    SpectatorCount {
+        count: usize,
+    },
+    // Chat
+    Chat {
+        entry: ChatEntry,
+    },
+    ChatHistory {
+        entries: Vec<ChatEntry>,
+    },
+    /// External engines that can be picked in `AiProfile::External`.
+    Engines {
+        names: Vec<String>,
+    },
+    // Invitations
+    InviteCode {
+        room_id: Uuid,
+        code: String,
+        expires_at: u64,
+    },
+    // Lobby
+    LobbySnapshot {
+        rooms: Vec<RoomSummary>,
+    },
+    LobbyRoomAdded {
+        room: RoomSummary,
+    },
+    LobbyRoomUpdated {
+        room: RoomSummary,
+    },
+    LobbyRoomRemoved {
+        room_id: Uuid,
+    },
+    // Matchmaking
+    Queued {
+        time_control: Option<TimeControl>,
+        rated: bool,
+        rating: f64,
+        rating_range: f64,
+    },
+    LeftQueue,
+    // History
+    GameList {
+        games: Vec<GameRecord>,
+    },
+    ArchivedGame {
+        game: Box<GameRecord>,
+    },
+    Pgn {
+        game_id: Uuid,
+        pgn: String,
+    },
     QuitGame,
     Ping,
 }
@@ -74,7 +369,33 @@ pub enum ClientMessage {
     CreateRoom {
         mode: GameMode,
         difficulty: Option<Difficulty>, // for AI
+        #[serde(default)]
+        rated: bool,
+        #[serde(default)]
+        time_control: Option<TimeControl>,
+        #[serde(default)]
+        public: bool,
+        /// Spectators see the game this many moves behind.
+        #[serde(default)]
+        spectator_delay: usize,
+        /// Per-side engines, overriding `difficulty` for that side.
+        #[serde(default)]
+        white_ai: Option<AiProfile>,
+        #[serde(default)]
+        black_ai: Option<AiProfile>,
+        /// Material or time given by one side.
+        #[serde(default)]
+        handicap: Option<Handicap>,
+        /// Starting position, the usual one when not set.
+        #[serde(default)]
+        fen: Option<String>,
+        #[serde(default)]
+        variant: Variant,
+        /// Bughouse seats played by the built-in AI at `difficulty`.
+        #[serde(default)]
+        ai_seats: Vec<Seat>,
     },
+    ListEngines,
     JoinRoom {
         room_id: Uuid, // Uuid as string
     },
@@ -82,9 +403,20 @@ pub enum ClientMessage {
         state: bool,
     },
     StartGame,
+    /// Bughouse: moves to a free seat before the match starts.
+    TakeSeat {
+        seat: Seat,
+    },
     Move {
         mv: String,
     },
+    /// Crazyhouse: drops a piece of the pocket ('N', 'P'...) on `square`.
+    DropPiece {
+        piece: char,
+        square: String,
+    },
+    /// Destinations of the piece on the square `mv` ("e2"), or drop
+    /// squares of a pocket piece ("N") in Crazyhouse and Bughouse.
     GetLegalMoves {
         mv: String,
     },
@@ -95,6 +427,81 @@ pub enum ClientMessage {
         piece: String,
         pos: String,
     },
+    /// Looks for a mate in `n` moves for the side to move.
+    SolveMate {
+        n: u8,
+    },
+    /// Plays the sandbox position as a game, the sender taking `side`.
+    PlaySandboxPosition {
+        side: Color,
+        opponent: SandboxOpponent,
+    },
+    /// Turns the evaluation breakdown on or off.
+    SetAnalysis {
+        enabled: bool,
+    },
     PauseRequest,
+    // Puzzles
+    /// Starts the next puzzle, giving up the current one if unsolved.
+    NextPuzzle,
+    // Rematch
+    OfferRematch,
+    DeclineRematch,
+    // Accounts
+    Register {
+        username: String,
+        password: String,
+        display_name: Option<String>,
+    },
+    Login {
+        username: String,
+        password: String,
+    },
+    Logout,
+    // Chat
+    Chat {
+        channel: ChatChannel,
+        text: String,
+    },
+    MutePlayer {
+        client_id: Uuid,
+        muted: bool,
+    },
+    KickPlayer {
+        client_id: Uuid,
+    },
+    // Invitations
+    CreateInvite,
+    JoinByCode {
+        code: String,
+    },
+    // Lobby
+    SubscribeLobby,
+    UnsubscribeLobby,
+    // Matchmaking
+    JoinQueue {
+        time_control: Option<TimeControl>,
+        #[serde(default)]
+        rated: bool,
+        rating_range: f64,
+    },
+    LeaveQueue,
+    // Ratings
+    GetLeaderboard {
+        category: TimeCategory,
+    },
+    GetRatingHistory {
+        account_id: Uuid,
+    },
+    // History
+    ListGames {
+        query: GameQuery,
+    },
+    GetGame {
+        game_id: Uuid,
+    },
+    GetPgn {
+        game_id: Uuid,
+    },
     Pong,
 }
@@ -0,0 +1,170 @@
+//! Tactics puzzles in the Lichess CSV format:
+//!
+//! ```text
+//! PuzzleId,FEN,Moves,Rating,RatingDeviation,Popularity,NbPlays,Themes,GameUrl,OpeningTags
+//! 00sHx,q3k1nr/1pp1nQpp/3p4/1P2p3/4P3/B1PP1b2/B5PP/5K2 b k - 0 17,e8d7 a2e6 d7d8 f7f8,1760,80,83,72,mate mateIn2 middlegame short,https://lichess.org/yyznGmXs/black#34,
+//! ```
+//!
+//! The FEN is the position before the opponent's last move: the first move
+//! of the line is played automatically, the solver plays every other move.
+
+use crate::messages::PuzzleInfo;
+use crate::piece::Color;
+use crate::uci::{from_uci, to_uci};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Puzzle {
+    pub id: String,
+    pub fen: String,
+    /// The whole line in server notation ("e2->e4"), setup move first.
+    pub moves: Vec<String>,
+    pub rating: u32,
+    pub deviation: u32,
+    pub themes: Vec<String>,
+}
+
+pub const CSV_HEADER: &str =
+    "PuzzleId,FEN,Moves,Rating,RatingDeviation,Popularity,NbPlays,Themes,GameUrl,OpeningTags";
+
+impl Puzzle {
+    /// Parses one line of the puzzle file. Columns after the themes are
+    /// ignored, they may be missing.
+    pub fn from_csv(line: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = line.trim().split(',').collect();
+        if fields.len() < 5 {
+            return Err(format!("Expected at least 5 columns, got {}", fields.len()));
+        }
+        let moves = fields[2]
+            .split_whitespace()
+            .map(|mv| from_uci(mv).ok_or_else(|| format!("Invalid move {}", mv)))
+            .collect::<Result<Vec<String>, String>>()?;
+        // Coup de mise en place, puis des paires coup du joueur / réponse,
+        // la ligne se terminant par un coup du joueur
+        if moves.is_empty() || !moves.len().is_multiple_of(2) {
+            return Err("The line must end with a move of the solver".into());
+        }
+        if fields[1].split_whitespace().nth(1).is_none() {
+            return Err(format!("Invalid FEN {}", fields[1]));
+        }
+        Ok(Puzzle {
+            id: fields[0].to_string(),
+            fen: fields[1].to_string(),
+            moves,
+            rating: fields[3]
+                .parse()
+                .map_err(|e| format!("Invalid rating {}: {}", fields[3], e))?,
+            deviation: fields[4]
+                .parse()
+                .map_err(|e| format!("Invalid rating deviation {}: {}", fields[4], e))?,
+            themes: fields
+                .get(7)
+                .map(|t| t.split_whitespace().map(String::from).collect())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Line of the puzzle file, popularity and play count left at 0.
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},0,0,{},,",
+            self.id,
+            self.fen,
+            self.moves
+                .iter()
+                .map(|mv| to_uci(mv))
+                .collect::<Vec<_>>()
+                .join(" "),
+            self.rating,
+            self.deviation,
+            self.themes.join(" ")
+        )
+    }
+
+    /// Side of the solver: the FEN has the opponent to move.
+    pub fn solver_color(&self) -> Color {
+        match self.fen.split_whitespace().nth(1) {
+            Some("w") => Color::Black,
+            _ => Color::White,
+        }
+    }
+
+    /// The moves the solver has to find.
+    pub fn solution(&self) -> Vec<String> {
+        self.moves[1..].iter().step_by(2).cloned().collect()
+    }
+
+    pub fn info(&self) -> PuzzleInfo {
+        PuzzleInfo {
+            id: self.id.clone(),
+            fen: self.fen.clone(),
+            rating: self.rating,
+            themes: self.themes.clone(),
+            color: self.solver_color(),
+            setup_move: self.moves[0].clone(),
+        }
+    }
+}
+
+/// What happens after a move of the solver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PuzzleStep {
+    /// Right move, the opponent answers with `reply`.
+    Continue {
+        reply: String,
+    },
+    Solved,
+    Failed,
+}
+
+/// Progress of the solver through the line of a puzzle.
+#[derive(Debug, Clone)]
+pub struct PuzzleAttempt {
+    /// Index in `moves` of the next move of the solver.
+    ply: usize,
+    finished: bool,
+}
+
+impl Default for PuzzleAttempt {
+    fn default() -> Self {
+        PuzzleAttempt {
+            ply: 1,
+            finished: false,
+        }
+    }
+}
+
+impl PuzzleAttempt {
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Checks a move of the solver against the line of `puzzle`.
+    pub fn play(&mut self, puzzle: &Puzzle, mv: &str) -> PuzzleStep {
+        if self.finished {
+            return PuzzleStep::Failed;
+        }
+        // "e7->e8Q" et "e7->e8q" désignent la même promotion
+        if !mv.trim().eq_ignore_ascii_case(&puzzle.moves[self.ply]) {
+            self.finished = true;
+            return PuzzleStep::Failed;
+        }
+        match puzzle.moves.get(self.ply + 1) {
+            Some(reply) => {
+                self.ply += 2;
+                PuzzleStep::Continue {
+                    reply: reply.clone(),
+                }
+            }
+            None => {
+                self.finished = true;
+                PuzzleStep::Solved
+            }
+        }
+    }
+
+    /// Ends the attempt, for instance when the solver gives up. Later moves
+    /// are rejected.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+}
@@ -7,6 +7,9 @@ pub enum GameMode {
     PlayerVsAI,
     AIvsAI,
     Sandbox,
+    Puzzle,
+    /// Four players on two linked boards.
+    Bughouse,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -25,3 +28,74 @@ pub enum RoomStatus {
     Finished,
     Paused,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Aborted,
+}
+
+impl GameResult {
+    pub fn winner(color: Color) -> GameResult {
+        match color {
+            Color::White => GameResult::WhiteWins,
+            Color::Black => GameResult::BlackWins,
+        }
+    }
+
+    // Notation PGN du résultat
+    pub fn to_pgn(&self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Aborted => "*",
+        }
+    }
+}
+
+/// Players channel is seen by everyone in the room, the spectators
+/// channel only by spectators and the team channel only by the two
+/// partners of a Bughouse team.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChatChannel {
+    Players,
+    Spectators,
+    Team,
+}
+
+/// Clock settings of a room, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TimeControl {
+    pub initial: u64,
+    pub increment: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TimeCategory {
+    Bullet,
+    Blitz,
+    Rapid,
+    Classical,
+    Correspondence,
+}
+
+impl TimeControl {
+    // Même découpage que Lichess: durée estimée = initial + 40 * incrément
+    pub fn category(&self) -> TimeCategory {
+        match self.initial + 40 * self.increment {
+            0..=179 => TimeCategory::Bullet,
+            180..=479 => TimeCategory::Blitz,
+            480..=1499 => TimeCategory::Rapid,
+            _ => TimeCategory::Classical,
+        }
+    }
+}
+
+impl TimeCategory {
+    pub fn of(time_control: Option<TimeControl>) -> TimeCategory {
+        time_control.map_or(TimeCategory::Correspondence, |tc| tc.category())
+    }
+}
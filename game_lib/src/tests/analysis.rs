@@ -0,0 +1,181 @@
+use crate::analysis::{motifs, parse_square, BoardView, Motif};
+use crate::automation::ai::EvalVariant;
+use crate::automation::eval::{evaluate, EvalTerm};
+use crate::fen::FenPosition;
+use crate::piece::Color;
+use crate::zobrist::PieceKind;
+
+/// A few pieces on an otherwise empty board.
+struct Pieces(Vec<(&'static str, Color, PieceKind)>);
+
+impl BoardView for Pieces {
+    fn turn(&self) -> Color {
+        Color::White
+    }
+
+    fn piece_at(&self, square: u8) -> Option<(Color, PieceKind)> {
+        self.0
+            .iter()
+            .find(|(name, _, _)| parse_square(name) == Some(square))
+            .map(|&(_, c, k)| (c, k))
+    }
+}
+
+fn hanging(position: &Pieces) -> Vec<String> {
+    motifs(position)
+        .into_iter()
+        .filter_map(|m| match m {
+            Motif::Hanging { square, .. } => Some(square),
+            _ => None,
+        })
+        .collect()
+}
+
+fn forkers(position: &Pieces) -> Vec<String> {
+    motifs(position)
+        .into_iter()
+        .filter_map(|m| match m {
+            Motif::Fork { forker, .. } => Some(forker),
+            _ => None,
+        })
+        .collect()
+}
+
+use Color::{Black, White};
+use PieceKind::*;
+
+#[test]
+fn a_defended_piece_next_to_the_king_is_not_hanging() {
+    let position = Pieces(vec![
+        ("e1", White, King),
+        ("e2", Black, Knight),
+        ("d3", Black, Pawn),
+        ("h8", Black, King),
+    ]);
+    assert!(hanging(&position).is_empty());
+}
+
+#[test]
+fn an_undefended_piece_next_to_the_king_is_hanging() {
+    let position = Pieces(vec![
+        ("e1", White, King),
+        ("e2", Black, Knight),
+        ("h8", Black, King),
+    ]);
+    assert_eq!(hanging(&position), ["e2"]);
+}
+
+#[test]
+fn a_piece_attacked_by_a_cheaper_one_is_hanging_even_if_defended() {
+    let position = Pieces(vec![
+        ("a1", White, King),
+        ("d4", White, Pawn),
+        ("e5", Black, Rook),
+        ("e8", Black, Rook),
+        ("h8", Black, King),
+    ]);
+    assert_eq!(hanging(&position), ["e5"]);
+}
+
+#[test]
+fn the_king_does_not_fork_defended_pieces() {
+    let mut position = Pieces(vec![
+        ("e4", White, King),
+        ("d5", Black, Knight),
+        ("f5", Black, Bishop),
+        ("e6", Black, Pawn),
+        ("h8", Black, King),
+    ]);
+    assert!(forkers(&position).is_empty());
+    // Sans le pion e6, les deux pièces sont en prise
+    position.0.retain(|&(name, _, _)| name != "e6");
+    assert_eq!(forkers(&position), ["e4"]);
+}
+
+#[test]
+fn a_knight_forks_king_and_rook() {
+    let position = Pieces(vec![
+        ("a1", White, King),
+        ("c7", White, Knight),
+        ("e8", Black, King),
+        ("a8", Black, Rook),
+        ("b8", Black, Queen),
+    ]);
+    assert_eq!(forkers(&position), ["c7"]);
+}
+
+// Même position, couleurs échangées et plateau retourné
+const MIRRORED: [(&str, &str); 3] = [
+    (
+        "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+        "rnbqk2r/pppp1ppp/5n2/2b1p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R b KQkq - 4 4",
+    ),
+    (
+        "6k1/5ppp/8/3P4/8/8/5PPP/2B1B1K1 w - - 0 1",
+        "2b1b1k1/5ppp/8/8/3p4/8/5PPP/6K1 b - - 0 1",
+    ),
+    (
+        "4k3/8/8/8/8/8/PP6/Q3K3 w - - 0 1",
+        "q3k3/pp6/8/8/8/8/8/4K3 b - - 0 1",
+    ),
+];
+
+#[test]
+fn evaluation_terms_sum_to_the_total() {
+    for (fen, _) in MIRRORED {
+        let position = FenPosition::parse(fen).unwrap();
+        for variant in [
+            EvalVariant::Material,
+            EvalVariant::Positional,
+            EvalVariant::Standard,
+        ] {
+            let breakdown = evaluate(&position, variant);
+            let terms: Vec<EvalTerm> = breakdown.terms.iter().map(|t| t.term).collect();
+            assert_eq!(terms, EvalTerm::of(variant));
+            let sum: i32 = breakdown.terms.iter().map(|t| t.white - t.black).sum();
+            assert_eq!(breakdown.total(), sum, "{}", fen);
+            assert_eq!(breakdown.for_side(White), sum);
+            assert_eq!(breakdown.for_side(Black), -sum);
+        }
+    }
+}
+
+#[test]
+fn evaluation_terms_swap_with_the_colours() {
+    for (fen, mirrored) in MIRRORED {
+        let breakdown = evaluate(&FenPosition::parse(fen).unwrap(), EvalVariant::Standard);
+        let other = evaluate(
+            &FenPosition::parse(mirrored).unwrap(),
+            EvalVariant::Standard,
+        );
+        for (term, swapped) in breakdown.terms.iter().zip(&other.terms) {
+            assert_eq!(
+                (term.white, term.black),
+                (swapped.black, swapped.white),
+                "{:?} in {}",
+                term.term,
+                fen
+            );
+        }
+        assert_eq!(breakdown.total(), -other.total());
+    }
+}
+
+#[test]
+fn evaluation_favours_the_side_ahead() {
+    // Dame et deux pions de plus pour les blancs
+    let (fen, mirrored) = MIRRORED[2];
+    let white_ahead = evaluate(&FenPosition::parse(fen).unwrap(), EvalVariant::Standard);
+    assert!(white_ahead.total() > 0);
+    assert!(white_ahead.for_side(Black) < 0);
+    let material = white_ahead.terms[0];
+    assert_eq!(material.term, EvalTerm::Material);
+    assert!(material.white > material.black);
+
+    let black_ahead = evaluate(
+        &FenPosition::parse(mirrored).unwrap(),
+        EvalVariant::Standard,
+    );
+    assert!(black_ahead.total() < 0);
+    assert!(black_ahead.for_side(Black) > 0);
+}
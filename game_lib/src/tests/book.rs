@@ -0,0 +1,113 @@
+use crate::automation::book::BookEntry;
+use crate::automation::mate::MatePosition;
+use crate::fen::FenPosition;
+use crate::piece::Color;
+use crate::zobrist::{KeyInput, ZobristKeys, RANDOM64_LEN, START_POSITION_KEY};
+
+fn after(moves: &[&str]) -> FenPosition {
+    let mut position = FenPosition::start();
+    for mv in moves {
+        position.play(mv);
+    }
+    position
+}
+
+// Clés publiées avec le format Polyglot, coups en notation UCI
+const SAMPLE_KEYS: [(&[&str], u64); 9] = [
+    (&[], 0x463b96181691fc9c),
+    (&["e2->e4"], 0x823c9b50fd114196),
+    (&["e2->e4", "d7->d5"], 0x0756b94461c50fb0),
+    (&["e2->e4", "d7->d5", "e4->e5"], 0x662fafb965db29d4),
+    (
+        &["e2->e4", "d7->d5", "e4->e5", "f7->f5"],
+        0x22a48b5a8e47ff78,
+    ),
+    (
+        &["e2->e4", "d7->d5", "e4->e5", "f7->f5", "e1->e2"],
+        0x652a607ca3f242c1,
+    ),
+    (
+        &["e2->e4", "d7->d5", "e4->e5", "f7->f5", "e1->e2", "e8->f7"],
+        0x00fdd303c946bdd9,
+    ),
+    (
+        &["a2->a4", "b7->b5", "h2->h4", "b5->b4", "c2->c4"],
+        0x3c8123ea7b067637,
+    ),
+    (
+        &[
+            "a2->a4", "b7->b5", "h2->h4", "b5->b4", "c2->c4", "b4->c3", "a1->a3",
+        ],
+        0x5c3f9b829b279560,
+    ),
+];
+
+#[test]
+fn key_input_of_the_start_position() {
+    let mut expected = KeyInput::start_position();
+    expected.pieces.sort_by_key(|&(_, _, square)| square);
+    assert_eq!(KeyInput::of(&FenPosition::start()), expected);
+}
+
+#[test]
+fn en_passant_file_counts_only_when_a_capture_is_possible() {
+    // Aucun pion noir à côté de e4
+    let input = KeyInput::of(&after(&["e2->e4"]));
+    assert_eq!(after(&["e2->e4"]).en_passant, Some(20));
+    assert_eq!(input.en_passant_file, None);
+    // e5xf6 est possible
+    let input = KeyInput::of(&after(&["e2->e4", "d7->d5", "e4->e5", "f7->f5"]));
+    assert_eq!(input.en_passant_file, Some(5));
+    // Un pion voisin de la mauvaise couleur ne compte pas
+    let position = FenPosition::parse("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+    assert_eq!(KeyInput::of(&position).en_passant_file, Some(4));
+    let position = FenPosition::parse("4k3/8/8/8/3PP3/8/8/4K3 b - e3 0 1").unwrap();
+    assert_eq!(KeyInput::of(&position).en_passant_file, None);
+}
+
+#[test]
+fn key_input_follows_castling_rights_and_turn() {
+    let input = KeyInput::of(&after(&["e2->e4", "d7->d5", "e1->e2"]));
+    assert!(!input.white_short && !input.white_long);
+    assert!(input.black_short && input.black_long);
+    assert!(!input.white_to_move);
+    assert!(input
+        .pieces
+        .contains(&(Color::White, crate::zobrist::PieceKind::King, 12)));
+}
+
+#[test]
+fn book_castling_is_the_king_taking_its_rook() {
+    // e1h1: colonne de départ 4, arrivée h1
+    let entry = BookEntry {
+        key: 0,
+        raw_move: 4 << 6 | 7,
+        weight: 1,
+    };
+    assert_eq!(entry.to_move(true), "e1->g1");
+    assert_eq!(entry.to_move(false), "e1->h1");
+}
+
+#[test]
+fn zobrist_keys_reject_another_table() {
+    assert!(ZobristKeys::new(vec![0; RANDOM64_LEN - 1]).is_err());
+    assert!(ZobristKeys::new((0..RANDOM64_LEN as u64).collect()).is_err());
+}
+
+// La table Random64 n'est pas livrée avec le code: le test la lit dans le
+// fichier du serveur, ou dans celui donné par POLYGLOT_KEYS
+#[test]
+#[ignore = "needs the Polyglot Random64 table in polyglot_random.txt or POLYGLOT_KEYS"]
+fn zobrist_keys_match_the_published_polyglot_keys() {
+    let path = std::env::var("POLYGLOT_KEYS").unwrap_or_else(|_| {
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../polyglot_random.txt").to_string()
+    });
+    let text =
+        std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("Cannot read {}: {}", path, e));
+    let keys = ZobristKeys::parse(&text).unwrap();
+    assert_eq!(keys.key(&KeyInput::start_position()), START_POSITION_KEY);
+    for (moves, key) in SAMPLE_KEYS {
+        let input = KeyInput::of(&after(moves));
+        assert_eq!(keys.key(&input), key, "after {:?}", moves);
+    }
+}
@@ -0,0 +1,39 @@
+use crate::bughouse::{BoardId, Bughouse, Seat};
+use crate::fen::FenPosition;
+use crate::piece::Color;
+use crate::sharedenums::GameResult;
+
+fn from_fen(fen: &str) -> Bughouse<FenPosition> {
+    Bughouse::new(FenPosition::parse(fen).unwrap(), None)
+}
+
+const WHITE_A: Seat = Seat {
+    board: BoardId::A,
+    color: Color::White,
+};
+
+#[test]
+fn a_check_a_drop_could_block_is_not_mate() {
+    let mut bughouse = from_fen("4k3/3ppp2/8/8/8/8/8/R3K3 w - - 0 1");
+    bughouse.play(WHITE_A, "a1->a8", 0).unwrap();
+    assert_eq!(bughouse.result(), None);
+    // Les noirs attendent une pièce de leur partenaire
+    assert!(bughouse.legal_moves(BoardId::A).is_empty());
+}
+
+#[test]
+fn a_contact_mate_ends_the_match() {
+    let mut bughouse = from_fen("k7/8/1K6/8/8/8/8/7Q w - - 0 1");
+    bughouse.play(WHITE_A, "h1->b7", 0).unwrap();
+    let result = bughouse.result().unwrap();
+    assert_eq!(result.board, BoardId::A);
+    assert_eq!(result.result, GameResult::WhiteWins);
+}
+
+#[test]
+fn promotions_without_a_piece_are_queens() {
+    let mut bughouse = from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+    bughouse.play(WHITE_A, "a7->a8", 0).unwrap();
+    assert_eq!(bughouse.board(BoardId::A).moves, ["a7->a8q"]);
+    assert!(bughouse.play(WHITE_A, "e1->e2", 0).is_err());
+}
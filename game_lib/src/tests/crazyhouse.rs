@@ -0,0 +1,59 @@
+use crate::crazyhouse::{encode_placement, move_targets, split_placement};
+use crate::fen::FenPosition;
+use crate::piece::Color;
+use crate::variant::{Variant, VariantState};
+use crate::zobrist::PieceKind;
+
+fn crazyhouse(fen: &str) -> (FenPosition, VariantState) {
+    let (standard, state) = Variant::Crazyhouse.split_fen(fen).unwrap();
+    (FenPosition::parse(&standard).unwrap(), state)
+}
+
+#[test]
+fn placement_round_trip_keeps_pockets_and_promoted_marks() {
+    let placement = "rQ~2k2r/8/8/8/8/8/6n~1/R3K2R[NPqp]";
+    let (board, pockets, promoted) = split_placement(placement).unwrap();
+    assert_eq!(board, "rQ2k2r/8/8/8/8/8/6n1/R3K2R");
+    assert_eq!(promoted, 1 << 57 | 1 << 14);
+    assert_eq!(pockets.of(Color::White).count(PieceKind::Knight), 1);
+    assert_eq!(pockets.of(Color::Black).count(PieceKind::Queen), 1);
+    assert_eq!(encode_placement(&board, &pockets, promoted), placement);
+
+    assert!(split_placement("8/8/8/8/8/8/8/8[Nx]").is_err());
+    assert!(split_placement("8/8/8/8/8/8/8/8[N").is_err());
+}
+
+#[test]
+fn a_promoted_piece_goes_back_to_a_pawn_when_taken() {
+    let (mut position, mut state) = crazyhouse("r2Q~k3/8/8/8/8/8/8/4K3[] b - - 0 1");
+    Variant::Crazyhouse.play(&mut position, &mut state, "a8->d8");
+    let pocket = state.pockets.of(Color::Black);
+    assert_eq!(pocket.count(PieceKind::Pawn), 1);
+    assert_eq!(pocket.count(PieceKind::Queen), 0);
+    assert_eq!(state.promoted, 0);
+}
+
+#[test]
+fn promotions_are_marked_and_followed() {
+    let (mut position, mut state) = crazyhouse("4k3/P7/8/8/8/8/8/4K3[] w - - 0 1");
+    Variant::Crazyhouse.play(&mut position, &mut state, "a7->a8");
+    assert_eq!(state.promoted, 1 << 56);
+    Variant::Crazyhouse.play(&mut position, &mut state, "e8->d7");
+    Variant::Crazyhouse.play(&mut position, &mut state, "a8->a4");
+    assert_eq!(state.promoted, 1 << 24);
+    let fen = Variant::Crazyhouse.encode_fen(&position.to_fen(), &state);
+    assert!(fen.starts_with("8/3k4/8/8/Q~7/8/8/4K3[] b"));
+}
+
+#[test]
+fn legal_moves_requests_give_drop_squares() {
+    let (position, state) = crazyhouse("4k3/8/8/8/8/8/8/4K3[Pn] w - - 0 1");
+    let moves = Variant::Crazyhouse.legal_moves(&position, &state);
+    let pawn = move_targets(&moves, "P");
+    assert_eq!(pawn.len(), 48);
+    assert!(!pawn.iter().any(|sq| sq.ends_with('1') || sq.ends_with('8')));
+    assert_eq!(move_targets(&moves, "p@"), pawn);
+    // Le cavalier est dans la poche des noirs
+    assert!(move_targets(&moves, "N").is_empty());
+    assert_eq!(move_targets(&moves, "e1").len(), 5);
+}
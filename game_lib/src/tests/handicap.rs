@@ -0,0 +1,141 @@
+use crate::fen::check_start_fen;
+use crate::handicap::{Handicap, MaterialOdds};
+use crate::piece::Color;
+use crate::sharedenums::TimeControl;
+
+use Color::{Black, White};
+use MaterialOdds::*;
+
+fn material(giver: Color, odds: MaterialOdds) -> Option<String> {
+    Handicap::Material { giver, odds }.start_fen()
+}
+
+const BLITZ: TimeControl = TimeControl {
+    initial: 300,
+    increment: 2,
+};
+const ONE_MINUTE: TimeControl = TimeControl {
+    initial: 60,
+    increment: 0,
+};
+
+#[test]
+fn material_odds_remove_the_giver_piece() {
+    let cases = [
+        (
+            White,
+            PawnAndMove,
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPP1PP/RNBQKBNR b KQkq - 0 1",
+        ),
+        (
+            White,
+            Knight,
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R1BQKBNR w KQkq - 0 1",
+        ),
+        (
+            White,
+            Rook,
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/1NBQKBNR w Kkq - 0 1",
+        ),
+        (
+            White,
+            Queen,
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1",
+        ),
+        (
+            Black,
+            PawnAndMove,
+            "rnbqkbnr/ppppp1pp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        ),
+        (
+            Black,
+            Knight,
+            "r1bqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        ),
+        (
+            Black,
+            Rook,
+            "1nbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQk - 0 1",
+        ),
+        (
+            Black,
+            Queen,
+            "rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        ),
+    ];
+    for (giver, odds, fen) in cases {
+        assert_eq!(
+            material(giver, odds).as_deref(),
+            Some(fen),
+            "{:?} {:?}",
+            giver,
+            odds
+        );
+        assert!(check_start_fen(fen).is_ok(), "{}", fen);
+    }
+}
+
+#[test]
+fn pawn_and_move_gives_the_first_move() {
+    for giver in [White, Black] {
+        let pawn = Handicap::Material {
+            giver,
+            odds: PawnAndMove,
+        };
+        assert_eq!(pawn.first_turn(), giver.opposite());
+        let knight = Handicap::Material {
+            giver,
+            odds: Knight,
+        };
+        assert_eq!(knight.first_turn(), White);
+    }
+}
+
+#[test]
+fn time_odds_shorten_only_the_giver_clock() {
+    let handicap = Handicap::Time {
+        giver: Black,
+        time_control: ONE_MINUTE,
+    };
+    assert_eq!(handicap.start_fen(), None);
+    assert_eq!(handicap.first_turn(), White);
+    assert_eq!(handicap.time_control(Black, Some(BLITZ)), Some(ONE_MINUTE));
+    assert_eq!(handicap.time_control(White, Some(BLITZ)), Some(BLITZ));
+    assert_eq!(handicap.time_control(White, None), None);
+    // La revanche échange les pendules
+    let swapped = handicap.swapped();
+    assert_eq!(swapped.time_control(White, Some(BLITZ)), Some(ONE_MINUTE));
+    assert_eq!(swapped.time_control(Black, Some(BLITZ)), Some(BLITZ));
+}
+
+#[test]
+fn material_odds_keep_the_room_clock() {
+    let handicap = Handicap::Material {
+        giver: White,
+        odds: Queen,
+    };
+    for color in [White, Black] {
+        assert_eq!(handicap.time_control(color, Some(BLITZ)), Some(BLITZ));
+    }
+}
+
+#[test]
+fn odds_games_are_unrated_and_time_odds_give_less_time() {
+    let time = |initial| Handicap::Time {
+        giver: White,
+        time_control: TimeControl {
+            initial,
+            increment: 0,
+        },
+    };
+    assert!(time(60).check(false, Some(BLITZ)).is_ok());
+    assert!(time(60).check(true, Some(BLITZ)).is_err());
+    assert!(time(60).check(false, None).is_err());
+    assert!(time(300).check(false, Some(BLITZ)).is_err());
+    assert!(time(0).check(false, Some(BLITZ)).is_err());
+    let knight = Handicap::Material {
+        giver: White,
+        odds: Knight,
+    };
+    assert!(knight.check(false, None).is_ok());
+}
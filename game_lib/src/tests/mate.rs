@@ -0,0 +1,137 @@
+use crate::automation::mate::{MateLine, MateOutcome, MatePosition, MateSearch};
+use crate::fen::FenPosition;
+
+// Arbre de jeu écrit à la main: (noeud, coup, noeud suivant). Un noeud
+// sans coup est mat s'il est en échec, pat sinon.
+#[derive(Clone)]
+struct Tree {
+    node: &'static str,
+    edges: &'static [(&'static str, &'static str, &'static str)],
+    checks: &'static [&'static str],
+}
+
+impl Tree {
+    fn new(
+        edges: &'static [(&'static str, &'static str, &'static str)],
+        checks: &'static [&'static str],
+    ) -> Self {
+        Tree {
+            node: "root",
+            edges,
+            checks,
+        }
+    }
+}
+
+impl MatePosition for Tree {
+    fn legal_moves(&self) -> Vec<String> {
+        self.edges
+            .iter()
+            .filter(|(from, _, _)| *from == self.node)
+            .map(|(_, mv, _)| mv.to_string())
+            .collect()
+    }
+
+    fn play(&mut self, mv: &str) {
+        if let Some((_, _, to)) = self
+            .edges
+            .iter()
+            .find(|(from, m, _)| *from == self.node && *m == mv)
+        {
+            self.node = to;
+        }
+    }
+
+    fn in_check(&self) -> bool {
+        self.checks.contains(&self.node)
+    }
+}
+
+fn line(moves_to_mate: u8, line: &[&str]) -> MateLine {
+    MateLine {
+        moves_to_mate,
+        line: line.iter().map(|mv| mv.to_string()).collect(),
+    }
+}
+
+#[test]
+fn finds_mate_in_one_and_ignores_stalemate() {
+    let tree = Tree::new(
+        &[
+            ("root", "mate", "mated"),
+            ("root", "stalemate", "stalemated"),
+            ("root", "quiet", "other"),
+            ("other", "escape", "root"),
+        ],
+        &["mated"],
+    );
+    assert_eq!(
+        MateSearch::default().solve(&tree, 1),
+        MateOutcome::Found {
+            lines: vec![line(1, &["mate"])]
+        }
+    );
+}
+
+#[test]
+fn mate_in_two_follows_the_longest_defence() {
+    let edges = &[
+        ("root", "key", "defence"),
+        ("defence", "short", "a"),
+        ("defence", "long", "b"),
+        ("a", "mate_a", "mated"),
+        ("b", "check", "c"),
+        ("c", "only", "d"),
+        ("d", "mate_d", "mated"),
+    ];
+    let tree = Tree::new(edges, &["mated", "c"]);
+    assert_eq!(MateSearch::default().solve(&tree, 1), MateOutcome::NoMate);
+    // Contre "long", il faut trois coups: pas de mat en deux
+    assert_eq!(MateSearch::default().solve(&tree, 2), MateOutcome::NoMate);
+    assert_eq!(
+        MateSearch::default().solve(&tree, 3),
+        MateOutcome::Found {
+            lines: vec![line(3, &["key", "long", "check", "only", "mate_d"])]
+        }
+    );
+}
+
+#[test]
+fn one_defence_refutes_the_key_move() {
+    let tree = Tree::new(
+        &[
+            ("root", "key", "defence"),
+            ("defence", "loses", "a"),
+            ("defence", "holds", "b"),
+            ("a", "mate", "mated"),
+            ("b", "nothing", "b2"),
+            ("b2", "wait", "b"),
+        ],
+        &["mated"],
+    );
+    assert_eq!(MateSearch::default().solve(&tree, 4), MateOutcome::NoMate);
+}
+
+#[test]
+fn node_limit_makes_the_search_inconclusive() {
+    let tree = Tree::new(
+        &[("root", "key", "defence"), ("defence", "reply", "root")],
+        &[],
+    );
+    let mut search = MateSearch::new(3);
+    assert!(matches!(
+        search.solve(&tree, 8),
+        MateOutcome::Inconclusive { nodes: 4 }
+    ));
+}
+
+#[test]
+fn back_rank_mate_on_a_real_position() {
+    let position = FenPosition::parse("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    assert_eq!(
+        MateSearch::default().solve(&position, 2),
+        MateOutcome::Found {
+            lines: vec![line(1, &["a1->a8"])]
+        }
+    );
+}
@@ -1 +1,18 @@
-
+#[cfg(test)]
+mod analysis;
+#[cfg(test)]
+mod book;
+#[cfg(test)]
+mod bughouse;
+#[cfg(test)]
+mod crazyhouse;
+#[cfg(test)]
+mod handicap;
+#[cfg(test)]
+mod mate;
+#[cfg(test)]
+mod notation;
+#[cfg(test)]
+mod tablebase;
+#[cfg(test)]
+mod variant;
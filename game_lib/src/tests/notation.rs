@@ -0,0 +1,154 @@
+use crate::automation::mate::MatePosition;
+use crate::fen::FenPosition;
+use crate::history::{pgn_movetext, pgn_movetext_from};
+use crate::sharedenums::GameResult;
+use crate::variant::{Variant, VariantState};
+
+fn perft(position: &FenPosition, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    position
+        .legal_moves()
+        .iter()
+        .map(|mv| {
+            let mut next = position.clone();
+            next.play(mv);
+            perft(&next, depth - 1)
+        })
+        .sum()
+}
+
+fn moves(list: &[&str]) -> Vec<String> {
+    list.iter().map(|m| m.to_string()).collect()
+}
+
+fn san(fen: &str, mv: &str) -> Option<String> {
+    let position = FenPosition::parse(fen).unwrap();
+    Variant::Standard.san(&position, &VariantState::default(), mv)
+}
+
+#[test]
+fn perft_from_the_start_position() {
+    let start = FenPosition::start();
+    assert_eq!(perft(&start, 1), 20);
+    assert_eq!(perft(&start, 2), 400);
+    assert_eq!(perft(&start, 3), 8902);
+}
+
+#[test]
+fn perft_with_castling_en_passant_and_promotions() {
+    let kiwipete =
+        FenPosition::parse("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    assert_eq!(perft(&kiwipete, 1), 48);
+    assert_eq!(perft(&kiwipete, 2), 2039);
+    let promotions = FenPosition::parse("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1").unwrap();
+    assert_eq!(perft(&promotions, 1), 24);
+    assert_eq!(perft(&promotions, 2), 496);
+}
+
+#[test]
+fn fen_round_trip_after_moves() {
+    let mut position = FenPosition::start();
+    for mv in ["e2->e4", "c7->c5", "g1->f3"] {
+        position.play(mv);
+    }
+    assert_eq!(
+        position.to_fen(),
+        "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+    );
+}
+
+#[test]
+fn san_of_usual_moves() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(san(start, "e2->e4").as_deref(), Some("e4"));
+    assert_eq!(san(start, "g1->f3").as_deref(), Some("Nf3"));
+    assert_eq!(san(start, "e2->e5"), None);
+    let castles = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    assert_eq!(san(castles, "e1->g1").as_deref(), Some("O-O"));
+    assert_eq!(san(castles, "e1->c1").as_deref(), Some("O-O-O"));
+}
+
+#[test]
+fn san_disambiguates_and_marks_captures() {
+    let knights = "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1";
+    assert_eq!(san(knights, "b1->d2").as_deref(), Some("Nbd2"));
+    let rooks = "4k3/8/R7/8/8/8/8/R3K3 w - - 0 1";
+    assert_eq!(san(rooks, "a1->a3").as_deref(), Some("R1a3"));
+    let queens = "4k3/8/8/8/8/Q1Q5/8/Q3K3 w - - 0 1";
+    assert_eq!(san(queens, "a3->b2").as_deref(), Some("Qa3b2"));
+    let en_passant = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+    assert_eq!(san(en_passant, "e5->d6").as_deref(), Some("exd6"));
+}
+
+#[test]
+fn san_of_promotions_and_mates() {
+    let promotion = "8/4P1k1/8/8/8/8/8/4K3 w - - 0 1";
+    assert_eq!(san(promotion, "e7->e8").as_deref(), Some("e8=Q"));
+    assert_eq!(san(promotion, "e7->e8n").as_deref(), Some("e8=N+"));
+    let back_rank = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+    assert_eq!(san(back_rank, "a1->a8").as_deref(), Some("Ra8#"));
+}
+
+#[test]
+fn movetext_is_written_in_san() {
+    let game = moves(&["f2->f3", "e7->e5", "g2->g4", "d8->h4"]);
+    assert_eq!(
+        pgn_movetext(&game, GameResult::BlackWins),
+        "1. f3 e5 2. g4 Qh4# 0-1"
+    );
+    let from_black = "4k3/8/8/8/8/8/4p3/4K3 b - - 0 1";
+    assert_eq!(
+        pgn_movetext_from(
+            &moves(&["e8->d7"]),
+            GameResult::Draw,
+            Some(from_black),
+            Variant::Standard
+        ),
+        "1... Kd7 1/2-1/2"
+    );
+    let middlegame = "4k3/8/8/8/8/8/8/R3K3 b Q - 4 23";
+    assert_eq!(
+        pgn_movetext_from(
+            &moves(&["e8->d7", "e1->c1", "d7->c6"]),
+            GameResult::Draw,
+            Some(middlegame),
+            Variant::Standard
+        ),
+        "23... Kd7 24. O-O-O+ Kc6 1/2-1/2"
+    );
+}
+
+#[test]
+fn movetext_falls_back_to_coordinates_after_an_unknown_move() {
+    let game = moves(&["e2->e4", "e7->e4", "g1->f3"]);
+    assert_eq!(
+        pgn_movetext(&game, GameResult::Draw),
+        "1. e4 e7e4 2. g1f3 1/2-1/2"
+    );
+}
+
+#[test]
+fn crazyhouse_drops_keep_their_notation() {
+    let fen = "4k3/8/8/8/8/8/8/4K3[N] w - - 0 1";
+    let (standard, state) = Variant::Crazyhouse.split_fen(fen).unwrap();
+    let position = FenPosition::parse(&standard).unwrap();
+    assert_eq!(
+        Variant::Crazyhouse
+            .san(&position, &state, "N@d6")
+            .as_deref(),
+        Some("N@d6+")
+    );
+}
+
+#[test]
+fn legal_move_completes_promotions() {
+    let position = FenPosition::parse("8/4P1k1/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let state = VariantState::default();
+    let legal = |mv| Variant::Standard.legal_move(&position, &state, mv);
+    assert_eq!(legal("e7->e8").as_deref(), Some("e7->e8q"));
+    assert_eq!(legal("e7->e8N").as_deref(), Some("e7->e8n"));
+    assert_eq!(legal("e1->e3"), None);
+}
@@ -0,0 +1,109 @@
+use crate::automation::syzygy::{indices, Material};
+use crate::automation::tablebase::{Tablebases, Wdl};
+use crate::fen::FenPosition;
+
+fn position(fen: &str) -> FenPosition {
+    FenPosition::parse(fen).unwrap()
+}
+
+#[test]
+fn king_placements_and_pawn_indices() {
+    let ix = indices();
+    let codes = ix.map_kk.iter().flatten().copied().max();
+    assert_eq!(codes, Some(461));
+    // Triangle a1-d1-d4: b1 d'abord, la diagonale à la fin
+    assert_eq!(ix.map_a1d1d4[1], 0);
+    assert_eq!(ix.map_a1d1d4[0], 6);
+    assert_eq!(ix.map_a1d1d4[27], 9);
+    assert_eq!(ix.binomial[2][5], 10);
+    assert_eq!(ix.binomial[5][63], 7_028_847);
+    // a2 et h2 sont les pions de tête les plus forts
+    assert_eq!(ix.map_pawns[8], 47);
+    assert_eq!(ix.map_pawns[15], 46);
+    assert_eq!(ix.lead_pawns_size[1], [6; 4]);
+    assert_eq!(ix.lead_pawns_size[2][0], 47 + 45 + 43 + 41 + 39 + 37);
+}
+
+#[test]
+fn material_of_table_names() {
+    let material = Material::from_name("KRPvKR").unwrap();
+    assert_eq!(material.piece_count, 5);
+    assert!(material.has_pawns && material.has_unique_pieces);
+    assert_eq!(material.pawn_count, [1, 0]);
+    assert!(!material.symmetric);
+    // Le camp avec le moins de pions mène
+    assert_eq!(Material::from_name("KPvKPP").unwrap().pawn_count, [1, 2]);
+    assert_eq!(Material::from_name("KPPvKP").unwrap().pawn_count, [1, 2]);
+    assert!(!Material::from_name("KRRvK").unwrap().has_unique_pieces);
+    assert!(Material::from_name("KRvKR").unwrap().symmetric);
+}
+
+#[test]
+fn positions_outside_the_tables_are_refused() {
+    let tb = Tablebases::default();
+    let kqk = position("k7/8/1K6/8/8/8/8/7Q w - - 0 1");
+    assert!(!tb.covers(&kqk));
+    assert!(tb.probe_wdl(&kqk).is_err());
+    assert_eq!(tb.best_move(&kqk), None);
+}
+
+// Les tables ne sont pas livrées avec le code: les tests lisent celles du
+// serveur, ou le dossier donné par SYZYGY_PATH
+fn tables() -> Tablebases {
+    let dir = std::env::var("SYZYGY_PATH")
+        .unwrap_or_else(|_| concat!(env!("CARGO_MANIFEST_DIR"), "/../syzygy").to_string());
+    let tb = Tablebases::open(&dir).unwrap_or_else(|e| panic!("{}: {}", dir, e));
+    assert!(tb.max_pieces() >= 5, "No 3-5 piece tables in {}", dir);
+    tb
+}
+
+#[test]
+#[ignore = "needs the Syzygy 3-5 piece tables in syzygy/ or SYZYGY_PATH"]
+fn probes_wdl_with_both_colours() {
+    let tb = tables();
+    let cases = [
+        ("8/8/8/4k3/8/8/8/KN6 w - - 0 1", Wdl::Draw),
+        ("8/8/8/4k3/8/8/8/KBN5 w - - 0 1", Wdl::Win),
+        ("kbn5/8/8/8/4K3/8/8/8 b - - 0 1", Wdl::Win),
+        ("kbn5/8/8/8/4K3/8/8/8 w - - 0 1", Wdl::Loss),
+        ("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", Wdl::Win),
+        ("4k3/8/K7/8/8/8/4p3/8 b - - 0 1", Wdl::Win),
+        // Position de Lucena
+        ("1K1k4/1P6/8/8/8/8/r7/2R5 w - - 0 1", Wdl::Win),
+        ("k6Q/8/1K6/8/8/8/8/8 b - - 1 1", Wdl::Loss),
+    ];
+    for (fen, wdl) in cases {
+        assert_eq!(tb.probe_wdl(&position(fen)), Ok(wdl), "{}", fen);
+    }
+    let castling = position("4k2r/8/8/8/8/8/8/4K3 b k - 0 1");
+    assert!(tb.probe_wdl(&castling).is_err());
+}
+
+#[test]
+#[ignore = "needs the Syzygy 3-5 piece tables in syzygy/ or SYZYGY_PATH"]
+fn probes_dtz_and_picks_the_winning_move() {
+    let tb = tables();
+    let mate_in_one = position("k7/8/1K6/8/8/8/8/7Q w - - 0 1");
+    assert_eq!(tb.probe_dtz(&mate_in_one), Ok(1));
+    assert_eq!(tb.best_move(&mate_in_one).as_deref(), Some("h1->h8"));
+    // Promotion: le coup de pion remet le compteur à zéro
+    let promotion = position("8/4P3/8/8/8/8/k7/4K3 w - - 0 1");
+    assert_eq!(tb.probe_dtz(&promotion), Ok(1));
+    assert!(
+        tb.probe_dtz(&position("k6Q/8/1K6/8/8/8/8/8 b - - 1 1"))
+            .unwrap()
+            < 0
+    );
+    assert_eq!(
+        tb.probe_dtz(&position("8/8/8/4k3/8/8/8/KN6 w - - 0 1")),
+        Ok(0)
+    );
+
+    let lucena = position("1K1k4/1P6/8/8/8/8/r7/2R5 w - - 0 1");
+    let moves = tb.root_moves(&lucena).unwrap();
+    assert_eq!(moves[0].wdl, Wdl::Win);
+    assert!(moves[0].dtz > 0);
+    assert!(moves
+        .iter()
+        .any(|m| m.wdl == Wdl::Draw || m.wdl == Wdl::Loss));
+}
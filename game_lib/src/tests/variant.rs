@@ -0,0 +1,44 @@
+use crate::fen::FenPosition;
+use crate::piece::Color;
+use crate::variant::{Variant, VariantState, VariantWin};
+
+fn atomic_moves(fen: &str) -> Vec<String> {
+    let position = FenPosition::parse(fen).unwrap();
+    Variant::Atomic.legal_moves(&position, &VariantState::default())
+}
+
+#[test]
+fn atomic_kings_may_touch_under_attack() {
+    let fen = "8/8/8/4k3/r7/4K3/8/8 w - - 0 1";
+    assert!(atomic_moves(fen).contains(&"e3->e4".to_string()));
+    let position = FenPosition::parse(fen).unwrap();
+    let standard = Variant::Standard.legal_moves(&position, &VariantState::default());
+    assert!(!standard.contains(&"e3->e4".to_string()));
+
+    // Le coup refusé par les règles classiques est bien joué
+    let mut state = VariantState::default();
+    let mut position = position;
+    Variant::Atomic.play(&mut position, &mut state, "e3->e4");
+    assert!(position.to_fen().starts_with("8/8/8/4k3/r3K3/8/8/8 b"));
+    assert!(!Variant::Atomic.in_check(&position));
+}
+
+#[test]
+fn atomic_captures_next_to_the_own_king_are_illegal() {
+    let moves = atomic_moves("4k3/8/8/8/8/8/3q4/3RK3 w - - 0 1");
+    assert!(!moves.contains(&"d1->d2".to_string()));
+    assert!(!moves.contains(&"e1->d2".to_string()));
+    assert!(moves.contains(&"e1->f1".to_string()));
+}
+
+#[test]
+fn atomic_explosion_ends_the_game() {
+    let mut position = FenPosition::parse("4k3/4q3/8/8/8/8/8/4RK2 w - - 0 1").unwrap();
+    let mut state = VariantState::default();
+    Variant::Atomic.play(&mut position, &mut state, "e1->e7");
+    assert_eq!(
+        Variant::Atomic.winner(&position, &state),
+        Some((Color::White, VariantWin::KingExploded))
+    );
+    assert!(position.to_fen().starts_with("8/8/8/8/8/8/8/5K2 b"));
+}
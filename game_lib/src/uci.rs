@@ -0,0 +1,29 @@
+//! Conversions between the coordinate notation used on the wire ("e2->e4")
+//! and the UCI long algebraic notation ("e2e4"). Crazyhouse drops ("N@e4")
+//! are written the same way in both.
+
+use crate::crazyhouse::parse_drop;
+
+/// "e2->e4" to "e2e4".
+pub fn to_uci(mv: &str) -> String {
+    mv.replace("->", "")
+}
+
+/// "e2e4" or "e7e8q" to "e2->e4" / "e7->e8q". `None` for "(none)" or garbage.
+pub fn from_uci(mv: &str) -> Option<String> {
+    let valid_square = |s: &str| {
+        let b = s.as_bytes();
+        b.len() == 2 && (b'a'..=b'h').contains(&b[0]) && (b'1'..=b'8').contains(&b[1])
+    };
+    if parse_drop(mv).is_some() {
+        return Some(mv.to_string());
+    }
+    if !(4..=5).contains(&mv.len()) || !mv.is_ascii() {
+        return None;
+    }
+    let (from, to) = (&mv[0..2], &mv[2..4]);
+    if !valid_square(from) || !valid_square(to) {
+        return None;
+    }
+    Some(format!("{}->{}{}", from, to, &mv[4..]))
+}
@@ -0,0 +1,756 @@
+//! Chess variants played on the usual board: King of the Hill, Three-check,
+//! Atomic and Crazyhouse. Each variant hooks into move legality, adds win
+//! conditions and may carry extra state in the FEN.
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{attackers_of, move_squares, parse_square, square_name, step, BoardView};
+use crate::automation::ai::EvalVariant;
+use crate::automation::eval::evaluate;
+use crate::automation::mate::MatePosition;
+use crate::automation::tablebase::{ProbePosition, Tablebases, Wdl};
+use crate::crazyhouse::{
+    drops, encode_placement, parse_drop, piece_letter, split_placement, Pockets,
+};
+use crate::fen::{check_start_fen, FenPosition};
+use crate::piece::Color;
+use crate::sharedenums::GameResult;
+use crate::zobrist::PieceKind;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Variant {
+    #[default]
+    Standard,
+    /// Bringing the king to one of the four centre squares wins.
+    KingOfTheHill,
+    /// Giving check three times wins.
+    ThreeCheck,
+    /// Captures explode every piece but pawns around the capture square.
+    Atomic,
+    /// Captured pieces join the capturer's pocket and can be dropped back.
+    Crazyhouse,
+}
+
+/// Positions the variant rules can be played on. `play` must accept any
+/// pseudo-legal move: atomic allows moves standard chess forbids.
+pub trait VariantPosition: MatePosition + BoardView {
+    /// Moves following the piece movement, own king safety left unchecked.
+    fn pseudo_legal_moves(&self) -> Vec<String>;
+    /// Plays one of `pseudo_legal_moves`, even one that `MatePosition::play`
+    /// would refuse because it leaves the own king attacked (Atomic).
+    fn play_pseudo_legal(&mut self, mv: &str);
+    fn remove_piece(&mut self, square: u8);
+    /// Puts a piece from the pocket on an empty square and gives the move
+    /// to the other side.
+    fn drop_piece(&mut self, square: u8, color: Color, kind: PieceKind);
+}
+
+/// How a game ended on a rule of its variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VariantWin {
+    KingOnHill,
+    ThreeChecks,
+    KingExploded,
+}
+
+impl VariantWin {
+    pub fn description(&self) -> &'static str {
+        match self {
+            VariantWin::KingOnHill => "king reached the hill",
+            VariantWin::ThreeChecks => "third check",
+            VariantWin::KingExploded => "king exploded",
+        }
+    }
+}
+
+/// State kept outside the board: checks given by each side, pieces in
+/// hand and the squares of promoted pieces (one bit per square).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VariantState {
+    pub white_checks: u8,
+    pub black_checks: u8,
+    #[serde(default)]
+    pub pockets: Pockets,
+    #[serde(default)]
+    pub promoted: u64,
+}
+
+impl VariantState {
+    pub fn checks_given(&self, color: Color) -> u8 {
+        match color {
+            Color::White => self.white_checks,
+            Color::Black => self.black_checks,
+        }
+    }
+
+    fn checks_mut(&mut self, color: Color) -> &mut u8 {
+        match color {
+            Color::White => &mut self.white_checks,
+            Color::Black => &mut self.black_checks,
+        }
+    }
+}
+
+pub const CHECKS_TO_WIN: u8 = 3;
+const WIN_SCORE: i32 = 100_000;
+const TABLEBASE_WIN: i32 = WIN_SCORE / 2;
+// d4, e4, d5, e5
+const HILL: [u8; 4] = [27, 28, 35, 36];
+
+fn king_square<P: BoardView>(position: &P, color: Color) -> Option<u8> {
+    (0..64).find(|&sq| position.piece_at(sq) == Some((color, PieceKind::King)))
+}
+
+fn kings_adjacent<P: BoardView>(position: &P) -> bool {
+    match (
+        king_square(position, Color::White),
+        king_square(position, Color::Black),
+    ) {
+        (Some(w), Some(b)) => {
+            ((w % 8) as i8 - (b % 8) as i8).abs() <= 1 && ((w / 8) as i8 - (b / 8) as i8).abs() <= 1
+        }
+        _ => false,
+    }
+}
+
+fn bit(square: u8) -> u64 {
+    1 << square
+}
+
+// Coup en minuscules, promotion en dame ajoutée quand la pièce n'est pas précisée
+fn with_promotion<P: BoardView>(position: &P, mv: &str) -> String {
+    let mv = mv.trim().to_ascii_lowercase();
+    let promotes = move_squares(&mv).is_some_and(|(from, to)| {
+        matches!(position.piece_at(from), Some((_, PieceKind::Pawn))) && !(8..56).contains(&to)
+    });
+    if promotes && mv.split_once("->").is_some_and(|(_, to)| to.len() == 2) {
+        format!("{}q", mv)
+    } else {
+        mv
+    }
+}
+
+// Case de la prise, y compris en passant (pion qui part en diagonale vers une case vide)
+fn capture_square<P: BoardView>(position: &P, mv: &str) -> Option<u8> {
+    let (from, to) = move_squares(mv)?;
+    if position.piece_at(to).is_some() {
+        return Some(to);
+    }
+    let is_pawn = matches!(position.piece_at(from), Some((_, PieceKind::Pawn)));
+    (is_pawn && from % 8 != to % 8).then_some(to)
+}
+
+impl Variant {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::Standard => "Standard",
+            Variant::KingOfTheHill => "King of the Hill",
+            Variant::ThreeCheck => "Three-check",
+            Variant::Atomic => "Atomic",
+            Variant::Crazyhouse => "Crazyhouse",
+        }
+    }
+
+    /// Value of the UCI_Variant option for external engines.
+    pub fn uci_name(&self) -> Option<&'static str> {
+        match self {
+            Variant::Standard => None,
+            Variant::KingOfTheHill => Some("kingofthehill"),
+            Variant::ThreeCheck => Some("3check"),
+            Variant::Atomic => Some("atomic"),
+            Variant::Crazyhouse => Some("crazyhouse"),
+        }
+    }
+
+    /// Moves allowed by the variant for the side to move, drops included.
+    pub fn legal_moves<P: VariantPosition>(
+        &self,
+        position: &P,
+        state: &VariantState,
+    ) -> Vec<String> {
+        let turn = position.turn();
+        if *self == Variant::Crazyhouse {
+            let mut moves = position.legal_moves();
+            // Un pion ou une pièce parachutée peut aussi couvrir un échec
+            moves.extend(
+                drops(position, state.pockets.of(turn))
+                    .into_iter()
+                    .filter(|mv| {
+                        let Some((kind, square)) = parse_drop(mv) else {
+                            return false;
+                        };
+                        let mut after = position.clone();
+                        after.drop_piece(square, turn, kind);
+                        king_square(&after, turn).is_some_and(|king| {
+                            attackers_of(&after, king, turn.opposite()).is_empty()
+                        })
+                    }),
+            );
+            return moves;
+        }
+        if *self != Variant::Atomic {
+            return position.legal_moves();
+        }
+        position
+            .pseudo_legal_moves()
+            .into_iter()
+            .filter(|mv| {
+                let Some((from, to)) = move_squares(mv) else {
+                    return false;
+                };
+                // Le roi ne peut pas prendre: il exploserait avec sa prise
+                if matches!(position.piece_at(from), Some((_, PieceKind::King)))
+                    && position.piece_at(to).is_some()
+                {
+                    return false;
+                }
+                let mut after = position.clone();
+                self.apply(&mut after, mv);
+                let Some(own_king) = king_square(&after, turn) else {
+                    return false;
+                };
+                king_square(&after, turn.opposite()).is_none()
+                    || kings_adjacent(&after)
+                    || attackers_of(&after, own_king, turn.opposite()).is_empty()
+            })
+            .collect()
+    }
+
+    // Joue le coup et les explosions, sans compter les échecs
+    fn apply<P: VariantPosition>(&self, position: &mut P, mv: &str) {
+        let exploded = match self {
+            Variant::Atomic => capture_square(position, mv),
+            _ => None,
+        };
+        position.play_pseudo_legal(mv);
+        if let Some(center) = exploded {
+            position.remove_piece(center);
+            let around =
+                (-1..=1).flat_map(|df| (-1..=1).filter_map(move |dr| step(center, df, dr)));
+            for sq in around {
+                if !matches!(position.piece_at(sq), Some((_, PieceKind::Pawn))) {
+                    position.remove_piece(sq);
+                }
+            }
+        }
+    }
+
+    /// Plays a move legal in the variant and updates the check counters
+    /// and pockets.
+    pub fn play<P: VariantPosition>(&self, position: &mut P, state: &mut VariantState, mv: &str) {
+        let mover = position.turn();
+        if let Some(kind) = self.play_capturing(position, state, mv) {
+            state.pockets.of_mut(mover).add(kind);
+        }
+    }
+
+    /// Same as `play`, except that the piece a Crazyhouse capture puts in
+    /// hand is returned instead of going to the mover's pocket: Bughouse
+    /// hands it to the partner on the other board.
+    pub fn play_capturing<P: VariantPosition>(
+        &self,
+        position: &mut P,
+        state: &mut VariantState,
+        mv: &str,
+    ) -> Option<PieceKind> {
+        let mover = position.turn();
+        let mut taken = None;
+        if let Some((kind, square)) = parse_drop(mv) {
+            state.pockets.of_mut(mover).take(kind);
+            position.drop_piece(square, mover, kind);
+        } else {
+            if *self == Variant::Crazyhouse {
+                taken = Self::pocket_capture(position, state, mv);
+            }
+            self.apply(position, mv);
+        }
+        if *self == Variant::ThreeCheck && self.in_check(position) {
+            *state.checks_mut(mover) += 1;
+        }
+        taken
+    }
+
+    // Pièce prise qui part en poche, et suivi des pièces promues, avant le coup
+    fn pocket_capture<P: BoardView>(
+        position: &P,
+        state: &mut VariantState,
+        mv: &str,
+    ) -> Option<PieceKind> {
+        let (from, to) = move_squares(mv)?;
+        // Une pièce promue redevient un pion; en passant, la case d'arrivée est vide
+        let taken = capture_square(position, mv).map(|square| match position.piece_at(square) {
+            Some((_, kind)) if state.promoted & bit(square) == 0 => kind,
+            _ => PieceKind::Pawn,
+        });
+        let promotes =
+            matches!(position.piece_at(from), Some((_, PieceKind::Pawn))) && !(8..56).contains(&to);
+        let was_promoted = state.promoted & bit(from) != 0;
+        state.promoted &= !(bit(from) | bit(to));
+        if promotes || was_promoted {
+            state.promoted |= bit(to);
+        }
+        taken
+    }
+
+    /// The legal move `mv` designates, with the notation of `legal_moves`.
+    /// A promotion without its piece is a promotion to a queen. None for an
+    /// illegal move.
+    pub fn legal_move<P: VariantPosition>(
+        &self,
+        position: &P,
+        state: &VariantState,
+        mv: &str,
+    ) -> Option<String> {
+        let wanted = with_promotion(position, mv);
+        self.legal_moves(position, state)
+            .into_iter()
+            .find(|m| with_promotion(position, m) == wanted)
+    }
+
+    /// Standard algebraic notation of a move legal in the variant: "Nbd7",
+    /// "exd6", "O-O", "e8=Q#". Drops keep their "N@e4" form. None when the
+    /// move is not legal in `position`.
+    pub fn san<P: VariantPosition>(
+        &self,
+        position: &P,
+        state: &VariantState,
+        mv: &str,
+    ) -> Option<String> {
+        let legal = self.legal_moves(position, state);
+        let wanted = with_promotion(position, mv);
+        let mv = legal
+            .iter()
+            .find(|m| with_promotion(position, m) == wanted)?;
+        let mut san = match move_squares(mv) {
+            None => mv.clone(),
+            Some((from, to)) => {
+                let (color, kind) = position.piece_at(from)?;
+                if kind == PieceKind::King && from.abs_diff(to) == 2 {
+                    if to > from { "O-O" } else { "O-O-O" }.to_string()
+                } else {
+                    let mut san = String::new();
+                    let capture = capture_square(position, mv).is_some();
+                    if kind != PieceKind::Pawn {
+                        san.push(piece_letter(kind));
+                        // Autres pièces du même type qui peuvent aller sur la case
+                        let rivals: Vec<u8> = legal
+                            .iter()
+                            .filter_map(|m| move_squares(m))
+                            .filter(|&(f, t)| t == to && f != from)
+                            .filter(|&(f, _)| position.piece_at(f) == Some((color, kind)))
+                            .map(|(f, _)| f)
+                            .collect();
+                        let name = square_name(from);
+                        if rivals.iter().all(|f| f % 8 != from % 8) {
+                            san.push_str(&name[..rivals.len().min(1)]);
+                        } else if rivals.iter().all(|f| f / 8 != from / 8) {
+                            san.push_str(&name[1..]);
+                        } else {
+                            san.push_str(&name);
+                        }
+                    } else if capture {
+                        san.push_str(&square_name(from)[..1]);
+                    }
+                    if capture {
+                        san.push('x');
+                    }
+                    san.push_str(&square_name(to));
+                    if let Some(promotion) = wanted.chars().nth(wanted.find("->")? + 4) {
+                        san.push('=');
+                        san.push(promotion.to_ascii_uppercase());
+                    }
+                    san
+                }
+            }
+        };
+        let mut after = position.clone();
+        let mut after_state = *state;
+        self.play(&mut after, &mut after_state, mv);
+        if self.winner(&after, &after_state).is_some()
+            || (self.in_check(&after) && self.legal_moves(&after, &after_state).is_empty())
+        {
+            san.push('#');
+        } else if self.in_check(&after) {
+            san.push('+');
+        }
+        Some(san)
+    }
+
+    /// Legal move written in SAN, read leniently: check marks and
+    /// annotations are ignored, the capture sign and extra disambiguation
+    /// are optional, "0-0" and "e8Q" are accepted. None when no legal move
+    /// or more than one matches.
+    pub fn from_san<P: VariantPosition>(
+        &self,
+        position: &P,
+        state: &VariantState,
+        san: &str,
+    ) -> Option<String> {
+        let san = san.trim_end_matches(['+', '#', '!', '?']);
+        let legal = self.legal_moves(position, state);
+        if san.contains('@') {
+            return legal.into_iter().find(|m| m == san);
+        }
+        let castle = match san {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(short) = castle {
+            return legal.into_iter().find(|m| {
+                move_squares(m).is_some_and(|(from, to)| {
+                    matches!(position.piece_at(from), Some((_, PieceKind::King)))
+                        && from.abs_diff(to) == 2
+                        && (to > from) == short
+                })
+            });
+        }
+
+        // Pièce, cases de départ données, case d'arrivée, promotion
+        let (kind, rest) = match san.chars().next()? {
+            'K' => (PieceKind::King, &san[1..]),
+            'Q' => (PieceKind::Queen, &san[1..]),
+            'R' => (PieceKind::Rook, &san[1..]),
+            'B' => (PieceKind::Bishop, &san[1..]),
+            'N' => (PieceKind::Knight, &san[1..]),
+            _ => (PieceKind::Pawn, san),
+        };
+        let (rest, promotion) = match rest.char_indices().last()? {
+            (i, c) if "QRBN".contains(c) => (rest[..i].trim_end_matches('='), Some(c)),
+            _ => (rest, None),
+        };
+        let rest = rest.replace('x', "");
+        if rest.len() < 2 || !rest.is_ascii() {
+            return None;
+        }
+        let to = parse_square(&rest[rest.len() - 2..])?;
+        let hint = &rest[..rest.len() - 2];
+        let mut found = legal.into_iter().filter(|m| {
+            let Some((from, target)) = move_squares(m) else {
+                return false;
+            };
+            let name = square_name(from);
+            target == to
+                && position.piece_at(from) == Some((position.turn(), kind))
+                && hint.chars().all(|c| name.contains(c))
+                && m.chars()
+                    .nth(m.find("->").unwrap_or_default() + 4)
+                    .map(|c| c.to_ascii_uppercase())
+                    == promotion
+        });
+        let mv = found.next()?;
+        found.next().is_none().then_some(mv)
+    }
+
+    /// Result when the side to move has no legal move left: mated or
+    /// stalemated. None while it can still play.
+    pub fn result_without_moves<P: VariantPosition>(
+        &self,
+        position: &P,
+        state: &VariantState,
+    ) -> Option<GameResult> {
+        if !self.legal_moves(position, state).is_empty() {
+            return None;
+        }
+        Some(if self.in_check(position) {
+            GameResult::winner(position.turn().opposite())
+        } else {
+            GameResult::Draw
+        })
+    }
+
+    /// Whether the side to move is in check. Touching kings never are in
+    /// atomic: taking the other king would blow up your own.
+    pub fn in_check<P: VariantPosition>(&self, position: &P) -> bool {
+        match self {
+            Variant::Atomic => {
+                let turn = position.turn();
+                king_square(position, turn).is_some_and(|king| {
+                    !kings_adjacent(position)
+                        && !attackers_of(position, king, turn.opposite()).is_empty()
+                })
+            }
+            _ => position.in_check(),
+        }
+    }
+
+    /// Winner by a rule of the variant. Checkmate and stalemate are left
+    /// to the caller, using `legal_moves` and `in_check`.
+    pub fn winner<P: BoardView>(
+        &self,
+        position: &P,
+        state: &VariantState,
+    ) -> Option<(Color, VariantWin)> {
+        let sides = [Color::White, Color::Black];
+        match self {
+            Variant::Standard | Variant::Crazyhouse => None,
+            Variant::KingOfTheHill => sides
+                .into_iter()
+                .find(|&c| king_square(position, c).is_some_and(|k| HILL.contains(&k)))
+                .map(|c| (c, VariantWin::KingOnHill)),
+            Variant::ThreeCheck => sides
+                .into_iter()
+                .find(|&c| state.checks_given(c) >= CHECKS_TO_WIN)
+                .map(|c| (c, VariantWin::ThreeChecks)),
+            Variant::Atomic => sides
+                .into_iter()
+                .find(|&c| king_square(position, c).is_none())
+                .map(|c| (c.opposite(), VariantWin::KingExploded)),
+        }
+    }
+
+    /// Writes the variant state into a standard FEN. Three-check adds the
+    /// checks still needed by each side after the en passant field, as in
+    /// "... w KQkq - 3+3 0 1". Crazyhouse adds the pockets to the piece
+    /// placement and marks promoted pieces with "~": "...RNBQ~KBNR[Pn] w".
+    pub fn encode_fen(&self, fen: &str, state: &VariantState) -> String {
+        let mut fields: Vec<String> = fen.split_whitespace().map(str::to_string).collect();
+        if *self == Variant::Crazyhouse {
+            if let Some(placement) = fields.first_mut() {
+                *placement = encode_placement(placement, &state.pockets, state.promoted);
+            }
+            return fields.join(" ");
+        }
+        if *self != Variant::ThreeCheck {
+            return fen.to_string();
+        }
+        let remaining = format!(
+            "{}+{}",
+            CHECKS_TO_WIN.saturating_sub(state.white_checks),
+            CHECKS_TO_WIN.saturating_sub(state.black_checks)
+        );
+        fields.insert(fields.len().min(4), remaining);
+        fields.join(" ")
+    }
+
+    /// Splits a variant FEN into the standard FEN and the variant state.
+    pub fn split_fen(&self, fen: &str) -> Result<(String, VariantState), String> {
+        let mut fields: Vec<&str> = fen.split_whitespace().collect();
+        let mut state = VariantState::default();
+        if *self == Variant::Crazyhouse {
+            let (placement, pockets, promoted) = split_placement(fields.first().unwrap_or(&""))?;
+            state.pockets = pockets;
+            state.promoted = promoted;
+            let rest = fields.get(1..).unwrap_or_default().join(" ");
+            return Ok((format!("{} {}", placement, rest), state));
+        }
+        if *self != Variant::ThreeCheck {
+            return Ok((fen.to_string(), state));
+        }
+        // Le compteur est facultatif: sans lui, aucun échec n'a été donné
+        if let Some(counter) = fields.get(4).filter(|f| f.contains('+')).copied() {
+            let (white, black) = counter.split_once('+').unwrap_or_default();
+            for (color, left) in [(Color::White, white), (Color::Black, black)] {
+                let left: u8 = left
+                    .parse()
+                    .ok()
+                    .filter(|n| *n <= CHECKS_TO_WIN)
+                    .ok_or_else(|| format!("Invalid check counter {}", counter))?;
+                *state.checks_mut(color) = CHECKS_TO_WIN - left;
+            }
+            fields.remove(4);
+        }
+        Ok((fields.join(" "), state))
+    }
+
+    /// Parses and validates a starting position for a room of this variant.
+    pub fn check_start_fen(&self, fen: &str) -> Result<(FenPosition, VariantState), String> {
+        let (standard, state) = self
+            .split_fen(fen)
+            .map_err(|e| format!("Invalid FEN: {}", e))?;
+        let position = check_start_fen(&standard)?;
+        if self.winner(&position, &state).is_some() {
+            return Err("Invalid FEN: the game is already won in this variant".into());
+        }
+        Ok((position, state))
+    }
+
+    /// Bonus of the variant for `color`, added to the static evaluation so
+    /// the engine goes after the extra win conditions.
+    pub fn eval_bonus<P: BoardView>(
+        &self,
+        position: &P,
+        state: &VariantState,
+        color: Color,
+    ) -> i32 {
+        match self {
+            Variant::KingOfTheHill => king_square(position, color).map_or(0, |king| {
+                let distance = HILL
+                    .iter()
+                    .map(|&h| {
+                        ((king % 8) as i32 - (h % 8) as i32)
+                            .abs()
+                            .max(((king / 8) as i32 - (h / 8) as i32).abs())
+                    })
+                    .min()
+                    .unwrap_or_default();
+                (6 - distance) * 25
+            }),
+            Variant::ThreeCheck => state.checks_given(color) as i32 * 150,
+            Variant::Crazyhouse => state.pockets.of(color).value(),
+            _ => 0,
+        }
+    }
+
+    /// Move of the built-in engine in a variant game: a short search on
+    /// the static evaluation, with the variant's wins and bonus.
+    pub fn best_move<P: VariantPosition>(
+        &self,
+        position: &P,
+        state: &VariantState,
+        eval: EvalVariant,
+        depth: u8,
+    ) -> Option<String> {
+        self.root_search(position, state, eval, depth, &|_| None)
+    }
+
+    /// Same search, with the Syzygy tables in standard chess: a position
+    /// they cover is played from the tables, and the search scores the
+    /// positions it reaches in them by their WDL value.
+    pub fn best_move_with_tablebases<P: VariantPosition + ProbePosition>(
+        &self,
+        position: &P,
+        state: &VariantState,
+        eval: EvalVariant,
+        depth: u8,
+        tablebases: &Tablebases,
+    ) -> Option<String> {
+        if *self != Variant::Standard {
+            return self.best_move(position, state, eval, depth);
+        }
+        if let Some(mv) = tablebases.best_move(position) {
+            return Some(mv);
+        }
+        let probe = |position: &P| {
+            if !tablebases.covers(position) {
+                return None;
+            }
+            tablebases.probe_wdl(position).ok()
+        };
+        self.root_search(position, state, eval, depth, &probe)
+    }
+
+    fn root_search<P: VariantPosition>(
+        &self,
+        position: &P,
+        state: &VariantState,
+        eval: EvalVariant,
+        depth: u8,
+        probe: &dyn Fn(&P) -> Option<Wdl>,
+    ) -> Option<String> {
+        self.legal_moves(position, state)
+            .into_iter()
+            .map(|mv| {
+                let mut next = position.clone();
+                let mut next_state = *state;
+                self.play(&mut next, &mut next_state, &mv);
+                let score = -self.search(&next, &next_state, eval, depth.saturating_sub(1), probe);
+                (score, mv)
+            })
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, mv)| mv)
+    }
+
+    // Negamax, score vu du camp au trait
+    fn search<P: VariantPosition>(
+        &self,
+        position: &P,
+        state: &VariantState,
+        eval: EvalVariant,
+        depth: u8,
+        probe: &dyn Fn(&P) -> Option<Wdl>,
+    ) -> i32 {
+        let turn = position.turn();
+        // Une victoire plus proche vaut plus
+        if let Some((winner, _)) = self.winner(position, state) {
+            let score = WIN_SCORE + depth as i32;
+            return if winner == turn { score } else { -score };
+        }
+        let moves = self.legal_moves(position, state);
+        if moves.is_empty() {
+            return if self.in_check(position) {
+                -WIN_SCORE - depth as i32
+            } else {
+                0
+            };
+        }
+        // Gain des tables: moins qu'un mat vu, plus que tout le reste
+        match probe(position) {
+            Some(Wdl::Win) => return TABLEBASE_WIN,
+            Some(Wdl::Loss) => return -TABLEBASE_WIN,
+            Some(_) => return 0,
+            None => {}
+        }
+        if depth == 0 {
+            return evaluate(position, eval).for_side(turn)
+                + self.eval_bonus(position, state, turn)
+                - self.eval_bonus(position, state, turn.opposite());
+        }
+        moves
+            .iter()
+            .map(|mv| {
+                let mut next = position.clone();
+                let mut next_state = *state;
+                self.play(&mut next, &mut next_state, mv);
+                -self.search(&next, &next_state, eval, depth - 1, probe)
+            })
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+/*
+impl VariantPosition for crate::game::Game {
+    fn pseudo_legal_moves(&self) -> Vec<String> {
+        let turn = self.board.turn;
+        self.board
+            .all_pseudo_legal_moves(turn)
+            .iter()
+            .map(|(from, to)| format!("{}->{}", from.to_algebraic(), to.to_algebraic()))
+            .collect()
+    }
+
+    fn play_pseudo_legal(&mut self, mv: &str) {
+        // make_move_algebraic refuse les coups qui laissent le roi attaqué
+        if self.make_move_algebraic(mv).is_ok() {
+            return;
+        }
+        let Some((from, to)) = move_squares(mv) else {
+            return;
+        };
+        let Some((color, kind)) = BoardView::piece_at(self, from) else {
+            return;
+        };
+        let kind = if kind == PieceKind::Pawn && !(8..56).contains(&to) {
+            match with_promotion(self, mv).chars().last() {
+                Some('r') => PieceKind::Rook,
+                Some('b') => PieceKind::Bishop,
+                Some('n') => PieceKind::Knight,
+                _ => PieceKind::Queen,
+            }
+        } else {
+            kind
+        };
+        self.remove_piece(from);
+        self.remove_piece(to);
+        self.drop_piece(to, color, kind);
+    }
+
+    fn remove_piece(&mut self, square: u8) {
+        let pos = crate::position::Position {
+            row: (square / 8) as usize,
+            col: (square % 8) as usize,
+        };
+        self.board.remove_piece(&pos);
+    }
+
+    fn drop_piece(&mut self, square: u8, color: Color, kind: PieceKind) {
+        let pos = crate::position::Position {
+            row: (square / 8) as usize,
+            col: (square % 8) as usize,
+        };
+        self.board.put_piece(&pos, color, kind);
+        self.board.turn = color.opposite();
+    }
+}
+*/
@@ -0,0 +1,171 @@
+//! Polyglot-compatible Zobrist keys, used to look positions up in opening
+//! books. The 781 random numbers are the `Random64` table of the Polyglot
+//! book format; they are loaded from a text file rather than compiled in.
+
+use crate::analysis::{step, BoardView};
+use crate::fen::FenPosition;
+use crate::piece::Color;
+
+pub const RANDOM64_LEN: usize = 781;
+const CASTLE_OFFSET: usize = 768;
+const EN_PASSANT_OFFSET: usize = 772;
+const TURN_OFFSET: usize = 780;
+
+/// Key of the initial position in the Polyglot format, used to check that
+/// a loaded table is the right one.
+pub const START_POSITION_KEY: u64 = 0x463B96181691FC9C;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PieceKind {
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+}
+
+/// What the key of a position depends on. Squares go from 0 (a1) to 63 (h8).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeyInput {
+    pub pieces: Vec<(Color, PieceKind, u8)>,
+    pub white_short: bool,
+    pub white_long: bool,
+    pub black_short: bool,
+    pub black_long: bool,
+    /// File of the en passant square, only when a pawn of the side to move
+    /// can actually take en passant (Polyglot rule).
+    pub en_passant_file: Option<u8>,
+    pub white_to_move: bool,
+}
+
+impl KeyInput {
+    pub fn start_position() -> Self {
+        let back_rank = [
+            PieceKind::Rook,
+            PieceKind::Knight,
+            PieceKind::Bishop,
+            PieceKind::Queen,
+            PieceKind::King,
+            PieceKind::Bishop,
+            PieceKind::Knight,
+            PieceKind::Rook,
+        ];
+        let mut pieces = Vec::with_capacity(32);
+        for (file, kind) in back_rank.into_iter().enumerate() {
+            let file = file as u8;
+            pieces.push((Color::White, kind, file));
+            pieces.push((Color::White, PieceKind::Pawn, 8 + file));
+            pieces.push((Color::Black, PieceKind::Pawn, 48 + file));
+            pieces.push((Color::Black, kind, 56 + file));
+        }
+        KeyInput {
+            pieces,
+            white_short: true,
+            white_long: true,
+            black_short: true,
+            black_long: true,
+            en_passant_file: None,
+            white_to_move: true,
+        }
+    }
+}
+
+impl KeyInput {
+    /// What the key of `position` depends on. Its en passant square only
+    /// counts when a pawn of the side to move stands next to the pawn that
+    /// just advanced, ready to take it.
+    pub fn of(position: &FenPosition) -> Self {
+        let pieces = (0..64)
+            .filter_map(|sq| position.piece_at(sq).map(|(color, kind)| (color, kind, sq)))
+            .collect();
+        let turn = position.turn;
+        // Le pion qui vient d'avancer est devant la case en passant
+        let behind = if turn == Color::White { -1 } else { 1 };
+        let en_passant_file = position
+            .en_passant
+            .filter(|&ep| {
+                let pushed = step(ep, 0, behind);
+                [-1, 1].into_iter().any(|df| {
+                    pushed
+                        .and_then(|sq| step(sq, df, 0))
+                        .and_then(|sq| position.piece_at(sq))
+                        == Some((turn, PieceKind::Pawn))
+                })
+            })
+            .map(|ep| ep % 8);
+        let castling = |right| position.castling.contains(right);
+        KeyInput {
+            pieces,
+            white_short: castling('K'),
+            white_long: castling('Q'),
+            black_short: castling('k'),
+            black_long: castling('q'),
+            en_passant_file,
+            white_to_move: turn == Color::White,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZobristKeys {
+    random: Vec<u64>,
+}
+
+impl ZobristKeys {
+    pub fn new(random: Vec<u64>) -> Result<Self, String> {
+        if random.len() != RANDOM64_LEN {
+            return Err(format!(
+                "Expected {} Zobrist keys, got {}",
+                RANDOM64_LEN,
+                random.len()
+            ));
+        }
+        let keys = ZobristKeys { random };
+        if keys.key(&KeyInput::start_position()) != START_POSITION_KEY {
+            return Err("The Zobrist keys are not the Polyglot Random64 table".into());
+        }
+        Ok(keys)
+    }
+
+    /// Reads the hexadecimal words ("0x9D39247E33776D41") of a text file, so
+    /// the table can be pasted from any C, Rust or Python source.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let random = text
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter_map(|word| word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")))
+            .map(|hex| {
+                let hex = hex.trim_end_matches(['u', 'U', 'l', 'L']);
+                u64::from_str_radix(hex, 16).map_err(|e| format!("Invalid key {}: {}", hex, e))
+            })
+            .collect::<Result<Vec<u64>, String>>()?;
+        Self::new(random)
+    }
+
+    pub fn key(&self, position: &KeyInput) -> u64 {
+        let mut key = 0;
+        for &(color, kind, square) in &position.pieces {
+            // Ordre Polyglot: pion noir, pion blanc, cavalier noir, ...
+            let piece = 2 * kind as usize + usize::from(color == Color::White);
+            key ^= self.random[64 * piece + square as usize];
+        }
+        let castling = [
+            position.white_short,
+            position.white_long,
+            position.black_short,
+            position.black_long,
+        ];
+        for (i, allowed) in castling.into_iter().enumerate() {
+            if allowed {
+                key ^= self.random[CASTLE_OFFSET + i];
+            }
+        }
+        if let Some(file) = position.en_passant_file {
+            key ^= self.random[EN_PASSANT_OFFSET + file as usize];
+        }
+        if position.white_to_move {
+            key ^= self.random[TURN_OFFSET];
+        }
+        key
+    }
+}
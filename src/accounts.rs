@@ -0,0 +1,224 @@
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use game_lib::messages::AccountInfo;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex, OnceLock},
+};
+use uuid::Uuid;
+
+use crate::now_timestamp;
+
+pub type SharedAccountStore = Arc<AccountStore>;
+
+// Hachage d'un mot de passe aléatoire, calculé une fois
+fn dummy_hash() -> &'static str {
+    static DUMMY: OnceLock<String> = OnceLock::new();
+    DUMMY.get_or_init(|| {
+        let mut password = [0u8; 32];
+        OsRng.fill_bytes(&mut password);
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(&password, &salt)
+            .map(|hash| hash.to_string())
+            .unwrap_or_default()
+    })
+}
+
+// Durée de validité d'un token de session (7 jours)
+const SESSION_TTL: u64 = 7 * 24 * 3600;
+const MIN_PASSWORD_LEN: usize = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub id: Uuid,
+    pub username: String,
+    pub display_name: String,
+    /// Argon2 PHC string, salt included.
+    pub password_hash: String,
+    pub created_at: u64,
+}
+
+impl Account {
+    pub fn info(&self) -> AccountInfo {
+        AccountInfo {
+            id: self.id,
+            display_name: self.display_name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Session {
+    account_id: Uuid,
+    expires_at: u64,
+}
+
+/// Accounts are persisted as a JSON file rewritten on every registration.
+/// Sessions only live in memory: a server restart asks everyone to log in again.
+#[derive(Debug)]
+pub struct AccountStore {
+    path: PathBuf,
+    accounts: Mutex<HashMap<Uuid, Account>>,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl AccountStore {
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let accounts: Vec<Account> = if path.exists() {
+            let data = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            serde_json::from_str(&data)
+                .map_err(|e| format!("Invalid accounts file {}: {}", path.display(), e))?
+        } else {
+            Vec::new()
+        };
+        println!("Loaded {} accounts from {}", accounts.len(), path.display());
+
+        Ok(Self {
+            path,
+            accounts: Mutex::new(accounts.into_iter().map(|a| (a.id, a)).collect()),
+            sessions: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn persist(&self, accounts: &HashMap<Uuid, Account>) -> Result<(), String> {
+        let list: Vec<&Account> = accounts.values().collect();
+        let data = serde_json::to_string_pretty(&list)
+            .map_err(|e| format!("Failed to serialize accounts: {}", e))?;
+        fs::write(&self.path, data)
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))
+    }
+
+    pub fn register(
+        &self,
+        username: &str,
+        password: &str,
+        display_name: Option<String>,
+    ) -> Result<(Account, String), String> {
+        let username = username.trim();
+        if username.len() < 3
+            || username.len() > 20
+            || !username
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err("Username must be 3-20 letters, digits or '_'.".into());
+        }
+        if password.len() < MIN_PASSWORD_LEN {
+            return Err(format!(
+                "Password must be at least {} characters.",
+                MIN_PASSWORD_LEN
+            ));
+        }
+
+        let salt = SaltString::generate(&mut OsRng);
+        let password_hash = Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| format!("Failed to hash password: {}", e))?
+            .to_string();
+
+        let account = {
+            let mut accounts = self.accounts.lock().map_err(|e| e.to_string())?;
+            if accounts
+                .values()
+                .any(|a| a.username.eq_ignore_ascii_case(username))
+            {
+                return Err("Username already taken.".into());
+            }
+            let account = Account {
+                id: Uuid::new_v4(),
+                username: username.to_string(),
+                display_name: display_name
+                    .map(|n| n.trim().to_string())
+                    .filter(|n| !n.is_empty())
+                    .unwrap_or_else(|| username.to_string()),
+                password_hash,
+                created_at: now_timestamp(),
+            };
+            accounts.insert(account.id, account.clone());
+            self.persist(&accounts)?;
+            account
+        };
+        println!("Account {} registered", account.username);
+
+        let token = self.open_session(account.id)?;
+        Ok((account, token))
+    }
+
+    pub fn login(&self, username: &str, password: &str) -> Result<(Account, String), String> {
+        let account = {
+            let accounts = self.accounts.lock().map_err(|e| e.to_string())?;
+            accounts
+                .values()
+                .find(|a| a.username.eq_ignore_ascii_case(username.trim()))
+                .cloned()
+        };
+        // Un nom inconnu est vérifié contre un hachage factice: même durée, même message
+        let verified = {
+            let stored = match &account {
+                Some(account) => account.password_hash.as_str(),
+                None => dummy_hash(),
+            };
+            let hash =
+                PasswordHash::new(stored).map_err(|e| format!("Corrupted password hash: {}", e))?;
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        };
+        let account = account
+            .filter(|_| verified)
+            .ok_or("Invalid username or password.")?;
+
+        let token = self.open_session(account.id)?;
+        Ok((account, token))
+    }
+
+    fn open_session(&self, account_id: Uuid) -> Result<String, String> {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+        let mut sessions = self.sessions.lock().map_err(|e| e.to_string())?;
+        let now = now_timestamp();
+        sessions.retain(|_, s| s.expires_at > now);
+        sessions.insert(
+            token.clone(),
+            Session {
+                account_id,
+                expires_at: now + SESSION_TTL,
+            },
+        );
+        Ok(token)
+    }
+
+    /// Resolves a session token to its account, if the session is still valid.
+    pub fn authenticate(&self, token: &str) -> Option<Account> {
+        let account_id = {
+            let sessions = self.sessions.lock().ok()?;
+            let session = sessions.get(token)?;
+            if session.expires_at <= now_timestamp() {
+                return None;
+            }
+            session.account_id
+        };
+        self.get(account_id)
+    }
+
+    pub fn logout(&self, token: &str) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.remove(token);
+        }
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<Account> {
+        self.accounts.lock().ok()?.get(&id).cloned()
+    }
+}
@@ -0,0 +1,110 @@
+//! Mate-in-N solver for composing and checking puzzles.
+//!
+//! ```text
+//! cargo run --release --bin mate -- --fen "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1" -n 1
+//! ```
+
+use game_lib::automation::mate::{MateOutcome, MateSearch, DEFAULT_MAX_NODES, MAX_MATE_DEPTH};
+use game_lib::fen::check_start_fen;
+use std::{env, process};
+
+const USAGE: &str = "Usage: mate --fen FEN -n N [--max-nodes NODES]";
+
+struct Config {
+    fen: String,
+    n: u8,
+    max_nodes: u64,
+}
+
+fn parse_args() -> Result<Config, String> {
+    let mut args = env::args().skip(1);
+    let mut fen = None;
+    let mut n = None;
+    let mut max_nodes = DEFAULT_MAX_NODES;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--fen" => fen = Some(value()?),
+            "-n" => n = Some(value()?.parse().map_err(|e| format!("Invalid N: {}", e))?),
+            "--max-nodes" => {
+                max_nodes = value()?
+                    .parse()
+                    .map_err(|e| format!("Invalid node limit: {}", e))?
+            }
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ => return Err(format!("Unknown argument {}", arg)),
+        }
+    }
+    let n: u8 = n.ok_or("Missing -n")?;
+    if !(1..=MAX_MATE_DEPTH).contains(&n) {
+        return Err(format!("N must be between 1 and {}", MAX_MATE_DEPTH));
+    }
+    Ok(Config {
+        fen: fen.ok_or("Missing --fen")?,
+        n,
+        max_nodes,
+    })
+}
+
+fn print_outcome(n: u8, outcome: &MateOutcome) {
+    match outcome {
+        MateOutcome::Found { lines } => {
+            for line in lines {
+                println!(
+                    "Mate in {}: {}",
+                    line.moves_to_mate,
+                    line.line
+                        .iter()
+                        .map(|mv| mv.replace("->", ""))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                );
+            }
+        }
+        MateOutcome::NoMate => println!("No mate in {}", n),
+        MateOutcome::Inconclusive { nodes } => {
+            println!("Inconclusive after {} nodes, raise --max-nodes", nodes)
+        }
+    }
+}
+
+fn main() {
+    let config = match parse_args() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    eprintln!(
+        "Searching mate in {} (at most {} nodes)",
+        config.n, config.max_nodes
+    );
+    let outcome = check_start_fen(&config.fen).map(|position| {
+        let mut search = MateSearch::new(config.max_nodes);
+        let outcome = search.solve(&position, config.n);
+        eprintln!("{} nodes", search.nodes());
+        outcome
+    });
+    match outcome {
+        Ok(outcome) => {
+            print_outcome(config.n, &outcome);
+            // Code de sortie utilisable dans les scripts de vérification de puzzles
+            process::exit(match outcome {
+                MateOutcome::Found { .. } => 0,
+                MateOutcome::NoMate => 1,
+                MateOutcome::Inconclusive { .. } => 3,
+            });
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,263 @@
+//! Puzzle generator: scans archived or PGN games for positions with a
+//! single clearly winning move and writes them in the puzzle file format.
+//!
+//! ```text
+//! cargo run --release --bin puzzlegen -- \
+//!     --games games.jsonl --mode AIvsAI \
+//!     --pgn tournament.pgn --out puzzles.csv --append
+//! ```
+
+mod pgn;
+
+use game_lib::{
+    automation::miner::{mine_game, MinerConfig},
+    fen::{check_start_fen, FenPosition},
+    history::GameRecord,
+    puzzle::CSV_HEADER,
+    sharedenums::GameMode,
+    variant::Variant,
+};
+use std::{
+    collections::HashSet,
+    env,
+    fs::{self, OpenOptions},
+    io::{BufWriter, Write},
+    path::Path,
+    process,
+};
+
+/// A game to scan, from the archive or from a PGN file.
+struct SourceGame {
+    /// Prefix of the ids of its puzzles.
+    id: String,
+    fen: Option<String>,
+    moves: Vec<String>,
+}
+
+struct Config {
+    games: Vec<String>,
+    pgn: Vec<String>,
+    mode: Option<GameMode>,
+    out: String,
+    append: bool,
+    miner: MinerConfig,
+}
+
+const USAGE: &str = "Usage: puzzlegen [--games FILE] [--pgn FILE] [--mode MODE] \
+[--out FILE] [--append] [--depth PLIES] [--min-gain CENTIPAWNS] [--max-moves N] \
+[--max-nodes NODES]";
+
+fn parse_args() -> Result<Config, String> {
+    let mut args = env::args().skip(1);
+    let mut config = Config {
+        games: Vec::new(),
+        pgn: Vec::new(),
+        mode: None,
+        out: "puzzles.csv".to_string(),
+        append: false,
+        miner: MinerConfig::default(),
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--games" => config.games.push(value()?),
+            "--pgn" => config.pgn.push(value()?),
+            "--mode" => {
+                let mode = value()?;
+                config.mode = Some(
+                    serde_json::from_value(serde_json::Value::String(mode.clone()))
+                        .map_err(|_| format!("Unknown mode {}", mode))?,
+                )
+            }
+            "--out" => config.out = value()?,
+            "--append" => config.append = true,
+            "--depth" => {
+                config.miner.depth = value()?
+                    .parse()
+                    .map_err(|e| format!("Invalid depth: {}", e))?
+            }
+            "--min-gain" => {
+                config.miner.min_gain = value()?
+                    .parse()
+                    .map_err(|e| format!("Invalid gain: {}", e))?
+            }
+            "--max-moves" => {
+                config.miner.max_solver_moves = value()?
+                    .parse()
+                    .map_err(|e| format!("Invalid move count: {}", e))?
+            }
+            "--max-nodes" => {
+                config.miner.max_nodes = value()?
+                    .parse()
+                    .map_err(|e| format!("Invalid node limit: {}", e))?
+            }
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ => return Err(format!("Unknown argument {}", arg)),
+        }
+    }
+    if config.games.is_empty() && config.pgn.is_empty() {
+        return Err("Give at least one --games or --pgn file".into());
+    }
+    if config.miner.depth == 0 || config.miner.max_solver_moves == 0 {
+        return Err("--depth and --max-moves must be positive".into());
+    }
+    Ok(config)
+}
+
+// Archive au format du serveur: un GameRecord JSON par ligne
+fn load_archive(path: &str, mode: Option<&GameMode>) -> Result<Vec<SourceGame>, String> {
+    let data = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let mut games = Vec::new();
+    for (i, line) in data.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: GameRecord = match serde_json::from_str(line) {
+            Ok(record) => record,
+            Err(e) => {
+                eprintln!("{}:{}: {}", path, i + 1, e);
+                continue;
+            }
+        };
+        // Le mineur ne connaît que les règles classiques
+        if mode.is_some_and(|m| *m != record.mode) || record.variant != Variant::Standard {
+            continue;
+        }
+        games.push(SourceGame {
+            id: format!("{}-", &record.id.simple().to_string()[..8]),
+            fen: record.start_position(),
+            moves: record.moves,
+        });
+    }
+    Ok(games)
+}
+
+fn load_pgn(path: &str) -> Result<Vec<SourceGame>, String> {
+    let data = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let stem = Path::new(path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("pgn")
+        .replace([',', ' '], "_");
+    let mut games = Vec::new();
+    for (i, game) in pgn::parse(&data).into_iter().enumerate() {
+        match game {
+            Ok(game) => games.push(SourceGame {
+                id: format!("{}{}-", stem, i + 1),
+                fen: game.fen().map(String::from),
+                moves: game.moves,
+            }),
+            Err(e) => eprintln!("{}: {}", path, e),
+        }
+    }
+    Ok(games)
+}
+
+// Identifiants déjà présents dans le fichier de sortie
+fn existing_ids(path: &str) -> HashSet<String> {
+    fs::read_to_string(path)
+        .map(|data| {
+            data.lines()
+                .filter(|l| !l.starts_with(CSV_HEADER))
+                .filter_map(|l| l.split(',').next())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn main() {
+    let config = match parse_args() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let mut games = Vec::new();
+    let loaded = config
+        .games
+        .iter()
+        .map(|path| load_archive(path, config.mode.as_ref()))
+        .chain(config.pgn.iter().map(|path| load_pgn(path)));
+    for result in loaded {
+        match result {
+            Ok(found) => games.extend(found),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    let known = if config.append {
+        existing_ids(&config.out)
+    } else {
+        HashSet::new()
+    };
+    let write_header = !config.append || !Path::new(&config.out).exists();
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(config.append)
+        .truncate(!config.append)
+        .open(&config.out);
+    let mut out = match file {
+        Ok(file) => BufWriter::new(file),
+        Err(e) => {
+            eprintln!("Failed to open {}: {}", config.out, e);
+            process::exit(1);
+        }
+    };
+    if write_header {
+        let _ = writeln!(out, "{}", CSV_HEADER);
+    }
+
+    println!(
+        "Scanning {} games (depth {}, gain {}cp, at most {} moves)",
+        games.len(),
+        config.miner.depth,
+        config.miner.min_gain,
+        config.miner.max_solver_moves
+    );
+    let mut written = 0;
+    let mut failed = 0;
+    for game in &games {
+        let puzzles = match &game.fen {
+            Some(fen) => check_start_fen(fen),
+            None => Ok(FenPosition::start()),
+        }
+        .map(|start| mine_game(&start, &game.moves, &game.id, &config.miner))
+        .map_err(|e| format!("{}: {}", game.id, e));
+        match puzzles {
+            Ok(puzzles) => {
+                for puzzle in puzzles.iter().filter(|p| !known.contains(&p.id)) {
+                    if let Err(e) = writeln!(out, "{}", puzzle.to_csv()) {
+                        eprintln!("Failed to write {}: {}", config.out, e);
+                        process::exit(1);
+                    }
+                    written += 1;
+                }
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                failed += 1;
+            }
+        }
+    }
+    if let Err(e) = out.flush() {
+        eprintln!("Failed to write {}: {}", config.out, e);
+        process::exit(1);
+    }
+    if failed > 0 {
+        println!("{} games could not be scanned", failed);
+    }
+    println!("{} puzzles written to {}", written, config.out);
+}
@@ -0,0 +1,172 @@
+use game_lib::{
+    automation::mate::MatePosition,
+    fen::FenPosition,
+    uci::from_uci,
+    variant::{Variant, VariantState},
+};
+use std::collections::HashMap;
+
+/// One game of a PGN file, moves in server notation ("e2->e4").
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnGame {
+    pub tags: HashMap<String, String>,
+    pub moves: Vec<String>,
+}
+
+impl PgnGame {
+    /// Starting position when the game does not start from the initial one.
+    pub fn fen(&self) -> Option<&str> {
+        self.tags.get("FEN").map(String::as_str)
+    }
+}
+
+/// Splits a PGN file into games. Moves may be in SAN ("Nf3") or in the
+/// coordinate notation of older server files ("g1f3"); each one is checked
+/// by replaying the game. Games that cannot be read, or that are not
+/// standard chess, are reported as errors with the number of the game.
+pub fn parse(text: &str) -> Vec<Result<PgnGame, String>> {
+    let mut games = Vec::new();
+    let mut tags = HashMap::new();
+    let mut movetext = String::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            // Une nouvelle section d'en-têtes commence la partie suivante
+            if !movetext.trim().is_empty() {
+                games.push(read_game(std::mem::take(&mut tags), &movetext));
+                movetext.clear();
+            }
+            if let Some((key, value)) = parse_tag(line) {
+                tags.insert(key, value);
+            }
+        } else if !line.starts_with('%') {
+            movetext.push_str(line);
+            movetext.push('\n');
+        }
+    }
+    if !movetext.trim().is_empty() || !tags.is_empty() {
+        games.push(read_game(tags, &movetext));
+    }
+    games
+        .into_iter()
+        .enumerate()
+        .map(|(i, game)| game.map_err(|e| format!("game {}: {}", i + 1, e)))
+        .collect()
+}
+
+// [White "Alice"] -> ("White", "Alice")
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (key, value) = inner.split_once(' ')?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((key.to_string(), value.replace("\\\"", "\"")))
+}
+
+fn read_game(tags: HashMap<String, String>, movetext: &str) -> Result<PgnGame, String> {
+    if let Some(variant) = tags.get("Variant").filter(|v| *v != "Standard") {
+        return Err(format!("unsupported variant {}", variant));
+    }
+    let rules = Variant::Standard;
+    let mut position = match tags.get("FEN") {
+        Some(fen) => FenPosition::parse(fen)?,
+        None => FenPosition::start(),
+    };
+    let mut state = VariantState::default();
+    let mut moves = Vec::new();
+    for token in tokens(movetext) {
+        let token = token.trim_end_matches(['+', '#', '!', '?']);
+        let is_number = token
+            .trim_end_matches('.')
+            .chars()
+            .all(|c| c.is_ascii_digit());
+        if token.is_empty() || is_number || token.starts_with('$') {
+            continue;
+        }
+        if matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*") {
+            break;
+        }
+        // "12.e4" et "12...e5"
+        let token = token.rsplit('.').next().unwrap_or(token);
+        let mv = from_uci(token)
+            .and_then(|mv| rules.legal_move(&position, &state, &mv))
+            .or_else(|| rules.from_san(&position, &state, token))
+            .ok_or_else(|| format!("illegal move {} after {} moves", token, moves.len()))?;
+        rules.play(&mut position, &mut state, &mv);
+        moves.push(mv);
+    }
+    Ok(PgnGame { tags, moves })
+}
+
+// Découpe le texte des coups en sautant commentaires et variantes
+fn tokens(movetext: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut comment = false;
+    let mut line_comment = false;
+    let mut variation = 0;
+    for c in movetext.chars() {
+        match c {
+            '\n' if line_comment => line_comment = false,
+            _ if line_comment => {}
+            '{' if !comment => comment = true,
+            '}' if comment => comment = false,
+            _ if comment => {}
+            ';' => line_comment = true,
+            '(' => variation += 1,
+            ')' => variation = 0.max(variation - 1),
+            _ if variation > 0 => {}
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_san_and_coordinate_games() {
+        let text = "[Event \"Test\"]\n[White \"A\"]\n\n\
+            1. e4 e5 2. Nf3 {développe} Nc6 3. Bb5 a6 (3... Nf6) 4. Bxc6 dxc6 \
+            5. O-O f6 6. d4 exd4 7. Nxd4 c5 8. Nb3 Qxd1 9. Rxd1 1-0\n\n\
+            [Event \"Ancien\"]\n\n1. e2e4 e7e5 2. g1f3 *\n";
+        let games: Vec<PgnGame> = parse(text).into_iter().map(Result::unwrap).collect();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].moves.len(), 17);
+        assert_eq!(games[0].moves[8], "e1->g1");
+        assert_eq!(games[0].moves[16], "f1->d1");
+        assert_eq!(games[1].moves, ["e2->e4", "e7->e5", "g1->f3"]);
+    }
+
+    #[test]
+    fn san_is_read_from_the_fen_and_promotes() {
+        let text = "[FEN \"4k3/P7/8/8/8/8/8/R3K2R w KQ - 0 1\"]\n\n\
+            1. a8=Q+ Kd7 2. O-O-O+ Kc7 3. Rhf1 *";
+        let game = parse(text).remove(0).unwrap();
+        assert_eq!(
+            game.moves,
+            ["a7->a8q", "e8->d7", "e1->c1", "d7->c7", "h1->f1"]
+        );
+    }
+
+    #[test]
+    fn illegal_or_ambiguous_moves_are_errors() {
+        let errors = parse("1. e4 e5 2. Ke3 *\n\n[Variant \"Atomic\"]\n\n1. e4 *");
+        assert!(errors[0].as_ref().unwrap_err().contains("Ke3"));
+        assert!(errors[1].as_ref().unwrap_err().contains("Atomic"));
+        // Deux cavaliers peuvent aller en d2
+        let text = "[FEN \"4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1\"]\n\n1. Nd2 *";
+        assert!(parse(text)[0].is_err());
+        let text = "[FEN \"4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1\"]\n\n1. Nbd2 *";
+        assert_eq!(parse(text)[0].as_ref().unwrap().moves, ["b1->d2"]);
+    }
+}
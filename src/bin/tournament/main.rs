@@ -0,0 +1,490 @@
+//! Headless engine-vs-engine matches, used to check whether an engine change
+//! is an improvement.
+//!
+//! ```text
+//! cargo run --release --bin tournament -- \
+//!     --engine new=Hard:Positional --engine old=Hard \
+//!     --games 1000 --tc 10+0.1 --openings book.epd \
+//!     --sprt 0,5 --pgn match.pgn
+//! ```
+
+mod openings;
+mod stats;
+
+use game_lib::{
+    automation::ai::AiProfile,
+    fen::FenPosition,
+    history::{pgn_date, pgn_movetext_from},
+    piece::Color,
+    sharedenums::GameResult,
+    variant::{Variant, VariantState},
+};
+use openings::Opening;
+use stats::{Score, Sprt, SprtDecision};
+use std::{
+    collections::VecDeque,
+    env,
+    fs::File,
+    io::{BufWriter, Write},
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Debug, Clone)]
+struct Entrant {
+    name: String,
+    profile: AiProfile,
+}
+
+/// Clock of each side, in milliseconds.
+#[derive(Debug, Clone, Copy)]
+struct Clock {
+    initial_ms: u64,
+    increment_ms: u64,
+}
+
+impl Clock {
+    /// Parses "seconds+increment", e.g. "10+0.1" or "60".
+    fn parse(spec: &str) -> Result<Self, String> {
+        let (initial, increment) = spec.split_once('+').unwrap_or((spec, "0"));
+        let to_ms = |v: &str| {
+            v.trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|s| *s >= 0.0)
+                .map(|s| (s * 1000.0).round() as u64)
+                .ok_or_else(|| format!("Invalid time control {}", spec))
+        };
+        let clock = Clock {
+            initial_ms: to_ms(initial)?,
+            increment_ms: to_ms(increment)?,
+        };
+        if clock.initial_ms == 0 {
+            return Err("The initial time must be positive".into());
+        }
+        Ok(clock)
+    }
+
+    // Tag PGN "TimeControl", en secondes
+    fn to_pgn(self) -> String {
+        let secs = |ms: u64| {
+            if ms.is_multiple_of(1000) {
+                (ms / 1000).to_string()
+            } else {
+                format!("{}", ms as f64 / 1000.0)
+            }
+        };
+        format!("{}+{}", secs(self.initial_ms), secs(self.increment_ms))
+    }
+}
+
+struct Config {
+    entrants: Vec<Entrant>,
+    /// Games per pairing, rounded up to an even number so that every
+    /// opening is played with both colours.
+    games: usize,
+    concurrency: usize,
+    clock: Clock,
+    openings: Vec<Opening>,
+    sprt: Option<Sprt>,
+    pgn: String,
+}
+
+/// One game to play. `white` and `black` index `Config::entrants`.
+#[derive(Debug, Clone)]
+struct Job {
+    round: usize,
+    pairing: usize,
+    white: usize,
+    black: usize,
+    opening: usize,
+}
+
+struct Played {
+    job: Job,
+    moves: Vec<String>,
+    result: GameResult,
+    reason: String,
+    started_at: u64,
+}
+
+fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+const USAGE: &str = "Usage: tournament --engine NAME=DIFFICULTY[:EVAL] --engine ... \
+[--games N] [--concurrency N] [--tc SECONDS+INC] [--openings FILE] \
+[--sprt ELO0,ELO1[,ALPHA,BETA]] [--pgn FILE]";
+
+// "new=Hard:Positional" -> moteur intégré, évaluation optionnelle
+fn parse_entrant(spec: &str) -> Result<Entrant, String> {
+    let (name, config) = spec
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid engine {}, expected NAME=DIFFICULTY[:EVAL]", spec))?;
+    let (difficulty, eval) = config.split_once(':').unwrap_or((config, "Standard"));
+    let profile = serde_json::from_value(serde_json::json!({
+        "engine": "Builtin",
+        "difficulty": difficulty,
+        "eval": eval,
+    }))
+    .map_err(|e| format!("Invalid engine {}: {}", spec, e))?;
+    Ok(Entrant {
+        name: name.to_string(),
+        profile,
+    })
+}
+
+fn parse_args() -> Result<Config, String> {
+    let mut args = env::args().skip(1);
+    let mut entrants = Vec::new();
+    let mut games = 100;
+    let mut concurrency = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut clock = Clock::parse("10+0.1")?;
+    let mut openings = vec![Opening::startpos()];
+    let mut sprt = None;
+    let mut pgn = "tournament.pgn".to_string();
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--engine" => entrants.push(parse_entrant(&value()?)?),
+            "--games" => {
+                games = value()?
+                    .parse()
+                    .map_err(|e| format!("Invalid game count: {}", e))?
+            }
+            "--concurrency" => {
+                concurrency = value()?
+                    .parse()
+                    .map_err(|e| format!("Invalid concurrency: {}", e))?
+            }
+            "--tc" => clock = Clock::parse(&value()?)?,
+            "--openings" => openings = openings::load(value()?)?,
+            "--sprt" => sprt = Some(Sprt::parse(&value()?)?),
+            "--pgn" => pgn = value()?,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ => return Err(format!("Unknown argument {}", arg)),
+        }
+    }
+
+    if entrants.len() < 2 {
+        return Err("At least two engines are needed".into());
+    }
+    if sprt.is_some() && entrants.len() != 2 {
+        return Err("SPRT needs exactly two engines".into());
+    }
+    if games == 0 || concurrency == 0 {
+        return Err("--games and --concurrency must be positive".into());
+    }
+    Ok(Config {
+        entrants,
+        games: games + games % 2,
+        concurrency,
+        clock,
+        openings,
+        sprt,
+        pgn,
+    })
+}
+
+// Toutes les paires d'engins; chaque ouverture est jouée deux fois,
+// couleurs inversées, l'une après l'autre
+fn schedule(config: &Config) -> (Vec<(usize, usize)>, VecDeque<Job>) {
+    let n = config.entrants.len();
+    let pairings: Vec<(usize, usize)> = (0..n)
+        .flat_map(|a| (a + 1..n).map(move |b| (a, b)))
+        .collect();
+    let mut jobs = VecDeque::new();
+    for round in 0..config.games {
+        for (pairing, &(a, b)) in pairings.iter().enumerate() {
+            let (white, black) = if round % 2 == 0 { (a, b) } else { (b, a) };
+            jobs.push_back(Job {
+                round: round + 1,
+                pairing,
+                white,
+                black,
+                opening: (round / 2) % config.openings.len(),
+            });
+        }
+    }
+    (pairings, jobs)
+}
+
+// Profondeur de la recherche courte, en demi-coups
+const SEARCH_DEPTH: u8 = 2;
+// Au-delà, la partie est déclarée nulle par arbitrage
+const MAX_PLIES: usize = 400;
+
+// Coup du moteur intégré. Le moteur complet travaille sur `Game`; sans lui,
+// une recherche courte sur l'évaluation choisie joue à sa place.
+fn engine_move(entrant: &Entrant, position: &FenPosition, state: &VariantState) -> Option<String> {
+    /*
+    if let Ok(game) = game_lib::game::Game::from_fen(&position.to_fen()) {
+        let ai = game_lib::automation::ai::AI::from_profile(&entrant.profile, position.turn);
+        return ai
+            .get_best_move(&game.board)
+            .map(|mv| format!("{}->{}", mv.0.to_algebraic(), mv.1.to_algebraic()));
+    }
+    */
+    Variant::Standard.best_move(position, state, entrant.profile.eval(), SEARCH_DEPTH)
+}
+
+// Attend le coup du moteur au plus `budget_ms`. Passé ce délai la recherche
+// continue dans son thread mais son coup est ignoré: la partie est perdue au
+// temps sans bloquer le match.
+fn engine_move_within(
+    entrant: &Entrant,
+    position: &FenPosition,
+    state: &VariantState,
+    budget_ms: u64,
+) -> Option<Option<String>> {
+    let (tx, rx) = mpsc::channel();
+    let (entrant, position, state) = (entrant.clone(), position.clone(), *state);
+    thread::spawn(move || {
+        let _ = tx.send(engine_move(&entrant, &position, &state));
+    });
+    rx.recv_timeout(Duration::from_millis(budget_ms)).ok()
+}
+
+// Joue une partie complète avec les pendules. Un moteur qui dépasse son
+// temps perd la partie, même si son coup finit par arriver.
+fn play_game(config: &Config, job: Job) -> Played {
+    let rules = Variant::Standard;
+    let opening = &config.openings[job.opening];
+    let started_at = now_timestamp();
+    // Les ouvertures sont vérifiées au chargement
+    let (mut position, mut state, mut moves) = opening.setup().unwrap_or_else(|e| {
+        eprintln!(
+            "Opening {} rejected ({}), using the initial position",
+            job.opening, e
+        );
+        (FenPosition::start(), VariantState::default(), Vec::new())
+    });
+    let mut clocks = [config.clock.initial_ms; 2];
+    let (result, reason) = loop {
+        let turn = position.turn;
+        if let Some(result) = rules.result_without_moves(&position, &state) {
+            let reason = if result == GameResult::Draw {
+                "Stalemate"
+            } else {
+                "Checkmate"
+            };
+            break (result, reason.to_string());
+        }
+        if position.halfmove >= 100 {
+            break (GameResult::Draw, "Fifty-move rule".to_string());
+        }
+        if moves.len() >= MAX_PLIES {
+            break (GameResult::Draw, "Adjudication".to_string());
+        }
+
+        let entrant = match turn {
+            Color::White => &config.entrants[job.white],
+            Color::Black => &config.entrants[job.black],
+        };
+        let clock = &mut clocks[turn as usize];
+        let start = std::time::Instant::now();
+        let Some(mv) = engine_move_within(entrant, &position, &state, *clock) else {
+            break (
+                GameResult::winner(turn.opposite()),
+                "Time forfeit".to_string(),
+            );
+        };
+        let elapsed = start.elapsed().as_millis() as u64;
+        if elapsed > *clock {
+            break (
+                GameResult::winner(turn.opposite()),
+                "Time forfeit".to_string(),
+            );
+        }
+        *clock = *clock - elapsed + config.clock.increment_ms;
+
+        let Some(mv) = mv.and_then(|mv| rules.legal_move(&position, &state, &mv)) else {
+            break (GameResult::winner(turn.opposite()), "No move".to_string());
+        };
+        rules.play(&mut position, &mut state, &mv);
+        moves.push(mv);
+    };
+    Played {
+        job,
+        moves,
+        result,
+        reason,
+        started_at,
+    }
+}
+
+fn write_pgn(out: &mut impl Write, config: &Config, played: &Played) -> std::io::Result<()> {
+    let opening = &config.openings[played.job.opening];
+    let mut tags = vec![
+        ("Event", "M&Chess tournament".to_string()),
+        ("Site", "M&Chess".to_string()),
+        ("Date", pgn_date(played.started_at)),
+        ("Round", played.job.round.to_string()),
+        ("White", config.entrants[played.job.white].name.clone()),
+        ("Black", config.entrants[played.job.black].name.clone()),
+        ("Result", played.result.to_pgn().to_string()),
+        ("TimeControl", config.clock.to_pgn()),
+        ("Termination", played.reason.clone()),
+    ];
+    if let Some(fen) = opening.fen() {
+        tags.push(("SetUp", "1".to_string()));
+        tags.push(("FEN", fen.to_string()));
+    }
+    for (tag, value) in tags {
+        writeln!(out, "[{} \"{}\"]", tag, value.replace('"', "'"))?;
+    }
+    writeln!(out)?;
+    let movetext = pgn_movetext_from(
+        &played.moves,
+        played.result,
+        opening.fen(),
+        Variant::Standard,
+    );
+    writeln!(out, "{}", movetext)?;
+    writeln!(out)
+}
+
+fn print_standings(config: &Config, pairings: &[(usize, usize)], scores: &[Score]) {
+    for (&(a, b), score) in pairings.iter().zip(scores) {
+        let elo = match score.elo() {
+            Some((elo, margin)) => format!("{:+.1} +/- {:.1}", elo, margin),
+            None => "n/a".to_string(),
+        };
+        println!(
+            "{} vs {}: +{} ={} -{} ({} games), Elo {}",
+            config.entrants[a].name,
+            config.entrants[b].name,
+            score.wins,
+            score.draws,
+            score.losses,
+            score.games(),
+            elo
+        );
+    }
+}
+
+fn main() {
+    let config = match parse_args() {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    let mut pgn = match File::create(&config.pgn) {
+        Ok(file) => BufWriter::new(file),
+        Err(e) => {
+            eprintln!("Failed to create {}: {}", config.pgn, e);
+            process::exit(1);
+        }
+    };
+
+    let (pairings, jobs) = schedule(&config);
+    for entrant in &config.entrants {
+        println!("Engine {}: {}", entrant.name, entrant.profile.label());
+    }
+    println!(
+        "{} games, {} openings, tc {}, {} threads",
+        jobs.len(),
+        config.openings.len(),
+        config.clock.to_pgn(),
+        config.concurrency
+    );
+
+    let jobs = Arc::new(Mutex::new(jobs));
+    let stop = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = mpsc::channel();
+    for _ in 0..config.concurrency {
+        let (config, jobs, stop, tx) = (config.clone(), jobs.clone(), stop.clone(), tx.clone());
+        thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                let job = match jobs.lock().unwrap().pop_front() {
+                    Some(job) => job,
+                    None => break,
+                };
+                if tx.send(play_game(&config, job)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(tx);
+
+    let mut scores = vec![Score::default(); pairings.len()];
+    let mut aborted = 0;
+    let mut decision = SprtDecision::Continue;
+    for played in rx {
+        if let Err(e) = write_pgn(&mut pgn, &config, &played) {
+            eprintln!("Failed to write {}: {}", config.pgn, e);
+        }
+        // Les scores sont comptés du point de vue du premier moteur de la paire
+        let first_is_white = pairings[played.job.pairing].0 == played.job.white;
+        let score = &mut scores[played.job.pairing];
+        match (played.result, first_is_white) {
+            (GameResult::Draw, _) => score.draws += 1,
+            (GameResult::WhiteWins, true) | (GameResult::BlackWins, false) => score.wins += 1,
+            (GameResult::WhiteWins, false) | (GameResult::BlackWins, true) => score.losses += 1,
+            (GameResult::Aborted, _) => {
+                aborted += 1;
+                eprintln!("Game {} aborted: {}", played.job.round, played.reason);
+                continue;
+            }
+        }
+
+        if let Some(sprt) = &config.sprt {
+            let (llr, d) = sprt.decide(score);
+            let (lower, upper) = sprt.bounds();
+            println!(
+                "Game {}: +{} ={} -{}, LLR {:.2} [{:.2}, {:.2}]",
+                score.games(),
+                score.wins,
+                score.draws,
+                score.losses,
+                llr,
+                lower,
+                upper
+            );
+            if d != SprtDecision::Continue && decision == SprtDecision::Continue {
+                // Les parties en cours se terminent mais aucune autre ne démarre
+                decision = d;
+                stop.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+    if let Err(e) = pgn.flush() {
+        eprintln!("Failed to write {}: {}", config.pgn, e);
+    }
+
+    println!();
+    print_standings(&config, &pairings, &scores);
+    if aborted > 0 {
+        println!("{} games aborted", aborted);
+    }
+    if let Some(sprt) = &config.sprt {
+        let verdict = match decision {
+            SprtDecision::AcceptH1 => "H1 accepted: the first engine is stronger",
+            SprtDecision::AcceptH0 => "H0 accepted: no improvement",
+            SprtDecision::Continue => "inconclusive, more games are needed",
+        };
+        println!(
+            "SPRT elo0={} elo1={} alpha={} beta={}: {}",
+            sprt.elo0, sprt.elo1, sprt.alpha, sprt.beta, verdict
+        );
+    }
+    println!("Games written to {}", config.pgn);
+}
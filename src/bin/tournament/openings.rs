@@ -0,0 +1,134 @@
+use game_lib::{
+    fen::{check_start_fen, FenPosition},
+    uci::from_uci,
+    variant::{Variant, VariantState},
+};
+use std::{fs, path::Path};
+
+/// Starting point of a tournament game.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Opening {
+    /// Moves played from the initial position, in server notation ("e2->e4").
+    Moves(Vec<String>),
+    /// Position given as the FEN part of an EPD record.
+    Fen(String),
+}
+
+impl Opening {
+    pub fn startpos() -> Self {
+        Opening::Moves(Vec::new())
+    }
+
+    pub fn moves(&self) -> &[String] {
+        match self {
+            Opening::Moves(moves) => moves,
+            Opening::Fen(_) => &[],
+        }
+    }
+
+    pub fn fen(&self) -> Option<&str> {
+        match self {
+            Opening::Moves(_) => None,
+            Opening::Fen(fen) => Some(fen),
+        }
+    }
+
+    /// Position reached after the opening, with the moves played to get
+    /// there. Fails on an invalid FEN or an illegal move.
+    pub fn setup(&self) -> Result<(FenPosition, VariantState, Vec<String>), String> {
+        let rules = Variant::Standard;
+        let mut position = match self {
+            Opening::Fen(fen) => check_start_fen(fen)?,
+            Opening::Moves(_) => FenPosition::start(),
+        };
+        let mut state = VariantState::default();
+        let mut moves = Vec::new();
+        for mv in self.moves() {
+            let mv = rules
+                .legal_move(&position, &state, mv)
+                .ok_or_else(|| format!("illegal opening move {}", mv))?;
+            rules.play(&mut position, &mut state, &mv);
+            moves.push(mv);
+        }
+        Ok((position, state, moves))
+    }
+}
+
+/// Reads an opening file. Each line is either an EPD record (the first four
+/// fields are the position) or a list of moves such as "e2e4 e7e5 g1f3".
+/// Empty lines and lines starting with '#' are skipped.
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<Opening>, String> {
+    let path = path.as_ref();
+    let data = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut openings = Vec::new();
+    for (i, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let opening = parse_line(line)
+            .ok_or_else(|| "invalid opening".to_string())
+            .and_then(|opening| opening.setup().map(|_| opening))
+            .map_err(|e| format!("{}:{}: {}", path.display(), i + 1, e))?;
+        openings.push(opening);
+    }
+    if openings.is_empty() {
+        return Err(format!("No opening found in {}", path.display()));
+    }
+    Ok(openings)
+}
+
+fn parse_line(line: &str) -> Option<Opening> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields[0].contains('/') {
+        // EPD: placement, trait, roques, en passant; les compteurs sont implicites
+        if fields.len() < 4 || fields[0].split('/').count() != 8 {
+            return None;
+        }
+        return Some(Opening::Fen(format!("{} 0 1", fields[..4].join(" "))));
+    }
+    fields
+        .iter()
+        .map(|mv| from_uci(mv))
+        .collect::<Option<Vec<String>>>()
+        .map(Opening::Moves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_str(name: &str, data: &str) -> Result<Vec<Opening>, String> {
+        let path = std::env::temp_dir().join(format!("openings-{}-{}", std::process::id(), name));
+        fs::write(&path, data).unwrap();
+        let openings = load(&path);
+        fs::remove_file(&path).unwrap();
+        openings
+    }
+
+    #[test]
+    fn valid_openings_load() {
+        let openings = load_str(
+            "valid",
+            "# commentaire\ne2e4 e7e5 g1f3\n\nrnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3\n",
+        )
+        .unwrap();
+        assert_eq!(openings.len(), 2);
+        assert_eq!(openings[0].moves().len(), 3);
+        assert!(openings[1].fen().is_some());
+    }
+
+    #[test]
+    fn illegal_moves_are_rejected_with_their_line() {
+        let err = load_str("illegal", "e2e4 e7e5\ne2e4 e2e4\n").unwrap_err();
+        assert!(err.ends_with(":2: illegal opening move e2->e4"), "{}", err);
+    }
+
+    #[test]
+    fn invalid_positions_are_rejected_with_their_line() {
+        // Pas de roi noir
+        let err = load_str("noking", "8/8/8/8/8/8/8/4K3 w - -\n").unwrap_err();
+        assert!(err.contains(":1: "), "{}", err);
+    }
+}
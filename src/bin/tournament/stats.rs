@@ -0,0 +1,174 @@
+/// Results of the first engine of a pairing against the second.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Score {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Score {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + 0.5 * self.draws as f64
+    }
+
+    fn mean(&self) -> f64 {
+        self.points() / self.games() as f64
+    }
+
+    // Variance du score d'une partie (modèle trinomial victoire/nulle/défaite)
+    fn variance(&self) -> f64 {
+        let n = self.games() as f64;
+        let m = self.mean();
+        (self.wins as f64 * (1.0 - m).powi(2)
+            + self.draws as f64 * (0.5 - m).powi(2)
+            + self.losses as f64 * m.powi(2))
+            / n
+    }
+
+    /// Elo difference and the half-width of its 95% confidence interval.
+    /// `None` until both sides have scored at least once.
+    pub fn elo(&self) -> Option<(f64, f64)> {
+        if self.games() == 0 {
+            return None;
+        }
+        let mean = self.mean();
+        if mean <= 0.0 || mean >= 1.0 {
+            return None;
+        }
+        let stderr = (self.variance() / self.games() as f64).sqrt();
+        let low = elo_of(mean - 1.96 * stderr);
+        let high = elo_of(mean + 1.96 * stderr);
+        Some((elo_of(mean), (high - low) / 2.0))
+    }
+}
+
+// Différence Elo correspondant à un score moyen (modèle logistique)
+fn elo_of(score: f64) -> f64 {
+    let score = score.clamp(1e-6, 1.0 - 1e-6);
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+fn score_of(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SprtDecision {
+    /// The change is not better than `elo0`.
+    AcceptH0,
+    /// The change is at least `elo1` better.
+    AcceptH1,
+    Continue,
+}
+
+/// Sequential probability ratio test between H0: elo = `elo0` and
+/// H1: elo = `elo1`, using the usual normal approximation of the
+/// log-likelihood ratio.
+#[derive(Debug, Clone, Copy)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        Sprt {
+            elo0: 0.0,
+            elo1: 5.0,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+}
+
+impl Sprt {
+    /// Parses "elo0,elo1[,alpha,beta]".
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let values = spec
+            .split(',')
+            .map(|v| v.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|e| format!("Invalid SPRT bounds {}: {}", spec, e))?;
+        let mut sprt = Sprt::default();
+        match values.as_slice() {
+            [elo0, elo1] => {
+                sprt.elo0 = *elo0;
+                sprt.elo1 = *elo1;
+            }
+            [elo0, elo1, alpha, beta] => {
+                sprt = Sprt {
+                    elo0: *elo0,
+                    elo1: *elo1,
+                    alpha: *alpha,
+                    beta: *beta,
+                };
+            }
+            _ => return Err(format!("Expected elo0,elo1[,alpha,beta], got {}", spec)),
+        }
+        if sprt.elo0 >= sprt.elo1 {
+            return Err("SPRT elo0 must be lower than elo1".into());
+        }
+        // Bornes strictes: 0 ou 1 donneraient des seuils LLR infinis
+        let open = |p: f64| p > 0.0 && p < 1.0;
+        if !open(sprt.alpha) || !open(sprt.beta) || sprt.alpha + sprt.beta >= 1.0 {
+            return Err("SPRT alpha and beta must be in (0, 1), with alpha + beta < 1".into());
+        }
+        Ok(sprt)
+    }
+
+    /// Lower and upper LLR bounds.
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    pub fn llr(&self, score: &Score) -> f64 {
+        if score.games() == 0 {
+            return 0.0;
+        }
+        let variance = score.variance();
+        if variance == 0.0 {
+            return 0.0;
+        }
+        let (s0, s1) = (score_of(self.elo0), score_of(self.elo1));
+        (s1 - s0) * (2.0 * score.points() - score.games() as f64 * (s0 + s1)) / (2.0 * variance)
+    }
+
+    pub fn decide(&self, score: &Score) -> (f64, SprtDecision) {
+        let llr = self.llr(score);
+        let (lower, upper) = self.bounds();
+        let decision = if llr >= upper {
+            SprtDecision::AcceptH1
+        } else if llr <= lower {
+            SprtDecision::AcceptH0
+        } else {
+            SprtDecision::Continue
+        };
+        (llr, decision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sprt_error_rates_must_be_strictly_between_0_and_1() {
+        let sprt = Sprt::parse("0,5,0.05,0.1").unwrap();
+        assert_eq!((sprt.alpha, sprt.beta), (0.05, 0.1));
+        let (lower, upper) = sprt.bounds();
+        assert!(lower < 0.0 && upper > 0.0);
+        for spec in ["0,5,0,0.05", "0,5,0.05,0", "0,5,1,0.05", "0,5,0.6,0.6"] {
+            assert!(Sprt::parse(spec).is_err(), "{} accepted", spec);
+        }
+        assert!(Sprt::parse("5,0").is_err());
+    }
+}
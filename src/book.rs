@@ -0,0 +1,58 @@
+use game_lib::{
+    automation::{book::OpeningBook, tablebase::Tablebases},
+    zobrist::ZobristKeys,
+};
+use std::{fs, path::Path, sync::Arc};
+
+pub type SharedOpeningBook = Arc<OpeningBook>;
+pub type SharedTablebases = Arc<Tablebases>;
+
+/// Loads a Polyglot book and the Random64 table used to hash positions.
+/// No book file means the AI plays without a book, and so does a book
+/// without its key file: the server starts and logs why.
+pub fn load_opening_book(
+    book_path: impl AsRef<Path>,
+    keys_path: impl AsRef<Path>,
+) -> Result<Option<OpeningBook>, String> {
+    let (book_path, keys_path) = (book_path.as_ref(), keys_path.as_ref());
+    if !book_path.exists() {
+        return Ok(None);
+    }
+    if !keys_path.exists() {
+        eprintln!(
+            "{} not found, {} is not used",
+            keys_path.display(),
+            book_path.display()
+        );
+        return Ok(None);
+    }
+    let keys = fs::read_to_string(keys_path)
+        .map_err(|e| format!("Failed to read {}: {}", keys_path.display(), e))?;
+    let keys = ZobristKeys::parse(&keys).map_err(|e| format!("{}: {}", keys_path.display(), e))?;
+    let bytes = fs::read(book_path)
+        .map_err(|e| format!("Failed to read {}: {}", book_path.display(), e))?;
+    let book = OpeningBook::from_bytes(keys, &bytes)
+        .map_err(|e| format!("{}: {}", book_path.display(), e))?;
+    println!(
+        "Loaded {} book moves from {}",
+        book.len(),
+        book_path.display()
+    );
+    Ok(Some(book))
+}
+
+/// Scans the Syzygy directory. No directory means no tablebases.
+pub fn load_tablebases(dir: impl AsRef<Path>) -> Result<Option<Tablebases>, String> {
+    let dir = dir.as_ref();
+    if !dir.is_dir() {
+        return Ok(None);
+    }
+    let tablebases = Tablebases::open(dir)?;
+    println!(
+        "Found {} tablebases (up to {} pieces) in {}",
+        tablebases.len(),
+        tablebases.max_pieces(),
+        dir.display()
+    );
+    Ok(Some(tablebases))
+}
@@ -0,0 +1,764 @@
+use crate::{
+    chat::{ChatLog, MAX_MESSAGE_LEN},
+    now_timestamp,
+    room::{RoomCommand, RoomServices, RoomSettings},
+    send_to_player,
+    utils::{guest_name, Player, PlayerType},
+};
+
+use game_lib::{
+    automation::ai::AiProfile,
+    bughouse::{BoardId, Bughouse, BughouseResult, Seat, SEATS},
+    crazyhouse::move_targets,
+    messages::{AccountInfo, ChatEntry, RoomSummary, SeatInfo, ServerMessage},
+    piece::Color,
+    sharedenums::{ChatChannel, GameMode, PlayerRole, RoomStatus, TimeCategory},
+    variant::VariantPosition,
+};
+use std::{collections::HashMap, time::Duration};
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    time::Instant,
+};
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+// Profondeur de recherche des IA, comme pour les autres variantes
+const AI_DEPTH: u8 = 2;
+
+/// Events the room sends to itself, apart from the client commands.
+#[derive(Debug)]
+enum BughouseEvent {
+    /// None when the side waits for a piece from its partner.
+    AiMove { seat: Seat, mv: Option<String> },
+}
+
+/// Room of a Bughouse match: four seats on two boards, each seat taken by
+/// a client or by the built-in engine.
+#[derive(Debug)]
+pub struct BughouseRoom<P> {
+    pub id: Uuid,
+    pub status: RoomStatus,
+    pub players: HashMap<Uuid, Player>,
+    /// Seat of each seated client.
+    seats: HashMap<Uuid, Seat>,
+    /// Seats played by the built-in engine.
+    ai_seats: HashMap<Seat, AiProfile>,
+    pub created_at: Instant,
+    pub opened_at: u64,
+    pub host: Option<Uuid>,
+    pub settings: RoomSettings,
+    bughouse: Bughouse<P>,
+    /// Instant the clocks are counted from.
+    clock_origin: Instant,
+    /// When the first clock runs out if nobody moves.
+    flag_deadline: Option<Instant>,
+    /// Boards whose engine is searching a move.
+    thinking: Vec<BoardId>,
+    chat: ChatLog,
+    banned: Vec<Uuid>,
+    rx: UnboundedReceiver<RoomCommand>,
+    events_rx: UnboundedReceiver<BughouseEvent>,
+    events_tx: UnboundedSender<BughouseEvent>,
+    services: RoomServices,
+}
+
+fn role_of(seat: Seat) -> PlayerRole {
+    match seat.color {
+        Color::White => PlayerRole::White,
+        Color::Black => PlayerRole::Black,
+    }
+}
+
+impl<P: VariantPosition + Send + 'static> BughouseRoom<P> {
+    pub fn new(
+        id: Uuid,
+        players: HashMap<Uuid, Player>,
+        seats: HashMap<Uuid, Seat>,
+        start: P,
+        settings: RoomSettings,
+        rx: UnboundedReceiver<RoomCommand>,
+        services: RoomServices,
+    ) -> Self {
+        let ai_seats = match settings.difficulty.clone().map(AiProfile::builtin) {
+            Some(profile) => settings
+                .ai_seats
+                .iter()
+                .map(|&seat| (seat, profile.clone()))
+                .collect(),
+            None => HashMap::new(),
+        };
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        let mut room = Self {
+            id,
+            status: RoomStatus::WaitingPlayers,
+            players,
+            seats,
+            ai_seats,
+            created_at: Instant::now(),
+            opened_at: now_timestamp(),
+            host: None,
+            bughouse: Bughouse::new(start, settings.time_control),
+            settings,
+            clock_origin: Instant::now(),
+            flag_deadline: None,
+            thinking: Vec::new(),
+            chat: ChatLog::default(),
+            banned: Vec::new(),
+            rx,
+            events_rx,
+            events_tx,
+            services,
+        };
+        room.update_status();
+        room
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.clock_origin.elapsed().as_millis() as u64
+    }
+
+    fn error(&self, client_id: Uuid, msg: &str) {
+        if let Some(player) = self.players.get(&client_id) {
+            let _ = send_to_player(player, &ServerMessage::Error { msg: msg.into() });
+        }
+    }
+
+    fn broadcast(&self, msg: &ServerMessage) {
+        for player in self.players.values() {
+            let _ = send_to_player(player, msg);
+        }
+    }
+
+    fn seat_taken(&self, seat: Seat) -> bool {
+        self.ai_seats.contains_key(&seat) || self.seats.values().any(|s| *s == seat)
+    }
+
+    fn free_seat(&self) -> Option<Seat> {
+        SEATS.into_iter().find(|&seat| !self.seat_taken(seat))
+    }
+
+    // Prêt à démarrer quand les quatre places sont prises et les humains prêts
+    fn update_status(&mut self) {
+        if matches!(self.status, RoomStatus::Running | RoomStatus::Finished) {
+            return;
+        }
+        let ready = self.free_seat().is_none()
+            && self
+                .seats
+                .keys()
+                .all(|id| self.players.get(id).is_some_and(|p| p.ready));
+        self.status = if ready {
+            RoomStatus::WaitingReady
+        } else {
+            RoomStatus::WaitingPlayers
+        };
+    }
+
+    fn seat_infos(&self) -> Vec<SeatInfo> {
+        let humans = self.seats.iter().filter_map(|(id, &seat)| {
+            let player = self.players.get(id)?;
+            Some(SeatInfo {
+                seat,
+                name: player.name.clone(),
+                is_ai: false,
+            })
+        });
+        let engines = self.ai_seats.iter().map(|(&seat, profile)| SeatInfo {
+            seat,
+            name: profile.label(),
+            is_ai: true,
+        });
+        let mut seats: Vec<SeatInfo> = humans.chain(engines).collect();
+        seats.sort_by_key(|info| SEATS.iter().position(|s| *s == info.seat));
+        seats
+    }
+
+    fn send_seats(&self) {
+        let seats = self.seat_infos();
+        for player in self.players.values() {
+            let _ = send_to_player(
+                player,
+                &ServerMessage::BughouseSeats {
+                    seats: seats.clone(),
+                    seat: self.seats.get(&player.id).copied(),
+                },
+            );
+        }
+    }
+
+    fn board_message(&self, id: BoardId) -> ServerMessage {
+        let board = self.bughouse.board(id);
+        let turn = board.position.turn();
+        ServerMessage::BughouseBoard {
+            board_id: id,
+            board: board.display(),
+            turn,
+            counter: board.moves.len(),
+            incheck: board.in_check().then_some(turn),
+            pockets: board.state.pockets,
+            clock: board.clock.map(|clock| clock.state(self.now_ms())),
+        }
+    }
+
+    fn send_boards(&self, player: &Player) {
+        for id in [BoardId::A, BoardId::B] {
+            let _ = send_to_player(player, &self.board_message(id));
+        }
+    }
+
+    fn handle_join(
+        &mut self,
+        client_id: Uuid,
+        account: Option<AccountInfo>,
+        sender: UnboundedSender<Message>,
+    ) -> ServerMessage {
+        if self.banned.contains(&client_id) {
+            return ServerMessage::Error {
+                msg: "You have been kicked from this room.".into(),
+            };
+        }
+        if self.players.contains_key(&client_id) {
+            return ServerMessage::Error {
+                msg: "You already joined this room.".into(),
+            };
+        }
+        // Une partie commencée ne s'ouvre qu'aux spectateurs
+        let seat = match self.status {
+            RoomStatus::WaitingPlayers | RoomStatus::WaitingReady => self.free_seat(),
+            _ => None,
+        };
+        let role = seat.map_or(PlayerRole::Spectator, role_of);
+        let player = Player {
+            id: client_id,
+            account_id: account.as_ref().map(|a| a.id),
+            name: account
+                .map(|a| a.display_name)
+                .unwrap_or_else(|| guest_name(client_id)),
+            role: role.clone(),
+            ready: false,
+            sender: Some(sender),
+            kind: PlayerType::Human,
+        };
+        if let Some(seat) = seat {
+            self.seats.insert(client_id, seat);
+        }
+        let in_progress =
+            self.status != RoomStatus::WaitingPlayers && self.status != RoomStatus::WaitingReady;
+        self.players.insert(client_id, player);
+        self.update_status();
+        // Joined d'abord: le client ne lit les plateaux qu'une fois dans la room
+        let joined = ServerMessage::Joined {
+            role: role.clone(),
+            room_id: self.id,
+            room_status: self.status,
+            host: false,
+            gamemod: GameMode::Bughouse,
+        };
+        if let Some(player) = self.players.get(&client_id) {
+            let _ = send_to_player(player, &joined);
+            if in_progress {
+                self.send_boards(player);
+            }
+            // Les messages d'équipe restent entre partenaires
+            let entries = self.chat.recent(match role {
+                PlayerRole::Spectator => &[ChatChannel::Players, ChatChannel::Spectators],
+                _ => &[ChatChannel::Players],
+            });
+            if !entries.is_empty() {
+                let _ = send_to_player(player, &ServerMessage::ChatHistory { entries });
+            }
+        }
+        self.send_seats();
+        self.broadcast(&ServerMessage::RoomStatus {
+            status: self.status,
+        });
+        joined
+    }
+
+    fn handle_take_seat(&mut self, client_id: Uuid, seat: Seat) {
+        if !self.players.contains_key(&client_id) {
+            return;
+        }
+        let error = if !matches!(
+            self.status,
+            RoomStatus::WaitingPlayers | RoomStatus::WaitingReady
+        ) {
+            Some("Seats cannot change once the match has started.")
+        } else if self.seat_taken(seat) {
+            Some("This seat is already taken.")
+        } else {
+            None
+        };
+        if let Some(msg) = error {
+            self.error(client_id, msg);
+            return;
+        }
+        self.seats.insert(client_id, seat);
+        if let Some(player) = self.players.get_mut(&client_id) {
+            player.role = role_of(seat);
+            player.ready = false;
+        }
+        self.update_status();
+        self.send_seats();
+        self.broadcast(&ServerMessage::RoomStatus {
+            status: self.status,
+        });
+    }
+
+    fn handle_ready(&mut self, client_id: Uuid, ready: bool) {
+        let Some(player) = self.players.get_mut(&client_id) else {
+            return;
+        };
+        player.ready = ready;
+        let _ = send_to_player(player, &ServerMessage::Status { ready });
+        self.update_status();
+        self.broadcast(&ServerMessage::RoomStatus {
+            status: self.status,
+        });
+    }
+
+    fn handle_start(&mut self, client_id: Uuid) {
+        let error = if self.host != Some(client_id) {
+            Some("Only the host can start the match.")
+        } else if self.status != RoomStatus::WaitingReady {
+            Some("All four seats must be taken and ready.")
+        } else {
+            None
+        };
+        if let Some(msg) = error {
+            self.error(client_id, msg);
+            return;
+        }
+        self.status = RoomStatus::Running;
+        self.clock_origin = Instant::now();
+        self.bughouse.start(self.now_ms());
+        for player in self.players.values() {
+            // Chacun reçoit d'abord son propre plateau
+            let board = self
+                .seats
+                .get(&player.id)
+                .map_or(BoardId::A, |seat| seat.board);
+            let turn = self.bughouse.board(board).position.turn();
+            let _ = send_to_player(
+                player,
+                &ServerMessage::GameStarted {
+                    room_status: self.status,
+                    board: self.bughouse.board(board).display(),
+                    turn,
+                    white_time_control: self.settings.time_control,
+                    black_time_control: self.settings.time_control,
+                },
+            );
+            self.send_boards(player);
+        }
+        println!("Room {} bughouse match started", self.id);
+        self.schedule_flag_check();
+        self.request_ai_moves();
+    }
+
+    fn handle_get_moves(&self, client_id: Uuid, mv: String) {
+        let Some(player) = self.players.get(&client_id) else {
+            return;
+        };
+        let moves = match self.seats.get(&client_id) {
+            Some(seat) if self.status == RoomStatus::Running => {
+                move_targets(&self.bughouse.legal_moves(seat.board), &mv)
+            }
+            _ => Vec::new(),
+        };
+        let _ = send_to_player(player, &ServerMessage::LegalMoves { moves });
+    }
+
+    fn handle_move(&mut self, client_id: Uuid, mv: String) {
+        let Some(&seat) = self.seats.get(&client_id) else {
+            self.error(client_id, "You are not allowed to make a move.");
+            return;
+        };
+        if self.status != RoomStatus::Running {
+            self.error(client_id, "The game hasn't started yet.");
+            return;
+        }
+        if let Err(e) = self.play(seat, &mv) {
+            self.error(client_id, &format!("Invalid move: {}", e));
+        }
+    }
+
+    // Coup d'un joueur ou d'une IA, diffusé à toute la room
+    fn play(&mut self, seat: Seat, mv: &str) -> Result<(), String> {
+        let played = self.bughouse.play(seat, mv, self.now_ms());
+        if let Ok(taken) = &played {
+            self.broadcast(&self.board_message(seat.board));
+            // La pièce prise passe dans la poche du partenaire
+            if taken.is_some() {
+                let other = seat.board.other();
+                self.broadcast(&ServerMessage::BughousePockets {
+                    board_id: other,
+                    pockets: self.bughouse.pockets(other),
+                });
+            }
+        }
+        match self.bughouse.result() {
+            Some(result) => self.finish_match(result),
+            None => {
+                self.schedule_flag_check();
+                self.request_ai_moves();
+            }
+        }
+        played.map(|_| ())
+    }
+
+    // Échéance de la première pendule qui tombera, attendue dans run
+    fn schedule_flag_check(&mut self) {
+        self.flag_deadline = self
+            .bughouse
+            .until_flag(self.now_ms())
+            .map(|ms| Instant::now() + Duration::from_millis(ms + 1));
+    }
+
+    fn check_clocks(&mut self) {
+        self.flag_deadline = None;
+        if self.status != RoomStatus::Running {
+            return;
+        }
+        match self.bughouse.check_flags(self.now_ms()) {
+            Some(result) => self.finish_match(result),
+            None => self.schedule_flag_check(),
+        }
+    }
+
+    // Lance la recherche des IA qui ont le trait et ne cherchent pas déjà
+    fn request_ai_moves(&mut self) {
+        if self.status != RoomStatus::Running {
+            return;
+        }
+        for board in [BoardId::A, BoardId::B] {
+            let seat = Seat {
+                board,
+                color: self.bughouse.board(board).position.turn(),
+            };
+            let Some(profile) = self.ai_seats.get(&seat) else {
+                continue;
+            };
+            if self.thinking.contains(&board) {
+                continue;
+            }
+            let eval = profile.eval();
+            self.thinking.push(board);
+            let bughouse = self.bughouse.clone();
+            let events_tx = self.events_tx.clone();
+            tokio::task::spawn_blocking(move || {
+                let mv = bughouse.best_move(board, eval, AI_DEPTH);
+                let _ = events_tx.send(BughouseEvent::AiMove { seat, mv });
+            });
+        }
+    }
+
+    fn handle_event(&mut self, event: BughouseEvent) {
+        match event {
+            BughouseEvent::AiMove { seat, mv } => {
+                self.thinking.retain(|board| *board != seat.board);
+                // Sans coup, l'IA sera relancée quand une pièce arrivera en poche
+                let Some(mv) = mv.filter(|_| self.status == RoomStatus::Running) else {
+                    return;
+                };
+                if let Err(e) = self.play(seat, &mv) {
+                    eprintln!("Room {}: engine move {} refused: {}", self.id, mv, e);
+                    self.request_ai_moves();
+                }
+            }
+        }
+    }
+
+    /// Ends the match on the result of the board that decided it. Bughouse
+    /// matches are neither rated nor archived.
+    fn finish_match(&mut self, result: BughouseResult) {
+        if self.status == RoomStatus::Finished {
+            return;
+        }
+        self.status = RoomStatus::Finished;
+        // Positions finales, pendules arrêtées
+        for player in self.players.values() {
+            self.send_boards(player);
+        }
+        let description = result.description();
+        self.broadcast(&ServerMessage::GameOver {
+            room_status: self.status,
+            result: description.clone(),
+            rating_changes: Vec::new(),
+            variant_win: None,
+            bughouse: Some(result),
+        });
+        println!("Room {}: {}", self.id, description);
+    }
+
+    fn handle_quit(&mut self, client_id: Uuid) {
+        let Some(player) = self.players.remove(&client_id) else {
+            return;
+        };
+        let _ = send_to_player(&player, &ServerMessage::QuitGame);
+        self.chat.forget(client_id);
+        if let Some(seat) = self.seats.remove(&client_id) {
+            // Quitter en cours de partie fait perdre l'équipe
+            if self.status == RoomStatus::Running {
+                let result = self.bughouse.forfeit(seat, self.now_ms());
+                self.finish_match(result);
+            }
+            self.update_status();
+            self.send_seats();
+            self.broadcast(&ServerMessage::RoomStatus {
+                status: self.status,
+            });
+        }
+        println!("Client {} removed from room {}", client_id, self.id);
+    }
+
+    fn handle_chat(&mut self, client_id: Uuid, channel: ChatChannel, text: String) {
+        let Some(player) = self.players.get(&client_id) else {
+            return;
+        };
+        let text = text.trim();
+        let seat = self.seats.get(&client_id).copied();
+        let error = if text.is_empty() {
+            return;
+        } else if text.chars().count() > MAX_MESSAGE_LEN {
+            Some(format!(
+                "Message too long ({} characters max).",
+                MAX_MESSAGE_LEN
+            ))
+        } else if self.chat.is_muted(client_id) {
+            Some("You are muted in this room.".to_string())
+        } else if channel == ChatChannel::Spectators && seat.is_some() {
+            Some("Only spectators can write in this channel.".to_string())
+        } else if channel == ChatChannel::Team && seat.is_none() {
+            Some("Only seated players can talk to their partner.".to_string())
+        } else {
+            None
+        };
+        if let Some(msg) = error {
+            self.error(client_id, &msg);
+            return;
+        }
+        if !self.chat.allow(client_id, Instant::now()) {
+            self.error(client_id, "You are sending messages too fast.");
+            return;
+        }
+
+        let entry = ChatEntry {
+            channel,
+            author_id: client_id,
+            author: player.name.clone(),
+            text: self.services.chat_filter.censor(text),
+            at: now_timestamp(),
+        };
+        let msg = ServerMessage::Chat {
+            entry: entry.clone(),
+        };
+        let team = seat.map(|s| s.team());
+        for p in self.players.values() {
+            let receiver = self.seats.get(&p.id);
+            let sees = match channel {
+                ChatChannel::Players => true,
+                ChatChannel::Spectators => receiver.is_none(),
+                ChatChannel::Team => receiver.map(|s| s.team()) == team,
+            };
+            if sees {
+                let _ = send_to_player(p, &msg);
+            }
+        }
+        // L'historique ne sert qu'aux nouveaux arrivants, qui n'ont pas à lire les équipes
+        if channel != ChatChannel::Team {
+            self.chat.push(entry);
+        }
+    }
+
+    fn handle_mute(&mut self, client_id: Uuid, target: Uuid, muted: bool) {
+        if self.host != Some(client_id) {
+            self.error(client_id, "Only the host can mute players.");
+            return;
+        }
+        if target == client_id {
+            return;
+        }
+        let Some(name) = self.players.get(&target).map(|p| p.name.clone()) else {
+            return;
+        };
+        self.chat.set_muted(target, muted);
+        let msg = if muted {
+            format!("{} has been muted.", name)
+        } else {
+            format!("{} can talk again.", name)
+        };
+        self.broadcast(&ServerMessage::Info { msg });
+    }
+
+    /// Removes a client at the host's request. Seated players can only be
+    /// kicked before the match starts.
+    fn handle_kick(&mut self, client_id: Uuid, target: Uuid) -> bool {
+        if self.host != Some(client_id) || target == client_id {
+            self.error(client_id, "Only the host can kick players.");
+            return false;
+        }
+        if !self.players.contains_key(&target) {
+            return false;
+        }
+        if matches!(self.status, RoomStatus::Running) && self.seats.contains_key(&target) {
+            self.error(client_id, "Players cannot be kicked during the match.");
+            return false;
+        }
+        if let Some(player) = self.players.remove(&target) {
+            let _ = send_to_player(&player, &ServerMessage::QuitGame);
+            self.seats.remove(&target);
+            self.update_status();
+            self.broadcast(&ServerMessage::Info {
+                msg: format!("{} has been kicked.", player.name),
+            });
+            self.broadcast(&ServerMessage::RoomStatus {
+                status: self.status,
+            });
+            self.send_seats();
+        }
+        self.chat.forget(target);
+        self.banned.push(target);
+        println!("Client {} kicked from room {}", target, self.id);
+        true
+    }
+
+    // Ferme la room si plus aucun humain n'y est, sauf pendant le match
+    fn handle_shutdown(&mut self) -> (Message, bool) {
+        let close = self.status != RoomStatus::Running
+            && (self.players.is_empty() || self.created_at.elapsed() > Duration::from_secs(300));
+        let msg = if close {
+            let clients_id = self.players.keys().copied().collect();
+            for player in self.players.values() {
+                let _ = send_to_player(player, &ServerMessage::QuitGame);
+            }
+            ServerMessage::InternalClose {
+                id: self.id,
+                clients_id,
+            }
+        } else {
+            ServerMessage::Error {
+                msg: "Running".into(),
+            }
+        };
+        (
+            Message::Text(serde_json::to_string(&msg).unwrap().into()),
+            close,
+        )
+    }
+
+    fn lobby_summary(&self) -> RoomSummary {
+        let host = self.host.and_then(|id| self.players.get(&id));
+        RoomSummary {
+            room_id: self.id,
+            mode: GameMode::Bughouse,
+            status: self.status,
+            time_control: self.settings.time_control,
+            rated: false,
+            handicap: None,
+            variant: self.settings.variant,
+            host: host.map_or_else(|| "?".to_string(), |p| p.name.clone()),
+            host_rating: host.and_then(|p| p.account_id).map(|id| {
+                let category = TimeCategory::of(self.settings.time_control);
+                self.services.ratings.get(id, category).rating
+            }),
+            players: self.seats.len() + self.ai_seats.len(),
+            spectators: self.players.len() - self.seats.len(),
+            created_at: self.opened_at,
+        }
+    }
+
+    fn sync_lobby(&self) {
+        if self.settings.public && self.status != RoomStatus::Finished {
+            self.services.lobby.publish(self.lobby_summary());
+        } else {
+            self.services.lobby.remove(self.id);
+        }
+    }
+
+    /// Handles a client command, false once the room must stop.
+    fn handle_command(&mut self, cmd: RoomCommand) -> bool {
+        match cmd {
+            RoomCommand::JoinRoom {
+                client_id,
+                account,
+                sender,
+                response_tx,
+            } => {
+                let msg = self.handle_join(client_id, account, sender);
+                let json = serde_json::to_string(&msg).unwrap();
+                let _ = response_tx.send(Message::Text(json.into()));
+            }
+            RoomCommand::ClientReady {
+                client_id, ready, ..
+            } => self.handle_ready(client_id, ready),
+            RoomCommand::TakeSeat { client_id, seat } => self.handle_take_seat(client_id, seat),
+            RoomCommand::StartGame { client_id } => self.handle_start(client_id),
+            RoomCommand::GetMoves { client_id, mv } => self.handle_get_moves(client_id, mv),
+            RoomCommand::ClientMove { client_id, mv } => self.handle_move(client_id, mv),
+            RoomCommand::PlayerQuit { client_id } => self.handle_quit(client_id),
+            RoomCommand::Chat {
+                client_id,
+                channel,
+                text,
+            } => self.handle_chat(client_id, channel, text),
+            RoomCommand::Mute {
+                client_id,
+                target,
+                muted,
+            } => self.handle_mute(client_id, target, muted),
+            RoomCommand::Kick {
+                client_id,
+                target,
+                response_tx,
+            } => {
+                let kicked = self.handle_kick(client_id, target);
+                let _ = response_tx.send(kicked);
+            }
+            RoomCommand::Shutdown { response_tx } => {
+                let (response, close) = self.handle_shutdown();
+                let _ = response_tx.send(response);
+                return !close;
+            }
+            RoomCommand::AddPiece { client_id, .. }
+            | RoomCommand::Pause { client_id }
+            | RoomCommand::Rematch { client_id, .. }
+            | RoomCommand::SolveMate { client_id, .. }
+            | RoomCommand::SetAnalysis { client_id, .. }
+            | RoomCommand::PlaySandboxPosition { client_id, .. }
+            | RoomCommand::NextPuzzle { client_id } => {
+                self.error(client_id, "Not available in bughouse rooms.")
+            }
+            // Les IA du Bughouse passent par les événements de la room
+            RoomCommand::AiMove
+            | RoomCommand::AIApplyMove { .. }
+            | RoomCommand::StartSandboxGame
+            | RoomCommand::MateSolved { .. } => {}
+        }
+        true
+    }
+
+    pub async fn run(&mut self) {
+        println!("Bughouse room {} awaiting command...", self.id);
+        loop {
+            self.sync_lobby();
+            let deadline = self.flag_deadline;
+            tokio::select! {
+                cmd = self.rx.recv() => match cmd {
+                    Some(cmd) => {
+                        if !self.handle_command(cmd) {
+                            break;
+                        }
+                    }
+                    None => break,
+                },
+                Some(event) = self.events_rx.recv() => self.handle_event(event),
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)),
+                    if deadline.is_some() => self.check_clocks(),
+            }
+        }
+        self.services.lobby.remove(self.id);
+        println!("Room {} stopped", self.id);
+    }
+}
@@ -0,0 +1,144 @@
+use game_lib::{messages::ChatEntry, sharedenums::ChatChannel};
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+pub type SharedChatFilter = Arc<ChatFilter>;
+
+pub const MAX_MESSAGE_LEN: usize = 500;
+// Messages gardés par room pour les nouveaux arrivants
+const HISTORY_LEN: usize = 50;
+// Au plus 5 messages par client sur 10 secondes
+const RATE_LIMIT_COUNT: usize = 5;
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(10);
+
+/// Words masked in chat messages, one per line in the filter file.
+/// Matching is case-insensitive on whole words.
+#[derive(Debug, Default)]
+pub struct ChatFilter {
+    words: Vec<String>,
+}
+
+impl ChatFilter {
+    /// A missing file means no filtering.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let words: Vec<String> = data
+            .lines()
+            .map(|l| l.trim().to_lowercase())
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .collect();
+        println!(
+            "Loaded {} filtered words from {}",
+            words.len(),
+            path.display()
+        );
+        Ok(Self { words })
+    }
+
+    pub fn censor(&self, text: &str) -> String {
+        if self.words.is_empty() {
+            return text.to_string();
+        }
+        text.split_inclusive(|c: char| !c.is_alphanumeric())
+            .map(|chunk| {
+                let word = chunk.trim_end_matches(|c: char| !c.is_alphanumeric());
+                if self.words.contains(&word.to_lowercase()) {
+                    let stars = "*".repeat(word.chars().count());
+                    format!("{}{}", stars, &chunk[word.len()..])
+                } else {
+                    chunk.to_string()
+                }
+            })
+            .collect()
+    }
+}
+
+/// Chat state of a room: recent history, mutes and rate limits.
+#[derive(Debug, Default)]
+pub struct ChatLog {
+    history: VecDeque<ChatEntry>,
+    muted: Vec<Uuid>,
+    sent: HashMap<Uuid, VecDeque<Instant>>,
+}
+
+impl ChatLog {
+    pub fn push(&mut self, entry: ChatEntry) {
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(entry);
+    }
+
+    /// Recent messages of the given channels, oldest first.
+    pub fn recent(&self, channels: &[ChatChannel]) -> Vec<ChatEntry> {
+        self.history
+            .iter()
+            .filter(|e| channels.contains(&e.channel))
+            .cloned()
+            .collect()
+    }
+
+    pub fn is_muted(&self, client_id: Uuid) -> bool {
+        self.muted.contains(&client_id)
+    }
+
+    pub fn set_muted(&mut self, client_id: Uuid, muted: bool) {
+        self.muted.retain(|id| *id != client_id);
+        if muted {
+            self.muted.push(client_id);
+        }
+    }
+
+    /// Records a message from the client, or returns false if it sent too
+    /// many messages recently.
+    pub fn allow(&mut self, client_id: Uuid, now: Instant) -> bool {
+        let sent = self.sent.entry(client_id).or_default();
+        while sent
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > RATE_LIMIT_WINDOW)
+        {
+            sent.pop_front();
+        }
+        if sent.len() >= RATE_LIMIT_COUNT {
+            return false;
+        }
+        sent.push_back(now);
+        true
+    }
+
+    pub fn forget(&mut self, client_id: Uuid) {
+        self.sent.remove(&client_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limit_is_forgotten_with_the_client() {
+        let mut chat = ChatLog::default();
+        let (client, now) = (Uuid::new_v4(), Instant::now());
+        for _ in 0..RATE_LIMIT_COUNT {
+            assert!(chat.allow(client, now));
+        }
+        assert!(!chat.allow(client, now));
+        assert!(chat.allow(client, now + RATE_LIMIT_WINDOW + Duration::from_secs(1)));
+
+        chat.forget(client);
+        assert!(chat.sent.is_empty());
+        assert!(chat.allow(client, now));
+    }
+}
@@ -0,0 +1,202 @@
+use game_lib::fen::FenPosition;
+use game_lib::uci::{from_uci, to_uci};
+use game_lib::variant::{Variant, VariantState};
+use serde::Deserialize;
+use std::{collections::HashMap, fs, path::Path, process::Stdio, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines},
+    process::{ChildStdin, ChildStdout, Command},
+    time::timeout,
+};
+
+pub type SharedEngineRegistry = Arc<EngineRegistry>;
+
+// Marge laissée au moteur au-delà du temps demandé avant d'abandonner
+const ENGINE_GRACE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EngineSpec {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// External UCI engines the server is allowed to run, loaded from a JSON
+/// object `{ "name": { "command": "...", "args": [...] } }`. Clients can
+/// only pick an engine by name, never pass a command.
+#[derive(Debug, Default)]
+pub struct EngineRegistry {
+    engines: HashMap<String, EngineSpec>,
+}
+
+impl EngineRegistry {
+    /// A missing file means no external engine.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let engines: HashMap<String, EngineSpec> = serde_json::from_str(&data)
+            .map_err(|e| format!("Invalid engine list {}: {}", path.display(), e))?;
+        println!(
+            "Loaded {} external engines from {}",
+            engines.len(),
+            path.display()
+        );
+        Ok(Self { engines })
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.engines.contains_key(name)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.engines.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Asks the engine for its move after `moves` (server notation, "e2->e4")
+    /// from `start_fen`, or the initial position. Returns the move in server
+    /// notation, checked against the rules of `variant`.
+    pub async fn best_move(
+        &self,
+        name: &str,
+        variant: Variant,
+        start_fen: Option<&str>,
+        moves: &[String],
+        movetime_ms: u64,
+    ) -> Result<String, String> {
+        let spec = self
+            .engines
+            .get(name)
+            .ok_or_else(|| format!("Unknown engine {}", name))?;
+        let limit = Duration::from_millis(movetime_ms) + ENGINE_GRACE;
+        let mv = timeout(limit, run_uci(spec, variant, start_fen, moves, movetime_ms))
+            .await
+            .map_err(|_| format!("Engine {} timed out", name))??;
+        legal_reply(variant, start_fen, moves, &mv)
+            .ok_or_else(|| format!("Engine {} sent an illegal move: {}", name, mv))
+    }
+}
+
+/// The engine's move as written by `legal_moves`, None if it is illegal
+/// in the position reached by `moves`.
+pub fn legal_reply(
+    variant: Variant,
+    start_fen: Option<&str>,
+    moves: &[String],
+    mv: &str,
+) -> Option<String> {
+    let (mut position, mut state) = match start_fen {
+        Some(fen) => variant.check_start_fen(fen).ok()?,
+        None => (FenPosition::start(), VariantState::default()),
+    };
+    for played in moves {
+        let played = variant.legal_move(&position, &state, played)?;
+        variant.play(&mut position, &mut state, &played);
+    }
+    variant.legal_move(&position, &state, mv)
+}
+
+// Un processus par coup: plus lent, mais aucun état partagé entre les rooms
+async fn run_uci(
+    spec: &EngineSpec,
+    variant: Variant,
+    start_fen: Option<&str>,
+    moves: &[String],
+    movetime_ms: u64,
+) -> Result<String, String> {
+    let mut child = Command::new(&spec.command)
+        .args(&spec.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", spec.command, e))?;
+    let mut stdin = child.stdin.take().ok_or("Engine has no stdin")?;
+    let stdout = child.stdout.take().ok_or("Engine has no stdout")?;
+    let mut lines = BufReader::new(stdout).lines();
+
+    let mut position = match start_fen {
+        Some(fen) => format!("position fen {}", fen),
+        None => "position startpos".to_string(),
+    };
+    if !moves.is_empty() {
+        let moves: Vec<String> = moves.iter().map(|mv| to_uci(mv)).collect();
+        position.push_str(&format!(" moves {}", moves.join(" ")));
+    }
+
+    send_line(&mut stdin, "uci").await?;
+    wait_for(&mut lines, "uciok").await?;
+    // Les moteurs qui ne connaissent pas le variant ignorent l'option
+    if let Some(uci_name) = variant.uci_name() {
+        send_line(
+            &mut stdin,
+            &format!("setoption name UCI_Variant value {}", uci_name),
+        )
+        .await?;
+    }
+    send_line(&mut stdin, "isready").await?;
+    wait_for(&mut lines, "readyok").await?;
+    send_line(&mut stdin, &position).await?;
+    send_line(&mut stdin, &format!("go movetime {}", movetime_ms)).await?;
+    let line = wait_for(&mut lines, "bestmove").await?;
+    let _ = send_line(&mut stdin, "quit").await;
+
+    let best = line
+        .split_whitespace()
+        .nth(1)
+        .ok_or("Engine sent an empty bestmove")?;
+    from_uci(best).ok_or_else(|| format!("Engine sent an invalid move: {}", best))
+}
+
+async fn send_line(stdin: &mut ChildStdin, cmd: &str) -> Result<(), String> {
+    stdin
+        .write_all(format!("{}\n", cmd).as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write to engine: {}", e))
+}
+
+async fn wait_for(
+    lines: &mut Lines<BufReader<ChildStdout>>,
+    prefix: &str,
+) -> Result<String, String> {
+    while let Some(line) = lines.next_line().await.map_err(|e| e.to_string())? {
+        if line.starts_with(prefix) {
+            return Ok(line);
+        }
+    }
+    Err(format!("Engine exited before sending {}", prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(list: &[&str]) -> Vec<String> {
+        list.iter().map(|m| m.to_string()).collect()
+    }
+
+    #[test]
+    fn engine_replies_are_checked_against_the_position() {
+        let played = moves(&["e2->e4", "e7->e5"]);
+        let reply = |mv| legal_reply(Variant::Standard, None, &played, mv);
+        assert_eq!(reply("g1->f3").as_deref(), Some("g1->f3"));
+        assert_eq!(reply("e4->e5"), None);
+        assert_eq!(reply("e7->e6"), None);
+
+        let fen = Some("4k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+        assert_eq!(
+            legal_reply(Variant::Standard, fen, &[], "a7->a8").as_deref(),
+            Some("a7->a8q")
+        );
+        // Hors échecs classiques, le roi peut toucher le roi adverse en atomique
+        let atomic = Some("8/8/8/4k3/r7/4K3/8/8 w - - 0 1");
+        assert!(legal_reply(Variant::Standard, atomic, &[], "e3->e4").is_none());
+        assert!(legal_reply(Variant::Atomic, atomic, &[], "e3->e4").is_some());
+    }
+}
@@ -40,6 +40,7 @@ pub fn send_game_state_to_clients(room: &Room) {
                     turn,
                     counter: room.game.board.counter,
                     incheck: None,
+                    pockets: None,
                 })
                 .unwrap()
                 .into(),
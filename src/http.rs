@@ -0,0 +1,148 @@
+use game_lib::history::GameQuery;
+use std::time::Duration;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use uuid::Uuid;
+
+use crate::{serverstate::MAX_LISTED_GAMES, store::SharedGameStore};
+
+// Les requêtes HTTP de l'API partagent le port du WebSocket
+const API_PREFIX: &str = "GET /api/";
+
+// Délai laissé au client pour envoyer le début de sa requête
+const PEEK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Looks at the first bytes of the connection without consuming them, so that a
+/// WebSocket handshake can still be accepted afterwards. None when the client
+/// sends nothing within `PEEK_TIMEOUT` or the connection fails.
+pub async fn is_api_request(stream: &TcpStream) -> Option<bool> {
+    let prefix = API_PREFIX.as_bytes();
+    let peek = async {
+        let mut buf = [0u8; API_PREFIX.len()];
+        loop {
+            let n = stream.peek(&mut buf).await.ok()?;
+            if n == 0 || !prefix.starts_with(&buf[..n]) {
+                return Some(false);
+            }
+            if n == prefix.len() {
+                return Some(true);
+            }
+            // Début du préfixe seulement: on attend la suite
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    };
+    tokio::time::timeout(PEEK_TIMEOUT, peek)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Serves the read-only history API:
+/// - `GET /api/games?player=&mode=&result=&from=&to=&limit=`
+/// - `GET /api/games/{id}`
+/// - `GET /api/games/{id}/pgn`
+pub async fn handle_api_request(mut stream: TcpStream, store: SharedGameStore) {
+    let mut buf = vec![0u8; 4096];
+    let n = match stream.read(&mut buf).await {
+        Ok(n) => n,
+        Err(e) => {
+            eprintln!("HTTP read error: {}", e);
+            return;
+        }
+    };
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let target = request
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("/");
+    println!("HTTP request: {}", target);
+
+    let (path, params) = target.split_once('?').unwrap_or((target, ""));
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let response = match segments.as_slice() {
+        ["api", "games"] => match store.query(&parse_query(params)) {
+            Ok(games) => json_response(&games),
+            Err(e) => error_response(500, &e),
+        },
+        ["api", "games", id] => match Uuid::parse_str(id).map(|id| store.get(id)) {
+            Ok(Ok(Some(game))) => json_response(&game),
+            Ok(Ok(None)) | Err(_) => error_response(404, "Game not found"),
+            Ok(Err(e)) => error_response(500, &e),
+        },
+        ["api", "games", id, "pgn"] => match Uuid::parse_str(id).map(|id| store.get(id)) {
+            Ok(Ok(Some(game))) => response(
+                200,
+                "application/x-chess-pgn",
+                &format!(
+                    "Content-Disposition: attachment; filename=\"{}.pgn\"\r\n",
+                    id
+                ),
+                &game.pgn,
+            ),
+            Ok(Ok(None)) | Err(_) => error_response(404, "Game not found"),
+            Ok(Err(e)) => error_response(500, &e),
+        },
+        _ => error_response(404, "Not found"),
+    };
+
+    if let Err(e) = stream.write_all(response.as_bytes()).await {
+        eprintln!("HTTP write error: {}", e);
+    }
+    let _ = stream.shutdown().await;
+}
+
+fn parse_query(params: &str) -> GameQuery {
+    let mut query = GameQuery::default();
+    for (key, value) in params.split('&').filter_map(|p| p.split_once('=')) {
+        match key {
+            "player" => query.player = Uuid::parse_str(value).ok(),
+            // Les enums sont sérialisés par leur nom ("AIvsAI", "WhiteWins", ...)
+            "mode" => query.mode = serde_json::from_str(&format!("\"{}\"", value)).ok(),
+            "result" => query.result = serde_json::from_str(&format!("\"{}\"", value)).ok(),
+            "from" => query.from = value.parse().ok(),
+            "to" => query.to = value.parse().ok(),
+            "limit" => query.limit = value.parse().ok(),
+            _ => {}
+        }
+    }
+    query.limit = Some(
+        query
+            .limit
+            .unwrap_or(MAX_LISTED_GAMES)
+            .min(MAX_LISTED_GAMES),
+    );
+    query
+}
+
+fn json_response<T: serde::Serialize>(body: &T) -> String {
+    match serde_json::to_string(body) {
+        Ok(json) => response(200, "application/json", "", &json),
+        Err(e) => error_response(500, &e.to_string()),
+    }
+}
+
+fn error_response(status: u16, msg: &str) -> String {
+    let body = serde_json::json!({ "error": msg }).to_string();
+    response(status, "application/json", "", &body)
+}
+
+fn response(status: u16, content_type: &str, extra_headers: &str, body: &str) -> String {
+    let reason = match status {
+        200 => "OK",
+        404 => "Not Found",
+        _ => "Internal Server Error",
+    };
+    format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\n{}Connection: close\r\n\r\n{}",
+        status,
+        reason,
+        content_type,
+        body.len(),
+        extra_headers,
+        body
+    )
+}
@@ -0,0 +1,154 @@
+use rand::Rng;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::now_timestamp;
+
+// Base32 de Crockford: sans I, L, O ni U pour éviter les confusions
+const ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+pub const CODE_LEN: usize = 6;
+// Un lien d'invitation reste valable 24 heures
+pub const INVITE_TTL: u64 = 24 * 3600;
+
+#[derive(Debug, Clone)]
+struct Invite {
+    room_id: Uuid,
+    expires_at: u64,
+}
+
+/// Short invite codes mapped to room ids.
+#[derive(Debug, Default)]
+pub struct InviteCodes {
+    codes: HashMap<String, Invite>,
+}
+
+impl InviteCodes {
+    /// Returns the code of the room, creating one if it has none yet.
+    /// The expiry is pushed back each time the code is asked for.
+    pub fn create(&mut self, room_id: Uuid) -> (String, u64) {
+        let now = now_timestamp();
+        self.codes.retain(|_, invite| invite.expires_at > now);
+        let expires_at = now + INVITE_TTL;
+
+        if let Some((code, invite)) = self
+            .codes
+            .iter_mut()
+            .find(|(_, invite)| invite.room_id == room_id)
+        {
+            invite.expires_at = expires_at;
+            return (code.clone(), expires_at);
+        }
+
+        let mut rng = rand::thread_rng();
+        let code = loop {
+            let code: String = (0..CODE_LEN)
+                .map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())] as char)
+                .collect();
+            if !self.codes.contains_key(&code) {
+                break code;
+            }
+        };
+        self.codes.insert(
+            code.clone(),
+            Invite {
+                room_id,
+                expires_at,
+            },
+        );
+        (code, expires_at)
+    }
+
+    pub fn resolve(&self, code: &str) -> Option<Uuid> {
+        let invite = self.codes.get(&normalize(code))?;
+        (invite.expires_at > now_timestamp()).then_some(invite.room_id)
+    }
+
+    /// Resolves a code for a join: a code lets a single guest in, the
+    /// host asks for a new one to invite someone else.
+    pub fn consume(&mut self, code: &str) -> Option<Uuid> {
+        let room_id = self.resolve(code)?;
+        self.codes.remove(&normalize(code));
+        Some(room_id)
+    }
+
+    pub fn remove_room(&mut self, room_id: Uuid) {
+        self.codes.retain(|_, invite| invite.room_id != room_id);
+    }
+}
+
+/// Canonical form of a typed code: case-insensitive, hyphens and spaces
+/// ignored, and the ambiguous letters read as the digits they look like.
+pub fn normalize(code: &str) -> String {
+    code.chars()
+        .filter(|c| !matches!(c, '-' | ' '))
+        .map(|c| match c.to_ascii_uppercase() {
+            'I' | 'L' => '1',
+            'O' => '0',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_read_whatever_the_case_and_separators() {
+        assert_eq!(normalize("ab3-x7k"), "AB3X7K");
+        assert_eq!(normalize(" AB3 X7K "), "AB3X7K");
+        // I et L se lisent 1, O se lit 0
+        assert_eq!(normalize("iLo-0l1"), "110011");
+    }
+
+    #[test]
+    fn created_codes_use_the_alphabet_and_are_reused_per_room() {
+        let mut invites = InviteCodes::default();
+        let room = Uuid::new_v4();
+        let (code, expires_at) = invites.create(room);
+        assert_eq!(code.len(), CODE_LEN);
+        assert!(code.bytes().all(|c| ALPHABET.contains(&c)));
+        assert!(expires_at >= now_timestamp() + INVITE_TTL - 1);
+        assert_eq!(invites.create(room).0, code);
+        assert_ne!(invites.create(Uuid::new_v4()).0, code);
+
+        let typed = format!("{}-{}", &code[..3], &code[3..]).to_lowercase();
+        assert_eq!(invites.resolve(&typed), Some(room));
+    }
+
+    #[test]
+    fn expired_codes_are_refused_and_dropped() {
+        let mut invites = InviteCodes::default();
+        let room = Uuid::new_v4();
+        let (code, _) = invites.create(room);
+        invites.codes.get_mut(&code).unwrap().expires_at = now_timestamp() - 1;
+        assert_eq!(invites.resolve(&code), None);
+        assert_eq!(invites.consume(&code), None);
+        // Les codes périmés disparaissent à la création suivante
+        let (fresh, _) = invites.create(room);
+        assert_eq!(invites.codes.len(), 1);
+        assert_eq!(invites.resolve(&fresh), Some(room));
+    }
+
+    #[test]
+    fn a_code_lets_a_single_guest_in() {
+        let mut invites = InviteCodes::default();
+        let room = Uuid::new_v4();
+        let (code, _) = invites.create(room);
+        assert_eq!(invites.consume(&code.to_lowercase()), Some(room));
+        assert_eq!(invites.consume(&code), None);
+        assert_eq!(invites.resolve(&code), None);
+        // L'hôte obtient un nouveau code pour l'invité suivant
+        let (next, _) = invites.create(room);
+        assert_eq!(invites.consume(&next), Some(room));
+    }
+
+    #[test]
+    fn closing_a_room_removes_its_code() {
+        let mut invites = InviteCodes::default();
+        let room = Uuid::new_v4();
+        let (code, _) = invites.create(room);
+        invites.remove_room(room);
+        assert_eq!(invites.resolve(&code), None);
+    }
+}
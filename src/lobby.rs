@@ -0,0 +1,83 @@
+use game_lib::messages::{RoomSummary, ServerMessage};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+pub type SharedLobby = Arc<Lobby>;
+
+#[derive(Debug, Default)]
+struct LobbyInner {
+    rooms: HashMap<Uuid, RoomSummary>,
+    subscribers: HashMap<Uuid, UnboundedSender<Message>>,
+}
+
+/// Public rooms currently open, and the clients watching the list.
+/// Rooms publish their summary themselves; subscribers receive a snapshot
+/// when they subscribe, then only the changes.
+#[derive(Debug, Default)]
+pub struct Lobby {
+    inner: Mutex<LobbyInner>,
+}
+
+impl Lobby {
+    pub fn subscribe(&self, client_id: Uuid, sender: UnboundedSender<Message>) {
+        let mut inner = match self.inner.lock() {
+            Ok(i) => i,
+            Err(_) => return,
+        };
+        let mut rooms: Vec<RoomSummary> = inner.rooms.values().cloned().collect();
+        rooms.sort_by_key(|r| r.created_at);
+        send(&sender, &ServerMessage::LobbySnapshot { rooms });
+        inner.subscribers.insert(client_id, sender);
+    }
+
+    pub fn unsubscribe(&self, client_id: Uuid) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.subscribers.remove(&client_id);
+        }
+    }
+
+    /// Adds or updates a room. Nothing is sent if the summary did not change.
+    pub fn publish(&self, room: RoomSummary) {
+        let mut inner = match self.inner.lock() {
+            Ok(i) => i,
+            Err(_) => return,
+        };
+        let msg = match inner.rooms.get(&room.room_id) {
+            Some(current) if *current == room => return,
+            Some(_) => ServerMessage::LobbyRoomUpdated { room: room.clone() },
+            None => ServerMessage::LobbyRoomAdded { room: room.clone() },
+        };
+        inner.rooms.insert(room.room_id, room);
+        broadcast(&mut inner, &msg);
+    }
+
+    pub fn remove(&self, room_id: Uuid) {
+        let mut inner = match self.inner.lock() {
+            Ok(i) => i,
+            Err(_) => return,
+        };
+        if inner.rooms.remove(&room_id).is_some() {
+            broadcast(&mut inner, &ServerMessage::LobbyRoomRemoved { room_id });
+        }
+    }
+}
+
+fn send(sender: &UnboundedSender<Message>, msg: &ServerMessage) -> bool {
+    match serde_json::to_string(msg) {
+        Ok(json) => sender.send(Message::Text(json.into())).is_ok(),
+        Err(e) => {
+            eprintln!("Failed to serialize lobby message: {}", e);
+            true
+        }
+    }
+}
+
+// Les abonnés dont le canal est fermé sont retirés au passage
+fn broadcast(inner: &mut LobbyInner, msg: &ServerMessage) {
+    inner.subscribers.retain(|_, sender| send(sender, msg));
+}
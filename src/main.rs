@@ -1,10 +1,11 @@
 use futures::{SinkExt, StreamExt};
 use game_lib::automation::ai::{Difficulty, AI};
+use game_lib::crazyhouse::parse_drop;
 use game_lib::game::Game;
 use game_lib::messages::{ClientMessage, ServerMessage};
 use game_lib::piece::Color;
 use game_lib::sharedenums::{GameMode, PlayerRole, RoomStatus};
-use room::RoomCommand;
+use room::{RoomCommand, RoomSettings};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -14,21 +15,58 @@ use tokio::net::TcpListener;
 use tokio::sync::oneshot;
 use tokio::sync::{mpsc, mpsc::UnboundedSender};
 use tokio::time::{interval, Duration, Instant};
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
 use tokio_tungstenite::tungstenite::http::response;
 use tokio_tungstenite::tungstenite::Bytes;
 use tokio_tungstenite::{
-    accept_async,
+    accept_async, accept_hdr_async,
     tungstenite::{Message, Utf8Bytes},
 };
 use utils::{Client, Player};
 use uuid::Uuid;
+mod accounts;
+mod book;
+mod bughouse;
+mod chat;
+mod engines;
 mod handler;
+use accounts::AccountStore;
+use book::{load_opening_book, load_tablebases};
+use chat::ChatFilter;
+use engines::EngineRegistry;
+use game_lib::messages::AccountInfo;
 use handler::*;
+use ratings::RatingStore;
+mod http;
+mod invites;
+mod lobby;
+mod matchmaking;
+mod puzzles;
+mod ratings;
 mod room;
 mod serverstate;
+mod store;
 mod utils;
+use invites::InviteCodes;
+use lobby::Lobby;
+use matchmaking::{Matchmaker, MATCHMAKING_ENABLED};
+use puzzles::PuzzleStore;
 use serverstate::{ServerState, SharedServerState};
 use std::time::{SystemTime, UNIX_EPOCH};
+use store::FileGameStore;
+
+const GAMES_FILE: &str = "games.jsonl";
+const ACCOUNTS_FILE: &str = "accounts.json";
+const RATINGS_FILE: &str = "ratings.json";
+const CHAT_FILTER_FILE: &str = "chat_filter.txt";
+const ENGINES_FILE: &str = "engines.json";
+const BOOK_FILE: &str = "book.bin";
+// Table Random64 de Polyglot, nécessaire pour lire le livre
+const POLYGLOT_KEYS_FILE: &str = "polyglot_random.txt";
+const TABLEBASE_DIR: &str = "syzygy";
+// Puzzles au format CSV de Lichess
+const PUZZLES_FILE: &str = "puzzles.csv";
+const PUZZLE_STATS_FILE: &str = "puzzle_stats.json";
 
 fn now_timestamp() -> u64 {
     SystemTime::now()
@@ -39,9 +77,52 @@ fn now_timestamp() -> u64 {
 
 #[tokio::main]
 async fn main() {
+    let store = match FileGameStore::open(GAMES_FILE) {
+        Ok(store) => store,
+        Err(e) => panic!("Cannot open game archive: {}", e),
+    };
+    let accounts = match AccountStore::open(ACCOUNTS_FILE) {
+        Ok(accounts) => accounts,
+        Err(e) => panic!("Cannot open accounts: {}", e),
+    };
+    let ratings = match RatingStore::open(RATINGS_FILE) {
+        Ok(ratings) => ratings,
+        Err(e) => panic!("Cannot open ratings: {}", e),
+    };
+    let chat_filter = match ChatFilter::load(CHAT_FILTER_FILE) {
+        Ok(filter) => filter,
+        Err(e) => panic!("Cannot load chat filter: {}", e),
+    };
+    let engines = match EngineRegistry::load(ENGINES_FILE) {
+        Ok(engines) => engines,
+        Err(e) => panic!("Cannot load engine list: {}", e),
+    };
+    let book = match load_opening_book(BOOK_FILE, POLYGLOT_KEYS_FILE) {
+        Ok(book) => book.map(Arc::new),
+        Err(e) => panic!("Cannot load opening book: {}", e),
+    };
+    let tablebases = match load_tablebases(TABLEBASE_DIR) {
+        Ok(tablebases) => tablebases.map(Arc::new),
+        Err(e) => panic!("Cannot load tablebases: {}", e),
+    };
+    let puzzles = match PuzzleStore::open(PUZZLES_FILE, PUZZLE_STATS_FILE) {
+        Ok(puzzles) => puzzles,
+        Err(e) => panic!("Cannot load puzzles: {}", e),
+    };
     let state = Arc::new(Mutex::new(ServerState {
         clients: HashMap::new(),
         room_senders: HashMap::new(),
+        store: Arc::new(store),
+        accounts: Arc::new(accounts),
+        ratings: Arc::new(ratings),
+        matchmaker: Matchmaker::default(),
+        lobby: Arc::new(Lobby::default()),
+        invites: InviteCodes::default(),
+        chat_filter: Arc::new(chat_filter),
+        engines: Arc::new(engines),
+        book,
+        tablebases,
+        puzzles: Arc::new(puzzles),
     }));
 
     let listener = TcpListener::bind("0.0.0.0:9001").await.unwrap();
@@ -50,12 +131,39 @@ async fn main() {
     tokio::spawn(cleanup_inactive_rooms(state.clone()));
     tokio::spawn(inactivity_check(state.clone()));
     tokio::spawn(server_ping_loop(state.clone()));
+    if MATCHMAKING_ENABLED {
+        tokio::spawn(matchmaking_loop(state.clone()));
+    }
 
     while let Ok((stream, _)) = listener.accept().await {
         let state = Arc::clone(&state);
 
         tokio::spawn(async move {
-            let ws_stream = match accept_async(stream).await {
+            // Le tri HTTP / WebSocket se fait ici pour ne pas bloquer l'accept
+            match http::is_api_request(&stream).await {
+                Some(true) => {
+                    let store = state.lock().unwrap().store.clone();
+                    http::handle_api_request(stream, store).await;
+                    return;
+                }
+                Some(false) => {}
+                None => return,
+            }
+
+            // Le token de session est passé dans l'URL: ws://host/ws?token=...
+            let mut token = None;
+            // La signature du callback est imposée par tungstenite
+            #[allow(clippy::result_large_err)]
+            let read_token = |req: &Request, resp: Response| {
+                token = req.uri().query().and_then(|query| {
+                    query
+                        .split('&')
+                        .find_map(|p| p.strip_prefix("token="))
+                        .map(|t| t.to_string())
+                });
+                Ok(resp)
+            };
+            let ws_stream = match accept_hdr_async(stream, read_token).await {
                 Ok(ws) => ws,
                 Err(e) => {
                     eprintln!("WebSocket handshake failed: {}", e);
@@ -68,17 +176,38 @@ async fn main() {
             let client_id = Uuid::new_v4();
 
             {
+                let mut state_lock = state.lock().unwrap();
+                let account = token
+                    .as_deref()
+                    .and_then(|t| state_lock.accounts.authenticate(t));
                 let client = Client {
                     id: client_id,
                     room_id: None,
                     sender: tx.clone(),
                     hb: Arc::new(AtomicU64::new(now_timestamp())),
+                    account: account.as_ref().map(|a| a.info()),
+                    session_token: account.and(token),
                 };
                 send_to_client(&client, &ServerMessage::QuitGame);
-                let mut state_lock = state.lock().unwrap();
+                if let (Some(account), Some(token)) = (&client.account, &client.session_token) {
+                    println!(
+                        "Client {} authenticated as {}",
+                        client_id, account.display_name
+                    );
+                    send_to_client(
+                        &client,
+                        &ServerMessage::LoggedIn {
+                            account: account.clone(),
+                            token: token.clone(),
+                        },
+                    );
+                }
                 state_lock.clients.insert(client_id, client);
                 println!("Client with id {} connected!!", client_id);
-                println!("Current clients: {:?}", state_lock.clients);
+                println!(
+                    "Current clients: {:?}",
+                    state_lock.clients.keys().collect::<Vec<_>>()
+                );
             }
 
             // WebSocket sender task
@@ -97,14 +226,43 @@ async fn main() {
                     Ok(Message::Text(text)) => {
                         let parsed: Result<ClientMessage, _> = serde_json::from_str(&text);
                         match parsed {
-                            Ok(ClientMessage::CreateRoom { mode, difficulty }) => {
+                            Ok(ClientMessage::CreateRoom {
+                                mode,
+                                difficulty,
+                                rated,
+                                time_control,
+                                public,
+                                spectator_delay,
+                                white_ai,
+                                black_ai,
+                                handicap,
+                                fen,
+                                variant,
+                                ai_seats,
+                            }) => {
                                 println!(
                                     "Client {} wants to create room in {:?} mode",
                                     client_id, mode
                                 );
                                 let mut server_state = state.lock().unwrap();
 
-                                let msg = server_state.create_room(client_id, mode, difficulty);
+                                let msg = server_state.create_room(
+                                    client_id,
+                                    mode,
+                                    RoomSettings {
+                                        difficulty,
+                                        rated,
+                                        time_control,
+                                        public,
+                                        spectator_delay,
+                                        white_ai,
+                                        black_ai,
+                                        handicap,
+                                        fen,
+                                        variant,
+                                        ai_seats,
+                                    },
+                                );
                                 // Handle room creation logic here.
                                 if let Some(msg) = msg {
                                     println!("Sending after join");
@@ -121,41 +279,69 @@ async fn main() {
                                 println!("Room created successfully");
                             }
                             Ok(ClientMessage::JoinRoom { room_id }) => {
-                                println!("Client {} wants to join room {:?}", client_id, room_id);
-                                let (room, client) = {
-                                    let server_state = state.lock().unwrap();
-                                    let client = server_state
-                                        .clients
-                                        .get(&client_id)
-                                        .map(|client| client.sender.clone());
-                                    let room = server_state.room_senders.get(&room_id).cloned();
-                                    (room, client)
-                                };
-                                if let (Some(room), Some(client)) = (room, client) {
-                                    let msg = join_room(room, client, client_id, room_id).await;
-
-                                    if let Some(msg) = msg {
-                                        if let Err(e) = send_to_client(
-                                            &state.lock().unwrap().clients[&client_id],
-                                            &msg,
-                                        ) {
-                                            eprintln!(
-                                                "Failed to send message to client {}: {}",
-                                                client_id, e
+                                handle_join_room(&state, client_id, room_id).await;
+                            }
+                            Ok(ClientMessage::JoinByCode { code }) => {
+                                let room_id = state.lock().unwrap().invites.consume(&code);
+                                match room_id {
+                                    Some(room_id) => {
+                                        handle_join_room(&state, client_id, room_id).await
+                                    }
+                                    None => {
+                                        let state = state.lock().unwrap();
+                                        if let Some(client) = state.clients.get(&client_id) {
+                                            let _ = send_to_client(
+                                                client,
+                                                &ServerMessage::Error {
+                                                    msg: "Invalid or expired invite code.".into(),
+                                                },
                                             );
                                         }
-                                        if let Some(client) =
-                                            state.lock().unwrap().clients.get_mut(&client_id)
-                                        {
-                                            client.room_id = Some(room_id);
-                                        }
-
-                                        println!("Successfully joined room");
-                                    } else {
-                                        println!("Failed to join room");
                                     }
                                 }
                             }
+                            Ok(ClientMessage::SolveMate { n }) => {
+                                let state = state.lock().unwrap();
+                                state.solve_mate(client_id, n);
+                            }
+                            Ok(ClientMessage::PlaySandboxPosition { side, opponent }) => {
+                                let state = state.lock().unwrap();
+                                state.play_sandbox_position(client_id, side, opponent);
+                            }
+                            Ok(ClientMessage::SetAnalysis { enabled }) => {
+                                let state = state.lock().unwrap();
+                                state.set_analysis(client_id, enabled);
+                            }
+                            Ok(ClientMessage::NextPuzzle) => {
+                                let state = state.lock().unwrap();
+                                state.next_puzzle(client_id);
+                            }
+                            Ok(ClientMessage::OfferRematch) => {
+                                let state = state.lock().unwrap();
+                                state.rematch(client_id, true);
+                            }
+                            Ok(ClientMessage::DeclineRematch) => {
+                                let state = state.lock().unwrap();
+                                state.rematch(client_id, false);
+                            }
+                            Ok(ClientMessage::Chat { channel, text }) => {
+                                let state = state.lock().unwrap();
+                                state.send_chat(client_id, channel, text);
+                            }
+                            Ok(ClientMessage::MutePlayer {
+                                client_id: target,
+                                muted,
+                            }) => {
+                                let state = state.lock().unwrap();
+                                state.mute_player(client_id, target, muted);
+                            }
+                            Ok(ClientMessage::KickPlayer { client_id: target }) => {
+                                kick_player(&state, client_id, target).await;
+                            }
+                            Ok(ClientMessage::CreateInvite) => {
+                                let mut state = state.lock().unwrap();
+                                state.create_invite(client_id);
+                            }
 
                             Ok(ClientMessage::Ready {
                                 state: client_state,
@@ -173,6 +359,29 @@ async fn main() {
                                 let mut server_state = state.lock().unwrap();
                                 server_state.make_move(client_id, mv);
                             }
+                            Ok(ClientMessage::DropPiece { piece, square }) => {
+                                // Un drop passe par le même chemin qu'un coup: "N@e4"
+                                let mv = format!("{}@{}", piece.to_ascii_uppercase(), square);
+                                let mut server_state = state.lock().unwrap();
+                                if parse_drop(&mv).is_some() {
+                                    println!("Drop from client: {}", mv);
+                                    server_state.make_move(client_id, mv);
+                                } else if let Err(e) = send_to_client(
+                                    &server_state.clients[&client_id],
+                                    &ServerMessage::Error {
+                                        msg: format!("Invalid drop {}", mv),
+                                    },
+                                ) {
+                                    eprintln!(
+                                        "Failed to send message to client {}: {}",
+                                        client_id, e
+                                    );
+                                }
+                            }
+                            Ok(ClientMessage::TakeSeat { seat }) => {
+                                let state = state.lock().unwrap();
+                                state.take_seat(client_id, seat);
+                            }
                             Ok(ClientMessage::StartGame) => {
                                 let mut state = state.lock().unwrap();
                                 state.start_game(client_id);
@@ -243,6 +452,81 @@ async fn main() {
                                     state_guard.toggle_pause_game(room_id, client_id);
                                 }
                             }
+                            Ok(ClientMessage::Register {
+                                username,
+                                password,
+                                display_name,
+                            }) => {
+                                // Le hachage Argon2 est coûteux: hors du runtime et sans verrou global
+                                let accounts = state.lock().unwrap().accounts.clone();
+                                let result = tokio::task::spawn_blocking(move || {
+                                    accounts.register(&username, &password, display_name)
+                                })
+                                .await
+                                .unwrap_or_else(|e| Err(format!("Registration failed: {}", e)));
+                                state.lock().unwrap().open_session(client_id, result);
+                            }
+                            Ok(ClientMessage::Login { username, password }) => {
+                                let accounts = state.lock().unwrap().accounts.clone();
+                                let result = tokio::task::spawn_blocking(move || {
+                                    accounts.login(&username, &password)
+                                })
+                                .await
+                                .unwrap_or_else(|e| Err(format!("Login failed: {}", e)));
+                                state.lock().unwrap().open_session(client_id, result);
+                            }
+                            Ok(ClientMessage::Logout) => {
+                                let mut state = state.lock().unwrap();
+                                state.logout(client_id);
+                            }
+                            Ok(ClientMessage::SubscribeLobby) => {
+                                let state = state.lock().unwrap();
+                                state.subscribe_lobby(client_id);
+                            }
+                            Ok(ClientMessage::UnsubscribeLobby) => {
+                                let state = state.lock().unwrap();
+                                state.lobby.unsubscribe(client_id);
+                            }
+                            Ok(ClientMessage::JoinQueue {
+                                time_control,
+                                rated,
+                                rating_range,
+                            }) => {
+                                let mut state = state.lock().unwrap();
+                                state.join_queue(client_id, time_control, rated, rating_range);
+                            }
+                            Ok(ClientMessage::LeaveQueue) => {
+                                let mut state = state.lock().unwrap();
+                                state.leave_queue(client_id);
+                            }
+                            Ok(ClientMessage::ListEngines) => {
+                                let state = state.lock().unwrap();
+                                if let Some(client) = state.clients.get(&client_id) {
+                                    let names = state.engines.names();
+                                    let _ =
+                                        send_to_client(client, &ServerMessage::Engines { names });
+                                }
+                            }
+                            Ok(ClientMessage::GetLeaderboard { category }) => {
+                                let state = state.lock().unwrap();
+                                state.send_leaderboard(client_id, category);
+                            }
+                            Ok(ClientMessage::GetRatingHistory { account_id }) => {
+                                let state = state.lock().unwrap();
+                                state.send_rating_history(client_id, account_id);
+                            }
+                            Ok(ClientMessage::ListGames { query }) => {
+                                let state = state.lock().unwrap();
+                                state.list_games(client_id, query);
+                            }
+                            Ok(ClientMessage::GetGame { game_id }) => {
+                                let state = state.lock().unwrap();
+                                state.get_game(client_id, game_id, false);
+                            }
+                            Ok(ClientMessage::GetPgn { game_id }) => {
+                                let state = state.lock().unwrap();
+                                state.get_game(client_id, game_id, true);
+                            }
                             Ok(ClientMessage::Pong) => {
                                 println!("Client {} sent Pong", client_id);
                                 let mut state = state.lock().unwrap();
@@ -292,6 +576,8 @@ async fn main() {
                 let (maybe_client, maybe_room_sender) = {
                     let mut state_guard = state.lock().unwrap();
 
+                    state_guard.matchmaker.leave(client_id);
+                    state_guard.lobby.unsubscribe(client_id);
                     let client = state_guard.clients.remove(&client_id);
 
                     let room_sender = client
@@ -465,10 +751,84 @@ pub async fn server_ping_loop(state: SharedServerState) {
     }
 }
 
+pub async fn matchmaking_loop(state: SharedServerState) {
+    let mut interval = interval(Duration::from_secs(2));
+    loop {
+        interval.tick().await;
+        let mut state_guard = match state.lock() {
+            Ok(s) => s,
+            Err(_) => continue,
+        };
+        state_guard.run_matchmaking();
+    }
+}
+
+pub async fn kick_player(state: &SharedServerState, client_id: Uuid, target: Uuid) {
+    let (room_id, room) = {
+        let server_state = state.lock().unwrap();
+        let room_id = match server_state.clients.get(&client_id).and_then(|c| c.room_id) {
+            Some(id) => id,
+            None => return,
+        };
+        match server_state.room_senders.get(&room_id) {
+            Some(room) => (room_id, room.clone()),
+            None => return,
+        }
+    };
+    let (response_tx, response_rx) = oneshot::channel();
+    let cmd = RoomCommand::Kick {
+        client_id,
+        target,
+        response_tx,
+    };
+    if room.send(cmd).is_err() {
+        return;
+    }
+    // Le client exclu n'est plus rattaché à la room côté serveur
+    if let Ok(true) = response_rx.await {
+        if let Some(client) = state.lock().unwrap().clients.get_mut(&target) {
+            if client.room_id == Some(room_id) {
+                client.room_id = None;
+            }
+        }
+    }
+}
+
+pub async fn handle_join_room(state: &SharedServerState, client_id: Uuid, room_id: Uuid) {
+    println!("Client {} wants to join room {:?}", client_id, room_id);
+    let (room, client, account) = {
+        let server_state = state.lock().unwrap();
+        let client = server_state.clients.get(&client_id);
+        let account = client.and_then(|c| c.account.clone());
+        let client = client.map(|client| client.sender.clone());
+        let room = server_state.room_senders.get(&room_id).cloned();
+        (room, client, account)
+    };
+    if let (Some(room), Some(client)) = (room, client) {
+        let msg = join_room(room, client, client_id, account, room_id).await;
+
+        if let Some(msg) = msg {
+            // La room envoie elle-même Joined, avant l'état de la partie
+            if let ServerMessage::Joined { .. } = msg {
+                if let Some(client) = state.lock().unwrap().clients.get_mut(&client_id) {
+                    client.room_id = Some(room_id);
+                }
+                println!("Successfully joined room");
+            } else if let Err(e) = send_to_client(&state.lock().unwrap().clients[&client_id], &msg)
+            {
+                eprintln!("Failed to send message to client {}: {}", client_id, e);
+            }
+        } else {
+            println!("Failed to join room");
+        }
+    }
+}
+
 pub async fn join_room(
     room: mpsc::UnboundedSender<RoomCommand>,
     client: UnboundedSender<Message>, // Le type de ton sender
     client_id: Uuid,
+    account: Option<AccountInfo>,
     room_id: Uuid,
 ) -> Option<ServerMessage> {
     // Channel pour la réponse
@@ -476,6 +836,7 @@ pub async fn join_room(
     // Construire la commande à envoyer au RoomActor
     let cmd = RoomCommand::JoinRoom {
         client_id,
+        account,
         sender: client,
         response_tx,
     };
@@ -0,0 +1,229 @@
+use game_lib::sharedenums::TimeControl;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+// La fenêtre de classement s'élargit de 10 points par seconde d'attente
+const WIDEN_PER_SEC: f64 = 10.0;
+pub const MAX_RATING_RANGE: f64 = 1000.0;
+/// Matched pairs need a PvP room, which cannot be opened until rooms run
+/// on a real game. Until then the queue refuses clients.
+pub const MATCHMAKING_ENABLED: bool = false;
+
+#[derive(Debug, Clone)]
+pub struct QueueEntry {
+    pub client_id: Uuid,
+    /// `None` for guests, who can only get casual games.
+    pub account_id: Option<Uuid>,
+    pub time_control: Option<TimeControl>,
+    pub rated: bool,
+    pub rating: f64,
+    /// Initial half-width of the accepted rating window.
+    pub range: f64,
+    pub since: Instant,
+}
+
+impl QueueEntry {
+    /// Current half-width of the window, widened with the waiting time.
+    pub fn window(&self, now: Instant) -> f64 {
+        let waited = now.saturating_duration_since(self.since).as_secs_f64();
+        (self.range + waited * WIDEN_PER_SEC).min(MAX_RATING_RANGE)
+    }
+
+    fn accepts(&self, other: &QueueEntry, now: Instant) -> bool {
+        (self.rating - other.rating).abs() <= self.window(now)
+    }
+
+    fn compatible(&self, other: &QueueEntry, now: Instant) -> bool {
+        self.client_id != other.client_id
+            // Un compte ouvert dans deux onglets ne joue pas contre lui-même
+            && (self.account_id.is_none() || self.account_id != other.account_id)
+            && self.time_control == other.time_control
+            && self.rated == other.rated
+            && self.accepts(other, now)
+            && other.accepts(self, now)
+    }
+}
+
+/// Waiting list of clients looking for a PvP game. Pairing is done
+/// periodically by `matchmaking_loop`, oldest entries first.
+#[derive(Debug, Default)]
+pub struct Matchmaker {
+    queue: Vec<QueueEntry>,
+}
+
+impl Matchmaker {
+    /// Adds a client to the queue, replacing its previous request if any.
+    pub fn enqueue(&mut self, entry: QueueEntry) {
+        self.leave(entry.client_id);
+        self.queue.push(entry);
+    }
+
+    /// Puts back an entry returned by `pair` at its place in the queue, so
+    /// that it keeps its priority.
+    pub fn requeue(&mut self, entry: QueueEntry) {
+        self.leave(entry.client_id);
+        let at = self.queue.partition_point(|e| e.since <= entry.since);
+        self.queue.insert(at, entry);
+    }
+
+    pub fn leave(&mut self, client_id: Uuid) -> bool {
+        let len = self.queue.len();
+        self.queue.retain(|e| e.client_id != client_id);
+        self.queue.len() != len
+    }
+
+    pub fn contains(&self, client_id: Uuid) -> bool {
+        self.queue.iter().any(|e| e.client_id == client_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Removes and returns every pair that can be matched now. Each entry is
+    /// paired with the compatible opponent closest in rating.
+    pub fn pair(&mut self, now: Instant) -> Vec<(QueueEntry, QueueEntry)> {
+        let mut pairs = Vec::new();
+        let mut i = 0;
+        while i < self.queue.len() {
+            let entry = &self.queue[i];
+            let best = self
+                .queue
+                .iter()
+                .enumerate()
+                .skip(i + 1)
+                .filter(|(_, other)| entry.compatible(other, now))
+                .min_by(|(_, a), (_, b)| {
+                    (a.rating - entry.rating)
+                        .abs()
+                        .total_cmp(&(b.rating - entry.rating).abs())
+                })
+                .map(|(j, _)| j);
+
+            match best {
+                Some(j) => {
+                    // j > i: on retire j d'abord pour garder l'indice i valide
+                    let second = self.queue.remove(j);
+                    let first = self.queue.remove(i);
+                    pairs.push((first, second));
+                }
+                None => i += 1,
+            }
+        }
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn entry(rating: f64, range: f64, since: Instant) -> QueueEntry {
+        QueueEntry {
+            client_id: Uuid::new_v4(),
+            account_id: Some(Uuid::new_v4()),
+            time_control: None,
+            rated: true,
+            rating,
+            range,
+            since,
+        }
+    }
+
+    #[test]
+    fn window_widens_with_waiting_time_up_to_the_maximum() {
+        let start = Instant::now();
+        let e = entry(1500.0, 100.0, start);
+        assert_eq!(e.window(start), 100.0);
+        assert_eq!(e.window(start + Duration::from_secs(10)), 200.0);
+        assert_eq!(
+            e.window(start + Duration::from_secs(3600)),
+            MAX_RATING_RANGE
+        );
+    }
+
+    #[test]
+    fn pairs_form_once_both_windows_accept() {
+        let start = Instant::now();
+        let mut matchmaker = Matchmaker::default();
+        matchmaker.enqueue(entry(1500.0, 50.0, start));
+        matchmaker.enqueue(entry(1700.0, 50.0, start));
+        assert!(matchmaker.pair(start).is_empty());
+        assert_eq!(matchmaker.len(), 2);
+        // 15 secondes d'attente: fenêtres de 50 + 15 * 10 = 200
+        let pairs = matchmaker.pair(start + Duration::from_secs(15));
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(matchmaker.len(), 0);
+    }
+
+    #[test]
+    fn closest_rating_is_preferred() {
+        let start = Instant::now();
+        let mut matchmaker = Matchmaker::default();
+        let first = entry(1500.0, 500.0, start);
+        let far = entry(1800.0, 500.0, start);
+        let close = entry(1550.0, 500.0, start);
+        matchmaker.enqueue(first.clone());
+        matchmaker.enqueue(far.clone());
+        matchmaker.enqueue(close.clone());
+        let pairs = matchmaker.pair(start);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].0.client_id, first.client_id);
+        assert_eq!(pairs[0].1.client_id, close.client_id);
+        assert!(matchmaker.contains(far.client_id));
+    }
+
+    #[test]
+    fn time_control_and_rated_must_match() {
+        let start = Instant::now();
+        let mut matchmaker = Matchmaker::default();
+        let blitz = QueueEntry {
+            time_control: Some(TimeControl {
+                initial: 180,
+                increment: 2,
+            }),
+            ..entry(1500.0, 500.0, start)
+        };
+        let casual = QueueEntry {
+            rated: false,
+            ..entry(1500.0, 500.0, start)
+        };
+        matchmaker.enqueue(blitz);
+        matchmaker.enqueue(casual);
+        matchmaker.enqueue(entry(1500.0, 500.0, start));
+        assert!(matchmaker.pair(start).is_empty());
+        matchmaker.enqueue(entry(1510.0, 500.0, start));
+        assert_eq!(matchmaker.pair(start).len(), 1);
+        assert_eq!(matchmaker.len(), 2);
+    }
+
+    #[test]
+    fn nobody_is_paired_with_themselves() {
+        let start = Instant::now();
+        let mut matchmaker = Matchmaker::default();
+        let tab = entry(1500.0, 500.0, start);
+        // Même compte dans un second onglet
+        let other_tab = QueueEntry {
+            client_id: Uuid::new_v4(),
+            ..tab.clone()
+        };
+        matchmaker.enqueue(tab.clone());
+        matchmaker.enqueue(other_tab);
+        assert!(matchmaker.pair(start).is_empty());
+        // Un client qui se remet en file remplace sa demande
+        matchmaker.enqueue(tab);
+        assert_eq!(matchmaker.len(), 2);
+
+        // Les invités n'ont pas de compte: deux invités peuvent jouer
+        let mut guests = Matchmaker::default();
+        for _ in 0..2 {
+            guests.enqueue(QueueEntry {
+                account_id: None,
+                rated: false,
+                ..entry(1500.0, 500.0, start)
+            });
+        }
+        assert_eq!(guests.pair(start).len(), 1);
+    }
+}
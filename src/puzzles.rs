@@ -0,0 +1,287 @@
+use game_lib::{
+    messages::PuzzleStats,
+    puzzle::{Puzzle, CSV_HEADER},
+};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+use crate::ratings::Rating;
+
+pub type SharedPuzzleStore = Arc<PuzzleStore>;
+
+// Le puzzle est tiré parmi les plus proches du classement du joueur
+const PICK_WINDOW: usize = 20;
+
+/// Puzzle progress of a player.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PuzzleProfile {
+    pub rating: Rating,
+    pub streak: u32,
+    pub best_streak: u32,
+    pub solved: u32,
+    pub attempts: u32,
+    /// Puzzles already played, never offered again while others are left.
+    pub seen: HashSet<String>,
+}
+
+impl PuzzleProfile {
+    /// Rates the attempt as a game against the puzzle. Returns the rating
+    /// before the update.
+    pub fn record(&mut self, puzzle: &Puzzle, solved: bool) -> f64 {
+        let before = self.rating.rating;
+        let opponent = Rating {
+            rating: puzzle.rating as f64,
+            deviation: puzzle.deviation as f64,
+            ..Rating::default()
+        };
+        self.rating = self
+            .rating
+            .update(&opponent, if solved { 1.0 } else { 0.0 });
+        self.attempts += 1;
+        if solved {
+            self.solved += 1;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+        } else {
+            self.streak = 0;
+        }
+        self.seen.insert(puzzle.id.clone());
+        before
+    }
+
+    pub fn stats(&self) -> PuzzleStats {
+        PuzzleStats {
+            rating: self.rating.rating,
+            deviation: self.rating.deviation,
+            streak: self.streak,
+            best_streak: self.best_streak,
+            solved: self.solved,
+            attempts: self.attempts,
+        }
+    }
+}
+
+/// Puzzles of the server, read once at startup, and the progress of every
+/// account, persisted as a JSON file rewritten after each attempt.
+#[derive(Debug)]
+pub struct PuzzleStore {
+    /// Sorted by rating.
+    puzzles: Vec<Arc<Puzzle>>,
+    path: PathBuf,
+    profiles: Mutex<HashMap<Uuid, PuzzleProfile>>,
+    /// Number of the last snapshot taken, and of the last one written:
+    /// a slow write never replaces a newer file.
+    snapshots: Mutex<u64>,
+    written: Mutex<u64>,
+}
+
+impl PuzzleStore {
+    /// No puzzle file means puzzle rooms have nothing to offer. Invalid
+    /// lines are skipped with a warning.
+    pub fn open(
+        puzzles_path: impl AsRef<Path>,
+        stats_path: impl Into<PathBuf>,
+    ) -> Result<Self, String> {
+        let puzzles_path = puzzles_path.as_ref();
+        let mut puzzles = Vec::new();
+        if puzzles_path.exists() {
+            let raw = fs::read_to_string(puzzles_path)
+                .map_err(|e| format!("Failed to read {}: {}", puzzles_path.display(), e))?;
+            for (i, line) in raw.lines().enumerate() {
+                if line.trim().is_empty() || line.starts_with(CSV_HEADER) {
+                    continue;
+                }
+                match Puzzle::from_csv(line) {
+                    Ok(puzzle) => puzzles.push(Arc::new(puzzle)),
+                    Err(e) => eprintln!("{}:{}: {}", puzzles_path.display(), i + 1, e),
+                }
+            }
+            println!(
+                "Loaded {} puzzles from {}",
+                puzzles.len(),
+                puzzles_path.display()
+            );
+        }
+        puzzles.sort_by_key(|p| p.rating);
+
+        let path = stats_path.into();
+        let profiles = if path.exists() {
+            let raw = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            serde_json::from_str(&raw)
+                .map_err(|e| format!("Invalid puzzle stats file {}: {}", path.display(), e))?
+        } else {
+            HashMap::new()
+        };
+
+        Ok(Self {
+            puzzles,
+            path,
+            profiles: Mutex::new(profiles),
+            snapshots: Mutex::new(0),
+            written: Mutex::new(0),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.puzzles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.puzzles.is_empty()
+    }
+
+    /// Picks a puzzle close to `rating`, preferring the ones not in `seen`.
+    pub fn pick(&self, rating: f64, seen: &HashSet<String>) -> Option<Arc<Puzzle>> {
+        let mut candidates = self.closest(rating, |p| !seen.contains(&p.id));
+        // Tout a été vu: on recommence depuis le début
+        if candidates.is_empty() {
+            candidates = self.closest(rating, |_| true);
+        }
+        candidates
+            .choose(&mut rand::thread_rng())
+            .map(|p| Arc::clone(p))
+    }
+
+    // Les PICK_WINDOW puzzles retenus les plus proches du classement, en
+    // partant de sa place dans la liste triée
+    fn closest(&self, rating: f64, keep: impl Fn(&Puzzle) -> bool) -> Vec<&Arc<Puzzle>> {
+        let start = self.puzzles.partition_point(|p| (p.rating as f64) < rating);
+        let (mut below, mut above) = (start, start);
+        let mut found = Vec::new();
+        while found.len() < PICK_WINDOW && (below > 0 || above < self.puzzles.len()) {
+            let take_above = below == 0
+                || (above < self.puzzles.len()
+                    && self.puzzles[above].rating as f64 - rating
+                        <= rating - self.puzzles[below - 1].rating as f64);
+            let puzzle = if take_above {
+                above += 1;
+                &self.puzzles[above - 1]
+            } else {
+                below -= 1;
+                &self.puzzles[below]
+            };
+            if keep(puzzle) {
+                found.push(puzzle);
+            }
+        }
+        found
+    }
+
+    pub fn profile(&self, account_id: Uuid) -> PuzzleProfile {
+        match self.profiles.lock() {
+            Ok(profiles) => profiles.get(&account_id).cloned().unwrap_or_default(),
+            Err(_) => PuzzleProfile::default(),
+        }
+    }
+
+    /// Rates an attempt of an account on its stored profile, so that two
+    /// rooms of the same account add up. The file is written in the
+    /// background. Returns the profile and the rating before the attempt.
+    pub fn record_attempt(
+        self: &Arc<Self>,
+        account_id: Uuid,
+        puzzle: &Puzzle,
+        solved: bool,
+    ) -> Result<(PuzzleProfile, f64), String> {
+        let (profile, before, snapshot, raw) = {
+            let mut profiles = self.profiles.lock().map_err(|e| e.to_string())?;
+            let profile = profiles.entry(account_id).or_default();
+            let before = profile.record(puzzle, solved);
+            let profile = profile.clone();
+            let raw = serde_json::to_string(&*profiles)
+                .map_err(|e| format!("Failed to serialize puzzle stats: {}", e))?;
+            let mut snapshots = self.snapshots.lock().map_err(|e| e.to_string())?;
+            *snapshots += 1;
+            (profile, before, *snapshots, raw)
+        };
+        let store = Arc::clone(self);
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = store.write(snapshot, &raw) {
+                eprintln!("Failed to save puzzle stats: {}", e);
+            }
+        });
+        Ok((profile, before))
+    }
+
+    fn write(&self, snapshot: u64, raw: &str) -> Result<(), String> {
+        let mut written = self.written.lock().map_err(|e| e.to_string())?;
+        if *written > snapshot {
+            return Ok(());
+        }
+        fs::write(&self.path, raw)
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))?;
+        *written = snapshot;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_of(ratings: &[u32]) -> PuzzleStore {
+        let puzzles = ratings
+            .iter()
+            .enumerate()
+            .map(|(i, rating)| {
+                Arc::new(Puzzle {
+                    id: i.to_string(),
+                    fen: "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1".into(),
+                    moves: vec!["e2->e4".into(), "e8->e7".into()],
+                    rating: *rating,
+                    deviation: 80,
+                    themes: Vec::new(),
+                })
+            })
+            .collect();
+        PuzzleStore {
+            puzzles,
+            path: PathBuf::new(),
+            profiles: Mutex::new(HashMap::new()),
+            snapshots: Mutex::new(0),
+            written: Mutex::new(0),
+        }
+    }
+
+    #[test]
+    fn picks_the_closest_unseen_puzzles() {
+        let ratings: Vec<u32> = (0..100).map(|i| 1000 + 10 * i).collect();
+        let store = store_of(&ratings);
+        let ids = |found: Vec<&Arc<Puzzle>>| {
+            let mut ids: Vec<u32> = found.iter().map(|p| p.rating).collect();
+            ids.sort();
+            ids
+        };
+        let expected: Vec<u32> = (40..60).map(|i| 1000 + 10 * i).collect();
+        assert_eq!(ids(store.closest(1495.0, |_| true)), expected);
+        // Au bord de la liste, la fenêtre ne déborde pas
+        assert_eq!(ids(store.closest(0.0, |_| true))[19], 1190);
+
+        let seen: HashSet<String> = (0..100)
+            .filter(|i| i % 2 == 0)
+            .map(|i| i.to_string())
+            .collect();
+        for _ in 0..20 {
+            let puzzle = store.pick(1500.0, &seen).unwrap();
+            assert!(!seen.contains(&puzzle.id));
+            assert!((1300..=1700).contains(&puzzle.rating));
+        }
+    }
+
+    #[test]
+    fn everything_seen_starts_over() {
+        let store = store_of(&[1200, 1500]);
+        let seen: HashSet<String> = ["0".to_string(), "1".to_string()].into();
+        assert!(store.pick(1500.0, &seen).is_some());
+        assert!(store.pick(1500.0, &HashSet::new()).is_some());
+        assert!(store_of(&[]).pick(1500.0, &seen).is_none());
+    }
+}
@@ -0,0 +1,338 @@
+use game_lib::{
+    messages::{RatingChange, RatingHistoryEntry},
+    sharedenums::TimeCategory,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    f64::consts::PI,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+use crate::now_timestamp;
+
+pub type SharedRatingStore = Arc<RatingStore>;
+
+// Paramètres Glicko-2 (Glickman, "Example of the Glicko-2 system")
+const DEFAULT_RATING: f64 = 1500.0;
+const DEFAULT_DEVIATION: f64 = 350.0;
+const DEFAULT_VOLATILITY: f64 = 0.06;
+const TAU: f64 = 0.5;
+const SCALE: f64 = 173.7178;
+const EPSILON: f64 = 0.000001;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rating {
+    pub rating: f64,
+    pub deviation: f64,
+    pub volatility: f64,
+    pub games: u32,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Self {
+            rating: DEFAULT_RATING,
+            deviation: DEFAULT_DEVIATION,
+            volatility: DEFAULT_VOLATILITY,
+            games: 0,
+        }
+    }
+}
+
+fn g(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi * phi / (PI * PI)).sqrt()
+}
+
+fn expected(mu: f64, mu_j: f64, phi_j: f64) -> f64 {
+    1.0 / (1.0 + (-g(phi_j) * (mu - mu_j)).exp())
+}
+
+impl Rating {
+    /// Glicko-2 update after a single game (one game = one rating period).
+    /// `score` is 1.0 for a win, 0.5 for a draw and 0.0 for a loss.
+    pub fn update(&self, opponent: &Rating, score: f64) -> Rating {
+        self.update_period(&[(*opponent, score)])
+    }
+
+    /// Glicko-2 update over a rating period with the given games. Without
+    /// games only the deviation grows.
+    pub fn update_period(&self, results: &[(Rating, f64)]) -> Rating {
+        let mu = (self.rating - DEFAULT_RATING) / SCALE;
+        let phi = self.deviation / SCALE;
+        if results.is_empty() {
+            return Rating {
+                deviation: (phi * phi + self.volatility * self.volatility).sqrt() * SCALE,
+                ..*self
+            };
+        }
+        // g(φj), E(µ, µj, φj) et score de chaque partie
+        let games: Vec<(f64, f64, f64)> = results
+            .iter()
+            .map(|(opponent, score)| {
+                let mu_j = (opponent.rating - DEFAULT_RATING) / SCALE;
+                let phi_j = opponent.deviation / SCALE;
+                (g(phi_j), expected(mu, mu_j, phi_j), *score)
+            })
+            .collect();
+        let v = 1.0
+            / games
+                .iter()
+                .map(|&(g_j, e, _)| g_j * g_j * e * (1.0 - e))
+                .sum::<f64>();
+        let improvement: f64 = games.iter().map(|&(g_j, e, score)| g_j * (score - e)).sum();
+        let delta = v * improvement;
+
+        // Nouvelle volatilité, méthode d'Illinois
+        let a = (self.volatility * self.volatility).ln();
+        let f = |x: f64| {
+            let ex = x.exp();
+            ex * (delta * delta - phi * phi - v - ex) / (2.0 * (phi * phi + v + ex).powi(2))
+                - (x - a) / (TAU * TAU)
+        };
+        let mut big_a = a;
+        let mut big_b = if delta * delta > phi * phi + v {
+            (delta * delta - phi * phi - v).ln()
+        } else {
+            let mut k = 1.0;
+            while f(a - k * TAU) < 0.0 {
+                k += 1.0;
+            }
+            a - k * TAU
+        };
+        let mut f_a = f(big_a);
+        let mut f_b = f(big_b);
+        while (big_b - big_a).abs() > EPSILON {
+            let big_c = big_a + (big_a - big_b) * f_a / (f_b - f_a);
+            let f_c = f(big_c);
+            if f_c * f_b <= 0.0 {
+                big_a = big_b;
+                f_a = f_b;
+            } else {
+                f_a /= 2.0;
+            }
+            big_b = big_c;
+            f_b = f_c;
+        }
+        let volatility = (big_a / 2.0).exp();
+
+        let phi_star = (phi * phi + volatility * volatility).sqrt();
+        let new_phi = 1.0 / (1.0 / (phi_star * phi_star) + 1.0 / v).sqrt();
+        let new_mu = mu + new_phi * new_phi * improvement;
+
+        Rating {
+            rating: new_mu * SCALE + DEFAULT_RATING,
+            deviation: new_phi * SCALE,
+            volatility,
+            games: self.games + results.len() as u32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RatingEntry {
+    account_id: Uuid,
+    category: TimeCategory,
+    rating: Rating,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RatingData {
+    ratings: Vec<RatingEntry>,
+    history: Vec<RatingHistoryEntry>,
+}
+
+/// Ratings per account and time category, with the full rating history.
+/// Persisted as a single JSON file rewritten after every rated game.
+#[derive(Debug)]
+pub struct RatingStore {
+    path: PathBuf,
+    data: Mutex<RatingData>,
+}
+
+impl RatingStore {
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let data = if path.exists() {
+            let raw = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            serde_json::from_str(&raw)
+                .map_err(|e| format!("Invalid ratings file {}: {}", path.display(), e))?
+        } else {
+            RatingData::default()
+        };
+
+        Ok(Self {
+            path,
+            data: Mutex::new(data),
+        })
+    }
+
+    pub fn get(&self, account_id: Uuid, category: TimeCategory) -> Rating {
+        let data = match self.data.lock() {
+            Ok(d) => d,
+            Err(_) => return Rating::default(),
+        };
+        find(&data, account_id, category)
+    }
+
+    /// Applies the result of a rated game. `white_score` is White's score
+    /// (1.0, 0.5 or 0.0). Returns the changes for White then Black.
+    pub fn record_game(
+        &self,
+        game_id: Uuid,
+        category: TimeCategory,
+        white: Uuid,
+        black: Uuid,
+        white_score: f64,
+    ) -> Result<Vec<RatingChange>, String> {
+        let mut data = self.data.lock().map_err(|e| e.to_string())?;
+        let white_before = find(&data, white, category);
+        let black_before = find(&data, black, category);
+        let now = now_timestamp();
+
+        let updates = [
+            (
+                white,
+                white_before,
+                white_before.update(&black_before, white_score),
+            ),
+            (
+                black,
+                black_before,
+                black_before.update(&white_before, 1.0 - white_score),
+            ),
+        ];
+
+        let mut changes = Vec::new();
+        for (account_id, before, after) in updates {
+            match data
+                .ratings
+                .iter_mut()
+                .find(|r| r.account_id == account_id && r.category == category)
+            {
+                Some(entry) => entry.rating = after,
+                None => data.ratings.push(RatingEntry {
+                    account_id,
+                    category,
+                    rating: after,
+                }),
+            }
+            data.history.push(RatingHistoryEntry {
+                account_id,
+                category,
+                game_id,
+                rating: after.rating,
+                deviation: after.deviation,
+                at: now,
+            });
+            changes.push(RatingChange {
+                account_id,
+                category,
+                before: before.rating,
+                after: after.rating,
+                deviation: after.deviation,
+            });
+        }
+
+        let raw = serde_json::to_string(&*data)
+            .map_err(|e| format!("Failed to serialize ratings: {}", e))?;
+        fs::write(&self.path, raw)
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))?;
+        Ok(changes)
+    }
+
+    /// Accounts of a category sorted by rating, best first.
+    pub fn ranking(&self, category: TimeCategory) -> Vec<(Uuid, Rating)> {
+        let data = match self.data.lock() {
+            Ok(d) => d,
+            Err(_) => return Vec::new(),
+        };
+        let mut ranking: Vec<(Uuid, Rating)> = data
+            .ratings
+            .iter()
+            .filter(|r| r.category == category)
+            .map(|r| (r.account_id, r.rating))
+            .collect();
+        ranking.sort_by(|a, b| b.1.rating.total_cmp(&a.1.rating));
+        ranking
+    }
+
+    pub fn history(&self, account_id: Uuid) -> Vec<RatingHistoryEntry> {
+        match self.data.lock() {
+            Ok(data) => data
+                .history
+                .iter()
+                .filter(|h| h.account_id == account_id)
+                .cloned()
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+fn find(data: &RatingData, account_id: Uuid, category: TimeCategory) -> Rating {
+    data.ratings
+        .iter()
+        .find(|r| r.account_id == account_id && r.category == category)
+        .map(|r| r.rating)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rating(rating: f64, deviation: f64) -> Rating {
+        Rating {
+            rating,
+            deviation,
+            ..Rating::default()
+        }
+    }
+
+    #[test]
+    fn glickman_worked_example() {
+        // Exemple de l'article de Glickman sur Glicko-2
+        let player = rating(1500.0, 200.0);
+        let updated = player.update_period(&[
+            (rating(1400.0, 30.0), 1.0),
+            (rating(1550.0, 100.0), 0.0),
+            (rating(1700.0, 300.0), 0.0),
+        ]);
+        assert!((updated.rating - 1464.06).abs() < 0.01, "{:?}", updated);
+        assert!((updated.deviation - 151.52).abs() < 0.01, "{:?}", updated);
+        assert!(
+            (updated.volatility - 0.05999).abs() < 0.00001,
+            "{:?}",
+            updated
+        );
+        assert_eq!(updated.games, 3);
+    }
+
+    #[test]
+    fn single_game_update_is_a_one_game_period() {
+        let player = rating(1500.0, 200.0);
+        let opponent = rating(1400.0, 30.0);
+        assert_eq!(
+            player.update(&opponent, 1.0),
+            player.update_period(&[(opponent, 1.0)])
+        );
+        assert!(player.update(&opponent, 1.0).rating > 1500.0);
+        assert!(player.update(&opponent, 0.0).rating < 1500.0);
+    }
+
+    #[test]
+    fn deviation_grows_without_games() {
+        let player = rating(1500.0, 50.0);
+        let idle = player.update_period(&[]);
+        let expected = ((50.0 / SCALE).powi(2) + 0.06f64.powi(2)).sqrt() * SCALE;
+        assert!((idle.deviation - expected).abs() < 1e-9);
+        assert!(idle.deviation > player.deviation);
+        assert_eq!(idle.rating, player.rating);
+        assert_eq!(idle.volatility, player.volatility);
+        assert_eq!(idle.games, 0);
+    }
+}
@@ -1,20 +1,39 @@
 use crate::{
+    book::{SharedOpeningBook, SharedTablebases},
+    chat::{ChatLog, SharedChatFilter, MAX_MESSAGE_LEN},
+    engines::SharedEngineRegistry,
     handler::send_game_state_to_clients,
-    now_timestamp, send_to_player,
-    utils::{Player, PlayerType},
+    lobby::SharedLobby,
+    now_timestamp,
+    puzzles::{PuzzleProfile, SharedPuzzleStore},
+    ratings::SharedRatingStore,
+    send_to_player,
+    store::SharedGameStore,
+    utils::{guest_name, Player, PlayerType},
 };
 
 use game_lib::{
-    automation::ai::{Difficulty, AI},
+    automation::ai::{AiProfile, Difficulty, EvalVariant, AI},
+    automation::eval::evaluate,
+    automation::mate::{MateOutcome, MatePosition, MateSearch, MAX_MATE_DEPTH},
     board,
+    bughouse::Seat,
+    crazyhouse::Pockets,
+    fen::{display_board, FenPosition},
     game::Game,
+    handicap::Handicap,
     piece::Color,
+    puzzle::{Puzzle, PuzzleAttempt, PuzzleStep},
+    variant::{Variant, VariantState, VariantWin},
 };
 use game_lib::{
-    messages::ServerMessage,
-    sharedenums::{GameMode, PlayerRole, RoomStatus},
+    history::{GameRecord, PlayerRecord},
+    messages::{AccountInfo, ChatEntry, RatingChange, RoomSummary, SandboxOpponent, ServerMessage},
+    sharedenums::{
+        ChatChannel, GameMode, GameResult, PlayerRole, RoomStatus, TimeCategory, TimeControl,
+    },
 };
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::{
     sync::{
         mpsc::{self, UnboundedReceiver, UnboundedSender},
@@ -25,6 +44,85 @@ use tokio::{
 use tokio_tungstenite::tungstenite::{http::response, Message};
 use uuid::Uuid;
 
+/// Options chosen by the host when creating the room.
+#[derive(Debug, Clone, Default)]
+pub struct RoomSettings {
+    pub difficulty: Option<Difficulty>,
+    pub rated: bool,
+    pub time_control: Option<TimeControl>,
+    /// Listed in the lobby. Private rooms are only reachable by id.
+    pub public: bool,
+    /// Number of moves spectators lag behind while the game is running.
+    pub spectator_delay: usize,
+    /// Engines of each side, `difficulty` is used when not set.
+    pub white_ai: Option<AiProfile>,
+    pub black_ai: Option<AiProfile>,
+    /// Odds given by one side.
+    pub handicap: Option<Handicap>,
+    /// Custom starting position, checked when the room is created.
+    pub fen: Option<String>,
+    pub variant: Variant,
+    /// Bughouse seats given to the built-in AI.
+    pub ai_seats: Vec<Seat>,
+}
+
+impl RoomSettings {
+    /// Position the game starts from when it is not the usual one.
+    pub fn start_fen(&self) -> Option<String> {
+        self.fen
+            .clone()
+            .or_else(|| self.handicap.as_ref().and_then(Handicap::start_fen))
+    }
+
+    /// Time control of `color`, the giver's own under time odds.
+    pub fn time_control_of(&self, color: Color) -> Option<TimeControl> {
+        match &self.handicap {
+            Some(handicap) => handicap.time_control(color, self.time_control),
+            None => self.time_control,
+        }
+    }
+
+    pub fn first_turn(&self) -> Color {
+        self.start_fen()
+            .and_then(|fen| self.variant.split_fen(&fen).ok())
+            .and_then(|(fen, _)| FenPosition::parse(&fen).ok())
+            .map_or(Color::White, |p| p.turn)
+    }
+
+    /// Variant counters of the starting position.
+    pub fn start_state(&self) -> VariantState {
+        self.start_fen()
+            .and_then(|fen| self.variant.split_fen(&fen).ok())
+            .map(|(_, state)| state)
+            .unwrap_or_default()
+    }
+
+    /// Engine playing the given side, falling back to the room difficulty.
+    pub fn side_ai(&self, role: PlayerRole) -> Option<AiProfile> {
+        let profile = match role {
+            PlayerRole::White => &self.white_ai,
+            PlayerRole::Black => &self.black_ai,
+            _ => return None,
+        };
+        profile
+            .clone()
+            .or_else(|| self.difficulty.clone().map(AiProfile::builtin))
+    }
+}
+
+/// Server-wide stores shared by every room.
+#[derive(Debug, Clone)]
+pub struct RoomServices {
+    pub store: SharedGameStore,
+    pub ratings: SharedRatingStore,
+    pub lobby: SharedLobby,
+    pub chat_filter: SharedChatFilter,
+    pub engines: SharedEngineRegistry,
+    pub book: Option<SharedOpeningBook>,
+    pub tablebases: Option<SharedTablebases>,
+    pub puzzles: SharedPuzzleStore,
+}
+
 #[derive(Debug)]
 pub struct Room {
     pub id: Uuid,
@@ -33,20 +131,50 @@ pub struct Room {
     pub players: HashMap<Uuid, Player>,
     pub game: Game,
     pub created_at: Instant,
+    pub opened_at: u64,
+    /// Client who created the room, `None` for matchmaking rooms.
+    pub host: Option<Uuid>,
+    pub settings: RoomSettings,
+    pub moves: Vec<String>,
+    pub started_at: Option<u64>,
+    /// White and Black as they were when the game started, so that a player
+    /// leaving mid-game is still credited in the archive.
+    lineup: Option<(PlayerRecord, PlayerRecord)>,
     paused: bool,
+    /// Spectator count last sent to the room.
+    spectators_sent: usize,
+    chat: ChatLog,
+    /// Clients kicked by the host, who cannot join again.
+    banned: Vec<Uuid>,
+    /// Players who asked for a rematch since the last game over.
+    rematch_offers: Vec<Uuid>,
+    /// Puzzle being solved in a puzzle room.
+    puzzle: Option<(Arc<Puzzle>, PuzzleAttempt)>,
+    /// Progress of the solver, loaded with the first puzzle. Guests keep
+    /// theirs for the lifetime of the room only.
+    puzzle_profile: Option<PuzzleProfile>,
+    /// Evaluation breakdown sent after every move of the sandbox.
+    analysis: bool,
+    /// Counters of the variant, such as the checks given in Three-check.
+    variant_state: VariantState,
     rx: UnboundedReceiver<RoomCommand>,
     tx: UnboundedSender<RoomCommand>,
+    services: RoomServices,
 }
 
 impl Room {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: Uuid,
         mode: GameMode,
         players: HashMap<Uuid, Player>,
         game: Game,
+        settings: RoomSettings,
         rx: UnboundedReceiver<RoomCommand>,
         tx: UnboundedSender<RoomCommand>,
+        services: RoomServices,
     ) -> Self {
+        let variant_state = settings.start_state();
         Self {
             id,
             mode,
@@ -54,15 +182,1029 @@ impl Room {
             players,
             game,
             created_at: Instant::now(),
+            opened_at: now_timestamp(),
+            host: None,
+            settings,
+            moves: Vec::new(),
+            started_at: None,
+            lineup: None,
             rx,
             tx,
             paused: false,
+            spectators_sent: 0,
+            chat: ChatLog::default(),
+            banned: Vec::new(),
+            rematch_offers: Vec::new(),
+            puzzle: None,
+            puzzle_profile: None,
+            analysis: false,
+            variant_state,
+            services,
+        }
+    }
+
+    fn player_record(&self, role: PlayerRole) -> PlayerRecord {
+        let player = self
+            .players
+            .values()
+            .find(|p| p.role == role)
+            .or_else(|| self.players.values().find(|p| p.role == PlayerRole::Solo));
+        match player {
+            Some(p) => PlayerRecord {
+                id: p.id,
+                account_id: p.account_id,
+                name: p.name.clone(),
+                is_ai: matches!(p.kind, PlayerType::Ai { .. }),
+            },
+            None => PlayerRecord {
+                id: Uuid::nil(),
+                account_id: None,
+                name: "?".to_string(),
+                is_ai: false,
+            },
+        }
+    }
+
+    fn current_lineup(&self) -> (PlayerRecord, PlayerRecord) {
+        match &self.lineup {
+            Some(lineup) => lineup.clone(),
+            None => (
+                self.player_record(PlayerRole::White),
+                self.player_record(PlayerRole::Black),
+            ),
+        }
+    }
+
+    // Sauvegarde la partie terminée et met à jour les classements si elle est classée
+    fn finish_game(&self, result: GameResult, reason: String) -> Vec<RatingChange> {
+        let (white, black) = self.current_lineup();
+        let mut record = GameRecord {
+            id: Uuid::new_v4(),
+            room_id: self.id,
+            mode: self.mode.clone(),
+            white,
+            black,
+            ai_profile: self.settings.difficulty.clone(),
+            white_ai: self.settings.white_ai.clone(),
+            black_ai: self.settings.black_ai.clone(),
+            rated: self.settings.rated,
+            time_control: self.settings.time_control,
+            handicap: self.settings.handicap,
+            start_fen: self.settings.fen.clone(),
+            variant: self.settings.variant,
+            moves: self.moves.clone(),
+            result,
+            reason,
+            started_at: self.started_at.unwrap_or_else(now_timestamp),
+            ended_at: now_timestamp(),
+            pgn: String::new(),
+        };
+        record.pgn = record.to_pgn();
+        let rating_changes = self.rate_game(&record);
+
+        match self.services.store.save(record) {
+            Ok(()) => println!("Room {} game archived", self.id),
+            Err(e) => eprintln!("Failed to archive game of room {}: {}", self.id, e),
+        }
+        rating_changes
+    }
+
+    fn rate_game(&self, record: &GameRecord) -> Vec<RatingChange> {
+        if !self.settings.rated {
+            return Vec::new();
+        }
+        let (white, black) = match (record.white.account_id, record.black.account_id) {
+            (Some(white), Some(black)) if white != black => (white, black),
+            _ => return Vec::new(),
+        };
+        let white_score = match record.result {
+            GameResult::WhiteWins => 1.0,
+            GameResult::BlackWins => 0.0,
+            GameResult::Draw => 0.5,
+            GameResult::Aborted => return Vec::new(),
+        };
+
+        let category = TimeCategory::of(self.settings.time_control);
+        match self
+            .services
+            .ratings
+            .record_game(record.id, category, white, black, white_score)
+        {
+            Ok(changes) => changes,
+            Err(e) => {
+                eprintln!("Failed to update ratings of room {}: {}", self.id, e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn spectator_count(&self) -> usize {
+        self.players
+            .values()
+            .filter(|p| p.role == PlayerRole::Spectator)
+            .count()
+    }
+
+    fn broadcast_spectator_count(&mut self) {
+        let count = self.spectator_count();
+        if count != self.spectators_sent {
+            self.spectators_sent = count;
+            for player in self.players.values() {
+                let _ = send_to_player(player, &ServerMessage::SpectatorCount { count });
+            }
+        }
+    }
+
+    /// Spectators that must not receive live positions.
+    fn is_delayed_spectator(&self, player: &Player) -> bool {
+        player.role == PlayerRole::Spectator
+            && self.settings.spectator_delay > 0
+            && self.status != RoomStatus::Finished
+    }
+
+    // Coups visibles par les spectateurs: tout une fois la partie finie
+    fn spectator_moves(&self) -> &[String] {
+        if self.status == RoomStatus::Finished {
+            return &self.moves;
+        }
+        let visible = self
+            .moves
+            .len()
+            .saturating_sub(self.settings.spectator_delay);
+        &self.moves[..visible]
+    }
+
+    // Position rejouée depuis le départ de la partie
+    fn replay(&self, moves: &[String]) -> (FenPosition, VariantState) {
+        let variant = self.settings.variant;
+        let mut state = self.settings.start_state();
+        let mut replayed = self
+            .settings
+            .start_fen()
+            .and_then(|fen| variant.split_fen(&fen).ok())
+            .and_then(|(fen, _)| FenPosition::parse(&fen).ok())
+            .unwrap_or_else(FenPosition::start);
+        for mv in moves {
+            let Some(mv) = variant.legal_move(&replayed, &state, mv) else {
+                eprintln!("Room {}: cannot replay {}", self.id, mv);
+                break;
+            };
+            variant.play(&mut replayed, &mut state, &mv);
+        }
+        (replayed, state)
+    }
+
+    // Plateau rejoué, pour les spectateurs en différé
+    fn position_after(&self, moves: &[String]) -> (Vec<Vec<Option<String>>>, VariantState) {
+        let (replayed, state) = self.replay(moves);
+        let mut board = display_board(&replayed);
+        if self.settings.variant == Variant::Crazyhouse {
+            game_lib::crazyhouse::mark_promoted(&mut board, state.promoted);
+        }
+        (board, state)
+    }
+
+    // Plateau envoyé aux joueurs, pièces promues marquées en Crazyhouse
+    fn display_board(&self) -> Vec<Vec<Option<String>>> {
+        /*
+        let mut board = self.game.board.export_display_board();
+        if self.settings.variant == Variant::Crazyhouse {
+            game_lib::crazyhouse::mark_promoted(&mut board, self.variant_state.promoted);
+        }
+        board
+        */
+        Vec::new()
+    }
+
+    // Poches jointes aux positions envoyées, en Crazyhouse seulement
+    fn pockets(&self, state: &VariantState) -> Option<Pockets> {
+        (self.settings.variant == Variant::Crazyhouse).then_some(state.pockets)
+    }
+
+    fn snapshot(&self) -> ServerMessage {
+        let moves = self.spectator_moves().to_vec();
+        let (white, black) = self.current_lineup();
+        ServerMessage::Snapshot {
+            room_status: self.status,
+            gamemod: self.mode.clone(),
+            board: self.position_after(&moves).0,
+            turn: side_to_move(moves.len(), self.settings.first_turn()),
+            moves,
+            white,
+            black,
+            time_control: self.settings.time_control,
+            white_time_control: self.settings.time_control_of(Color::White),
+            black_time_control: self.settings.time_control_of(Color::Black),
+            handicap: self.settings.handicap,
+            variant: self.settings.variant,
+            spectators: self.spectator_count(),
+            spectator_delay: self.settings.spectator_delay,
+        }
+    }
+
+    /// Sends the delayed position to spectators after a move.
+    fn send_delayed_state(&self) {
+        if self.settings.spectator_delay == 0 {
+            return;
+        }
+        let moves = self.spectator_moves();
+        let (board, state) = self.position_after(moves);
+        let msg = ServerMessage::State {
+            board,
+            turn: side_to_move(moves.len(), self.settings.first_turn()),
+            counter: moves.len(),
+            incheck: None,
+            pockets: self.pockets(&state),
+        };
+        for player in self.players.values() {
+            if self.is_delayed_spectator(player) {
+                let _ = send_to_player(player, &msg);
+            }
+        }
+    }
+
+    fn chat_channels(role: &PlayerRole) -> &'static [ChatChannel] {
+        match role {
+            PlayerRole::Spectator => &[ChatChannel::Players, ChatChannel::Spectators],
+            _ => &[ChatChannel::Players],
+        }
+    }
+
+    fn chat_error(&self, client_id: Uuid, msg: &str) {
+        if let Some(player) = self.players.get(&client_id) {
+            let _ = send_to_player(player, &ServerMessage::Error { msg: msg.into() });
+        }
+    }
+
+    fn handle_chat(&mut self, client_id: Uuid, channel: ChatChannel, text: String) {
+        let player = match self.players.get(&client_id) {
+            Some(p) => p,
+            None => return,
+        };
+        let text = text.trim();
+        let spectator = player.role == PlayerRole::Spectator;
+        let in_game = matches!(self.status, RoomStatus::Running | RoomStatus::Paused);
+
+        let error = if text.is_empty() {
+            return;
+        } else if text.chars().count() > MAX_MESSAGE_LEN {
+            Some(format!(
+                "Message too long ({} characters max).",
+                MAX_MESSAGE_LEN
+            ))
+        } else if self.chat.is_muted(client_id) {
+            Some("You are muted in this room.".to_string())
+        } else if channel == ChatChannel::Spectators && !spectator {
+            Some("Only spectators can write in this channel.".to_string())
+        } else if channel == ChatChannel::Team {
+            Some("Team chat is only available in bughouse rooms.".to_string())
+        } else if channel == ChatChannel::Players && spectator && self.settings.rated && in_game {
+            // Pas d'aide extérieure pendant une partie classée
+            Some("Spectators cannot talk to players during a rated game.".to_string())
+        } else {
+            None
+        };
+        if let Some(msg) = error {
+            self.chat_error(client_id, &msg);
+            return;
+        }
+        if !self.chat.allow(client_id, Instant::now()) {
+            self.chat_error(client_id, "You are sending messages too fast.");
+            return;
+        }
+
+        let entry = ChatEntry {
+            channel,
+            author_id: client_id,
+            author: player.name.clone(),
+            text: self.services.chat_filter.censor(text),
+            at: now_timestamp(),
+        };
+        let msg = ServerMessage::Chat {
+            entry: entry.clone(),
+        };
+        for p in self.players.values() {
+            if Self::chat_channels(&p.role).contains(&channel) {
+                let _ = send_to_player(p, &msg);
+            }
+        }
+        self.chat.push(entry);
+    }
+
+    fn handle_mute(&mut self, client_id: Uuid, target: Uuid, muted: bool) {
+        if self.host != Some(client_id) {
+            self.chat_error(client_id, "Only the host can mute players.");
+            return;
+        }
+        if target == client_id || !self.players.contains_key(&target) {
+            return;
+        }
+        self.chat.set_muted(target, muted);
+        let name = self.players[&target].name.clone();
+        let info = if muted {
+            format!("{} has been muted.", name)
+        } else {
+            format!("{} can talk again.", name)
+        };
+        for p in self.players.values() {
+            let _ = send_to_player(p, &ServerMessage::Info { msg: info.clone() });
+        }
+    }
+
+    /// Removes a client at the host's request. Players can only be kicked
+    /// before the game starts.
+    fn handle_kick(&mut self, client_id: Uuid, target: Uuid) -> bool {
+        if self.host != Some(client_id) || target == client_id {
+            self.chat_error(client_id, "Only the host can kick players.");
+            return false;
+        }
+        let in_game = matches!(self.status, RoomStatus::Running | RoomStatus::Paused);
+        match self.players.get(&target) {
+            Some(p) if in_game && p.role != PlayerRole::Spectator => {
+                self.chat_error(client_id, "Players cannot be kicked during the game.");
+                return false;
+            }
+            Some(_) => {}
+            None => return false,
+        }
+
+        if let Some(player) = self.players.remove(&target) {
+            let _ = send_to_player(&player, &ServerMessage::QuitGame);
+            // La place de l'adversaire se libère
+            if player.role != PlayerRole::Spectator && self.status == RoomStatus::WaitingReady {
+                self.status = RoomStatus::WaitingPlayers;
+            }
+            let info = ServerMessage::Info {
+                msg: format!("{} has been kicked.", player.name),
+            };
+            for p in self.players.values() {
+                let _ = send_to_player(p, &info);
+                let _ = send_to_player(
+                    p,
+                    &ServerMessage::RoomStatus {
+                        status: self.status,
+                    },
+                );
+            }
+        }
+        self.chat.forget(target);
+        self.banned.push(target);
+        println!("Client {} kicked from room {}", target, self.id);
+        true
+    }
+
+    fn handle_solve_mate(&self, client_id: Uuid, n: u8) {
+        let error = if self.mode != GameMode::Sandbox {
+            Some("Mate search is only available in sandbox rooms.".to_string())
+        } else if !(1..=MAX_MATE_DEPTH).contains(&n) {
+            Some(format!(
+                "Mate depth must be between 1 and {}.",
+                MAX_MATE_DEPTH
+            ))
+        } else {
+            None
+        };
+        let position = match error {
+            Some(msg) => Err(msg),
+            None => self.sandbox_position(),
+        };
+        match position {
+            Ok(position) => {
+                // La recherche peut prendre plusieurs secondes: hors de la boucle de la room
+                let tx = self.tx.clone();
+                tokio::task::spawn_blocking(move || {
+                    let outcome = MateSearch::default().solve(&position, n);
+                    let _ = tx.send(RoomCommand::MateSolved {
+                        client_id,
+                        n,
+                        outcome,
+                    });
+                });
+            }
+            Err(msg) => {
+                if let Some(player) = self.players.get(&client_id) {
+                    let _ = send_to_player(player, &ServerMessage::Error { msg });
+                }
+            }
+        }
+    }
+
+    // Position du bac à sable, pour le mat et l'évaluation: celle de départ
+    // de la room suivie des coups joués
+    fn sandbox_position(&self) -> Result<FenPosition, String> {
+        Ok(self.replay(&self.moves).0)
+        /*
+        FenPosition::parse(&self.game.board.to_fen())
+        */
+    }
+
+    fn handle_set_analysis(&mut self, client_id: Uuid, enabled: bool) {
+        let Some(player) = self.players.get(&client_id) else {
+            return;
+        };
+        let error = if self.mode != GameMode::Sandbox {
+            Some("Analysis is only available in sandbox rooms.".to_string())
+        } else if enabled {
+            self.sandbox_position().err()
+        } else {
+            None
+        };
+        if let Some(msg) = error {
+            let _ = send_to_player(player, &ServerMessage::Error { msg });
+            return;
+        }
+        if !enabled {
+            let msg = "Analysis disabled.".to_string();
+            let _ = send_to_player(player, &ServerMessage::Info { msg });
+        }
+        self.analysis = enabled;
+        self.send_evaluation();
+    }
+
+    // Explication de l'évaluation statique pour le joueur du bac à sable
+    fn send_evaluation(&self) {
+        if !self.analysis {
+            return;
+        }
+        let Ok(position) = self.sandbox_position() else {
+            return;
+        };
+        let msg = ServerMessage::Evaluation {
+            breakdown: evaluate(&position, EvalVariant::Standard),
+        };
+        for player in self.players.values() {
+            let _ = send_to_player(player, &msg);
+        }
+    }
+
+    // Compteurs du variant, pour ceux qui voient la position courante
+    fn send_variant_status(&self) {
+        if self.settings.variant == Variant::Standard {
+            return;
+        }
+        let msg = ServerMessage::VariantStatus {
+            variant: self.settings.variant,
+            state: self.variant_state,
+        };
+        for player in self
+            .players
+            .values()
+            .filter(|p| !self.is_delayed_spectator(p))
+        {
+            let _ = send_to_player(player, &msg);
+        }
+    }
+
+    /// Ends the game won by `winner` on a rule of the room's variant.
+    fn finish_variant_game(&mut self, winner: Color, win: VariantWin) {
+        let result = format!("{:?} wins: {}.", winner, win.description());
+        let rating_changes = self.finish_game(GameResult::winner(winner), result.clone());
+        self.status = RoomStatus::Finished;
+        let msg = ServerMessage::GameOver {
+            room_status: self.status,
+            result,
+            rating_changes,
+            variant_win: Some(win),
+            bughouse: None,
+        };
+        for player in self.players.values() {
+            let _ = send_to_player(player, &msg);
+        }
+        self.reveal_to_spectators();
+        println!("Room {}: {:?} wins, {}", self.id, winner, win.description());
+    }
+
+    // Coup d'IA vérifié sur la position courante; s'il est illégal, la
+    // recherche intégrée rejoue à sa place
+    fn checked_ai_move(&self, mv: &str) -> Option<String> {
+        let variant = self.settings.variant;
+        let (position, state) = self.replay(&self.moves);
+        if let Some(mv) = variant.legal_move(&position, &state, mv) {
+            return Some(mv);
+        }
+        eprintln!(
+            "Room {}: illegal engine move {}, built-in search plays instead",
+            self.id, mv
+        );
+        if let Some(fallback) = variant.best_move(&position, &state, EvalVariant::default(), 2) {
+            let _ = self.tx.send(RoomCommand::AIApplyMove { mv: fallback });
+        }
+        None
+    }
+
+    /// Ends the game if the last move decided it, on the rules of the
+    /// room's variant: variant win, mate, stalemate or the fifty-move rule.
+    fn finish_if_over(&mut self) -> bool {
+        let variant = self.settings.variant;
+        let (position, state) = self.replay(&self.moves);
+        if let Some((winner, win)) = variant.winner(&position, &state) {
+            self.finish_variant_game(winner, win);
+            return true;
+        }
+        let outcome = variant
+            .result_without_moves(&position, &state)
+            .or_else(|| (position.halfmove >= 100).then_some(GameResult::Draw));
+        let Some(outcome) = outcome else {
+            return false;
+        };
+        let result = match outcome {
+            GameResult::Draw => "Draw!".to_string(),
+            _ => format!("Checkmate! {:?} wins.", position.turn.opposite()),
+        };
+        let rating_changes = self.finish_game(outcome, result.clone());
+        self.status = RoomStatus::Finished;
+        let msg = ServerMessage::GameOver {
+            room_status: self.status,
+            result: result.clone(),
+            rating_changes,
+            variant_win: None,
+            bughouse: None,
+        };
+        for player in self.players.values() {
+            let _ = send_to_player(player, &msg);
+        }
+        self.reveal_to_spectators();
+        println!("Room {}: game over, {}", self.id, result);
+        true
+    }
+
+    fn handle_play_sandbox(&mut self, client_id: Uuid, side: Color, opponent: SandboxOpponent) {
+        let Some(player) = self.players.get(&client_id) else {
+            return;
+        };
+        let error = if self.mode != GameMode::Sandbox {
+            Some("Only sandbox positions can be turned into a game.".to_string())
+        } else if self.host != Some(client_id) {
+            Some("Only the host can start a game from the sandbox.".to_string())
+        } else {
+            match &opponent {
+                SandboxOpponent::Ai {
+                    profile: AiProfile::External { name, .. },
+                } if !self.services.engines.contains(name) => {
+                    Some(format!("Unknown engine: {}", name))
+                }
+                _ => None,
+            }
+        };
+        // La position posée doit être jouable: rois, roques, échecs
+        let fen = match error {
+            Some(msg) => Err(msg),
+            None => self.sandbox_position().and_then(|position| {
+                let fen = position.to_fen();
+                game_lib::fen::check_start_fen(&fen).map(|_| fen)
+            }),
+        };
+        match fen {
+            Ok(fen) => self.start_from_sandbox(client_id, side, opponent, fen),
+            Err(msg) => {
+                let _ = send_to_player(player, &ServerMessage::Error { msg });
+            }
+        }
+    }
+
+    /// Turns the sandbox into a game starting from `fen`, the host playing
+    /// `side`. Against a human, the room waits for the opponent to join.
+    fn start_from_sandbox(
+        &mut self,
+        client_id: Uuid,
+        side: Color,
+        opponent: SandboxOpponent,
+        fen: String,
+    ) {
+        self.settings.fen = Some(fen);
+        self.settings.handicap = None;
+        self.moves.clear();
+        self.analysis = false;
+        self.variant_state = self.settings.start_state();
+        self.rematch_offers.clear();
+        if let Some(player) = self.players.get_mut(&client_id) {
+            player.role = match side {
+                Color::White => PlayerRole::White,
+                Color::Black => PlayerRole::Black,
+            };
+            player.ready = true;
+        }
+        match opponent {
+            SandboxOpponent::Ai { profile } => {
+                /*
+                let ai_id = Uuid::new_v4();
+                self.players.insert(
+                    ai_id,
+                    Player {
+                        id: ai_id,
+                        account_id: None,
+                        name: profile.label(),
+                        role: match side {
+                            Color::White => PlayerRole::Black,
+                            Color::Black => PlayerRole::White,
+                        },
+                        ready: true,
+                        sender: None,
+                        kind: PlayerType::Ai {
+                            ai: AI::from_profile(&profile, side.opposite()),
+                            profile: profile.clone(),
+                        },
+                    },
+                );
+                */
+                match side {
+                    Color::White => self.settings.black_ai = Some(profile),
+                    Color::Black => self.settings.white_ai = Some(profile),
+                }
+                self.mode = GameMode::PlayerVsAI;
+                self.status = RoomStatus::Running;
+                self.started_at = Some(now_timestamp());
+                self.lineup = Some(self.current_lineup());
+            }
+            SandboxOpponent::Human => {
+                self.mode = GameMode::PlayerVsPlayer;
+                self.status = RoomStatus::WaitingPlayers;
+            }
+        }
+
+        for player in self.players.values() {
+            let _ = send_to_player(
+                player,
+                &ServerMessage::GameModeChanged {
+                    gamemod: self.mode.clone(),
+                    role: player.role.clone(),
+                    room_status: self.status,
+                },
+            );
+            /*
+            if self.status == RoomStatus::Running {
+                let _ = send_to_player(
+                    player,
+                    &ServerMessage::GameStarted {
+                        room_status: self.status,
+                        board: self.game.board.export_display_board(),
+                        turn: self.game.board.turn,
+                        white_time_control: self.settings.time_control_of(Color::White),
+                        black_time_control: self.settings.time_control_of(Color::Black),
+                    },
+                );
+            }
+            */
+        }
+        self.send_variant_status();
+        if self.status == RoomStatus::Running && self.ai_moves_first() {
+            let _ = self.tx.send(RoomCommand::AiMove);
+        }
+        println!(
+            "Room {} sandbox position now played as {:?}",
+            self.id, self.mode
+        );
+    }
+
+    // Couleur encore libre dans une partie entre joueurs
+    fn free_seat(&self) -> Option<PlayerRole> {
+        [PlayerRole::White, PlayerRole::Black]
+            .into_iter()
+            .find(|seat| self.players.values().all(|p| p.role != *seat))
+    }
+
+    fn handle_next_puzzle(&mut self, client_id: Uuid) {
+        let account_id = match self.players.get(&client_id) {
+            Some(p) if self.mode == GameMode::Puzzle => p.account_id,
+            Some(_) => {
+                self.chat_error(client_id, "This is not a puzzle room.");
+                return;
+            }
+            None => return,
+        };
+        // Passer un puzzle en cours compte comme un échec
+        if self
+            .puzzle
+            .as_ref()
+            .is_some_and(|(_, attempt)| !attempt.is_finished())
+        {
+            self.finish_puzzle(client_id, false);
+        }
+
+        // Le profil d'un compte est relu: une autre room a pu le faire avancer
+        let services = &self.services;
+        if let Some(id) = account_id {
+            self.puzzle_profile = Some(services.puzzles.profile(id));
+        }
+        let profile = self
+            .puzzle_profile
+            .get_or_insert_with(PuzzleProfile::default);
+        let puzzle = match services.puzzles.pick(profile.rating.rating, &profile.seen) {
+            Some(puzzle) => puzzle,
+            None => {
+                self.chat_error(client_id, "No puzzles available on this server.");
+                return;
+            }
+        };
+        let stats = profile.stats();
+
+        /*
+        self.game = match Game::from_fen(&puzzle.fen) {
+            Ok(game) => game,
+            Err(e) => {
+                eprintln!("Room {}: invalid puzzle {}: {}", self.id, puzzle.id, e);
+                self.chat_error(client_id, "This puzzle could not be loaded.");
+                return;
+            }
+        };
+        */
+        self.moves.clear();
+        self.started_at = Some(now_timestamp());
+        self.status = RoomStatus::Running;
+        println!("Room {} puzzle {} ({})", self.id, puzzle.id, puzzle.rating);
+        if let Some(player) = self.players.get(&client_id) {
+            let _ = send_to_player(
+                player,
+                &ServerMessage::PuzzleStarted {
+                    puzzle: puzzle.info(),
+                    stats,
+                },
+            );
+        }
+        // Le coup de l'adversaire qui amène la position du puzzle
+        let setup_move = puzzle.moves[0].clone();
+        self.puzzle = Some((puzzle, PuzzleAttempt::default()));
+        self.play_puzzle_move(&setup_move);
+    }
+
+    fn handle_puzzle_move(&mut self, client_id: Uuid, mv: String) {
+        let step = match &mut self.puzzle {
+            Some((puzzle, attempt)) if !attempt.is_finished() => attempt.play(puzzle, &mv),
+            _ => {
+                self.chat_error(client_id, "No puzzle in progress.");
+                return;
+            }
+        };
+        let correct = step != PuzzleStep::Failed;
+        // Un mauvais coup n'est pas joué: le plateau reste sur la position du puzzle
+        if correct {
+            self.play_puzzle_move(&mv);
+        }
+        let reply = match &step {
+            PuzzleStep::Continue { reply } => Some(reply.clone()),
+            _ => None,
+        };
+        if let Some(player) = self.players.get(&client_id) {
+            let _ = send_to_player(
+                player,
+                &ServerMessage::PuzzleMove {
+                    mv,
+                    correct,
+                    reply: reply.clone(),
+                },
+            );
+        }
+        match step {
+            PuzzleStep::Continue { reply } => self.play_puzzle_move(&reply),
+            PuzzleStep::Solved => self.finish_puzzle(client_id, true),
+            PuzzleStep::Failed => self.finish_puzzle(client_id, false),
+        }
+    }
+
+    // Joue un coup de la ligne du puzzle et envoie la position au joueur
+    fn play_puzzle_move(&mut self, mv: &str) {
+        /*
+        if let Err(e) = self.game.make_move_algebraic(mv) {
+            eprintln!("Room {}: puzzle move {} rejected: {}", self.id, mv, e);
+            return;
+        }
+        */
+        self.moves.push(mv.to_string());
+        let Some((puzzle, _)) = &self.puzzle else {
+            return;
+        };
+        let mut position = match FenPosition::parse(&puzzle.fen) {
+            Ok(position) => position,
+            Err(e) => {
+                eprintln!("Room {}: invalid puzzle {}: {}", self.id, puzzle.id, e);
+                return;
+            }
+        };
+        for mv in &self.moves {
+            position.play(mv);
+        }
+        let turn = position.turn;
+        let state_msg = ServerMessage::State {
+            board: display_board(&position),
+            turn,
+            counter: self.moves.len(),
+            incheck: position.in_check().then_some(turn),
+            pockets: None,
+        };
+        for player in self.players.values() {
+            let _ = send_to_player(player, &state_msg);
+        }
+    }
+
+    fn finish_puzzle(&mut self, client_id: Uuid, solved: bool) {
+        let puzzle = match &mut self.puzzle {
+            Some((puzzle, attempt)) => {
+                attempt.finish();
+                puzzle.clone()
+            }
+            None => return,
+        };
+        let account_id = self.players.get(&client_id).and_then(|p| p.account_id);
+        let profile = self
+            .puzzle_profile
+            .get_or_insert_with(PuzzleProfile::default);
+        // Un compte progresse sur le profil partagé, un invité sur celui de la room
+        let recorded = account_id.map(|id| {
+            self.services
+                .puzzles
+                .record_attempt(id, &puzzle, solved)
+                .map_err(|e| eprintln!("Failed to save puzzle stats of {}: {}", id, e))
+        });
+        let rating_before = match recorded {
+            Some(Ok((stored, before))) => {
+                *profile = stored;
+                before
+            }
+            _ => profile.record(&puzzle, solved),
+        };
+        let msg = ServerMessage::PuzzleFinished {
+            solved,
+            solution: puzzle.solution(),
+            stats: profile.stats(),
+            rating_before,
+        };
+        self.status = RoomStatus::Finished;
+        if let Some(player) = self.players.get(&client_id) {
+            let _ = send_to_player(player, &msg);
+        }
+    }
+
+    fn handle_rematch(&mut self, client_id: Uuid, accept: bool) {
+        let player = match self.players.get(&client_id) {
+            Some(p) if matches!(p.role, PlayerRole::White | PlayerRole::Black) => p,
+            _ => return,
+        };
+        if self.status != RoomStatus::Finished {
+            self.chat_error(client_id, "The game is not over yet.");
+            return;
+        }
+        let name = player.name.clone();
+
+        if !accept {
+            self.rematch_offers.clear();
+            for p in self.players.values() {
+                let _ = send_to_player(p, &ServerMessage::RematchDeclined { by: name.clone() });
+            }
+            return;
+        }
+        // Les deux couleurs doivent être tenues: un adversaire parti ne rejoue pas
+        let seated = |role: PlayerRole| self.players.values().any(|p| p.role == role);
+        if !seated(PlayerRole::White) || !seated(PlayerRole::Black) {
+            self.chat_error(client_id, "Your opponent left the room.");
+            return;
+        }
+        if self.rematch_offers.contains(&client_id) {
+            return;
+        }
+        self.rematch_offers.push(client_id);
+
+        // Les IA acceptent toujours: il suffit de l'accord des humains assis
+        let waiting = self.players.values().any(|p| {
+            matches!(p.role, PlayerRole::White | PlayerRole::Black)
+                && matches!(p.kind, PlayerType::Human)
+                && !self.rematch_offers.contains(&p.id)
+        });
+        if waiting {
+            for p in self.players.values() {
+                let msg = if p.id == client_id {
+                    ServerMessage::Info {
+                        msg: "Rematch offer sent.".into(),
+                    }
+                } else {
+                    ServerMessage::RematchOffered { by: name.clone() }
+                };
+                let _ = send_to_player(p, &msg);
+            }
+        } else {
+            self.start_rematch(client_id);
+        }
+    }
+
+    // Un moteur tient la couleur qui joue le premier coup
+    fn ai_moves_first(&self) -> bool {
+        let first = match self.settings.first_turn() {
+            Color::White => PlayerRole::White,
+            Color::Black => PlayerRole::Black,
+        };
+        self.players
+            .values()
+            .any(|p| p.role == first && matches!(p.kind, PlayerType::Ai { .. }))
+    }
+
+    /// Resets the room for a new game with the same settings, White and
+    /// Black swapped. Spectators stay.
+    fn start_rematch(&mut self, client_id: Uuid) {
+        // Le handicap reste à la charge du même joueur
+        self.settings.handicap = self.settings.handicap.map(|h| h.swapped());
+        self.variant_state = self.settings.start_state();
+        /*
+        self.game = match self.settings.start_fen() {
+            Some(fen) => self
+                .settings
+                .variant
+                .split_fen(&fen)
+                .and_then(|(fen, _)| Game::from_fen(&fen))
+                .unwrap_or_else(|_| Game::init(false)),
+            None => Game::init(false),
+        };
+        */
+        for player in self.players.values_mut() {
+            player.role = match player.role {
+                PlayerRole::White => PlayerRole::Black,
+                PlayerRole::Black => PlayerRole::White,
+                ref role => role.clone(),
+            };
+            if let PlayerType::Ai { ai, .. } = &mut player.kind {
+                /*
+                ai.color = ai.color.opposite();
+                */
+            }
+            player.ready = matches!(player.role, PlayerRole::White | PlayerRole::Black);
+        }
+        self.moves.clear();
+        self.started_at = None;
+        self.lineup = None;
+        self.rematch_offers.clear();
+        self.paused = false;
+        self.status = RoomStatus::WaitingReady;
+
+        for player in self.players.values() {
+            let _ = send_to_player(
+                player,
+                &ServerMessage::RematchStarted {
+                    role: player.role.clone(),
+                    room_status: self.status,
+                },
+            );
+        }
+        println!("Room {} rematch, colours swapped", self.id);
+
+        // StartGame lance l'IA si elle a le trait
+        let _ = self.tx.send(RoomCommand::StartGame { client_id });
+    }
+
+    // En fin de partie le délai ne sert plus: les spectateurs voient la position finale
+    fn reveal_to_spectators(&self) {
+        if self.settings.spectator_delay == 0 {
+            return;
+        }
+        let snapshot = self.snapshot();
+        for player in self.players.values() {
+            if player.role == PlayerRole::Spectator {
+                let _ = send_to_player(player, &snapshot);
+            }
+        }
+    }
+
+    fn lobby_summary(&self) -> RoomSummary {
+        let host = self.host.and_then(|id| self.players.get(&id));
+        let category = TimeCategory::of(self.settings.time_control);
+        RoomSummary {
+            room_id: self.id,
+            mode: self.mode.clone(),
+            status: self.status,
+            time_control: self.settings.time_control,
+            rated: self.settings.rated,
+            handicap: self.settings.handicap,
+            variant: self.settings.variant,
+            host: host.map_or_else(|| "?".to_string(), |p| p.name.clone()),
+            host_rating: host
+                .and_then(|p| p.account_id)
+                .map(|id| self.services.ratings.get(id, category).rating),
+            players: self
+                .players
+                .values()
+                .filter(|p| p.role != PlayerRole::Spectator)
+                .count(),
+            spectators: self.spectator_count(),
+            created_at: self.opened_at,
+        }
+    }
+
+    // Publie l'état de la room dans le lobby (le lobby ignore les résumés inchangés)
+    fn sync_lobby(&self) {
+        if self.settings.public && self.status != RoomStatus::Finished {
+            self.services.lobby.publish(self.lobby_summary());
+        } else {
+            self.services.lobby.remove(self.id);
         }
     }
 
     pub async fn run(&mut self) {
         println!("Room awaiting command...");
-        while let Some(cmd) = self.rx.recv().await {
+        loop {
+            // Après chaque commande, y compris celles qui font `continue`
+            self.broadcast_spectator_count();
+            self.sync_lobby();
+            let cmd = match self.rx.recv().await {
+                Some(cmd) => cmd,
+                None => break,
+            };
             match cmd {
                 RoomCommand::ClientReady {
                     client_id,
@@ -131,12 +1273,13 @@ impl Room {
                 }
                 RoomCommand::JoinRoom {
                     client_id,
+                    account,
                     sender,
                     response_tx,
                 } => {
-                    if matches!(self.status, RoomStatus::Running | RoomStatus::Finished) {
+                    if self.banned.contains(&client_id) {
                         let err = ServerMessage::Error {
-                            msg: "Cannot join this room.".into(),
+                            msg: "You have been kicked from this room.".into(),
                         };
                         let json = serde_json::to_string(&err).unwrap();
                         let _ = response_tx.send(Message::Text(json.into()));
@@ -147,13 +1290,19 @@ impl Room {
                         let json = serde_json::to_string(&err).unwrap();
                         let _ = response_tx.send(Message::Text(json.into()));
                     } else {
+                        let in_progress = matches!(
+                            self.status,
+                            RoomStatus::Running | RoomStatus::Paused | RoomStatus::Finished
+                        );
                         let role = match self.mode {
-                            GameMode::PlayerVsPlayer if self.players.len() == 1 => {
-                                Some(PlayerRole::Black)
-                            }
-                            GameMode::AIvsAI | GameMode::PlayerVsPlayer => {
-                                Some(PlayerRole::Spectator)
+                            // Une partie commencée ne s'ouvre qu'aux spectateurs
+                            GameMode::Sandbox | GameMode::Puzzle => None,
+                            _ if in_progress => Some(PlayerRole::Spectator),
+                            // La couleur libre peut être les Blancs après un bac à sable
+                            GameMode::PlayerVsPlayer => {
+                                Some(self.free_seat().unwrap_or(PlayerRole::Spectator))
                             }
+                            GameMode::AIvsAI => Some(PlayerRole::Spectator),
                             _ => None,
                         };
                         if let Some(role) = role {
@@ -161,6 +1310,10 @@ impl Room {
                                 client_id,
                                 Player {
                                     id: client_id,
+                                    account_id: account.as_ref().map(|a| a.id),
+                                    name: account
+                                        .map(|a| a.display_name)
+                                        .unwrap_or_else(|| guest_name(client_id)),
                                     role: role.clone(),
                                     ready: false,
                                     sender: Some(sender.clone()),
@@ -168,14 +1321,30 @@ impl Room {
                                 },
                             );
 
+                            // Joined d'abord: le client ne lit la partie qu'une fois dans la room
                             let resp = ServerMessage::Joined {
-                                role,
+                                role: role.clone(),
                                 room_id: self.id,
                                 room_status: self.status,
                                 host: false,
                                 gamemod: self.mode.clone(),
                             };
                             let json = serde_json::to_string(&resp).unwrap();
+                            let _ = sender.send(Message::Text(json.clone().into()));
+                            if in_progress {
+                                let _ = sender.send(Message::Text(
+                                    serde_json::to_string(&self.snapshot()).unwrap().into(),
+                                ));
+                            }
+                            // Contexte de la discussion pour les nouveaux arrivants
+                            let entries = self.chat.recent(Self::chat_channels(&role));
+                            if !entries.is_empty() {
+                                let _ = sender.send(Message::Text(
+                                    serde_json::to_string(&ServerMessage::ChatHistory { entries })
+                                        .unwrap()
+                                        .into(),
+                                ));
+                            }
                             let _ = response_tx.send(Message::Text(json.into()));
                         } else {
                             let err = ServerMessage::Error {
@@ -196,6 +1365,8 @@ impl Room {
                     ) && self.status == RoomStatus::WaitingReady
                     {
                         self.status = RoomStatus::Running;
+                        self.started_at = Some(now_timestamp());
+                        self.lineup = Some(self.current_lineup());
                         for player in self.players.values() {
                             let _ = send_to_player(
                                 player,
@@ -203,12 +1374,25 @@ impl Room {
                                     room_status: self.status,
                                     board: self.game.board.export_display_board(),
                                     turn: self.game.board.turn,
+                                    white_time_control: self
+                                        .settings
+                                        .time_control_of(Color::White),
+                                    black_time_control: self
+                                        .settings
+                                        .time_control_of(Color::Black),
                                 },
                             );
                         }
                         println!("Room {:?} game started", self.id);
+                        self.send_variant_status();
+                        // L'IA qui a le trait joue le premier coup
+                        if self.mode == GameMode::PlayerVsAI && self.ai_moves_first() {
+                            let _ = self.tx.send(RoomCommand::AiMove);
+                        }
                     } else if self.mode == GameMode::AIvsAI {
                         self.status = RoomStatus::Running;
+                        self.started_at = Some(now_timestamp());
+                        self.lineup = Some(self.current_lineup());
                         for player in self.players.values() {
                             /*
                             let _ = send_to_player(
@@ -217,10 +1401,17 @@ impl Room {
                                     room_status: self.status,
                                     board: self.game.board.export_display_board(),
                                     turn: self.game.board.turn,
+                                    white_time_control: self
+                                        .settings
+                                        .time_control_of(Color::White),
+                                    black_time_control: self
+                                        .settings
+                                        .time_control_of(Color::Black),
                                 },
                             );
                             */
                         }
+                        self.send_variant_status();
                         let _ = self.tx.send(RoomCommand::AiMove);
                     }
                     */
@@ -238,7 +1429,16 @@ impl Room {
                         let mv = mv.trim().replace('"', "");
 
                         /*
-                        let movelist = self.game.get_list_moves(mv);
+                        // Cases d'arrivée selon les règles du variant (explosions de l'atomique, drops)
+                        let variant = self.settings.variant;
+                        let movelist = if variant == Variant::Standard {
+                            self.game.get_list_moves(mv)
+                        } else {
+                            Ok(game_lib::crazyhouse::move_targets(
+                                &variant.legal_moves(&self.game, &self.variant_state),
+                                &mv,
+                            ))
+                        };
                         match movelist {
                             Ok(moves) => {
                                 println!("Moves: {:?}", moves);
@@ -258,6 +1458,11 @@ impl Room {
                     }
                 }
                 RoomCommand::ClientMove { client_id, mv } => {
+                    // Les coups d'un puzzle sont comparés à la solution
+                    if self.mode == GameMode::Puzzle {
+                        self.handle_puzzle_move(client_id, mv.trim().replace('"', ""));
+                        continue;
+                    }
                     /*
                     let mv = mv.trim().replace('"', "");
 
@@ -313,56 +1518,48 @@ impl Room {
                         continue;
                     }
 
-                    let move_result = self.game.make_move_algebraic(&mv);
+                    // Les variantes passent par leurs propres règles de légalité
+                    let variant = self.settings.variant;
+                    let move_result = if variant == Variant::Standard {
+                        self.game.make_move_algebraic(&mv).map(|_| ())
+                    } else if variant.legal_moves(&self.game, &self.variant_state).contains(&mv) {
+                        variant.play(&mut self.game, &mut self.variant_state, &mv);
+                        Ok(())
+                    } else {
+                        Err(format!("Illegal move in {}", variant.name()))
+                    };
                     match move_result {
                         Ok(_) => {
                             let turn = self.game.board.turn;
-                            let king_pos = self.game.board.pieces[turn as usize][15].position;
 
                             println!("Moved: {}", mv);
+                            self.moves.push(mv.clone());
                             // Préparer le message à diffuser
                             let state_msg = ServerMessage::State {
-                                board: self.game.board.export_display_board(),
+                                board: self.display_board(),
                                 turn: self.game.board.turn,
                                 counter: self.game.board.counter,
-                                incheck: if self.game.board.is_attacked(&king_pos, turn) {
+                                incheck: if variant.in_check(&self.game) {
                                     Some(turn)
                                 } else {
                                     None
                                 },
+                                pockets: self.pockets(&self.variant_state),
                             };
 
                             // Diffuser à tous les joueurs (accès immuable)
                             for player in self.players.values() {
-                                send_to_player(player, &state_msg);
+                                if !self.is_delayed_spectator(player) {
+                                    send_to_player(player, &state_msg);
+                                }
                             }
+                            self.send_delayed_state();
+                            self.send_evaluation();
+                            self.send_variant_status();
 
                             // Vérifier si la partie est terminée
-                            if self.game.board.is_game_over() {
-                                let result = if self.game.board.is_checkmate(self.game.board.turn) {
-                                    format!(
-                                        "Checkmate! {:?} wins.",
-                                        match self.game.board.turn {
-                                            Color::White => Color::Black,
-                                            Color::Black => Color::White,
-                                        }
-                                    )
-                                } else {
-                                    "Draw!".to_string()
-                                };
-
-                                // Update du status
-                                self.status = RoomStatus::Finished;
-
-                                // Envoyer le message de fin
-                                let game_over_msg = ServerMessage::GameOver {
-                                    room_status: self.status,
-                                    result,
-                                };
-
-                                for player in self.players.values() {
-                                    send_to_player(player, &game_over_msg);
-                                }
+                            if self.finish_if_over() {
+                                continue;
                             }
 
                             // Si PlayerVsAI, envoyer un nouveau AiMove
@@ -386,12 +1583,97 @@ impl Room {
                 RoomCommand::AiMove => {
                     /*
                     let ai_player_opt = self.players.values().find(|p| match &p.kind {
-                        PlayerType::Ai { ai } => ai.color == self.game.board.turn,
+                        PlayerType::Ai { ai, .. } => ai.color == self.game.board.turn,
                         _ => false,
                     });
                     if !self.paused {
-                        if let Some(ai_player) = ai_player_opt {
-                            if let PlayerType::Ai { ai } = &ai_player.kind {
+                        // Tant que le livre a des coups pour la position, on les joue
+                        let book_move = ai_player_opt.and_then(|p| match (&p.kind, &self.services.book) {
+                            (
+                                PlayerType::Ai {
+                                    profile: AiProfile::Builtin { book: usage, .. },
+                                    ..
+                                },
+                                Some(book),
+                            ) if usage.allows(self.moves.len())
+                                && self.settings.variant == Variant::Standard =>
+                            {
+                                let (position, _) = self.replay(&self.moves);
+                                let mut rng = rand::thread_rng();
+                                book.pick(&position, |total| rng.gen_range(0..total))
+                            }
+                            _ => None,
+                        });
+                        // Avec peu de pièces, le moteur intégré joue le coup des tables Syzygy
+                        let tablebase_move = match (ai_player_opt, &self.services.tablebases) {
+                            (
+                                Some(Player {
+                                    kind: PlayerType::Ai { profile: AiProfile::Builtin { .. }, .. },
+                                    ..
+                                }),
+                                Some(tb),
+                            ) if self.settings.variant == Variant::Standard => {
+                                tb.best_move(&self.replay(&self.moves).0)
+                            }
+                            _ => None,
+                        };
+                        if let Some(mv) = book_move.or(tablebase_move) {
+                            self.tx.send(RoomCommand::AIApplyMove { mv });
+                        } else if let Some(ai_player) = ai_player_opt {
+                            let eval = match &ai_player.kind {
+                                PlayerType::Ai { profile, .. } => profile.eval(),
+                                _ => EvalVariant::default(),
+                            };
+                            if let PlayerType::Ai {
+                                profile: AiProfile::External { name, movetime_ms },
+                                ..
+                            } = &ai_player.kind
+                            {
+                                // Moteur UCI externe, lancé hors de la boucle de la room
+                                let engines = self.services.engines.clone();
+                                let (name, movetime_ms) = (name.clone(), *movetime_ms);
+                                let start_fen = self.settings.start_fen();
+                                let variant = self.settings.variant;
+                                let moves = self.moves.clone();
+                                let (position, state) = self.replay(&self.moves);
+                                let tx = self.tx.clone();
+                                let room_id = self.id;
+                                tokio::spawn(async move {
+                                    let mv = match engines
+                                        .best_move(&name, variant, start_fen.as_deref(), &moves, movetime_ms)
+                                        .await
+                                    {
+                                        Ok(mv) => Some(mv),
+                                        // Moteur en panne ou coup illégal: la recherche intégrée joue
+                                        Err(e) => {
+                                            eprintln!("Room {}: {}, built-in search plays instead", room_id, e);
+                                            variant.best_move(&position, &state, EvalVariant::default(), 2)
+                                        }
+                                    };
+                                    if let Some(mv) = mv {
+                                        tx.send(RoomCommand::AIApplyMove { mv });
+                                    }
+                                });
+                            } else if self.settings.variant != Variant::Standard
+                                || eval != EvalVariant::Standard
+                            {
+                                // Recherche courte qui connaît les règles du variant, ou qui
+                                // suit l'évaluation choisie à la place de celle du moteur
+                                let variant = self.settings.variant;
+                                let (position, state) = self.replay(&self.moves);
+                                let tablebases = self.services.tablebases.clone();
+                                let tx = self.tx.clone();
+                                tokio::task::spawn_blocking(move || {
+                                    let mv = match &tablebases {
+                                        Some(tb) => variant
+                                            .best_move_with_tablebases(&position, &state, eval, 2, tb),
+                                        None => variant.best_move(&position, &state, eval, 2),
+                                    };
+                                    if let Some(mv) = mv {
+                                        tx.send(RoomCommand::AIApplyMove { mv });
+                                    }
+                                });
+                            } else if let PlayerType::Ai { ai, .. } = &ai_player.kind {
                                 let ai = ai.clone();
                                 let board = self.game.board.clone();
                                 let tx = self.tx.clone();
@@ -415,52 +1697,44 @@ impl Room {
                     */
                 }
                 RoomCommand::AIApplyMove { mv } => {
+                    // Un coup illégal d'un moteur ne doit jamais corrompre ni faire tomber la room
+                    let Some(mv) = self.checked_ai_move(&mv) else {
+                        continue;
+                    };
                     /*
                     tokio::time::sleep(Duration::from_millis(500)).await;
-                    match self.game.make_move_algebraic(&mv) {
+                    let variant = self.settings.variant;
+                    let move_result = if variant == Variant::Standard {
+                        self.game.make_move_algebraic(&mv).map(|_| ())
+                    } else {
+                        variant.play(&mut self.game, &mut self.variant_state, &mv);
+                        Ok(())
+                    };
+                    match move_result {
                         Ok(_) => {
                             let turn = self.game.board.turn;
-                            let king_pos = self.game.board.pieces[turn as usize][15].position;
                             println!("Moved");
-                            for player in self.players.values() {
+                            self.moves.push(mv.clone());
+                            for player in self.players.values().filter(|p| !self.is_delayed_spectator(p)) {
                                 send_to_player(
                                     player,
                                     &ServerMessage::State {
-                                        board: self.game.board.export_display_board(),
+                                        board: self.display_board(),
                                         turn: self.game.board.turn,
                                         counter: self.game.board.counter,
-                                        incheck: if self.game.board.is_attacked(&king_pos, turn) {
+                                        incheck: if variant.in_check(&self.game) {
                                             Some(turn)
                                         } else {
                                             None
                                         },
+                                        pockets: self.pockets(&self.variant_state),
                                     },
                                 );
                             }
+                            self.send_delayed_state();
+                            self.send_variant_status();
 
-                            if self.game.board.is_game_over() {
-                                let result = if self.game.board.is_checkmate(self.game.board.turn) {
-                                    format!(
-                                        "Checkmate! {:?} wins.",
-                                        match self.game.board.turn {
-                                            Color::White => Color::Black,
-                                            Color::Black => Color::White,
-                                        }
-                                    )
-                                } else {
-                                    "Draw!".to_string()
-                                };
-
-                                self.status = RoomStatus::Finished;
-                                let game_over_msg = ServerMessage::GameOver {
-                                    room_status: self.status,
-                                    result: result.clone(),
-                                };
-
-                                for player in self.players.values() {
-                                    send_to_player(player, &game_over_msg);
-                                }
-                                println!("Game Over: {}", result);
+                            if self.finish_if_over() {
                                 continue;
                             }
 
@@ -469,15 +1743,14 @@ impl Room {
                             }
                         }
                         Err(e) => {
-                            println!("Not moved");
-                            panic!()
+                            eprintln!("Room {}: engine move {} refused: {}", self.id, mv, e);
                         }
                     }
                     */
                     /*
                     // Vérifier si c'est encore à une IA de jouer
                     let next_ai_turn = self.players.values().any(|p| match &p.kind {
-                        PlayerType::Ai { ai } => ai.color == self.game.board.turn,
+                        PlayerType::Ai { ai, .. } => ai.color == self.game.board.turn,
                         _ => false,
                     });
 
@@ -488,6 +1761,9 @@ impl Room {
                     */
                 }
                 RoomCommand::PlayerQuit { client_id } => {
+                    // Départ ou déconnexion: le compteur anti-spam du client est oublié
+                    self.chat.forget(client_id);
+                    self.rematch_offers.retain(|id| *id != client_id);
                     /*
                     println!("A player Want to quit");
                     let mut role = None;
@@ -511,16 +1787,26 @@ impl Room {
                                 .find(|p| matches!(p.role, PlayerRole::White | PlayerRole::Black))
                             {
                                 self.status = RoomStatus::Finished;
+                                let outcome = match winner.role {
+                                    PlayerRole::White => GameResult::WhiteWins,
+                                    _ => GameResult::BlackWins,
+                                };
+                                let rating_changes =
+                                    self.finish_game(outcome, "Forfeit".to_string());
                                 let msg = ServerMessage::GameOver {
                                     room_status: self.status,
                                     result: format!(
                                         "A player quit the game!!\nVictory by forfeit for {:?} !!!",
                                         winner.role
                                     ),
+                                    rating_changes,
+                                    variant_win: None,
+                                    bughouse: None,
                                 };
                                 for p in self.players.values() {
                                     let _ = send_to_player(p, &msg);
                                 }
+                                self.reveal_to_spectators();
                                 println!(
                                     "Player {} quit the game. Victory by forfeit for {:?}.",
                                     client_id, winner.role
@@ -529,6 +1815,9 @@ impl Room {
                         }
                         if self.mode == GameMode::AIvsAI {
                             // On arrête la partie
+                            if self.status == RoomStatus::Running {
+                                self.finish_game(GameResult::Aborted, "Stopped".to_string());
+                            }
                             self.status = RoomStatus::Finished;
                             self.paused = true;
                         }
@@ -623,9 +1912,16 @@ impl Room {
                                     room_status: self.status,
                                     board: self.game.board.export_display_board(),
                                     turn: self.game.board.turn,
+                                    white_time_control: self
+                                        .settings
+                                        .time_control_of(Color::White),
+                                    black_time_control: self
+                                        .settings
+                                        .time_control_of(Color::Black),
                                 },
                             );
                         }
+                        self.send_evaluation();
                     }
                     */
                 }
@@ -649,6 +1945,7 @@ impl Room {
                                 &ServerMessage::SandboxPieceAdded { piece, pos },
                             );
                         }
+                        self.send_evaluation();
                     }
                     */
                 }
@@ -694,12 +1991,65 @@ impl Room {
                     }
                     */
                 }
+                RoomCommand::Chat {
+                    client_id,
+                    channel,
+                    text,
+                } => self.handle_chat(client_id, channel, text),
+                RoomCommand::Rematch { client_id, accept } => {
+                    self.handle_rematch(client_id, accept)
+                }
+                RoomCommand::SolveMate { client_id, n } => self.handle_solve_mate(client_id, n),
+                RoomCommand::SetAnalysis { client_id, enabled } => {
+                    self.handle_set_analysis(client_id, enabled)
+                }
+                RoomCommand::PlaySandboxPosition {
+                    client_id,
+                    side,
+                    opponent,
+                } => self.handle_play_sandbox(client_id, side, opponent),
+                RoomCommand::NextPuzzle { client_id } => self.handle_next_puzzle(client_id),
+                RoomCommand::TakeSeat { client_id, .. } => {
+                    self.chat_error(client_id, "Seats can only be chosen in bughouse rooms.")
+                }
+                RoomCommand::MateSolved {
+                    client_id,
+                    n,
+                    outcome,
+                } => {
+                    if let Some(player) = self.players.get(&client_id) {
+                        let _ = send_to_player(player, &ServerMessage::MateSolution { n, outcome });
+                    }
+                }
+                RoomCommand::Mute {
+                    client_id,
+                    target,
+                    muted,
+                } => self.handle_mute(client_id, target, muted),
+                RoomCommand::Kick {
+                    client_id,
+                    target,
+                    response_tx,
+                } => {
+                    let kicked = self.handle_kick(client_id, target);
+                    let _ = response_tx.send(kicked);
+                }
             }
         }
+        self.services.lobby.remove(self.id);
         println!("Room {} stopped", self.id);
     }
 }
 
+// Trait au prochain coup après `plies` demi-coups depuis la position initiale
+fn side_to_move(plies: usize, first_turn: Color) -> Color {
+    if plies.is_multiple_of(2) {
+        first_turn
+    } else {
+        first_turn.opposite()
+    }
+}
+
 pub enum RoomCommand {
     ClientMove {
         client_id: Uuid,
@@ -715,6 +2065,7 @@ pub enum RoomCommand {
     },
     JoinRoom {
         client_id: Uuid,
+        account: Option<AccountInfo>,
         sender: UnboundedSender<Message>,
         response_tx: Sender<Message>,
     },
@@ -741,4 +2092,126 @@ pub enum RoomCommand {
     Pause {
         client_id: Uuid,
     },
+    Chat {
+        client_id: Uuid,
+        channel: ChatChannel,
+        text: String,
+    },
+    Mute {
+        client_id: Uuid,
+        target: Uuid,
+        muted: bool,
+    },
+    Kick {
+        client_id: Uuid,
+        target: Uuid,
+        response_tx: Sender<bool>,
+    },
+    Rematch {
+        client_id: Uuid,
+        accept: bool,
+    },
+    SolveMate {
+        client_id: Uuid,
+        n: u8,
+    },
+    SetAnalysis {
+        client_id: Uuid,
+        enabled: bool,
+    },
+    PlaySandboxPosition {
+        client_id: Uuid,
+        side: Color,
+        opponent: SandboxOpponent,
+    },
+    MateSolved {
+        client_id: Uuid,
+        n: u8,
+        outcome: MateOutcome,
+    },
+    NextPuzzle {
+        client_id: Uuid,
+    },
+    TakeSeat {
+        client_id: Uuid,
+        seat: Seat,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chat::ChatFilter, engines::EngineRegistry, lobby::Lobby, puzzles::PuzzleStore,
+        ratings::RatingStore, store::InMemoryGameStore,
+    };
+
+    // Room de bac à sable sans fichiers, l'hôte seul dedans
+    fn sandbox_room(fen: Option<&str>) -> (Room, Uuid) {
+        let missing = std::env::temp_dir().join(format!("room-test-{}-missing", Uuid::new_v4()));
+        let services = RoomServices {
+            store: Arc::new(InMemoryGameStore::new()),
+            ratings: Arc::new(RatingStore::open(&missing).unwrap()),
+            lobby: Arc::new(Lobby::default()),
+            chat_filter: Arc::new(ChatFilter::default()),
+            engines: Arc::new(EngineRegistry::default()),
+            book: None,
+            tablebases: None,
+            puzzles: Arc::new(PuzzleStore::open(&missing, &missing).unwrap()),
+        };
+        let host = Uuid::new_v4();
+        let player = Player {
+            id: host,
+            account_id: None,
+            name: "Host".to_string(),
+            role: PlayerRole::Solo,
+            ready: false,
+            sender: None,
+            kind: PlayerType::Human,
+        };
+        let settings = RoomSettings {
+            fen: fen.map(str::to_string),
+            ..RoomSettings::default()
+        };
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut room = Room::new(
+            Uuid::new_v4(),
+            GameMode::Sandbox,
+            HashMap::from([(host, player)]),
+            Game {},
+            settings,
+            rx,
+            tx,
+            services,
+        );
+        room.host = Some(host);
+        (room, host)
+    }
+
+    #[test]
+    fn the_sandbox_position_becomes_the_start_fen() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K2R w K - 0 1";
+        let (mut room, host) = sandbox_room(Some(fen));
+        room.moves = vec!["e2->e4".to_string()];
+        room.handle_play_sandbox(host, Color::White, SandboxOpponent::Human);
+        assert_eq!(room.mode, GameMode::PlayerVsPlayer);
+        assert_eq!(
+            room.settings.start_fen().as_deref(),
+            Some("4k3/8/8/8/4P3/8/8/4K2R b K e3 0 1")
+        );
+        assert!(room.moves.is_empty());
+        assert_eq!(room.players[&host].role, PlayerRole::White);
+    }
+
+    #[test]
+    fn an_unplayable_sandbox_position_is_refused() {
+        // Les noirs n'ont pas de roi
+        let (mut room, host) = sandbox_room(Some("8/8/8/8/8/8/8/4K3 w - - 0 1"));
+        room.handle_play_sandbox(host, Color::White, SandboxOpponent::Human);
+        assert_eq!(room.mode, GameMode::Sandbox);
+        assert_eq!(
+            room.settings.start_fen().as_deref(),
+            Some("8/8/8/8/8/8/8/4K3 w - - 0 1")
+        );
+    }
 }
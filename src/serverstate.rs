@@ -1,16 +1,32 @@
 use crate::{
+    accounts::{Account, SharedAccountStore},
+    book::{SharedOpeningBook, SharedTablebases},
+    bughouse::BughouseRoom,
+    chat::SharedChatFilter,
+    engines::SharedEngineRegistry,
+    invites::InviteCodes,
+    lobby::SharedLobby,
+    matchmaking::{Matchmaker, QueueEntry, MATCHMAKING_ENABLED, MAX_RATING_RANGE},
     now_timestamp,
-    room::{Room, RoomCommand},
+    puzzles::SharedPuzzleStore,
+    ratings::SharedRatingStore,
+    room::{Room, RoomCommand, RoomServices, RoomSettings},
+    send_to_client,
+    store::SharedGameStore,
     utils::{Client, Player, PlayerType},
 };
 use game_lib::{
-    automation::ai::{Difficulty, AI},
+    automation::ai::{AiProfile, Difficulty, AI},
+    bughouse::{Seat, SEATS},
+    fen::FenPosition,
     game::Game,
     piece::Color,
+    variant::Variant,
 };
 use game_lib::{
-    messages::ServerMessage,
-    sharedenums::{GameMode, PlayerRole, RoomStatus},
+    history::GameQuery,
+    messages::{LeaderboardEntry, SandboxOpponent, ServerMessage},
+    sharedenums::{ChatChannel, GameMode, PlayerRole, RoomStatus, TimeCategory, TimeControl},
 };
 use std::{
     collections::HashMap,
@@ -28,20 +44,123 @@ use uuid::Uuid;
 pub struct ServerState {
     pub clients: HashMap<Uuid, Client>,
     pub room_senders: HashMap<Uuid, UnboundedSender<RoomCommand>>,
+    pub store: SharedGameStore,
+    pub accounts: SharedAccountStore,
+    pub ratings: SharedRatingStore,
+    pub matchmaker: Matchmaker,
+    pub lobby: SharedLobby,
+    pub invites: InviteCodes,
+    pub chat_filter: SharedChatFilter,
+    pub engines: SharedEngineRegistry,
+    pub book: Option<SharedOpeningBook>,
+    pub tablebases: Option<SharedTablebases>,
+    pub puzzles: SharedPuzzleStore,
 }
 pub type SharedServerState = Arc<Mutex<ServerState>>;
 
+pub const MAX_LISTED_GAMES: usize = 100;
+// Nombre de parties récentes prises en compte pour équilibrer les couleurs
+const COLOUR_HISTORY: usize = 20;
+
 impl ServerState {
+    pub fn room_services(&self) -> RoomServices {
+        RoomServices {
+            store: self.store.clone(),
+            ratings: self.ratings.clone(),
+            lobby: self.lobby.clone(),
+            chat_filter: self.chat_filter.clone(),
+            engines: self.engines.clone(),
+            book: self.book.clone(),
+            tablebases: self.tablebases.clone(),
+            puzzles: self.puzzles.clone(),
+        }
+    }
+
     pub fn create_room(
         &mut self,
         client_id: Uuid,
         mode: GameMode,
-        difficulty: Option<Difficulty>,
+        settings: RoomSettings,
     ) -> Option<ServerMessage> {
+        if mode == GameMode::Bughouse {
+            return self.create_bughouse_room(client_id, settings);
+        }
+        // Les moteurs externes doivent figurer dans la liste du serveur
+        for profile in [&settings.white_ai, &settings.black_ai]
+            .into_iter()
+            .flatten()
+        {
+            if let AiProfile::External { name, .. } = profile {
+                if !self.engines.contains(name) {
+                    return Some(ServerMessage::Error {
+                        msg: format!("Unknown engine: {}", name),
+                    });
+                }
+            }
+        }
+        if let Some(fen) = &settings.fen {
+            let error = if mode == GameMode::Puzzle {
+                Err("Puzzles cannot start from a custom position.".to_string())
+            } else if settings.handicap.is_some() {
+                Err("Odds cannot be combined with a custom position.".to_string())
+            } else {
+                settings.variant.check_start_fen(fen).map(|_| ())
+            };
+            if let Err(msg) = error {
+                return Some(ServerMessage::Error { msg });
+            }
+        }
+        if settings.variant != Variant::Standard {
+            let error = if matches!(mode, GameMode::Puzzle) {
+                Err("Puzzles are only available in standard chess.")
+            } else if settings.rated {
+                Err("Variant games cannot be rated.")
+            } else {
+                Ok(())
+            };
+            if let Err(msg) = error {
+                return Some(ServerMessage::Error { msg: msg.into() });
+            }
+        }
+        if let Some(handicap) = &settings.handicap {
+            let error = if matches!(mode, GameMode::Sandbox | GameMode::Puzzle) {
+                Err("Odds are only available in games between two sides.".to_string())
+            } else {
+                handicap.check(settings.rated, settings.time_control)
+            };
+            if let Err(msg) = error {
+                return Some(ServerMessage::Error { msg });
+            }
+        }
         /*
+        // Seules les parties entre deux comptes peuvent être classées
+        if settings.rated
+            && (mode != GameMode::PlayerVsPlayer
+                || self.clients.get(&client_id)?.account.is_none())
+        {
+            return Some(ServerMessage::Error {
+                msg: "Rated games are only available between logged-in players.".into(),
+            });
+        }
+        // Les puzzles se jouent seul: la room n'apparaît pas dans le lobby
+        let mut settings = settings;
+        if mode == GameMode::Puzzle {
+            settings.public = false;
+        }
         let room_id = Uuid::new_v4();
         let (tx, rx) = mpsc::unbounded_channel();
-        let game = Game::init(matches!(mode, GameMode::Sandbox));
+        // Position choisie par l'hôte, ou sans la pièce donnée en handicap
+        let game = match settings.start_fen() {
+            Some(fen) => match settings
+                .variant
+                .split_fen(&fen)
+                .and_then(|(fen, _)| Game::from_fen(&fen))
+            {
+                Ok(game) => game,
+                Err(e) => return Some(ServerMessage::Error { msg: e }),
+            },
+            None => Game::init(matches!(mode, GameMode::Sandbox)),
+        };
         let mut client = self.clients.get_mut(&client_id)?;
         client.room_id = Some(room_id);
 
@@ -54,18 +173,21 @@ impl ServerState {
             GameMode::PlayerVsPlayer => PlayerRole::White,
             GameMode::PlayerVsAI => {
                 let player_id = Uuid::new_v4();
+                let profile = settings
+                    .side_ai(PlayerRole::Black)
+                    .unwrap_or(AiProfile::builtin(Difficulty::Easy));
                 players.insert(
                     player_id,
                     Player {
                         id: player_id,
+                        account_id: None,
+                        name: profile.label(),
                         role: PlayerRole::Black,
                         ready: true,
                         sender: None,
                         kind: PlayerType::Ai {
-                            ai: AI {
-                                difficulty: difficulty.unwrap_or(Difficulty::Easy),
-                                color: Color::Black,
-                            },
+                            ai: AI::from_profile(&profile, Color::Black),
+                            profile: profile,
                         },
                     },
                 );
@@ -73,47 +195,55 @@ impl ServerState {
             }
             GameMode::AIvsAI => {
                 let player_id = Uuid::new_v4();
+                let profile = settings
+                    .side_ai(PlayerRole::Black)
+                    .unwrap_or(AiProfile::builtin(Difficulty::Easy));
                 players.insert(
                     player_id,
                     Player {
                         id: player_id,
+                        account_id: None,
+                        name: profile.label(),
                         role: PlayerRole::Black,
                         ready: true,
                         sender: None,
                         kind: PlayerType::Ai {
-                            ai: AI {
-                                difficulty: difficulty.clone().unwrap_or(Difficulty::Easy),
-                                color: Color::Black,
-                            },
+                            ai: AI::from_profile(&profile, Color::Black),
+                            profile: profile,
                         },
                     },
                 );
                 let player_id = Uuid::new_v4();
+                let profile = settings
+                    .side_ai(PlayerRole::White)
+                    .unwrap_or(AiProfile::builtin(Difficulty::Easy));
                 players.insert(
                     player_id,
                     Player {
                         id: player_id,
+                        account_id: None,
+                        name: profile.label(),
                         role: PlayerRole::White,
                         ready: true,
                         sender: None,
                         kind: PlayerType::Ai {
-                            ai: AI {
-                                difficulty: difficulty.unwrap_or(Difficulty::Easy),
-                                color: Color::White,
-                            },
+                            ai: AI::from_profile(&profile, Color::White),
+                            profile: profile,
                         },
                     },
                 );
 
                 PlayerRole::Spectator
             }
-            GameMode::Sandbox => PlayerRole::Solo,
+            GameMode::Sandbox | GameMode::Puzzle => PlayerRole::Solo,
         };
 
         players.insert(
             client_id,
             Player {
                 id: client_id,
+                account_id: client.account.as_ref().map(|a| a.id),
+                name: client.display_name(),
                 role: role.clone(),
                 ready: false,
                 sender: Some(client.sender.clone()),
@@ -121,12 +251,26 @@ impl ServerState {
             },
         );
         println!("Here!!");
-        let mut room_actor = Room::new(room_id, mode.clone(), players, game, rx, tx.clone());
+        let mut room_actor = Room::new(
+            room_id,
+            mode.clone(),
+            players,
+            game,
+            settings,
+            rx,
+            tx.clone(),
+            self.room_services(),
+        );
+        room_actor.host = Some(client_id);
 
         // Lancer la task asynchrone pour gérer la room
         tokio::spawn(async move {
             room_actor.run().await;
         });
+        // Le premier puzzle est chargé dès l'entrée dans la room
+        if mode == GameMode::Puzzle {
+            let _ = tx.send(RoomCommand::NextPuzzle { client_id });
+        }
 
         self.room_senders.insert(room_id, tx);
         println!("Created");
@@ -141,6 +285,378 @@ impl ServerState {
         None
     }
 
+    // Quatre places sur deux plateaux, la position et les règles sont imposées
+    fn create_bughouse_room(
+        &mut self,
+        client_id: Uuid,
+        settings: RoomSettings,
+    ) -> Option<ServerMessage> {
+        let mut taken = settings.ai_seats.clone();
+        taken.sort_by_key(|seat| SEATS.iter().position(|s| s == seat));
+        taken.dedup();
+        let error = if settings.rated {
+            Some("Bughouse matches cannot be rated.")
+        } else if settings.fen.is_some() || settings.handicap.is_some() {
+            Some("Bughouse matches start from the usual position, without odds.")
+        } else if settings.variant != Variant::Standard {
+            Some("Bughouse boards already follow the Crazyhouse rules.")
+        } else if taken.len() != settings.ai_seats.len() {
+            Some("Each seat can only be given to the AI once.")
+        } else if !settings.ai_seats.is_empty() && settings.difficulty.is_none() {
+            Some("Choose a difficulty for the AI seats.")
+        } else {
+            None
+        };
+        if let Some(msg) = error {
+            return Some(ServerMessage::Error { msg: msg.into() });
+        }
+
+        let services = self.room_services();
+        let client = self.clients.get_mut(&client_id)?;
+        // L'hôte prend la première place laissée aux humains
+        let seat = SEATS
+            .into_iter()
+            .find(|seat| !settings.ai_seats.contains(seat));
+        let role = match seat.map(|s| s.color) {
+            Some(Color::White) => PlayerRole::White,
+            Some(Color::Black) => PlayerRole::Black,
+            None => PlayerRole::Spectator,
+        };
+        let mut players = HashMap::new();
+        players.insert(
+            client_id,
+            Player {
+                id: client_id,
+                account_id: client.account.as_ref().map(|a| a.id),
+                name: client.display_name(),
+                role: role.clone(),
+                ready: false,
+                sender: Some(client.sender.clone()),
+                kind: PlayerType::Human,
+            },
+        );
+        let seats: HashMap<Uuid, Seat> = seat.map(|seat| (client_id, seat)).into_iter().collect();
+        let room_id = Uuid::new_v4();
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut room_actor = BughouseRoom::new(
+            room_id,
+            players,
+            seats,
+            FenPosition::start(),
+            settings,
+            rx,
+            services,
+        );
+        room_actor.host = Some(client_id);
+        let room_status = room_actor.status;
+        tokio::spawn(async move {
+            room_actor.run().await;
+        });
+        client.room_id = Some(room_id);
+        self.room_senders.insert(room_id, tx);
+        Some(ServerMessage::Joined {
+            role,
+            room_id,
+            room_status,
+            host: true,
+            gamemod: GameMode::Bughouse,
+        })
+    }
+
+    pub fn join_queue(
+        &mut self,
+        client_id: Uuid,
+        time_control: Option<TimeControl>,
+        rated: bool,
+        rating_range: f64,
+    ) {
+        let client = match self.clients.get(&client_id) {
+            Some(c) => c,
+            None => return,
+        };
+        let error = if !MATCHMAKING_ENABLED {
+            Some("Matchmaking is unavailable on this server.")
+        } else if client.room_id.is_some() {
+            Some("Leave your current room before looking for a game.")
+        } else if rated && client.account.is_none() {
+            Some("Rated games are only available to logged-in players.")
+        } else if !rating_range.is_finite() || rating_range < 0.0 {
+            Some("Invalid rating range.")
+        } else {
+            None
+        };
+        if let Some(msg) = error {
+            let _ = send_to_client(client, &ServerMessage::Error { msg: msg.into() });
+            return;
+        }
+
+        let account_id = client.account.as_ref().map(|a| a.id);
+        let rating = account_id.map_or(1500.0, |id| {
+            self.ratings.get(id, TimeCategory::of(time_control)).rating
+        });
+        let rating_range = rating_range.min(MAX_RATING_RANGE);
+        let _ = send_to_client(
+            client,
+            &ServerMessage::Queued {
+                time_control,
+                rated,
+                rating,
+                rating_range,
+            },
+        );
+        self.matchmaker.enqueue(QueueEntry {
+            client_id,
+            account_id,
+            time_control,
+            rated,
+            rating,
+            range: rating_range,
+            since: Instant::now(),
+        });
+        println!(
+            "Client {} queued ({} waiting)",
+            client_id,
+            self.matchmaker.len()
+        );
+    }
+
+    fn current_room(&self, client_id: Uuid) -> Option<&UnboundedSender<RoomCommand>> {
+        let room_id = self.clients.get(&client_id)?.room_id?;
+        self.room_senders.get(&room_id)
+    }
+
+    pub fn send_chat(&self, client_id: Uuid, channel: ChatChannel, text: String) {
+        if let Some(room) = self.current_room(client_id) {
+            let _ = room.send(RoomCommand::Chat {
+                client_id,
+                channel,
+                text,
+            });
+        }
+    }
+
+    pub fn rematch(&self, client_id: Uuid, accept: bool) {
+        if let Some(room) = self.current_room(client_id) {
+            let _ = room.send(RoomCommand::Rematch { client_id, accept });
+        }
+    }
+
+    pub fn solve_mate(&self, client_id: Uuid, n: u8) {
+        if let Some(room) = self.current_room(client_id) {
+            let _ = room.send(RoomCommand::SolveMate { client_id, n });
+        }
+    }
+
+    pub fn play_sandbox_position(&self, client_id: Uuid, side: Color, opponent: SandboxOpponent) {
+        if let Some(room) = self.current_room(client_id) {
+            let _ = room.send(RoomCommand::PlaySandboxPosition {
+                client_id,
+                side,
+                opponent,
+            });
+        }
+    }
+
+    pub fn set_analysis(&self, client_id: Uuid, enabled: bool) {
+        if let Some(room) = self.current_room(client_id) {
+            let _ = room.send(RoomCommand::SetAnalysis { client_id, enabled });
+        }
+    }
+
+    pub fn take_seat(&self, client_id: Uuid, seat: Seat) {
+        if let Some(room) = self.current_room(client_id) {
+            let _ = room.send(RoomCommand::TakeSeat { client_id, seat });
+        }
+    }
+
+    pub fn next_puzzle(&self, client_id: Uuid) {
+        if let Some(room) = self.current_room(client_id) {
+            let _ = room.send(RoomCommand::NextPuzzle { client_id });
+        }
+    }
+
+    pub fn mute_player(&self, client_id: Uuid, target: Uuid, muted: bool) {
+        if let Some(room) = self.current_room(client_id) {
+            let _ = room.send(RoomCommand::Mute {
+                client_id,
+                target,
+                muted,
+            });
+        }
+    }
+
+    pub fn create_invite(&mut self, client_id: Uuid) {
+        let client = match self.clients.get(&client_id) {
+            Some(c) => c,
+            None => return,
+        };
+        let msg = match client.room_id {
+            Some(room_id) if self.room_senders.contains_key(&room_id) => {
+                let (code, expires_at) = self.invites.create(room_id);
+                ServerMessage::InviteCode {
+                    room_id,
+                    code,
+                    expires_at,
+                }
+            }
+            _ => ServerMessage::Error {
+                msg: "You are not in a room.".into(),
+            },
+        };
+        let _ = send_to_client(client, &msg);
+    }
+
+    pub fn subscribe_lobby(&self, client_id: Uuid) {
+        if let Some(client) = self.clients.get(&client_id) {
+            self.lobby.subscribe(client_id, client.sender.clone());
+        }
+    }
+
+    pub fn leave_queue(&mut self, client_id: Uuid) {
+        if self.matchmaker.leave(client_id) {
+            if let Some(client) = self.clients.get(&client_id) {
+                let _ = send_to_client(client, &ServerMessage::LeftQueue);
+            }
+        }
+    }
+
+    /// Pairs compatible queue entries and opens a PvP room for each pair.
+    pub fn run_matchmaking(&mut self) {
+        for (a, b) in self.matchmaker.pair(Instant::now()) {
+            // Un client a pu rejoindre une room entre-temps
+            let available = |e: &QueueEntry| {
+                self.clients
+                    .get(&e.client_id)
+                    .is_some_and(|c| c.room_id.is_none())
+            };
+            match (available(&a), available(&b)) {
+                (true, true) => {}
+                (true, false) => {
+                    self.matchmaker.requeue(a);
+                    continue;
+                }
+                (false, true) => {
+                    self.matchmaker.requeue(b);
+                    continue;
+                }
+                (false, false) => continue,
+            }
+
+            let (white, black) = self.assign_colours(a, b);
+            // Sans room, les deux joueurs restent dans la file à leur place
+            if self.create_matched_room(&white, &black).is_none() {
+                self.matchmaker.requeue(white);
+                self.matchmaker.requeue(black);
+                continue;
+            }
+            println!(
+                "Matched {} (White) with {} (Black)",
+                white.client_id, black.client_id
+            );
+        }
+    }
+
+    // Blancs pour celui qui les a eus le moins souvent récemment, sinon au hasard
+    fn assign_colours(&self, a: QueueEntry, b: QueueEntry) -> (QueueEntry, QueueEntry) {
+        let balance = |entry: &QueueEntry| -> i32 {
+            let id = entry.account_id.unwrap_or(entry.client_id);
+            let query = GameQuery {
+                player: Some(id),
+                limit: Some(COLOUR_HISTORY),
+                ..GameQuery::default()
+            };
+            self.store
+                .query(&query)
+                .unwrap_or_default()
+                .iter()
+                .map(|game| {
+                    if game.white.id == id || game.white.account_id == Some(id) {
+                        1
+                    } else {
+                        -1
+                    }
+                })
+                .sum()
+        };
+        let (balance_a, balance_b) = (balance(&a), balance(&b));
+        if balance_a < balance_b || (balance_a == balance_b && rand::random::<bool>()) {
+            (a, b)
+        } else {
+            (b, a)
+        }
+    }
+
+    fn create_matched_room(&mut self, white: &QueueEntry, black: &QueueEntry) -> Option<Uuid> {
+        /*
+        let room_id = Uuid::new_v4();
+        let (tx, rx) = mpsc::unbounded_channel();
+        let game = Game::init(false);
+
+        let mut players = HashMap::new();
+        for (entry, role) in [(white, PlayerRole::White), (black, PlayerRole::Black)] {
+            let client = self.clients.get(&entry.client_id)?;
+            players.insert(
+                entry.client_id,
+                Player {
+                    id: entry.client_id,
+                    account_id: entry.account_id,
+                    name: client.display_name(),
+                    role,
+                    ready: false,
+                    sender: Some(client.sender.clone()),
+                    kind: PlayerType::Human,
+                },
+            );
+        }
+
+        let mut room_actor = Room::new(
+            room_id,
+            GameMode::PlayerVsPlayer,
+            players,
+            game,
+            RoomSettings {
+                difficulty: None,
+                rated: white.rated,
+                time_control: white.time_control,
+                public: false,
+                spectator_delay: 0,
+                white_ai: None,
+                black_ai: None,
+                handicap: None,
+                fen: None,
+                variant: Variant::Standard,
+                ai_seats: Vec::new(),
+            },
+            rx,
+            tx.clone(),
+            self.room_services(),
+        );
+        room_actor.status = RoomStatus::WaitingReady;
+        tokio::spawn(async move {
+            room_actor.run().await;
+        });
+        self.room_senders.insert(room_id, tx);
+
+        // Les Blancs sont hôtes et lancent la partie une fois les deux joueurs prêts
+        for (entry, role) in [(white, PlayerRole::White), (black, PlayerRole::Black)] {
+            let client = self.clients.get_mut(&entry.client_id)?;
+            client.room_id = Some(room_id);
+            let _ = send_to_client(
+                client,
+                &ServerMessage::Joined {
+                    role: role.clone(),
+                    room_id,
+                    room_status: RoomStatus::WaitingReady,
+                    host: role == PlayerRole::White,
+                    gamemod: GameMode::PlayerVsPlayer,
+                },
+            );
+        }
+        Some(room_id)
+        */
+        None
+    }
+
     pub fn set_player_ready(&mut self, client_id: Uuid, client_state: bool) {
         let client = match self.clients.get(&client_id) {
             Some(r) => r,
@@ -271,6 +787,8 @@ impl ServerState {
     }
 
     pub fn remove_client(&mut self, client_id: &Uuid) {
+        self.matchmaker.leave(*client_id);
+        self.lobby.unsubscribe(*client_id);
         self.clients.remove(client_id);
     }
 
@@ -285,6 +803,7 @@ impl ServerState {
     }
 
     pub fn remove_room(&mut self, room_id: Uuid) {
+        self.invites.remove_room(room_id);
         self.room_senders.remove(&room_id);
     }
 
@@ -298,4 +817,118 @@ impl ServerState {
 
         room.send(RoomCommand::Pause { client_id });
     }
+
+    // Associe le compte au client et lui renvoie son token
+    pub fn open_session(&mut self, client_id: Uuid, result: Result<(Account, String), String>) {
+        let client = match self.clients.get_mut(&client_id) {
+            Some(c) => c,
+            None => return,
+        };
+
+        let msg = match result {
+            Ok((account, token)) => {
+                if let Some(old) = client.session_token.take() {
+                    self.accounts.logout(&old);
+                }
+                println!("Client {} logged in as {}", client_id, account.username);
+                client.account = Some(account.info());
+                client.session_token = Some(token.clone());
+                ServerMessage::LoggedIn {
+                    account: account.info(),
+                    token,
+                }
+            }
+            Err(msg) => ServerMessage::Error { msg },
+        };
+        let _ = send_to_client(client, &msg);
+    }
+
+    pub fn logout(&mut self, client_id: Uuid) {
+        let client = match self.clients.get_mut(&client_id) {
+            Some(c) => c,
+            None => return,
+        };
+        if let Some(token) = client.session_token.take() {
+            self.accounts.logout(&token);
+        }
+        client.account = None;
+        let _ = send_to_client(client, &ServerMessage::LoggedOut);
+    }
+
+    pub fn send_leaderboard(&self, client_id: Uuid, category: TimeCategory) {
+        let client = match self.clients.get(&client_id) {
+            Some(c) => c,
+            None => return,
+        };
+        let entries = self
+            .ratings
+            .ranking(category)
+            .into_iter()
+            .map(|(account_id, rating)| LeaderboardEntry {
+                account_id,
+                display_name: self
+                    .accounts
+                    .get(account_id)
+                    .map_or_else(|| "?".to_string(), |a| a.display_name),
+                rating: rating.rating,
+                deviation: rating.deviation,
+                games: rating.games,
+            })
+            .collect();
+        let _ = send_to_client(client, &ServerMessage::Leaderboard { category, entries });
+    }
+
+    pub fn send_rating_history(&self, client_id: Uuid, account_id: Uuid) {
+        if let Some(client) = self.clients.get(&client_id) {
+            let entries = self.ratings.history(account_id);
+            let _ = send_to_client(
+                client,
+                &ServerMessage::RatingHistory {
+                    account_id,
+                    entries,
+                },
+            );
+        }
+    }
+
+    pub fn list_games(&self, client_id: Uuid, mut query: GameQuery) {
+        let client = match self.clients.get(&client_id) {
+            Some(c) => c,
+            None => return,
+        };
+        query.limit = Some(
+            query
+                .limit
+                .unwrap_or(MAX_LISTED_GAMES)
+                .min(MAX_LISTED_GAMES),
+        );
+
+        let msg = match self.store.query(&query) {
+            Ok(games) => ServerMessage::GameList { games },
+            Err(e) => ServerMessage::Error { msg: e },
+        };
+        let _ = send_to_client(client, &msg);
+    }
+
+    pub fn get_game(&self, client_id: Uuid, game_id: Uuid, pgn_only: bool) {
+        let client = match self.clients.get(&client_id) {
+            Some(c) => c,
+            None => return,
+        };
+
+        let msg = match self.store.get(game_id) {
+            Ok(Some(game)) if pgn_only => ServerMessage::Pgn {
+                game_id,
+                pgn: game.pgn,
+            },
+            Ok(Some(game)) => ServerMessage::ArchivedGame {
+                game: Box::new(game),
+            },
+            Ok(None) => ServerMessage::Error {
+                msg: "Game not found.".into(),
+            },
+            Err(e) => ServerMessage::Error { msg: e },
+        };
+        let _ = send_to_client(client, &msg);
+    }
 }
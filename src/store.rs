@@ -0,0 +1,314 @@
+use game_lib::history::{GameQuery, GameRecord};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+pub type SharedGameStore = Arc<dyn GameStore>;
+
+pub trait GameStore: Send + Sync + std::fmt::Debug {
+    fn save(&self, record: GameRecord) -> Result<(), String>;
+    fn get(&self, id: Uuid) -> Result<Option<GameRecord>, String>;
+    /// Returns the matching games, most recent first.
+    fn query(&self, query: &GameQuery) -> Result<Vec<GameRecord>, String>;
+}
+
+fn select(records: &[GameRecord], query: &GameQuery) -> Vec<GameRecord> {
+    let mut games: Vec<GameRecord> = records
+        .iter()
+        .filter(|r| query.matches(r))
+        .cloned()
+        .collect();
+    games.sort_by_key(|g| std::cmp::Reverse(g.ended_at));
+    if let Some(limit) = query.limit {
+        games.truncate(limit);
+    }
+    games
+}
+
+// Store volatile, utilisé pour les tests
+#[derive(Debug, Default)]
+pub struct InMemoryGameStore {
+    records: Mutex<Vec<GameRecord>>,
+}
+
+impl InMemoryGameStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl GameStore for InMemoryGameStore {
+    fn save(&self, record: GameRecord) -> Result<(), String> {
+        let mut records = self.records.lock().map_err(|e| e.to_string())?;
+        records.retain(|r| r.id != record.id);
+        records.push(record);
+        Ok(())
+    }
+
+    fn get(&self, id: Uuid) -> Result<Option<GameRecord>, String> {
+        let records = self.records.lock().map_err(|e| e.to_string())?;
+        Ok(records.iter().find(|r| r.id == id).cloned())
+    }
+
+    fn query(&self, query: &GameQuery) -> Result<Vec<GameRecord>, String> {
+        let records = self.records.lock().map_err(|e| e.to_string())?;
+        Ok(select(&records, query))
+    }
+}
+
+/// JSON lines file, one `GameRecord` per line. New games are appended; saving
+/// a game already archived rewrites the file. The whole archive is loaded at
+/// startup and kept in memory to answer queries.
+#[derive(Debug)]
+pub struct FileGameStore {
+    path: PathBuf,
+    records: Mutex<Vec<GameRecord>>,
+}
+
+impl FileGameStore {
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let mut records = Vec::new();
+
+        if path.exists() {
+            let file = File::open(&path)
+                .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+            for (i, line) in BufReader::new(file).lines().enumerate() {
+                let line = line.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<GameRecord>(&line) {
+                    // La dernière version d'une partie remplace les précédentes
+                    Ok(record) => {
+                        records.retain(|r: &GameRecord| r.id != record.id);
+                        records.push(record);
+                    }
+                    Err(e) => eprintln!("Skipping invalid game record at line {}: {}", i + 1, e),
+                }
+            }
+        }
+        println!(
+            "Loaded {} archived games from {}",
+            records.len(),
+            path.display()
+        );
+
+        Ok(Self {
+            path,
+            records: Mutex::new(records),
+        })
+    }
+}
+
+impl GameStore for FileGameStore {
+    fn save(&self, record: GameRecord) -> Result<(), String> {
+        let line = serde_json::to_string(&record)
+            .map_err(|e| format!("Failed to serialize game record: {}", e))?;
+        let mut records = self.records.lock().map_err(|e| e.to_string())?;
+
+        if !records.iter().any(|r| r.id == record.id) {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .map_err(|e| format!("Failed to open {}: {}", self.path.display(), e))?;
+            writeln!(file, "{}", line)
+                .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))?;
+            records.push(record);
+            return Ok(());
+        }
+
+        // Partie déjà archivée: le fichier est réécrit puis remplacé d'un bloc
+        let mut updated: Vec<GameRecord> = records
+            .iter()
+            .filter(|r| r.id != record.id)
+            .cloned()
+            .collect();
+        updated.push(record);
+        let mut text = String::new();
+        for r in &updated {
+            let line = serde_json::to_string(r)
+                .map_err(|e| format!("Failed to serialize game record: {}", e))?;
+            text.push_str(&line);
+            text.push('\n');
+        }
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, text)
+            .and_then(|_| std::fs::rename(&tmp, &self.path))
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))?;
+        *records = updated;
+        Ok(())
+    }
+
+    fn get(&self, id: Uuid) -> Result<Option<GameRecord>, String> {
+        let records = self.records.lock().map_err(|e| e.to_string())?;
+        Ok(records.iter().find(|r| r.id == id).cloned())
+    }
+
+    fn query(&self, query: &GameQuery) -> Result<Vec<GameRecord>, String> {
+        let records = self.records.lock().map_err(|e| e.to_string())?;
+        Ok(select(&records, query))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game_lib::{
+        history::PlayerRecord,
+        sharedenums::{GameMode, GameResult},
+        variant::Variant,
+    };
+
+    fn player(name: &str) -> PlayerRecord {
+        PlayerRecord {
+            id: Uuid::new_v4(),
+            account_id: None,
+            name: name.to_string(),
+            is_ai: false,
+        }
+    }
+
+    fn record(
+        white: &PlayerRecord,
+        mode: GameMode,
+        result: GameResult,
+        ended_at: u64,
+    ) -> GameRecord {
+        let mut record = GameRecord {
+            id: Uuid::new_v4(),
+            room_id: Uuid::new_v4(),
+            mode,
+            white: white.clone(),
+            black: player("black"),
+            ai_profile: None,
+            white_ai: None,
+            black_ai: None,
+            rated: false,
+            time_control: None,
+            handicap: None,
+            start_fen: None,
+            variant: Variant::Standard,
+            moves: vec!["e2->e4".into(), "e7->e5".into()],
+            result,
+            reason: "agreement".into(),
+            started_at: ended_at - 60,
+            ended_at,
+            pgn: String::new(),
+        };
+        record.pgn = record.to_pgn();
+        record
+    }
+
+    // Trois parties: deux d'Alice, une seule gagnée par les blancs
+    fn fill(store: &dyn GameStore, alice: &PlayerRecord) -> Vec<GameRecord> {
+        let games = vec![
+            record(
+                alice,
+                GameMode::PlayerVsPlayer,
+                GameResult::WhiteWins,
+                1_000,
+            ),
+            record(alice, GameMode::PlayerVsAI, GameResult::Draw, 3_000),
+            record(
+                &player("bob"),
+                GameMode::PlayerVsPlayer,
+                GameResult::Draw,
+                2_000,
+            ),
+        ];
+        for game in &games {
+            store.save(game.clone()).unwrap();
+        }
+        games
+    }
+
+    fn check_queries(store: &dyn GameStore, alice: &PlayerRecord, games: &[GameRecord]) {
+        assert_eq!(store.get(games[0].id).unwrap().as_ref(), Some(&games[0]));
+        assert_eq!(store.get(Uuid::new_v4()).unwrap(), None);
+
+        let all = store.query(&GameQuery::default()).unwrap();
+        let ended: Vec<u64> = all.iter().map(|g| g.ended_at).collect();
+        assert_eq!(ended, vec![3_000, 2_000, 1_000]);
+
+        let by_alice = GameQuery {
+            player: Some(alice.id),
+            ..GameQuery::default()
+        };
+        assert_eq!(store.query(&by_alice).unwrap().len(), 2);
+        let draws = GameQuery {
+            result: Some(GameResult::Draw),
+            mode: Some(GameMode::PlayerVsPlayer),
+            ..GameQuery::default()
+        };
+        assert_eq!(store.query(&draws).unwrap(), vec![games[2].clone()]);
+        let window = GameQuery {
+            from: Some(1_500),
+            to: Some(2_500),
+            ..GameQuery::default()
+        };
+        assert_eq!(store.query(&window).unwrap(), vec![games[2].clone()]);
+        let latest = GameQuery {
+            limit: Some(1),
+            ..GameQuery::default()
+        };
+        assert_eq!(store.query(&latest).unwrap(), vec![games[1].clone()]);
+    }
+
+    #[test]
+    fn in_memory_store_answers_queries() {
+        let store = InMemoryGameStore::new();
+        let alice = player("alice");
+        let games = fill(&store, &alice);
+        check_queries(&store, &alice, &games);
+
+        // Sauver à nouveau une partie la remplace
+        let mut replayed = games[0].clone();
+        replayed.reason = "resignation".into();
+        store.save(replayed.clone()).unwrap();
+        assert_eq!(store.get(replayed.id).unwrap(), Some(replayed));
+        assert_eq!(store.query(&GameQuery::default()).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn file_store_reloads_saved_games() {
+        let path = std::env::temp_dir().join(format!("m_chess_games_{}.jsonl", Uuid::new_v4()));
+        let alice = player("alice");
+        let games = {
+            let store = FileGameStore::open(&path).unwrap();
+            fill(&store, &alice)
+        };
+        // Une ligne illisible est ignorée au chargement
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "not a game").unwrap();
+
+        let reopened = FileGameStore::open(&path).unwrap();
+        check_queries(&reopened, &alice, &games);
+        assert!(games[0].pgn.contains("1. e4 e5 1-0"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn file_store_replaces_a_game_saved_twice() {
+        let path = std::env::temp_dir().join(format!("m_chess_games_{}.jsonl", Uuid::new_v4()));
+        let alice = player("alice");
+        let store = FileGameStore::open(&path).unwrap();
+        let games = fill(&store, &alice);
+        let mut replayed = games[0].clone();
+        replayed.reason = "resignation".into();
+        store.save(replayed.clone()).unwrap();
+        store.save(replayed.clone()).unwrap();
+        assert_eq!(store.query(&GameQuery::default()).unwrap().len(), 3);
+        assert_eq!(store.get(replayed.id).unwrap(), Some(replayed.clone()));
+
+        let reopened = FileGameStore::open(&path).unwrap();
+        assert_eq!(reopened.query(&GameQuery::default()).unwrap().len(), 3);
+        assert_eq!(reopened.get(replayed.id).unwrap(), Some(replayed));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
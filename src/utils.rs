@@ -1,27 +1,64 @@
 use std::sync::{atomic::AtomicU64, Arc};
 
-use game_lib::{automation::ai::AI, sharedenums::PlayerRole};
+use game_lib::{
+    automation::ai::{AiProfile, AI},
+    messages::AccountInfo,
+    sharedenums::PlayerRole,
+};
 use tokio::sync::mpsc::UnboundedSender;
 use tokio_tungstenite::tungstenite::Message;
 use uuid::Uuid;
 
-#[derive(Debug)]
 pub struct Client {
     pub id: Uuid,
     pub room_id: Option<Uuid>,
     pub sender: UnboundedSender<Message>,
     pub hb: Arc<AtomicU64>,
+    pub account: Option<AccountInfo>,
+    pub session_token: Option<String>,
+}
+
+// Le token de session ne doit pas finir dans les logs
+impl std::fmt::Debug for Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Client")
+            .field("id", &self.id)
+            .field("room_id", &self.room_id)
+            .field("account", &self.account)
+            .field(
+                "session_token",
+                &self.session_token.as_ref().map(|_| "<redacted>"),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl Client {
+    pub fn display_name(&self) -> String {
+        match &self.account {
+            Some(account) => account.display_name.clone(),
+            None => guest_name(self.id),
+        }
+    }
+}
+
+// Nom affiché pour les clients non connectés
+pub fn guest_name(client_id: Uuid) -> String {
+    format!("Guest {}", &client_id.to_string()[..8])
 }
 
 #[derive(Debug, Clone)]
 pub enum PlayerType {
     Human,
-    Ai { ai: AI }, // tu peux même ajouter un champ `name`, `strategy`, etc.
+    Ai { ai: AI, profile: AiProfile },
 }
 
 #[derive(Debug)]
 pub struct Player {
     pub id: Uuid,
+    /// Persistent account, `None` for guests and AIs.
+    pub account_id: Option<Uuid>,
+    pub name: String,
     pub role: PlayerRole,
     pub ready: bool,
     pub sender: Option<UnboundedSender<Message>>,